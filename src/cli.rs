@@ -0,0 +1,193 @@
+//! Maintenance subcommands that run instead of the bot and don't need a Discord token.
+
+use crate::config::Config;
+use crate::databases::{self, Databases, Format};
+use crate::modules::admin::export::export_guild;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+const MIGRATE_USAGE: &str =
+    "usage: prometheus migrate --from <bincode|json> --to <bincode|json> <data dir> [--out <dir>]";
+
+/// Runs the subcommand named by `args` (without the program name), if there is one, and
+/// returns its exit code. `None` means the bot should start as usual.
+pub async fn run(args: &[String]) -> Option<i32> {
+    let code = match args.first().map(String::as_str) {
+        Some("migrate") => migrate(&args[1..]).await,
+        Some("--verify-config") => verify_config().await,
+        Some("--list-guilds") => with_databases(list_guilds).await,
+        Some("--vacuum") => with_databases(vacuum).await,
+        Some("--export-guild") => {
+            let (Some(guild_id), path) = (args.get(1).and_then(|id| id.parse().ok()), args.get(2))
+            else {
+                eprintln!("usage: prometheus --export-guild <guild id> [file]");
+                return Some(2);
+            };
+            let path = path.cloned();
+            with_databases(move |dbs| async move { export(dbs, guild_id, path).await }).await
+        }
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Opens the configured databases for an offline command. The bot must be stopped, or
+/// the two would overwrite each other's changes.
+async fn with_databases<F, Fut>(command: F) -> i32
+where
+    F: FnOnce(Databases) -> Fut,
+    Fut: std::future::Future<Output = Result<(), String>>,
+{
+    let config = match Config::load_offline() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            return 1;
+        }
+    };
+    let dbs = match Databases::open(&config.storage).await {
+        Ok(dbs) => dbs,
+        Err(e) => {
+            eprintln!("Failed to open databases: {}", e);
+            return 1;
+        }
+    };
+
+    match command(dbs).await {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+/// `--verify-config`: checks the configuration the bot would start with, and that every
+/// database opens with it.
+async fn verify_config() -> i32 {
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            return 1;
+        }
+    };
+    println!(
+        "configuration OK: {} storage in {}{}",
+        config.storage.backend,
+        config.storage.data_dir.display(),
+        if config.storage.encryption_key.is_some() { ", encrypted" } else { "" }
+    );
+
+    match Databases::open(&config.storage).await {
+        Ok(_) => {
+            println!("all databases opened");
+            0
+        }
+        Err(e) => {
+            eprintln!("Failed to open databases: {}", e);
+            1
+        }
+    }
+}
+
+async fn list_guilds(dbs: Databases) -> Result<(), String> {
+    for (guild_id, modules) in dbs.guilds().await {
+        println!("{}\t{}", guild_id, modules.join(", "));
+    }
+    Ok(())
+}
+
+async fn vacuum(dbs: Databases) -> Result<(), String> {
+    dbs.vacuum().await.map_err(|e| format!("Vacuum failed: {}", e))?;
+    println!("rewrote every database");
+    Ok(())
+}
+
+/// `--export-guild`: the same JSON as `/settings export`, to a file or stdout. Member lists
+/// aren't available offline, so testing limits are left out.
+async fn export(dbs: Databases, guild_id: u64, path: Option<String>) -> Result<(), String> {
+    let export = export_guild(&dbs, guild_id, &HashSet::new()).await;
+    if export.summary().is_empty() {
+        return Err(format!("No data stored for guild {}", guild_id));
+    }
+
+    let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+    match path {
+        Some(path) => {
+            std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+            eprintln!("wrote {}", path);
+        }
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+/// `prometheus migrate`: converts the databases in a data directory between formats.
+/// Output goes to `<data dir>/<format>` unless `--out` says otherwise, so live data is
+/// never overwritten.
+async fn migrate(args: &[String]) -> i32 {
+    let mut from = None;
+    let mut to = None;
+    let mut input = None;
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let result = match arg.as_str() {
+            "--from" | "--to" | "--out" => match args.next() {
+                Some(value) if arg == "--out" => {
+                    output = Some(PathBuf::from(value));
+                    Ok(())
+                }
+                Some(value) => value.parse::<Format>().map(|format| match arg.as_str() {
+                    "--from" => from = Some(format),
+                    _ => to = Some(format),
+                }),
+                None => Err(format!("{} needs a value", arg)),
+            },
+            path if !path.starts_with("--") && input.is_none() => {
+                input = Some(PathBuf::from(path));
+                Ok(())
+            }
+            _ => Err(format!("unexpected argument `{}`", arg)),
+        };
+        if let Err(e) = result {
+            eprintln!("{}\n{}", e, MIGRATE_USAGE);
+            return 2;
+        }
+    }
+
+    let (Some(from), Some(to), Some(input)) = (from, to, input) else {
+        eprintln!("{}", MIGRATE_USAGE);
+        return 2;
+    };
+    if from == to {
+        eprintln!("--from and --to are the same format; nothing to do");
+        return 2;
+    }
+    let output = output.unwrap_or_else(|| {
+        input.join(match to {
+            Format::Bincode => "bincode",
+            Format::Json => "json",
+        })
+    });
+
+    let key = std::env::var("DB_ENCRYPTION_KEY").ok();
+    match databases::convert(&input, &output, from, to, key.as_deref()).await {
+        Ok(written) if written.is_empty() => {
+            eprintln!("No databases found in {}", input.display());
+            1
+        }
+        Ok(written) => {
+            for path in written {
+                println!("wrote {}", path.display());
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("Migration failed: {}", e);
+            1
+        }
+    }
+}
@@ -0,0 +1,608 @@
+//! Process configuration, read from `config.toml` (or `CONFIG_FILE`) with environment
+//! variables taking precedence over the file.
+
+use crate::default_struct;
+use crate::utils::operators::Severity;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock, RwLock},
+    time::Duration,
+};
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Resolves `name` inside the configured data directory (`data` until config is loaded).
+pub fn data_path(name: impl AsRef<Path>) -> PathBuf {
+    DATA_DIR
+        .get()
+        .map(PathBuf::as_path)
+        .unwrap_or(Path::new("data"))
+        .join(name)
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct DiscordConfig {
+    pub token: String,
+    /// Guilds commands are registered in instead of globally while staging changes.
+    pub dev_guilds: Vec<u64>,
+    /// Users allowed to run `/owner` and `/admin`, on top of the application's owners.
+    pub owners: Vec<u64>,
+    /// Shards to run. Unset uses the count Discord recommends.
+    pub shard_count: Option<u32>,
+}
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ArchonConfig {
+    pub master_key: String,
+    pub base_url: String = "https://archon.pyro.host/modrinth/v0".to_string(),
+    /// Use the in-process fake Archon API (`--mock-archon`).
+    pub mock: bool,
+    /// With `mock`, fail every Nth Archon request.
+    pub mock_fail_every: Option<u64>,
+    /// Hosts `/testing create` accepts artifact URLs from; subdomains are included.
+    pub artifact_domains: Vec<String> = vec![
+        "github.com".to_string(),
+        "objects.githubusercontent.com".to_string(),
+        "cdn.modrinth.com".to_string(),
+    ],
+}
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    pub data_dir: PathBuf = PathBuf::from("data"),
+    /// `file` or `sqlite`.
+    pub backend: String = "file".to_string(),
+    /// Write batching interval for flat files, which journal transactions in between; 0
+    /// writes on every transaction. SQLite writes every transaction regardless.
+    pub flush_interval_ms: u64 = 1000,
+    /// 64 hex characters; enables encryption at rest.
+    pub encryption_key: Option<String>,
+}
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct PrometheusConfig {
+    /// Used by guilds that haven't set their own URL with `/stats set_prometheus`.
+    pub default_url: String,
+    /// Prometheus queried for node names reserved from Lorax submissions.
+    pub node_names_url: String = "https://metrics.pyro.host".to_string(),
+}
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct TaskConfig {
+    pub stats_interval_secs: u64 = 300,
+    pub testing_interval_secs: u64 = 300,
+    pub backup_interval_hours: u64 = 24,
+    /// Number of backup snapshots to keep.
+    pub backup_keep: usize = 7,
+    /// Consecutive failed runs before a task is reported; 0 disables reports.
+    pub failure_alert_threshold: u32 = 5,
+    /// Runs of each task kept for `/admin tasks history`; 0 keeps none.
+    pub history_runs: usize = 50,
+    /// Older name for `operators.channel`, used when that isn't set.
+    pub alert_channel: Option<u64>,
+    /// Older name for `operators.webhook_url`, used when that isn't set.
+    pub alert_webhook_url: Option<String>,
+}
+}
+
+impl TaskConfig {
+    pub fn stats_interval(&self) -> Duration {
+        Duration::from_secs(self.stats_interval_secs.max(1))
+    }
+
+    pub fn testing_interval(&self) -> Duration {
+        Duration::from_secs(self.testing_interval_secs.max(1))
+    }
+
+    pub fn backup_interval(&self) -> Duration {
+        Duration::from_secs(self.backup_interval_hours.max(1) * 60 * 60)
+    }
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    pub interval_hours: u64 = 6,
+    /// Archived Lorax resets kept per guild for `/lorax restore`; 0 keeps the most allowed.
+    pub lorax_resets: usize = 10,
+    /// Days audit entries are kept; 0 keeps them until the per-guild cap drops them.
+    pub audit_days: u64 = 90,
+    /// Days saved recordings are kept on disk; 0 keeps them forever.
+    pub recording_days: u64 = 30,
+}
+}
+
+impl RetentionConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_hours.max(1) * 60 * 60)
+    }
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Address the HTTP server listens on, e.g. `0.0.0.0:9100`. Unset disables it.
+    pub listen: Option<SocketAddr>,
+    /// Serves live updates over a WebSocket at `/live`.
+    pub live: bool,
+    /// Token that sees every guild's live updates; guild API tokens see only their own.
+    pub live_token: Option<String>,
+}
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RecordingConfig {
+    /// Transcription endpoint finished recordings are uploaded to before summarizing.
+    /// Unset disables summaries.
+    pub transcription_endpoint: Option<String>,
+    /// Sent to the transcription endpoint as a bearer token.
+    pub transcription_api_key: Option<String>,
+    pub transcription_timeout_secs: u64 = 300,
+    /// Summarization endpoint transcripts are sent to, for guilds that turn summaries on.
+    /// Unset disables summaries.
+    pub summary_endpoint: Option<String>,
+    /// Sent to the endpoint as a bearer token.
+    pub summary_api_key: Option<String>,
+    pub summary_timeout_secs: u64 = 60,
+}
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct CooldownConfig {
+    /// Minimum time between two commands from the same user, anywhere; 0 disables it.
+    pub global_per_user_ms: u64,
+}
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct OperatorConfig {
+    /// Channel operator notifications are posted in.
+    pub channel: Option<u64>,
+    /// Discord webhook operator notifications are posted to.
+    pub webhook_url: Option<String>,
+    /// Quieter notifications are only logged.
+    pub min_severity: Severity = Severity::Info,
+    /// How long to collect notifications into one message.
+    pub batch_secs: u64 = 30,
+}
+}
+
+impl OperatorConfig {
+    pub fn batch_window(&self) -> Duration {
+        Duration::from_secs(self.batch_secs)
+    }
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ErrorConfig {
+    /// Sentry DSN command and task errors are reported to.
+    pub sentry_dsn: Option<String>,
+    /// Channel command and task errors are posted in.
+    pub channel: Option<u64>,
+    /// Tagged on Sentry events, e.g. `production` or `staging`.
+    pub environment: String = "production".to_string(),
+    /// Repeats of an error within this window are counted rather than reported.
+    pub dedup_window_secs: u64 = 600,
+}
+}
+
+impl ErrorConfig {
+    pub fn dedup_window(&self) -> Duration {
+        Duration::from_secs(self.dedup_window_secs)
+    }
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct PresenceActivity {
+    /// `playing`, `listening`, `watching` or `competing`.
+    pub kind: String = "watching".to_string(),
+    /// May use `{guilds}`, `{test_servers}` and `{lorax_events}`.
+    pub text: String,
+}
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct PresenceConfig {
+    /// `online`, `idle`, `dnd` or `invisible`.
+    pub status: String = "dnd".to_string(),
+    pub rotate_interval_secs: u64 = 60,
+    /// Shown in turn, one per interval.
+    pub activities: Vec<PresenceActivity> = vec![PresenceActivity {
+        kind: "watching".to_string(),
+        text: "over pyro.host".to_string(),
+    }],
+}
+}
+
+impl PresenceConfig {
+    pub fn rotate_interval(&self) -> Duration {
+        Duration::from_secs(self.rotate_interval_secs.max(15))
+    }
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// A `tracing` filter such as `info` or `pyrobot=debug`; `RUST_LOG` overrides it.
+    pub level: String = "info".to_string(),
+    /// `text` for people, `json` (one object per line) for log aggregation.
+    pub format: String = "text".to_string(),
+    /// Levels for single modules, e.g. `stats = "debug"`. Keys with `::` are used as
+    /// `tracing` targets as they are.
+    pub modules: BTreeMap<String, String>,
+    /// Directory logs are also written to, one file per day. Unset logs to stdout only.
+    pub directory: Option<PathBuf>,
+    /// Daily log files kept in `directory`.
+    pub keep_files: usize = 7,
+}
+}
+
+impl LoggingConfig {
+    /// The `tracing` filter for `level` plus the per-module overrides.
+    pub fn filter(&self) -> String {
+        let mut filter = self.level.clone();
+        for (module, level) in &self.modules {
+            let target = if module.contains("::") {
+                module.clone()
+            } else {
+                format!("pyrobot::modules::{}", module)
+            };
+            filter.push_str(&format!(",{}={}", target, level));
+        }
+        filter
+    }
+}
+
+default_struct! {
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub discord: DiscordConfig,
+    pub archon: ArchonConfig,
+    pub storage: StorageConfig,
+    pub prometheus: PrometheusConfig,
+    pub tasks: TaskConfig,
+    pub retention: RetentionConfig,
+    pub operators: OperatorConfig,
+    pub errors: ErrorConfig,
+    pub cooldowns: CooldownConfig,
+    pub recording: RecordingConfig,
+    pub server: ServerConfig,
+    pub presence: PresenceConfig,
+    pub logging: LoggingConfig,
+}
+}
+
+/// Overrides `target` with the environment variable `name` when it's set and parses.
+fn env_override<T: std::str::FromStr>(name: &str, target: &mut T) -> Result<(), String> {
+    if let Ok(value) = std::env::var(name) {
+        *target = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("{} has an invalid value: {}", name, value))?;
+    }
+    Ok(())
+}
+
+/// The config commands read, replaced in place by `/owner reload`.
+#[derive(Debug)]
+pub struct LiveConfig {
+    current: RwLock<Arc<Config>>,
+}
+
+impl LiveConfig {
+    pub fn new(config: Config) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(config)),
+        }
+    }
+
+    pub fn get(&self) -> Arc<Config> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Loads the config again and swaps it in, returning the sections that changed but
+    /// are only read at startup.
+    pub fn reload(&self) -> Result<Vec<&'static str>, String> {
+        let config = Config::load()?;
+        let current = self.get();
+        let differs = |a: &dyn std::fmt::Debug, b: &dyn std::fmt::Debug| {
+            format!("{:?}", a) != format!("{:?}", b)
+        };
+
+        let restart_needed = [
+            ("discord", differs(&config.discord, &current.discord)),
+            ("archon", differs(&config.archon, &current.archon)),
+            ("storage", differs(&config.storage, &current.storage)),
+            ("tasks", differs(&config.tasks, &current.tasks)),
+            ("retention", differs(&config.retention, &current.retention)),
+            ("operators", differs(&config.operators, &current.operators)),
+            ("errors", differs(&config.errors, &current.errors)),
+            ("server", differs(&config.server, &current.server)),
+            ("presence", differs(&config.presence, &current.presence)),
+            ("logging", differs(&config.logging, &current.logging)),
+        ]
+        .into_iter()
+        .filter_map(|(section, changed)| changed.then_some(section))
+        .collect();
+
+        *self.current.write().unwrap() = Arc::new(config);
+        Ok(restart_needed)
+    }
+}
+
+impl Config {
+    /// Reads the config file if present, applies environment overrides and checks that
+    /// required values are set. Also fixes the data directory used by [`data_path`].
+    pub fn load() -> Result<Self, String> {
+        let config = Self::read()?;
+        config.validate_credentials()?;
+        Ok(config)
+    }
+
+    /// Like [`Config::load`], but without requiring the Discord token or Archon key, for
+    /// CLI tools that only touch the data files.
+    pub fn load_offline() -> Result<Self, String> {
+        Self::read()
+    }
+
+    fn read() -> Result<Self, String> {
+        let path = std::env::var("CONFIG_FILE").ok();
+        let mut config = match &path {
+            Some(path) => Self::from_file(Path::new(path))?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => Self::default(),
+        };
+
+        config.apply_env()?;
+        config.validate()?;
+
+        let _ = DATA_DIR.set(config.storage.data_dir.clone());
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+    }
+
+    fn apply_env(&mut self) -> Result<(), String> {
+        env_override("DISCORD_TOKEN", &mut self.discord.token)?;
+        if let Ok(ids) = std::env::var("DEV_GUILDS") {
+            self.discord.dev_guilds = ids
+                .split(',')
+                .filter_map(|id| id.trim().parse().ok())
+                .collect();
+        }
+        if let Ok(ids) = std::env::var("DISCORD_OWNERS") {
+            self.discord.owners = ids
+                .split(',')
+                .filter_map(|id| id.trim().parse().ok())
+                .collect();
+        }
+        if let Ok(count) = std::env::var("DISCORD_SHARD_COUNT") {
+            let count = count
+                .trim()
+                .parse()
+                .map_err(|_| format!("DISCORD_SHARD_COUNT has an invalid value: {}", count))?;
+            self.discord.shard_count = Some(count);
+        }
+
+        env_override("MASTER_KEY", &mut self.archon.master_key)?;
+        env_override("ARCHON_URL", &mut self.archon.base_url)?;
+        if std::env::args().any(|arg| arg == "--mock-archon") {
+            self.archon.mock = true;
+        }
+        if let Ok(n) = std::env::var("MOCK_ARCHON_FAIL_EVERY") {
+            self.archon.mock_fail_every = n.trim().parse().ok();
+        }
+
+        env_override("DATA_DIR", &mut self.storage.data_dir)?;
+        env_override("STORAGE_BACKEND", &mut self.storage.backend)?;
+        env_override("DB_FLUSH_INTERVAL_MS", &mut self.storage.flush_interval_ms)?;
+        if let Ok(key) = std::env::var("DB_ENCRYPTION_KEY") {
+            self.storage.encryption_key = Some(key);
+        }
+
+        env_override("PROMETHEUS_URL", &mut self.prometheus.default_url)?;
+        env_override("NODE_NAMES_PROMETHEUS_URL", &mut self.prometheus.node_names_url)?;
+
+        env_override("STATS_INTERVAL_SECS", &mut self.tasks.stats_interval_secs)?;
+        env_override("TESTING_INTERVAL_SECS", &mut self.tasks.testing_interval_secs)?;
+        env_override("BACKUP_INTERVAL_HOURS", &mut self.tasks.backup_interval_hours)?;
+        env_override("BACKUP_KEEP", &mut self.tasks.backup_keep)?;
+        env_override(
+            "TASK_FAILURE_ALERT_THRESHOLD",
+            &mut self.tasks.failure_alert_threshold,
+        )?;
+        env_override("TASK_HISTORY_RUNS", &mut self.tasks.history_runs)?;
+        if let Ok(id) = std::env::var("TASK_ALERT_CHANNEL") {
+            self.tasks.alert_channel = id.trim().parse().ok();
+        }
+        if let Ok(url) = std::env::var("TASK_ALERT_WEBHOOK_URL") {
+            self.tasks.alert_webhook_url = Some(url);
+        }
+
+        env_override("RETENTION_INTERVAL_HOURS", &mut self.retention.interval_hours)?;
+        env_override("RETENTION_LORAX_RESETS", &mut self.retention.lorax_resets)?;
+        env_override("RETENTION_AUDIT_DAYS", &mut self.retention.audit_days)?;
+        env_override("RETENTION_RECORDING_DAYS", &mut self.retention.recording_days)?;
+
+        if let Ok(id) = std::env::var("OPERATOR_CHANNEL") {
+            self.operators.channel = id.trim().parse().ok();
+        }
+        if let Ok(url) = std::env::var("OPERATOR_WEBHOOK_URL") {
+            self.operators.webhook_url = Some(url);
+        }
+        env_override("OPERATOR_BATCH_SECS", &mut self.operators.batch_secs)?;
+        // Task alerts were the first operator notifications; keep their settings working
+        if self.operators.channel.is_none() {
+            self.operators.channel = self.tasks.alert_channel;
+        }
+        if self.operators.webhook_url.is_none() {
+            self.operators.webhook_url = self.tasks.alert_webhook_url.clone();
+        }
+
+        if let Ok(dsn) = std::env::var("SENTRY_DSN") {
+            self.errors.sentry_dsn = Some(dsn.trim().to_string());
+        }
+        if let Ok(id) = std::env::var("ERROR_CHANNEL") {
+            self.errors.channel = id.trim().parse().ok();
+        }
+        env_override("SENTRY_ENVIRONMENT", &mut self.errors.environment)?;
+
+        env_override(
+            "COOLDOWN_GLOBAL_PER_USER_MS",
+            &mut self.cooldowns.global_per_user_ms,
+        )?;
+
+        if let Ok(url) = std::env::var("RECORDING_TRANSCRIPTION_ENDPOINT") {
+            self.recording.transcription_endpoint = Some(url.trim().to_string());
+        }
+        if let Ok(key) = std::env::var("RECORDING_TRANSCRIPTION_API_KEY") {
+            self.recording.transcription_api_key = Some(key);
+        }
+        env_override(
+            "RECORDING_TRANSCRIPTION_TIMEOUT_SECS",
+            &mut self.recording.transcription_timeout_secs,
+        )?;
+        if let Ok(url) = std::env::var("RECORDING_SUMMARY_ENDPOINT") {
+            self.recording.summary_endpoint = Some(url.trim().to_string());
+        }
+        if let Ok(key) = std::env::var("RECORDING_SUMMARY_API_KEY") {
+            self.recording.summary_api_key = Some(key);
+        }
+        env_override(
+            "RECORDING_SUMMARY_TIMEOUT_SECS",
+            &mut self.recording.summary_timeout_secs,
+        )?;
+
+        if let Ok(listen) = std::env::var("SERVER_LISTEN") {
+            let listen = listen
+                .trim()
+                .parse()
+                .map_err(|_| format!("SERVER_LISTEN has an invalid value: {}", listen))?;
+            self.server.listen = Some(listen);
+        }
+        env_override("SERVER_LIVE", &mut self.server.live)?;
+        if let Ok(token) = std::env::var("SERVER_LIVE_TOKEN") {
+            self.server.live_token = Some(token);
+        }
+
+        env_override("PRESENCE_STATUS", &mut self.presence.status)?;
+        env_override(
+            "PRESENCE_ROTATE_INTERVAL_SECS",
+            &mut self.presence.rotate_interval_secs,
+        )?;
+
+        env_override("LOG_LEVEL", &mut self.logging.level)?;
+        env_override("LOG_FORMAT", &mut self.logging.format)?;
+        if let Ok(modules) = std::env::var("LOG_MODULES") {
+            for entry in modules.split(',').filter(|entry| !entry.trim().is_empty()) {
+                let Some((module, level)) = entry.split_once('=') else {
+                    return Err(format!("LOG_MODULES entry `{}` isn't module=level", entry));
+                };
+                self.logging
+                    .modules
+                    .insert(module.trim().to_string(), level.trim().to_string());
+            }
+        }
+        if let Ok(dir) = std::env::var("LOG_DIR") {
+            self.logging.directory = Some(PathBuf::from(dir));
+        }
+        env_override("LOG_KEEP_FILES", &mut self.logging.keep_files)?;
+        Ok(())
+    }
+
+    fn validate_credentials(&self) -> Result<(), String> {
+        if self.discord.token.is_empty() {
+            return Err("missing Discord token (discord.token or DISCORD_TOKEN)".into());
+        }
+        // The master key is only needed to talk to the real Archon API
+        if !self.archon.mock && self.archon.master_key.is_empty() {
+            return Err("missing Archon master key (archon.master_key or MASTER_KEY)".into());
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !matches!(self.logging.format.as_str(), "text" | "json") {
+            return Err(format!(
+                "unknown log format `{}` (expected text or json)",
+                self.logging.format
+            ));
+        }
+        tracing_subscriber::EnvFilter::try_new(self.logging.filter())
+            .map_err(|e| format!("invalid log level or module levels: {}", e))?;
+        if let Some(dsn) = &self.errors.sentry_dsn {
+            crate::utils::errors::SentryDsn::parse(dsn)?;
+        }
+        if self.discord.shard_count == Some(0) {
+            return Err("discord.shard_count must be at least 1".into());
+        }
+        if !matches!(self.storage.backend.as_str(), "file" | "sqlite") {
+            return Err(format!(
+                "unknown storage backend `{}` (expected file or sqlite)",
+                self.storage.backend
+            ));
+        }
+        if !matches!(
+            self.presence.status.as_str(),
+            "online" | "idle" | "dnd" | "invisible"
+        ) {
+            return Err(format!(
+                "unknown presence status `{}` (expected online, idle, dnd or invisible)",
+                self.presence.status
+            ));
+        }
+        if let Some(activity) = self.presence.activities.iter().find(|activity| {
+            !matches!(
+                activity.kind.as_str(),
+                "playing" | "listening" | "watching" | "competing"
+            )
+        }) {
+            return Err(format!(
+                "unknown presence activity kind `{}` (expected playing, listening, watching or competing)",
+                activity.kind
+            ));
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,21 @@
-use serde::{de::DeserializeOwned, Serialize};
-use std::{path::Path, sync::Arc, time::Duration};
+use crate::utils::operators::{self, Severity};
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap},
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use thiserror::Error;
-use tokio::{fs, sync::RwLock, time};
-use tracing::error;
+use tokio::{fs, io::AsyncWriteExt, sync::RwLock, task, time};
+use tracing::{error, info, warn};
 
 #[derive(Error, Debug)]
 pub enum DbError {
@@ -10,22 +23,85 @@ pub enum DbError {
     Io(#[from] std::io::Error),
     #[error("Serialization error: {0}")]
     Codec(String),
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
     #[error("Database error: {0}")]
     Custom(String),
 }
 
-#[derive(Debug)]
-struct DatabaseInner<T> {
-    data: T,
-    path: String,
+/// Key of the single row used by backends that store the whole database as one blob.
+const WHOLE_ROW: &str = "";
+
+/// Rows changed since an earlier state, and the keys removed. Journal entries hold a
+/// batched transaction's changes in [`Rows::to_rows`] form.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub rows: Vec<(String, Vec<u8>)>,
+    pub removed: Vec<String>,
 }
 
-#[derive(Clone, Debug)]
-pub struct Database<T: Serialize + DeserializeOwned + Default + Send + Sync + Clone + 'static> {
-    inner: Arc<RwLock<DatabaseInner<T>>>,
+impl JournalEntry {
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Where a database's bytes live. Data is exchanged as keyed rows so that partitioned
+/// backends only rewrite the rows that changed.
+#[async_trait]
+pub trait StorageBackend: Send + Sync + Debug {
+    /// Loads every stored row.
+    async fn load(&self) -> Result<BTreeMap<String, Vec<u8>>, DbError>;
+
+    /// Upserts `rows` and deletes `removed` in one write.
+    async fn write(&self, rows: Vec<(String, Vec<u8>)>, removed: Vec<String>)
+        -> Result<(), DbError>;
+
+    /// Loads the previous version kept by the backend, if it keeps one.
+    async fn load_backup(&self) -> Result<Option<BTreeMap<String, Vec<u8>>>, DbError> {
+        Ok(None)
+    }
+
+    /// Moves unreadable data aside so a fresh database doesn't overwrite it.
+    async fn quarantine(&self) -> Result<(), DbError> {
+        Ok(())
+    }
+
+    /// Whether the backend stores one row per [`Rows::to_rows`] key rather than a single blob.
+    fn partitioned(&self) -> bool {
+        false
+    }
+
+    /// Durably appends a batched transaction's changes, to be replayed if the process stops
+    /// before the next write. Returns false when the backend keeps no journal, in which case
+    /// the caller writes the changes straight away.
+    async fn append_journal(&self, _entry: JournalEntry) -> Result<bool, DbError> {
+        Ok(false)
+    }
+
+    /// Journal entries appended since the last write, oldest first.
+    async fn load_journal(&self) -> Result<Vec<JournalEntry>, DbError> {
+        Ok(Vec::new())
+    }
+
+    /// Empties the journal once a write has saved everything in it.
+    async fn clear_journal(&self) -> Result<(), DbError> {
+        Ok(())
+    }
+}
+
+/// The original flat-file backend: the whole database bincode-encoded in one file.
+///
+/// Saves go to `{path}.tmp` first and are renamed over `{path}` in one step, with the
+/// previous version kept as `{path}.bak`, so a crash mid-save never leaves a truncated or
+/// missing database behind. Batched transactions are appended to `{path}.journal` between
+/// saves.
+#[derive(Debug)]
+pub struct FileBackend {
+    path: String,
 }
 
-impl<T: Serialize + DeserializeOwned + Default + Send + Sync + Clone + 'static> Database<T> {
+impl FileBackend {
     pub async fn new(path: impl Into<String>) -> Result<Self, DbError> {
         let path = path.into();
 
@@ -36,38 +112,175 @@ impl<T: Serialize + DeserializeOwned + Default + Send + Sync + Clone + 'static>
             })?;
         }
 
-        let data = if Path::new(&path).exists() {
-            match fs::read(&path).await {
-                Ok(bytes) => match bincode::deserialize(&bytes) {
-                    Ok(data) => data,
-                    Err(e) => {
-                        error!("Failed to deserialize database {}: {}", path, e);
-                        T::default()
-                    }
-                },
-                Err(e) => {
-                    error!("Failed to read database {}: {}", path, e);
-                    T::default()
+        Ok(Self { path })
+    }
+
+    fn tmp_path(&self) -> String {
+        format!("{}.tmp", self.path)
+    }
+
+    fn backup_path(&self) -> String {
+        format!("{}.bak", self.path)
+    }
+
+    fn journal_path(&self) -> String {
+        format!("{}.journal", self.path)
+    }
+
+    /// Splits the journal into its length-prefixed entries. A torn last entry is a
+    /// transaction that never finished appending, so it's dropped.
+    fn read_journal(path: &str, mut bytes: &[u8]) -> Vec<JournalEntry> {
+        let mut entries = Vec::new();
+        while !bytes.is_empty() {
+            let record = bytes.get(..4).and_then(move |len| {
+                let len = u32::from_le_bytes(len.try_into().expect("slice is 4 bytes"));
+                bytes.get(4..4 + len as usize)
+            });
+            match record.map(|record| (record.len(), decode::<JournalEntry>(record))) {
+                Some((len, Ok(entry))) => {
+                    entries.push(entry);
+                    bytes = &bytes[4 + len..];
+                }
+                _ => {
+                    warn!("Discarding incomplete entry at the end of {}", path);
+                    break;
                 }
             }
-        } else {
-            T::default()
-        };
+        }
+        entries
+    }
 
-        Ok(Self {
-            inner: Arc::new(RwLock::new(DatabaseInner { data, path })),
-        })
+    async fn read_rows(path: &str) -> Result<Option<BTreeMap<String, Vec<u8>>>, DbError> {
+        if !Path::new(path).exists() {
+            return Ok(None);
+        }
+        let bytes = fs::read(path).await?;
+        Ok(Some(BTreeMap::from([(WHOLE_ROW.to_string(), bytes)])))
     }
 
-    async fn save(&self, data: &T) -> Result<(), DbError> {
-        let path = {
-            let guard = self.inner.read().await;
-            guard.path.clone()
-        };
+    /// Points `{path}.bak` at the current file: a hard link when the filesystem allows,
+    /// otherwise a copy.
+    async fn keep_backup(&self) -> Result<(), DbError> {
+        let backup = self.backup_path();
+        if let Err(e) = fs::remove_file(&backup).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err(e.into());
+            }
+        }
+        if fs::hard_link(&self.path, &backup).await.is_err() {
+            fs::copy(&self.path, &backup).await?;
+        }
+        Ok(())
+    }
+
+    async fn write_atomic(&self, bytes: Vec<u8>) -> Result<(), DbError> {
+        let tmp = self.tmp_path();
+
+        let mut file = fs::File::create(&tmp).await?;
+        file.write_all(&bytes).await?;
+        file.sync_all().await?;
+        drop(file);
+
+        // Keep the current version as the backup without ever moving it out of the way,
+        // then replace it in one rename
+        if Path::new(&self.path).exists() {
+            self.keep_backup().await?;
+        }
+        fs::rename(&tmp, &self.path).await?;
+
+        // Persist the renames themselves
+        if let Some(parent) = Path::new(&self.path).parent() {
+            if let Ok(dir) = fs::File::open(parent).await {
+                let _ = dir.sync_all().await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageBackend for FileBackend {
+    async fn load(&self) -> Result<BTreeMap<String, Vec<u8>>, DbError> {
+        // A leftover temp file is a save that never finished; the rename is the commit point
+        if Path::new(&self.tmp_path()).exists() {
+            warn!("Discarding incomplete save {}", self.tmp_path());
+            fs::remove_file(self.tmp_path()).await?;
+        }
+
+        if let Some(rows) = Self::read_rows(&self.path).await? {
+            return Ok(rows);
+        }
+
+        // Saves never remove the file, so it was deleted or moved by hand
+        if let Some(rows) = Self::read_rows(&self.backup_path()).await? {
+            warn!("{} is missing, recovering from {}", self.path, self.backup_path());
+            return Ok(rows);
+        }
+
+        Ok(BTreeMap::new())
+    }
+
+    async fn load_backup(&self) -> Result<Option<BTreeMap<String, Vec<u8>>>, DbError> {
+        Self::read_rows(&self.backup_path()).await
+    }
+
+    async fn quarantine(&self) -> Result<(), DbError> {
+        if Path::new(&self.path).exists() {
+            let target = format!("{}.corrupt-{}", self.path, chrono::Utc::now().timestamp());
+            fs::rename(&self.path, &target).await?;
+            error!("Moved unreadable database {} to {}", self.path, target);
+            // The journal continues the unreadable file, not the backup
+            if Path::new(&self.journal_path()).exists() {
+                fs::rename(self.journal_path(), format!("{}.journal", target)).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn append_journal(&self, entry: JournalEntry) -> Result<bool, DbError> {
+        let record = encode(&entry)?;
+        let len = u32::try_from(record.len())
+            .map_err(|_| DbError::Custom("journal entry is too large".into()))?;
+        let mut bytes = Vec::with_capacity(record.len() + 4);
+        bytes.extend_from_slice(&len.to_le_bytes());
+        bytes.extend(record);
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.journal_path())
+            .await?;
+        file.write_all(&bytes).await?;
+        file.sync_data().await?;
+        Ok(true)
+    }
+
+    async fn load_journal(&self) -> Result<Vec<JournalEntry>, DbError> {
+        match fs::read(self.journal_path()).await {
+            Ok(bytes) => Ok(Self::read_journal(&self.journal_path(), &bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn clear_journal(&self) -> Result<(), DbError> {
+        match fs::remove_file(self.journal_path()).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
 
-        let bytes = bincode::serialize(data).map_err(|e| DbError::Codec(e.to_string()))?;
+    async fn write(
+        &self,
+        rows: Vec<(String, Vec<u8>)>,
+        _removed: Vec<String>,
+    ) -> Result<(), DbError> {
+        let Some((_, bytes)) = rows.into_iter().find(|(key, _)| key == WHOLE_ROW) else {
+            return Ok(());
+        };
 
-        match time::timeout(Duration::from_secs(5), fs::write(&path, bytes)).await {
+        match time::timeout(Duration::from_secs(5), self.write_atomic(bytes)).await {
             Ok(result) => Ok(result?),
             Err(_) => {
                 error!("Database save operation timed out");
@@ -75,22 +288,725 @@ impl<T: Serialize + DeserializeOwned + Default + Send + Sync + Clone + 'static>
             }
         }
     }
+}
+
+/// A shared SQLite file; each database gets its own table through [`SqliteStore::table`].
+#[derive(Clone, Debug)]
+pub struct SqliteStore {
+    conn: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl SqliteStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DbError> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = rusqlite::Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Writes a consistent copy of the whole store to `path`.
+    pub async fn backup_to(&self, path: impl AsRef<Path>) -> Result<(), DbError> {
+        let conn = self.conn.clone();
+        let path = path.as_ref().to_string_lossy().into_owned();
+
+        task::spawn_blocking(move || {
+            conn.lock()
+                .map_err(|_| DbError::Custom("SQLite connection poisoned".into()))?
+                .execute("VACUUM INTO ?1", [path])?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| DbError::Custom(e.to_string()))?
+    }
+
+    /// Rebuilds the file to reclaim space left by deleted rows.
+    pub async fn vacuum(&self) -> Result<(), DbError> {
+        let conn = self.conn.clone();
+        task::spawn_blocking(move || {
+            conn.lock()
+                .map_err(|_| DbError::Custom("SQLite connection poisoned".into()))?
+                .execute_batch("VACUUM")?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| DbError::Custom(e.to_string()))?
+    }
+
+    /// Returns a backend bound to `table`, creating the table if needed.
+    pub fn table(&self, table: &str) -> Result<SqliteBackend, DbError> {
+        self.conn
+            .lock()
+            .map_err(|_| DbError::Custom("SQLite connection poisoned".into()))?
+            .execute_batch(&format!(
+                "CREATE TABLE IF NOT EXISTS \"{table}\" (key TEXT PRIMARY KEY, value BLOB NOT NULL)"
+            ))?;
+
+        Ok(SqliteBackend {
+            conn: self.conn.clone(),
+            table: table.to_string(),
+        })
+    }
+}
+
+/// Row-per-key storage in one table of a [`SqliteStore`].
+#[derive(Debug)]
+pub struct SqliteBackend {
+    conn: Arc<Mutex<rusqlite::Connection>>,
+    table: String,
+}
+
+impl SqliteBackend {
+    /// Runs blocking SQLite work off the async runtime.
+    async fn with_conn<R, F>(&self, f: F) -> Result<R, DbError>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut rusqlite::Connection, &str) -> Result<R, DbError> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        let table = self.table.clone();
+
+        task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|_| DbError::Custom("SQLite connection poisoned".into()))?;
+            f(&mut conn, &table)
+        })
+        .await
+        .map_err(|e| DbError::Custom(e.to_string()))?
+    }
+}
+
+#[async_trait]
+impl StorageBackend for SqliteBackend {
+    async fn load(&self) -> Result<BTreeMap<String, Vec<u8>>, DbError> {
+        self.with_conn(|conn, table| {
+            let mut stmt = conn.prepare(&format!("SELECT key, value FROM \"{table}\""))?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<BTreeMap<String, Vec<u8>>, _>>()?;
+            Ok(rows)
+        })
+        .await
+    }
+
+    async fn write(
+        &self,
+        rows: Vec<(String, Vec<u8>)>,
+        removed: Vec<String>,
+    ) -> Result<(), DbError> {
+        self.with_conn(move |conn, table| {
+            let tx = conn.transaction()?;
+            {
+                let mut upsert = tx.prepare(&format!(
+                    "INSERT INTO \"{table}\" (key, value) VALUES (?1, ?2) \
+                     ON CONFLICT(key) DO UPDATE SET value = excluded.value"
+                ))?;
+                for (key, value) in &rows {
+                    upsert.execute(rusqlite::params![key, value])?;
+                }
+
+                let mut delete = tx.prepare(&format!("DELETE FROM \"{table}\" WHERE key = ?1"))?;
+                for key in &removed {
+                    delete.execute([key])?;
+                }
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    fn partitioned(&self) -> bool {
+        true
+    }
+}
+
+/// Marks a row encrypted by [`EncryptedBackend`].
+const ENCRYPTION_MAGIC: &[u8; 4] = b"PREN";
+
+/// AES-GCM nonce length in bytes.
+const NONCE_LEN: usize = 12;
+
+fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(ENCRYPTION_MAGIC)
+}
+
+/// Encrypts every row with AES-256-GCM before it reaches the wrapped backend. Rows are
+/// stored as `PREN`, a random nonce, then the ciphertext; the row key is authenticated
+/// too, so rows can't be swapped. Plaintext rows written before encryption was enabled
+/// are encrypted in place on load.
+pub struct EncryptedBackend {
+    inner: Arc<dyn StorageBackend>,
+    cipher: Aes256Gcm,
+}
+
+impl Debug for EncryptedBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptedBackend")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EncryptedBackend {
+    pub fn new(inner: Arc<dyn StorageBackend>, key: &[u8; 32]) -> Self {
+        Self {
+            inner,
+            cipher: Aes256Gcm::new(key.into()),
+        }
+    }
+
+    fn encrypt(&self, key: &str, plaintext: &[u8]) -> Result<Vec<u8>, DbError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, Payload { msg: plaintext, aad: key.as_bytes() })
+            .map_err(|_| DbError::Custom(format!("failed to encrypt row {:?}", key)))?;
+
+        let mut bytes = Vec::with_capacity(ENCRYPTION_MAGIC.len() + NONCE_LEN + ciphertext.len());
+        bytes.extend_from_slice(ENCRYPTION_MAGIC);
+        bytes.extend_from_slice(&nonce);
+        bytes.extend(ciphertext);
+        Ok(bytes)
+    }
+
+    fn decrypt(&self, key: &str, bytes: &[u8]) -> Result<Vec<u8>, DbError> {
+        let header = ENCRYPTION_MAGIC.len() + NONCE_LEN;
+        if bytes.len() < header {
+            return Err(DbError::Codec(format!("encrypted row {:?} is truncated", key)));
+        }
+
+        let nonce = Nonce::from_slice(&bytes[ENCRYPTION_MAGIC.len()..header]);
+        self.cipher
+            .decrypt(nonce, Payload { msg: &bytes[header..], aad: key.as_bytes() })
+            .map_err(|_| {
+                DbError::Custom(format!(
+                    "failed to decrypt row {:?} (wrong DB_ENCRYPTION_KEY?)",
+                    key
+                ))
+            })
+    }
+
+    /// Decrypts `rows`, returning them along with the keys that were still plaintext.
+    fn decrypt_rows(
+        &self,
+        rows: BTreeMap<String, Vec<u8>>,
+    ) -> Result<(BTreeMap<String, Vec<u8>>, Vec<String>), DbError> {
+        let mut plaintext = Vec::new();
+        let rows = rows
+            .into_iter()
+            .map(|(key, bytes)| {
+                if is_encrypted(&bytes) {
+                    let bytes = self.decrypt(&key, &bytes)?;
+                    Ok((key, bytes))
+                } else {
+                    plaintext.push(key.clone());
+                    Ok((key, bytes))
+                }
+            })
+            .collect::<Result<_, DbError>>()?;
+        Ok((rows, plaintext))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for EncryptedBackend {
+    async fn load(&self) -> Result<BTreeMap<String, Vec<u8>>, DbError> {
+        let (rows, plaintext) = self.decrypt_rows(self.inner.load().await?)?;
+
+        if !plaintext.is_empty() {
+            let encrypted = plaintext
+                .iter()
+                .map(|key| Ok((key.clone(), self.encrypt(key, &rows[key])?)))
+                .collect::<Result<Vec<_>, DbError>>()?;
+            self.inner.write(encrypted, Vec::new()).await?;
+            info!("Encrypted {} plaintext row(s) of {:?}", plaintext.len(), self.inner);
+        }
+
+        Ok(rows)
+    }
+
+    async fn write(
+        &self,
+        rows: Vec<(String, Vec<u8>)>,
+        removed: Vec<String>,
+    ) -> Result<(), DbError> {
+        let rows = rows
+            .into_iter()
+            .map(|(key, bytes)| {
+                let bytes = self.encrypt(&key, &bytes)?;
+                Ok((key, bytes))
+            })
+            .collect::<Result<Vec<_>, DbError>>()?;
+        self.inner.write(rows, removed).await
+    }
+
+    async fn load_backup(&self) -> Result<Option<BTreeMap<String, Vec<u8>>>, DbError> {
+        match self.inner.load_backup().await? {
+            Some(rows) => Ok(Some(self.decrypt_rows(rows)?.0)),
+            None => Ok(None),
+        }
+    }
+
+    async fn quarantine(&self) -> Result<(), DbError> {
+        self.inner.quarantine().await
+    }
+
+    fn partitioned(&self) -> bool {
+        self.inner.partitioned()
+    }
+
+    async fn append_journal(&self, entry: JournalEntry) -> Result<bool, DbError> {
+        let rows = entry
+            .rows
+            .into_iter()
+            .map(|(key, bytes)| {
+                let bytes = self.encrypt(&key, &bytes)?;
+                Ok((key, bytes))
+            })
+            .collect::<Result<Vec<_>, DbError>>()?;
+        self.inner.append_journal(JournalEntry { rows, removed: entry.removed }).await
+    }
+
+    async fn load_journal(&self) -> Result<Vec<JournalEntry>, DbError> {
+        self.inner
+            .load_journal()
+            .await?
+            .into_iter()
+            .map(|entry| {
+                let rows = entry
+                    .rows
+                    .into_iter()
+                    .map(|(key, bytes)| {
+                        let bytes = self.decrypt(&key, &bytes)?;
+                        Ok((key, bytes))
+                    })
+                    .collect::<Result<Vec<_>, DbError>>()?;
+                Ok(JournalEntry { rows, removed: entry.removed })
+            })
+            .collect()
+    }
+
+    async fn clear_journal(&self) -> Result<(), DbError> {
+        self.inner.clear_journal().await
+    }
+}
+
+/// Marks a row as carrying a version header. Data written before versioning has none
+/// and is treated as version 1.
+const VERSION_MAGIC: &[u8; 4] = b"PRDB";
+
+/// Upgrades stored rows of a database from one schema version to the next.
+///
+/// `key` is the row being migrated: `""` for databases stored as a single blob, or the
+/// [`Rows::to_rows`] key (e.g. `events/<guild>`) on partitioned backends. Implementations
+/// typically decode into a frozen copy of the old struct and re-encode the new one.
+pub trait Migration: Send + Sync {
+    /// The version this migration upgrades from; it produces `from_version() + 1`.
+    fn from_version(&self) -> u32;
+
+    fn migrate(&self, key: &str, bytes: Vec<u8>) -> Result<Vec<u8>, DbError>;
+}
+
+fn wrap_version(version: u32, payload: Vec<u8>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(payload.len() + 8);
+    bytes.extend_from_slice(VERSION_MAGIC);
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend(payload);
+    bytes
+}
+
+fn unwrap_version(mut bytes: Vec<u8>) -> (u32, Vec<u8>) {
+    if bytes.len() >= 8 && bytes.starts_with(VERSION_MAGIC) {
+        let version = u32::from_le_bytes(bytes[4..8].try_into().expect("slice is 4 bytes"));
+        (version, bytes.split_off(8))
+    } else {
+        (1, bytes)
+    }
+}
+
+/// How a database splits into rows on partitioned backends. The defaults keep everything
+/// in one row; per-guild databases override them so a write only touches that guild.
+///
+/// Bump [`Rows::VERSION`] and add a [`Migration`] whenever the stored layout changes.
+pub trait Rows: Serialize + DeserializeOwned + Default + Send + Sync + Clone + 'static {
+    /// Current schema version, written into every row.
+    const VERSION: u32 = 1;
+
+    /// Upgrades from older versions, one step each.
+    fn migrations() -> Vec<Box<dyn Migration>> {
+        Vec::new()
+    }
+
+    /// Strips version headers and runs migrations until every row is at [`Rows::VERSION`].
+    fn upgrade(rows: BTreeMap<String, Vec<u8>>) -> Result<BTreeMap<String, Vec<u8>>, DbError> {
+        let migrations = Self::migrations();
+
+        rows.into_iter()
+            .map(|(key, bytes)| {
+                let (mut version, mut payload) = unwrap_version(bytes);
+                if version > Self::VERSION {
+                    return Err(DbError::Codec(format!(
+                        "row {:?} is schema v{}, newer than supported v{}",
+                        key,
+                        version,
+                        Self::VERSION
+                    )));
+                }
+
+                while version < Self::VERSION {
+                    let migration = migrations
+                        .iter()
+                        .find(|m| m.from_version() == version)
+                        .ok_or_else(|| {
+                            DbError::Codec(format!("no migration from schema v{}", version))
+                        })?;
+                    payload = migration.migrate(&key, payload)?;
+                    version += 1;
+                }
+
+                Ok((key, payload))
+            })
+            .collect()
+    }
+
+    fn to_rows(&self) -> Result<BTreeMap<String, Vec<u8>>, DbError> {
+        Ok(BTreeMap::from([(WHOLE_ROW.to_string(), encode(self)?)]))
+    }
+
+    fn from_rows(rows: BTreeMap<String, Vec<u8>>) -> Result<Self, DbError> {
+        rows.get(WHOLE_ROW)
+            .map(|bytes| decode(bytes))
+            .unwrap_or_else(|| Ok(Self::default()))
+    }
+}
+
+pub fn encode<V: Serialize>(value: &V) -> Result<Vec<u8>, DbError> {
+    bincode::serialize(value).map_err(|e| DbError::Codec(e.to_string()))
+}
+
+pub fn decode<V: DeserializeOwned>(bytes: &[u8]) -> Result<V, DbError> {
+    bincode::deserialize(bytes).map_err(|e| DbError::Codec(e.to_string()))
+}
+
+/// Adds one `{prefix}/{guild_id}` row per entry of a guild-keyed map.
+pub fn put_guild_rows<V: Serialize>(
+    rows: &mut BTreeMap<String, Vec<u8>>,
+    prefix: &str,
+    map: &HashMap<u64, V>,
+) -> Result<(), DbError> {
+    for (guild_id, value) in map {
+        rows.insert(format!("{prefix}/{guild_id}"), encode(value)?);
+    }
+    Ok(())
+}
+
+/// Reads back the rows written by [`put_guild_rows`].
+pub fn take_guild_rows<V: DeserializeOwned>(
+    rows: &BTreeMap<String, Vec<u8>>,
+    prefix: &str,
+) -> Result<HashMap<u64, V>, DbError> {
+    rows.iter()
+        .filter_map(|(key, bytes)| {
+            let guild_id = key.strip_prefix(prefix)?.strip_prefix('/')?.parse().ok()?;
+            Some(decode(bytes).map(|value| (guild_id, value)))
+        })
+        .collect()
+}
+
+fn row_hash(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The rows that differ from `previous` (or all of them when `force` is set) and the keys
+/// no longer present, along with the hashes of `rows`.
+fn diff_rows(
+    previous: &HashMap<String, u64>,
+    rows: BTreeMap<String, Vec<u8>>,
+    force: bool,
+) -> (JournalEntry, HashMap<String, u64>) {
+    let hashes: HashMap<String, u64> = rows
+        .iter()
+        .map(|(key, bytes)| (key.clone(), row_hash(bytes)))
+        .collect();
+
+    let changed = rows
+        .into_iter()
+        .filter(|(key, _)| force || previous.get(key) != hashes.get(key))
+        .collect::<Vec<_>>();
+    let removed = previous
+        .keys()
+        .filter(|key| !hashes.contains_key(*key))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    (JournalEntry { rows: changed, removed }, hashes)
+}
+
+#[derive(Debug)]
+struct DatabaseInner<T> {
+    data: T,
+    /// Hashes of the rows last written, so saves can skip unchanged rows.
+    written: HashMap<String, u64>,
+    /// Hashes of the [`Rows::to_rows`] rows as of the last transaction, so journal entries
+    /// only carry the rows it changed.
+    journaled: HashMap<String, u64>,
+    /// Whether `data` has changes that haven't been written yet.
+    dirty: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Database<T: Rows> {
+    inner: Arc<RwLock<DatabaseInner<T>>>,
+    backend: Arc<dyn StorageBackend>,
+    /// When set, transactions are journaled and mark the data dirty, and a background loop
+    /// writes it at most this often. `None` writes on every transaction.
+    flush_interval: Option<Duration>,
+}
+
+impl<T: Rows> Database<T> {
+    pub async fn new(path: impl Into<String>) -> Result<Self, DbError> {
+        Self::with_backend(Arc::new(FileBackend::new(path).await?)).await
+    }
+
+    pub async fn with_backend(backend: Arc<dyn StorageBackend>) -> Result<Self, DbError> {
+        // Refuse to start rather than save over data we couldn't read
+        let rows = backend.load().await.map_err(|e| {
+            error!("Failed to read database {:?}: {}", backend, e);
+            e
+        })?;
+
+        if rows.values().any(|bytes| is_encrypted(bytes)) {
+            error!("Database {:?} is encrypted but DB_ENCRYPTION_KEY is not set", backend);
+            return Err(DbError::Custom(
+                "database is encrypted; set DB_ENCRYPTION_KEY to open it".into(),
+            ));
+        }
+
+        let written = rows
+            .iter()
+            .map(|(key, bytes)| (key.clone(), row_hash(bytes)))
+            .collect();
+
+        let (data, replayed) = match Self::decode_rows(backend.as_ref(), rows) {
+            Ok(data) => Self::replay(backend.as_ref(), data).await?,
+            Err(e) => {
+                error!("Failed to deserialize database {:?}: {}", backend, e);
+                (Self::recover(backend.as_ref()).await?, false)
+            }
+        };
+        let (_, journaled) = diff_rows(&HashMap::new(), Self::journal_rows(&data)?, false);
+
+        let db = Self {
+            inner: Arc::new(RwLock::new(DatabaseInner {
+                data,
+                written,
+                journaled,
+                dirty: false,
+            })),
+            backend,
+            flush_interval: None,
+        };
+        // Save what was replayed so the journal can start over
+        if replayed {
+            db.flush().await?;
+        }
+        Ok(db)
+    }
+
+    /// Batches writes: transactions update memory immediately and changes are flushed every
+    /// `interval`. Until then each transaction's changed rows are appended to the backend's
+    /// journal, which is replayed on load, so a crash loses nothing; backends without a
+    /// journal write every transaction as before.
+    pub fn with_flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = Some(interval);
+
+        let db = self.clone();
+        tokio::spawn(async move {
+            loop {
+                time::sleep(interval).await;
+                if let Err(e) = db.flush_if_dirty().await {
+                    operators::notify(
+                        Severity::Critical,
+                        format!("Failed to flush database {:?}: {}", db.backend, e),
+                    );
+                }
+            }
+        });
+
+        self
+    }
+
+    /// Falls back to the backend's previous version when the current one can't be decoded.
+    /// Unreadable data is quarantined rather than overwritten by the empty default.
+    async fn recover(backend: &dyn StorageBackend) -> Result<T, DbError> {
+        let backup = backend.load_backup().await?;
+        backend.quarantine().await?;
+
+        match backup.map(|rows| Self::decode_rows(backend, rows)) {
+            Some(Ok(data)) => {
+                operators::notify(
+                    Severity::Warning,
+                    format!("Database {:?} was unreadable and has been restored from its backup", backend),
+                );
+                Ok(data)
+            }
+            Some(Err(e)) => {
+                operators::notify(
+                    Severity::Critical,
+                    format!("Database {:?} and its backup are unreadable, starting empty: {}", backend, e),
+                );
+                Ok(T::default())
+            }
+            None => Ok(T::default()),
+        }
+    }
+
+    /// Applies the transactions journaled since `data` was last written, returning whether
+    /// there were any.
+    async fn replay(backend: &dyn StorageBackend, data: T) -> Result<(T, bool), DbError> {
+        let entries = backend.load_journal().await?;
+        if entries.is_empty() {
+            return Ok((data, false));
+        }
+
+        // Each entry holds whole rows, so only the latest version of each counts
+        let count = entries.len();
+        let mut latest = BTreeMap::new();
+        for entry in entries {
+            latest.extend(entry.rows.into_iter().map(|(key, bytes)| (key, Some(bytes))));
+            latest.extend(entry.removed.into_iter().map(|key| (key, None)));
+        }
+
+        let mut rows = data.to_rows()?;
+        let mut changed = BTreeMap::new();
+        for (key, bytes) in latest {
+            match bytes {
+                Some(bytes) => {
+                    changed.insert(key, bytes);
+                }
+                None => {
+                    rows.remove(&key);
+                }
+            }
+        }
+        rows.extend(T::upgrade(changed)?);
+
+        warn!("Replayed {} unsaved transaction(s) of {:?}", count, backend);
+        Ok((T::from_rows(rows)?, true))
+    }
+
+    fn decode_rows(backend: &dyn StorageBackend, rows: BTreeMap<String, Vec<u8>>) -> Result<T, DbError> {
+        let rows = T::upgrade(rows)?;
+        if backend.partitioned() {
+            T::from_rows(rows)
+        } else {
+            rows.get(WHOLE_ROW)
+                .map(|bytes| decode(bytes))
+                .unwrap_or_else(|| Ok(T::default()))
+        }
+    }
+
+    /// `data` split by [`Rows::to_rows`] whatever the backend, as journal entries store it.
+    fn journal_rows(data: &T) -> Result<BTreeMap<String, Vec<u8>>, DbError> {
+        Ok(data
+            .to_rows()?
+            .into_iter()
+            .map(|(key, payload)| (key, wrap_version(T::VERSION, payload)))
+            .collect())
+    }
+
+    fn encode_rows(&self, data: &T) -> Result<BTreeMap<String, Vec<u8>>, DbError> {
+        let rows = if self.backend.partitioned() {
+            data.to_rows()?
+        } else {
+            BTreeMap::from([(WHOLE_ROW.to_string(), encode(data)?)])
+        };
+
+        Ok(rows
+            .into_iter()
+            .map(|(key, payload)| (key, wrap_version(T::VERSION, payload)))
+            .collect())
+    }
+
+    /// Writes the rows of `data` that differ from what was last written (or all of them
+    /// when `force` is set), returning the new row hashes.
+    async fn write_rows(
+        &self,
+        written: &HashMap<String, u64>,
+        data: &T,
+        force: bool,
+    ) -> Result<HashMap<String, u64>, DbError> {
+        let (changes, hashes) = diff_rows(written, self.encode_rows(data)?, force);
+        if !changes.is_empty() {
+            self.backend.write(changes.rows, changes.removed).await?;
+        }
+
+        Ok(hashes)
+    }
+
+    /// Writes the current in-memory state to storage.
+    pub async fn flush(&self) -> Result<(), DbError> {
+        let mut guard = self.inner.write().await;
+        guard.written = self.write_rows(&guard.written, &guard.data, true).await?;
+        self.backend.clear_journal().await?;
+        guard.dirty = false;
+        Ok(())
+    }
+
+    async fn flush_if_dirty(&self) -> Result<(), DbError> {
+        let mut guard = self.inner.write().await;
+        if guard.dirty {
+            guard.written = self.write_rows(&guard.written, &guard.data, false).await?;
+            self.backend.clear_journal().await?;
+            guard.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Size of the data as stored, before any encryption, in bytes.
+    pub async fn size(&self) -> Result<usize, DbError> {
+        let guard = self.inner.read().await;
+        Ok(guard.data.to_rows()?.values().map(Vec::len).sum())
+    }
 
     pub async fn get_data(&self) -> T {
         let guard = self.inner.read().await;
         guard.data.clone()
     }
 
+    /// Applies `f` to a copy of the data and commits it only if `f` succeeds. The write
+    /// lock is held throughout, so concurrent transactions can't overwrite each other.
     pub async fn transaction<F, R>(&self, f: F) -> Result<R, DbError>
     where
         F: FnOnce(&mut T) -> Result<R, String>,
     {
-        let mut data = self.get_data().await;
+        let mut guard = self.inner.write().await;
+        let mut data = guard.data.clone();
         let result = f(&mut data).map_err(DbError::Custom)?;
 
-        self.save(&data).await?;
-
-        let mut guard = self.inner.write().await;
+        if self.flush_interval.is_some() {
+            let (changes, hashes) = diff_rows(&guard.journaled, Self::journal_rows(&data)?, false);
+            if !changes.is_empty() {
+                if self.backend.append_journal(changes).await? {
+                    guard.dirty = true;
+                } else {
+                    guard.written = self.write_rows(&guard.written, &data, false).await?;
+                }
+            }
+            guard.journaled = hashes;
+        } else {
+            guard.written = self.write_rows(&guard.written, &data, false).await?;
+        }
         guard.data = data;
 
         Ok(result)
@@ -104,3 +1020,124 @@ impl<T: Serialize + DeserializeOwned + Default + Send + Sync + Clone + 'static>
         f(&guard.data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Counter {
+        value: u32,
+    }
+
+    impl Rows for Counter {}
+
+    /// A fresh directory for one test's files.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("prometheus-db-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Saves `values` in turn to the database at `path`.
+    async fn save(path: &str, values: &[u32]) {
+        let db = Database::<Counter>::new(path).await.unwrap();
+        for value in values {
+            db.transaction(|data| {
+                data.value = *value;
+                Ok(())
+            })
+            .await
+            .unwrap();
+        }
+    }
+
+    async fn open(path: &str) -> u32 {
+        Database::<Counter>::new(path).await.unwrap().read(|data| data.value).await
+    }
+
+    #[tokio::test]
+    async fn saves_replace_the_file_and_keep_a_backup() {
+        let dir = scratch("backup");
+        let path = dir.join("db.bin").to_string_lossy().into_owned();
+        save(&path, &[1, 2]).await;
+
+        assert_eq!(open(&path).await, 2);
+        assert_eq!(open(&format!("{}.bak", path)).await, 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn discards_a_torn_temp_file() {
+        let dir = scratch("torn");
+        let path = dir.join("db.bin").to_string_lossy().into_owned();
+        save(&path, &[1]).await;
+        std::fs::write(format!("{}.tmp", path), b"\x00\x01half a sa").unwrap();
+
+        assert_eq!(open(&path).await, 1);
+        assert!(!Path::new(&format!("{}.tmp", path)).exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn recovers_a_missing_file_from_the_backup() {
+        let dir = scratch("missing");
+        let path = dir.join("db.bin").to_string_lossy().into_owned();
+        save(&path, &[1, 2]).await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(open(&path).await, 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn quarantines_a_corrupt_file_and_falls_back_to_the_backup() {
+        let dir = scratch("corrupt");
+        let path = dir.join("db.bin").to_string_lossy().into_owned();
+        save(&path, &[1, 2]).await;
+        // Too short for even the counter
+        std::fs::write(&path, b"\x01\x02").unwrap();
+
+        assert_eq!(open(&path).await, 1);
+        let quarantined = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.file_name().to_string_lossy().starts_with("db.bin.corrupt-"));
+        assert!(quarantined);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn replays_batched_transactions_after_a_crash() {
+        let dir = scratch("journal");
+        let path = dir.join("db.bin").to_string_lossy().into_owned();
+        save(&path, &[1]).await;
+
+        // Never flushed: only the journal has the change
+        let db = Database::<Counter>::new(&path)
+            .await
+            .unwrap()
+            .with_flush_interval(Duration::from_secs(3600));
+        db.transaction(|data| {
+            data.value = 2;
+            Ok(())
+        })
+        .await
+        .unwrap();
+        drop(db);
+
+        // A torn entry from a transaction that was still being appended
+        let journal = format!("{}.journal", path);
+        let mut bytes = std::fs::read(&journal).unwrap();
+        bytes.extend_from_slice(&[200, 0, 0, 0, 1, 2]);
+        std::fs::write(&journal, bytes).unwrap();
+
+        assert_eq!(open(&path).await, 2);
+        assert!(!Path::new(&journal).exists());
+        assert_eq!(open(&path).await, 2);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
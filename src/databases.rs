@@ -1,10 +1,129 @@
-use crate::database::Database;
+use crate::config::{data_path, StorageConfig};
+use crate::database::{
+    Database, DbError, EncryptedBackend, FileBackend, Rows, SqliteStore, StorageBackend,
+};
 use crate::modules::{
+    audit::database::AuditDatabase,
+    cooldowns::database::CooldownDatabase,
     lorax::database::LoraxDatabase, modrinth::database::ModrinthDatabase,
+    preferences::database::PreferencesDatabase,
     stats::database::StatsDatabase, testing::database::TestingDatabase,
-    recording::database::RecordingDatabase,
+    recording::database::RecordingDatabase, roles::database::RoleJobsDatabase,
+    system::database::SystemDatabase,
+    toggles::database::ModulesDatabase,
+};
+use crate::utils::json;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
 };
-use std::fs;
+use tracing::{error, info};
+
+/// Flat-file name of each database in the data directory, by table name.
+const FILES: [(&str, &str); 11] = [
+    ("lorax", "lorax.db"),
+    ("stats", "stats.db"),
+    ("testing", "testing.db"),
+    ("modrinth", "modrinth.json"),
+    ("recording", "recording.json"),
+    ("system", "system.db"),
+    ("preferences", "preferences.db"),
+    ("modules", "modules.db"),
+    ("audit", "audit.db"),
+    ("cooldowns", "cooldowns.db"),
+    ("roles", "roles.db"),
+];
+
+const SQLITE_FILE: &str = "prometheus.sqlite";
+
+fn file_of(table: &str) -> String {
+    FILES
+        .iter()
+        .find(|(name, _)| *name == table)
+        .map(|(_, file)| data_path(file).to_string_lossy().into_owned())
+        .expect("unknown database table")
+}
+
+/// Parses the encryption key: 32 bytes as 64 hex characters. Unset disables encryption.
+fn encryption_key(hex: Option<&str>) -> Result<Option<[u8; 32]>, String> {
+    let Some(hex) = hex else {
+        return Ok(None);
+    };
+    let hex = hex.trim();
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err("The database encryption key must be 64 hex characters (32 bytes)".into());
+    }
+
+    let mut key = [0u8; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .map_err(|_| "The database encryption key must be 64 hex characters (32 bytes)".to_string())?;
+    }
+    Ok(Some(key))
+}
+
+/// Wraps `backend` in [`EncryptedBackend`] when a key is configured.
+fn encrypted(backend: Arc<dyn StorageBackend>, key: Option<&[u8; 32]>) -> Arc<dyn StorageBackend> {
+    match key {
+        Some(key) => Arc::new(EncryptedBackend::new(backend, key)),
+        None => backend,
+    }
+}
+
+async fn open_file<T: Rows>(path: &str, key: Option<&[u8; 32]>) -> Result<Database<T>, DbError> {
+    match key {
+        Some(_) => {
+            Database::with_backend(encrypted(Arc::new(FileBackend::new(path).await?), key)).await
+        }
+        None => Database::new(path).await,
+    }
+}
+
+/// Opens a database in `store` when SQLite storage is enabled, otherwise from its flat file.
+/// A fresh SQLite table is seeded from the flat file so switching backends keeps existing data.
+async fn open<T: Rows>(
+    store: Option<&SqliteStore>,
+    key: Option<&[u8; 32]>,
+    table: &str,
+    path: &str,
+    flush_interval: Option<Duration>,
+) -> Result<Database<T>, DbError> {
+    let db = open_backend(store, key, table, path).await?;
+    Ok(match flush_interval {
+        Some(interval) => db.with_flush_interval(interval),
+        None => db,
+    })
+}
+
+async fn open_backend<T: Rows>(
+    store: Option<&SqliteStore>,
+    key: Option<&[u8; 32]>,
+    table: &str,
+    path: &str,
+) -> Result<Database<T>, DbError> {
+    let Some(store) = store else {
+        return open_file(path, key).await;
+    };
+
+    let backend = encrypted(Arc::new(store.table(table)?), key);
+    let is_empty = backend.load().await?.is_empty();
+    let db = Database::<T>::with_backend(backend).await?;
+
+    if is_empty && Path::new(path).exists() {
+        let legacy = open_file::<T>(path, key).await?.get_data().await;
+        db.transaction(|data| {
+            *data = legacy;
+            Ok(())
+        })
+        .await?;
+        info!("Imported {} into SQLite table {}", path, table);
+    }
+
+    Ok(db)
+}
 
 #[derive(Debug)]
 pub struct Databases {
@@ -13,6 +132,13 @@ pub struct Databases {
     pub testing: Database<TestingDatabase>,
     pub modrinth: Database<ModrinthDatabase>,
     pub recording: Database<RecordingDatabase>,
+    pub system: Database<SystemDatabase>,
+    pub preferences: Database<PreferencesDatabase>,
+    pub modules: Database<ModulesDatabase>,
+    pub audit: Database<AuditDatabase>,
+    pub cooldowns: Database<CooldownDatabase>,
+    pub roles: Database<RoleJobsDatabase>,
+    store: Option<SqliteStore>,
 }
 
 impl Default for Databases {
@@ -22,16 +148,387 @@ impl Default for Databases {
 }
 
 impl Databases {
-    pub async fn default() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn open(
+        storage: &StorageConfig,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         // Create data directory if it doesn't exist
-        fs::create_dir_all("data")?;
-        
+        fs::create_dir_all(&storage.data_dir)?;
+
+        // The sqlite backend keeps everything in one SQLite file with per-guild rows
+        let store = match storage.backend.as_str() {
+            "sqlite" => Some(SqliteStore::open(data_path(SQLITE_FILE))?),
+            _ => None,
+        };
+        let s = store.as_ref();
+
+        // An encryption key encrypts every row at rest with AES-256-GCM
+        let key = encryption_key(storage.encryption_key.as_deref())?;
+        let k = key.as_ref();
+
+        // Batches writes (default 1000ms); 0 writes on every transaction
+        let flush = match storage.flush_interval_ms {
+            0 => None,
+            ms => Some(Duration::from_millis(ms)),
+        };
+
         Ok(Self {
-            lorax: Database::new("data/lorax.db").await?,
-            stats: Database::new("data/stats.db").await?,
-            testing: Database::new("data/testing.db").await?,
-            modrinth: Database::new("data/modrinth.json").await?,
-            recording: Database::new("data/recording.json").await?,
+            lorax: open(s, k, "lorax", &file_of("lorax"), flush).await?,
+            stats: open(s, k, "stats", &file_of("stats"), flush).await?,
+            testing: open(s, k, "testing", &file_of("testing"), flush).await?,
+            modrinth: open(s, k, "modrinth", &file_of("modrinth"), flush).await?,
+            recording: open(s, k, "recording", &file_of("recording"), flush).await?,
+            system: open(s, k, "system", &file_of("system"), flush).await?,
+            preferences: open(s, k, "preferences", &file_of("preferences"), flush).await?,
+            modules: open(s, k, "modules", &file_of("modules"), flush).await?,
+            audit: open(s, k, "audit", &file_of("audit"), flush).await?,
+            cooldowns: open(s, k, "cooldowns", &file_of("cooldowns"), flush).await?,
+            roles: open(s, k, "roles", &file_of("roles"), flush).await?,
+            store,
         })
     }
+
+    /// Forces a final save of every database, logging (not failing on) errors.
+    pub async fn flush_all(&self) {
+        let results = [
+            ("lorax", self.lorax.flush().await),
+            ("stats", self.stats.flush().await),
+            ("testing", self.testing.flush().await),
+            ("modrinth", self.modrinth.flush().await),
+            ("recording", self.recording.flush().await),
+            ("system", self.system.flush().await),
+            ("preferences", self.preferences.flush().await),
+            ("modules", self.modules.flush().await),
+            ("audit", self.audit.flush().await),
+            ("cooldowns", self.cooldowns.flush().await),
+            ("roles", self.roles.flush().await),
+        ];
+
+        for (name, result) in results {
+            if let Err(e) = result {
+                error!("Failed to flush {} database: {}", name, e);
+            }
+        }
+    }
+
+    /// Rewrites every database in full at the current schema version, dropping rows that
+    /// no longer belong to anything, and compacts the SQLite file if there is one.
+    pub async fn vacuum(&self) -> Result<(), DbError> {
+        self.lorax.flush().await?;
+        self.stats.flush().await?;
+        self.testing.flush().await?;
+        self.modrinth.flush().await?;
+        self.recording.flush().await?;
+        self.system.flush().await?;
+        self.preferences.flush().await?;
+        self.modules.flush().await?;
+        self.audit.flush().await?;
+        self.cooldowns.flush().await?;
+        self.roles.flush().await?;
+        if let Some(store) = &self.store {
+            store.vacuum().await?;
+        }
+        Ok(())
+    }
+
+    /// Stored size of every database, in bytes. Databases that fail to encode are left out.
+    pub async fn sizes(&self) -> Vec<(&'static str, usize)> {
+        let results = [
+            ("lorax", self.lorax.size().await),
+            ("stats", self.stats.size().await),
+            ("testing", self.testing.size().await),
+            ("modrinth", self.modrinth.size().await),
+            ("recording", self.recording.size().await),
+            ("system", self.system.size().await),
+            ("preferences", self.preferences.size().await),
+            ("modules", self.modules.size().await),
+            ("audit", self.audit.size().await),
+            ("cooldowns", self.cooldowns.size().await),
+            ("roles", self.roles.size().await),
+        ];
+
+        results
+            .into_iter()
+            .filter_map(|(name, size)| Some((name, size.ok()?)))
+            .collect()
+    }
+
+    /// Every guild with stored data, and the modules holding it.
+    pub async fn guilds(&self) -> BTreeMap<u64, Vec<&'static str>> {
+        let mut guilds: BTreeMap<u64, Vec<&'static str>> = BTreeMap::new();
+        let mut add = |module: &'static str, ids: Vec<u64>| {
+            for guild_id in ids {
+                let modules = guilds.entry(guild_id).or_default();
+                if !modules.contains(&module) {
+                    modules.push(module);
+                }
+            }
+        };
+
+        add(
+            "lorax",
+            self.lorax
+                .read(|db| {
+                    db.settings
+                        .keys()
+                        .chain(db.events.keys())
+                        .chain(db.schedules.keys())
+                        .chain(db.history.keys())
+                        .chain(db.participation.keys())
+                        .copied()
+                        .collect()
+                })
+                .await,
+        );
+        add(
+            "stats",
+            self.stats
+                .read(|db| {
+                    db.stat_bars
+                        .keys()
+                        .chain(db.guild_settings.keys())
+                        .chain(db.named_queries.keys())
+                        .copied()
+                        .collect()
+                })
+                .await,
+        );
+        add(
+            "recording",
+            self.recording
+                .read(|db| db.channels.keys().copied().collect())
+                .await,
+        );
+        add(
+            "system",
+            self.system
+                .read(|db| db.guilds.keys().chain(db.api_tokens.keys()).copied().collect())
+                .await,
+        );
+        add(
+            "audit",
+            self.audit
+                .read(|db| db.channels.keys().chain(db.entries.keys()).copied().collect())
+                .await,
+        );
+        add(
+            "modules",
+            self.modules
+                .read(|db| db.disabled.keys().copied().collect())
+                .await,
+        );
+        add(
+            "roles",
+            self.roles
+                .read(|db| db.jobs.iter().map(|job| job.guild_id).collect())
+                .await,
+        );
+        guilds
+    }
+
+    /// Removes everything stored about a user from every module, returning a description
+    /// of each removed record. Stops at the first module that fails.
+    pub async fn forget_user(&self, user_id: u64) -> Result<Vec<String>, String> {
+        let mut removed = Vec::new();
+        removed.extend(self.lorax.forget_user(user_id).await?);
+        removed.extend(self.modrinth.forget_user(user_id).await?);
+        removed.extend(self.testing.forget_user(user_id).await?);
+        removed.extend(self.recording.forget_user(user_id).await?);
+        removed.extend(self.preferences.forget_user(user_id).await?);
+        removed.extend(self.audit.forget_user(user_id).await?);
+        removed.extend(self.cooldowns.forget_user(user_id).await?);
+        removed.extend(self.roles.forget_user(user_id).await?);
+        Ok(removed)
+    }
+
+    /// Drops references to a deleted channel from every module's configuration, returning
+    /// a description of each change.
+    pub async fn forget_channel(&self, guild_id: u64, channel_id: u64) -> Result<Vec<String>, String> {
+        let mut changed = Vec::new();
+        changed.extend(self.stats.forget_channel(guild_id, channel_id).await?);
+        changed.extend(self.lorax.forget_channel(guild_id, channel_id).await?);
+        changed.extend(self.recording.forget_channel(guild_id, channel_id).await?);
+        changed.extend(self.system.forget_channel(guild_id, channel_id).await?);
+        changed.extend(self.audit.forget_channel(guild_id, channel_id).await?);
+        changed.extend(self.roles.forget_channel(guild_id, channel_id).await?);
+        Ok(changed)
+    }
+
+    /// Drops references to a deleted role from every module's configuration, returning a
+    /// description of each change.
+    pub async fn forget_role(&self, guild_id: u64, role_id: u64) -> Result<Vec<String>, String> {
+        let mut changed = self.lorax.forget_role(guild_id, role_id).await?;
+        changed.extend(self.roles.forget_role(guild_id, role_id).await?);
+        Ok(changed)
+    }
+
+    /// Flushes every database and copies its storage into `dir`, returning the files written.
+    pub async fn snapshot(&self, dir: &Path) -> Result<Vec<PathBuf>, DbError> {
+        self.flush_all().await;
+        tokio::fs::create_dir_all(dir).await?;
+
+        let mut written = Vec::new();
+        if let Some(store) = &self.store {
+            let target = dir.join("prometheus.sqlite");
+            store.backup_to(&target).await?;
+            written.push(target);
+        } else {
+            for (_, file) in FILES {
+                let path = data_path(file);
+                if !path.exists() {
+                    continue;
+                }
+                let target = dir.join(file);
+                tokio::fs::copy(&path, &target).await?;
+                written.push(target);
+            }
+        }
+
+        Ok(written)
+    }
+}
+
+/// Serialization formats `prometheus migrate` converts the flat-file databases between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// The files the bot reads, at the current schema version.
+    Bincode,
+    /// One pretty-printed `<table>.json` per database.
+    Json,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bincode" => Ok(Self::Bincode),
+            "json" => Ok(Self::Json),
+            other => Err(format!("unknown format `{}` (expected bincode or json)", other)),
+        }
+    }
+}
+
+impl Format {
+    fn file(&self, dir: &Path, table: &str) -> PathBuf {
+        match self {
+            Self::Bincode => {
+                let (_, file) = FILES
+                    .iter()
+                    .find(|(name, _)| *name == table)
+                    .expect("unknown database table");
+                dir.join(file)
+            }
+            Self::Json => dir.join(format!("{}.json", table)),
+        }
+    }
+
+    async fn read<T: Rows>(&self, path: &Path, key: Option<&[u8; 32]>) -> Result<T, String> {
+        match self {
+            Self::Bincode => Ok(open_file::<T>(&path.to_string_lossy(), key)
+                .await
+                .map_err(|e| e.to_string())?
+                .get_data()
+                .await),
+            Self::Json => {
+                let bytes = fs::read(path).map_err(|e| e.to_string())?;
+                json::parse(&bytes)
+            }
+        }
+    }
+
+    async fn write<T: Rows>(
+        &self,
+        path: &Path,
+        key: Option<&[u8; 32]>,
+        data: &T,
+    ) -> Result<(), String> {
+        match self {
+            Self::Bincode => {
+                let db = open_file::<T>(&path.to_string_lossy(), key)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                db.transaction(|stored| {
+                    *stored = data.clone();
+                    Ok(())
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+                db.flush().await.map_err(|e| e.to_string())
+            }
+            Self::Json => {
+                let json = serde_json::to_vec_pretty(data).map_err(|e| e.to_string())?;
+                fs::write(path, json).map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+/// Converts one table, then reads the result back and checks it matches what was read.
+async fn convert_table<T: Rows>(
+    table: &str,
+    input: &Path,
+    output: &Path,
+    from: Format,
+    to: Format,
+    key: Option<&[u8; 32]>,
+) -> Result<Option<PathBuf>, String> {
+    let source = from.file(input, table);
+    if !source.exists() {
+        return Ok(None);
+    }
+    let target = to.file(output, table);
+    if target.exists() {
+        return Err(format!("{} already exists", target.display()));
+    }
+
+    let run = async {
+        let data = from.read::<T>(&source, key).await?;
+        to.write(&target, key, &data).await?;
+
+        let written = to.read::<T>(&target, key).await?;
+        let as_json = |data: &T| serde_json::to_value(data).map_err(|e| e.to_string());
+        if as_json(&written)? != as_json(&data)? {
+            return Err(format!("{} doesn't read back the same", target.display()));
+        }
+        Ok(())
+    };
+    run.await.map_err(|e| format!("{}: {}", table, e))?;
+    Ok(Some(target))
+}
+
+/// Converts every flat-file database in `input` from `from` to `to`, writing the results to
+/// `output`. Existing files in `output` are never overwritten. `key` is the
+/// `DB_ENCRYPTION_KEY` for encrypted bincode files. Returns the files written.
+pub async fn convert(
+    input: &Path,
+    output: &Path,
+    from: Format,
+    to: Format,
+    key: Option<&str>,
+) -> Result<Vec<PathBuf>, String> {
+    let key = encryption_key(key)?;
+    let k = key.as_ref();
+    fs::create_dir_all(output).map_err(|e| e.to_string())?;
+
+    let mut written = Vec::new();
+    written.extend(convert_table::<LoraxDatabase>("lorax", input, output, from, to, k).await?);
+    written.extend(convert_table::<StatsDatabase>("stats", input, output, from, to, k).await?);
+    written.extend(convert_table::<TestingDatabase>("testing", input, output, from, to, k).await?);
+    written.extend(convert_table::<ModrinthDatabase>("modrinth", input, output, from, to, k).await?);
+    written.extend(
+        convert_table::<RecordingDatabase>("recording", input, output, from, to, k).await?,
+    );
+    written.extend(convert_table::<SystemDatabase>("system", input, output, from, to, k).await?);
+    written.extend(
+        convert_table::<PreferencesDatabase>("preferences", input, output, from, to, k).await?,
+    );
+    written.extend(
+        convert_table::<ModulesDatabase>("modules", input, output, from, to, k).await?,
+    );
+    written.extend(convert_table::<AuditDatabase>("audit", input, output, from, to, k).await?);
+    written.extend(
+        convert_table::<CooldownDatabase>("cooldowns", input, output, from, to, k).await?,
+    );
+    written.extend(convert_table::<RoleJobsDatabase>("roles", input, output, from, to, k).await?);
+    Ok(written)
 }
@@ -1,10 +1,40 @@
 use async_trait::async_trait;
 use futures::stream::{FuturesUnordered, StreamExt};
-use poise::serenity_prelude::{Context, FullEvent};
+use poise::serenity_prelude::{Context, FullEvent, GuildId, Interaction};
+use crate::health::{ComponentHealth, Health};
+use crate::tasks::{TaskManager, TaskStats};
+use dashmap::DashMap;
+use std::time::{Duration, Instant};
+
+/// How long a handler error keeps the handler reported as degraded.
+const ERROR_HEALTH_WINDOW: Duration = Duration::from_secs(10 * 60);
 use std::fmt::Debug;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use tokio::sync::Mutex;
-use crate::{Data, modules::recording::handler::RecordingHandler};
+use crate::{
+    database::Database,
+    modules::{
+        lorax::{
+            ballot::BallotHandler, holders::WinnerHoldersHandler,
+            submit_button::SubmitButtonHandler,
+        },
+        recording::handler::RecordingHandler,
+        system::cleanup::ConfigCleanupHandler,
+        toggles::database::{Module, ModulesDatabase},
+    },
+    Data,
+};
+
+/// How a handler runs relative to the others at its priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HandlerMode {
+    /// Runs alongside the other parallel handlers at the same priority.
+    #[default]
+    Parallel,
+    /// Runs alone: it starts once every earlier handler has finished, and later handlers
+    /// wait for it.
+    Sequential,
+}
 
 #[async_trait]
 pub trait EventHandler: Send + Sync + Debug {
@@ -15,6 +45,43 @@ pub trait EventHandler: Send + Sync + Debug {
         event: &FullEvent,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
     fn box_clone(&self) -> Box<dyn EventHandler>;
+    /// Handler-specific health. Recent errors from `handle` are reported on top of this.
+    fn health(&self) -> Health {
+        Health::ok()
+    }
+    /// Handlers with a higher priority finish handling an event before lower ones start.
+    fn priority(&self) -> i32 {
+        0
+    }
+    fn mode(&self) -> HandlerMode {
+        HandlerMode::Parallel
+    }
+    /// The module this handler belongs to. It isn't given events from guilds that turned
+    /// the module off.
+    fn module(&self) -> Option<Module> {
+        None
+    }
+}
+
+/// The guild an event happened in, for the events module handlers care about.
+fn event_guild(event: &FullEvent) -> Option<GuildId> {
+    match event {
+        FullEvent::VoiceStateUpdate { new, .. } => new.guild_id,
+        FullEvent::Message { new_message } => new_message.guild_id,
+        FullEvent::ReactionAdd { add_reaction } => add_reaction.guild_id,
+        FullEvent::ReactionRemove { removed_reaction } => removed_reaction.guild_id,
+        FullEvent::ChannelDelete { channel, .. } => Some(channel.guild_id),
+        FullEvent::GuildRoleDelete { guild_id, .. } => Some(*guild_id),
+        FullEvent::GuildMemberAddition { new_member } => Some(new_member.guild_id),
+        FullEvent::GuildMemberRemoval { guild_id, .. } => Some(*guild_id),
+        FullEvent::GuildMemberUpdate { event, .. } => Some(event.guild_id),
+        FullEvent::InteractionCreate { interaction } => match interaction {
+            Interaction::Component(component) => component.guild_id,
+            Interaction::Modal(modal) => modal.guild_id,
+            _ => None,
+        },
+        _ => None,
+    }
 }
 
 impl Clone for Box<dyn EventHandler> {
@@ -26,40 +93,181 @@ impl Clone for Box<dyn EventHandler> {
 #[derive(Debug, Default)]
 pub struct EventManager {
     handlers: Mutex<Vec<Box<dyn EventHandler>>>,
+    /// Set by [`EventManager::init`]; until then every handler gets every event.
+    modules: OnceLock<Database<ModulesDatabase>>,
+    /// Latest error and when it happened, by handler name.
+    last_errors: Arc<DashMap<String, (String, Instant)>>,
+    /// Call counts and timings by handler name.
+    stats: Arc<DashMap<String, TaskStats>>,
 }
 
 impl EventManager {
     pub fn new() -> Self {
         Self {
             handlers: Mutex::new(Vec::new()),
+            modules: OnceLock::new(),
+            last_errors: Arc::new(DashMap::new()),
+            stats: Arc::new(DashMap::new()),
         }
     }
 
+    /// Call counts and timings of every handler that has handled an event, by name.
+    pub fn handler_stats(&self) -> Vec<(String, TaskStats)> {
+        let mut stats: Vec<_> = self
+            .stats
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        stats.sort_by(|a, b| a.0.cmp(&b.0));
+        stats
+    }
+
+    pub async fn health(&self) -> Vec<ComponentHealth> {
+        self.handlers
+            .lock()
+            .await
+            .iter()
+            .map(|handler| {
+                let mut health = handler.health();
+                if let Some(entry) = self.last_errors.get(handler.name()) {
+                    let (error, at) = entry.value();
+                    if at.elapsed() < ERROR_HEALTH_WINDOW {
+                        health = health.worst(Health::degraded(format!(
+                            "Error {}s ago: {}",
+                            at.elapsed().as_secs(),
+                            error
+                        )));
+                    }
+                }
+                ComponentHealth {
+                    kind: "event handler",
+                    name: handler.name().to_string(),
+                    health,
+                }
+            })
+            .collect()
+    }
+
     pub async fn init(&self, data: &Arc<Data>) {
-        let mut handlers = self.handlers.lock().await;
-        handlers.push(Box::new(RecordingHandler::new(data.dbs.recording.clone())));
+        let _ = self.modules.set(data.dbs.modules.clone());
+        self.add_handler(RecordingHandler::new(
+            data.dbs.recording.clone(),
+            data.config.clone(),
+        ))
+        .await;
+        self.add_handler(GuildLifecycleHandler {
+            task_manager: data.task_manager.clone(),
+        })
+        .await;
+        self.add_handler(WinnerHoldersHandler {
+            db: data.dbs.lorax.clone(),
+        })
+        .await;
+        self.add_handler(BallotHandler {
+            db: data.dbs.lorax.clone(),
+        })
+        .await;
+        self.add_handler(SubmitButtonHandler {
+            dbs: data.dbs.clone(),
+            config: data.config.clone(),
+        })
+        .await;
+        self.add_handler(ConfigCleanupHandler {
+            dbs: data.dbs.clone(),
+        })
+        .await;
     }
 
+    /// Registers a handler. Handlers are kept in priority order, then registration order.
     pub async fn add_handler(&self, handler: impl EventHandler + 'static) {
-        self.handlers.lock().await.push(Box::new(handler));
+        let mut handlers = self.handlers.lock().await;
+        handlers.push(Box::new(handler));
+        handlers.sort_by_key(|handler| std::cmp::Reverse(handler.priority()));
     }
 
     pub async fn handle_event(&self, ctx: &Context, event: &FullEvent) {
         let handlers = self.handlers.lock().await;
         let mut futures = FuturesUnordered::new();
+        let mut priority = None;
+        let guild_id = event_guild(event);
 
         for handler in handlers.iter() {
+            if let (Some(module), Some(guild_id), Some(modules)) =
+                (handler.module(), guild_id, self.modules.get())
+            {
+                if !modules.is_enabled(guild_id.get(), module).await {
+                    continue;
+                }
+            }
+
+            // Everything running has to finish before a new priority or a sequential handler
+            let sequential = handler.mode() == HandlerMode::Sequential;
+            if sequential || priority != Some(handler.priority()) {
+                while futures.next().await.is_some() {}
+            }
+            priority = Some(handler.priority());
+
             let handler = handler.box_clone();
             let ctx = ctx.clone();
             let event = event.clone();
+            let last_errors = self.last_errors.clone();
+            let stats = self.stats.clone();
 
             futures.push(tokio::spawn(async move {
-                if let Err(e) = handler.handle(&ctx, &event).await {
+                let started = Instant::now();
+                let result = handler.handle(&ctx, &event).await;
+                stats
+                    .entry(handler.name().to_string())
+                    .or_default()
+                    .record(started.elapsed(), result.is_err());
+                if let Err(e) = result {
                     tracing::error!("Error in event handler {}: {}", handler.name(), e);
+                    last_errors.insert(handler.name().to_string(), (e.to_string(), Instant::now()));
                 }
             }));
+
+            if sequential {
+                while futures.next().await.is_some() {}
+            }
         }
 
         while futures.next().await.is_some() {}
     }
 }
+
+/// Starts and stops per-guild tasks as shards connect and the bot joins and leaves guilds.
+#[derive(Debug, Clone)]
+struct GuildLifecycleHandler {
+    task_manager: Arc<TaskManager>,
+}
+
+#[async_trait]
+impl EventHandler for GuildLifecycleHandler {
+    fn name(&self) -> &str {
+        "GuildLifecycle"
+    }
+
+    async fn handle(
+        &self,
+        ctx: &Context,
+        event: &FullEvent,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match event {
+            FullEvent::Ready { .. } => self.task_manager.register_shard(ctx),
+            FullEvent::GuildCreate { guild, .. } => {
+                self.task_manager.register_shard(ctx);
+                self.task_manager.sync_guild(guild.id.get()).await;
+            }
+            // An outage leaves `unavailable` set; only a real removal stops the tasks
+            FullEvent::GuildDelete { incomplete, .. } if !incomplete.unavailable => {
+                self.task_manager.remove_guild(incomplete.id.get()).await;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn box_clone(&self) -> Box<dyn EventHandler> {
+        Box::new(self.clone())
+    }
+}
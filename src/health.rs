@@ -0,0 +1,85 @@
+use std::fmt;
+
+/// Severity of a subsystem's health, ordered from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HealthState {
+    Ok,
+    Degraded,
+    Failed,
+}
+
+impl HealthState {
+    pub fn emoji(self) -> &'static str {
+        match self {
+            Self::Ok => "🟢",
+            Self::Degraded => "🟡",
+            Self::Failed => "🔴",
+        }
+    }
+}
+
+impl fmt::Display for HealthState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ok => "ok",
+            Self::Degraded => "degraded",
+            Self::Failed => "failed",
+        })
+    }
+}
+
+/// Health reported by a task or event handler.
+#[derive(Debug, Clone)]
+pub struct Health {
+    pub state: HealthState,
+    pub message: String,
+}
+
+impl Health {
+    pub fn ok() -> Self {
+        Self {
+            state: HealthState::Ok,
+            message: String::new(),
+        }
+    }
+
+    pub fn degraded(message: impl Into<String>) -> Self {
+        Self {
+            state: HealthState::Degraded,
+            message: message.into(),
+        }
+    }
+
+    pub fn failed(message: impl Into<String>) -> Self {
+        Self {
+            state: HealthState::Failed,
+            message: message.into(),
+        }
+    }
+
+    /// Keeps whichever of the two is worse.
+    pub fn worst(self, other: Health) -> Health {
+        if other.state > self.state {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+/// Health of one named subsystem.
+#[derive(Debug, Clone)]
+pub struct ComponentHealth {
+    pub kind: &'static str,
+    pub name: String,
+    pub health: Health,
+}
+
+/// Overall state across components: the worst of them.
+pub fn overall(components: &[ComponentHealth]) -> HealthState {
+    components
+        .iter()
+        .map(|c| c.health.state)
+        .max()
+        .unwrap_or(HealthState::Ok)
+}
@@ -0,0 +1,197 @@
+//! Log output set up from `[logging]`: text or JSON lines on stdout, optionally mirrored
+//! into a directory with one file per day.
+
+use crate::config::LoggingConfig;
+use chrono::{NaiveDate, SecondsFormat, Utc};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriterExt};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+/// Installs the global subscriber. `RUST_LOG` replaces the configured levels when set.
+pub fn init(config: &LoggingConfig) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(config.filter()));
+
+    let (writer, ansi) = match &config.directory {
+        Some(dir) => match DailyFile::open(dir.clone(), config.keep_files) {
+            Ok(file) => (BoxMakeWriter::new(io::stdout.and(file)), false),
+            Err(e) => {
+                eprintln!("Failed to open log directory {}: {}", dir.display(), e);
+                (BoxMakeWriter::new(io::stdout), true)
+            }
+        },
+        None => (BoxMakeWriter::new(io::stdout), true),
+    };
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer);
+    if config.format == "json" {
+        builder.event_format(JsonFormat).init();
+    } else {
+        builder.with_ansi(ansi).init();
+    }
+}
+
+/// One JSON object per event: timestamp, level, target, message, the event's other
+/// fields and the names of the spans it happened in.
+struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut fields = JsonFields::default();
+        event.record(&mut fields);
+
+        let mut line = Map::new();
+        line.insert(
+            "timestamp".into(),
+            Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true).into(),
+        );
+        line.insert("level".into(), metadata.level().as_str().into());
+        line.insert("target".into(), metadata.target().into());
+        if let Some(message) = fields.0.remove("message") {
+            line.insert("message".into(), message);
+        }
+        if !fields.0.is_empty() {
+            line.insert("fields".into(), Value::Object(fields.0));
+        }
+        if let Some(scope) = ctx.event_scope() {
+            let spans: Vec<Value> = scope.from_root().map(|span| span.name().into()).collect();
+            line.insert("spans".into(), spans.into());
+        }
+
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
+
+#[derive(Default)]
+struct JsonFields(Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().into(), format!("{:?}", value).into());
+    }
+}
+
+/// `pyrobot.<date>.log` in a directory, switching to a new file at midnight UTC and
+/// deleting the oldest beyond `keep`.
+struct DailyFile {
+    dir: PathBuf,
+    keep: usize,
+    current: Mutex<(NaiveDate, File)>,
+}
+
+impl DailyFile {
+    fn open(dir: PathBuf, keep: usize) -> io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        let today = Utc::now().date_naive();
+        let file = Self::open_day(&dir, today)?;
+        let daily = Self {
+            dir,
+            keep,
+            current: Mutex::new((today, file)),
+        };
+        daily.prune();
+        Ok(daily)
+    }
+
+    fn open_day(dir: &Path, day: NaiveDate) -> io::Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(format!("pyrobot.{}.log", day.format("%Y-%m-%d"))))
+    }
+
+    /// Deletes the oldest log files beyond `keep`. Dated names sort chronologically.
+    fn prune(&self) {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+        let mut files: Vec<_> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("pyrobot.") && name.ends_with(".log"))
+            })
+            .collect();
+        files.sort();
+
+        let excess = files.len().saturating_sub(self.keep.max(1));
+        for path in &files[..excess] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    fn write_line(&self, buf: &[u8]) -> io::Result<usize> {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        let today = Utc::now().date_naive();
+        if current.0 != today {
+            *current = (today, Self::open_day(&self.dir, today)?);
+            self.prune();
+        }
+        current.1.write_all(buf)?;
+        Ok(buf.len())
+    }
+}
+
+struct DailyFileWriter<'a>(&'a DailyFile);
+
+impl Write for DailyFileWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_line(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.current.lock().unwrap_or_else(|e| e.into_inner()).1.flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for DailyFile {
+    type Writer = DailyFileWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        DailyFileWriter(self)
+    }
+}
@@ -1,26 +1,49 @@
 use crate::modules::lorax::database::LoraxHandler;
 use databases::Databases;
+use metrics::MetricsRegistry;
 use modules::{
-    lorax::{commands::lorax, task::LoraxEventTask},
+    admin::{admin, backup::BackupTask},
+    audit::{audit, log as audit_log},
+    help::help,
+    lorax::{commands::lorax, task::LoraxGuildTask},
     modrinth::modrinth,
+    preferences::preferences,
+    privacy::privacy,
     recording::recording,
-    stats::{stats, task::StatsTask},
-    testing::{task::TestingTask, testing},
+    roles::worker::RoleJobTask,
+    stats::{rename::RenameQueueTask, stats, task::StatsTask},
+    system::{
+        owner::owner, presence::PresenceTask, retention::RetentionTask, settings,
+        task::HealthReportTask,
+    },
+    testing::{archon::ArchonClient, task::TestingTask, testing},
+    toggles::{database::Module, toggles},
     utils::server_costs,
 };
 use poise::serenity_prelude::{self as serenity, CreateAllowedMentions};
+use poise::ChoiceParameter;
 use songbird::SerenityInit;
 use std::sync::Arc;
-use tasks::TaskManager;
+use tasks::{FailureAlerts, RunHistory, TaskManager};
+use utils::errors::{self, ErrorReport};
+use utils::operators::{self, Severity};
+use utils::validate::Invalid;
 use tracing::{error, info, trace};
 
+mod cli;
+mod config;
 mod database;
 mod databases;
 mod events;
+mod health;
+mod logging;
+mod metrics;
 mod modules;
+mod server;
 mod tasks;
 mod utils;
 
+use crate::config::{Config, LiveConfig};
 use crate::events::EventManager;
 
 #[derive(Clone, Debug)]
@@ -28,33 +51,68 @@ pub struct Data {
     pub dbs: Arc<Databases>,
     pub task_manager: Arc<TaskManager>,
     pub event_manager: Arc<EventManager>,
-    pub config: Config,
-}
-
-#[derive(Clone, Debug)]
-pub struct Config {
-    pub master_key: String,
+    pub metrics: Arc<MetricsRegistry>,
+    pub archon: Arc<ArchonClient>,
+    /// Gateway shards this process runs.
+    pub shards: Arc<serenity::ShardManager>,
+    pub config: Arc<LiveConfig>,
 }
 
 impl Data {
     pub async fn init_tasks(&self, ctx: &serenity::Context) {
-        let lorax_db = Arc::new(self.dbs.lorax.clone());
-        let guild_ids: Vec<u64> = lorax_db
-            .read(|db| db.events.keys().cloned().collect())
-            .await;
-
-        for guild_id in guild_ids {
-            let lorax_task = LoraxEventTask::new(guild_id, lorax_db.clone());
-            self.task_manager.add_task(lorax_task).await;
-        }
+        let config = self.config.get();
+        let lorax_task = LoraxGuildTask {
+            dbs: self.dbs.clone(),
+            config: self.config.clone(),
+        };
+        self.task_manager.add_guild_task(lorax_task).await;
 
-        let stats_task = StatsTask::new(self.dbs.stats.clone());
+        let stats_task = StatsTask::new(
+            self.dbs.stats.clone(),
+            self.dbs.system.clone(),
+            self.dbs.modules.clone(),
+            self.metrics.clone(),
+            config.tasks.stats_interval(),
+            self.config.clone(),
+        );
         self.task_manager.add_task(stats_task).await;
+        self.task_manager
+            .add_task(RenameQueueTask::new(self.dbs.stats.clone()))
+            .await;
 
-        let testing_task =
-            TestingTask::new(self.dbs.testing.clone(), self.config.master_key.clone());
+        let testing_task = TestingTask::new(
+            self.dbs.testing.clone(),
+            self.dbs.preferences.clone(),
+            self.dbs.system.clone(),
+            self.archon.clone(),
+            config.tasks.testing_interval(),
+        );
         self.task_manager.add_task(testing_task).await;
 
+        let health_task = HealthReportTask::new(self.dbs.clone(), self.config.clone());
+        self.task_manager.add_task(health_task).await;
+
+        let presence_task = PresenceTask::new(
+            self.dbs.clone(),
+            config.presence.clone(),
+            self.shards.clone(),
+        );
+        self.task_manager.add_task(presence_task).await;
+
+        let backup_task = BackupTask::new(
+            self.dbs.clone(),
+            config.tasks.backup_interval(),
+            config.tasks.backup_keep,
+        );
+        self.task_manager.add_task(backup_task).await;
+
+        self.task_manager
+            .add_task(RoleJobTask::new(self.dbs.roles.clone()))
+            .await;
+
+        let retention_task = RetentionTask::new(self.dbs.clone(), config.retention.clone());
+        self.task_manager.add_task(retention_task).await;
+
         self.task_manager.start_tasks(ctx.clone()).await;
     }
 }
@@ -67,6 +125,7 @@ impl LoraxHandler {
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, Error>;
+type ApplicationContext<'a> = poise::ApplicationContext<'a, Data, Error>;
 
 #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
 async fn register(ctx: Context<'_>) -> Result<(), Error> {
@@ -77,24 +136,99 @@ async fn register(ctx: Context<'_>) -> Result<(), Error> {
 #[tokio::main]
 async fn main() {
     dotenvy::dotenv().ok();
-    tracing_subscriber::fmt::init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args).await {
+        std::process::exit(code);
+    }
+
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    logging::init(&config.logging);
     info!("starting prometheus");
+    errors::install_panic_hook();
 
-    let token = std::env::var("DISCORD_TOKEN").expect("missing DISCORD_TOKEN");
+    let token = config.discord.token.clone();
     let intents = serenity::GatewayIntents::all();
 
+    // Created up front so the shutdown path can reach them
+    let dbs = Arc::new(
+        Databases::open(&config.storage)
+            .await
+            .expect("failed to open databases"),
+    );
+    let shard_count = config.discord.shard_count;
+    let task_manager = Arc::new(
+        tasks::TaskManager::new()
+            .with_alerts(FailureAlerts {
+                threshold: config.tasks.failure_alert_threshold,
+            })
+            .with_history(RunHistory {
+                db: dbs.system.clone(),
+                keep: config.tasks.history_runs,
+            }),
+    );
+    let owners = config
+        .discord
+        .owners
+        .iter()
+        .filter(|id| **id != 0)
+        .map(|id| serenity::UserId::new(*id))
+        .collect();
+    let operator_config = config.operators.clone();
+    let error_config = config.errors.clone();
+    let setup_dbs = dbs.clone();
+    let setup_task_manager = task_manager.clone();
+
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions::<Data, Error> {
             allowed_mentions: Some(CreateAllowedMentions::new().empty_roles().empty_users()),
+            owners,
             commands: vec![
                 register(),
+                help(),
                 lorax(),
                 stats(),
                 testing(),
                 modrinth(),
                 server_costs(),
                 recording(),
+                settings(),
+                preferences(),
+                privacy(),
+                admin(),
+                owner(),
+                toggles(),
+                audit(),
             ],
+            command_check: Some(|ctx| {
+                Box::pin(async move {
+                    // Commands of modules a guild turned off refuse to run there
+                    let root = ctx.command().qualified_name.split(' ').next().unwrap_or_default();
+                    let (Some(guild_id), Some(module)) = (ctx.guild_id(), Module::of_command(root))
+                    else {
+                        return modules::cooldowns::check(ctx).await;
+                    };
+                    if ctx.data().dbs.modules.is_enabled(guild_id.get(), module).await {
+                        return modules::cooldowns::check(ctx).await;
+                    }
+
+                    let reply = poise::CreateReply::default()
+                        .content(format!(
+                            "🔴 The **{}** module is turned off in this server. \
+                            Admins can turn it on with `/modules enable`.",
+                            module.name()
+                        ))
+                        .ephemeral(true);
+                    ctx.send(reply).await?;
+                    Ok(false)
+                })
+            }),
             pre_command: |ctx| {
                 Box::pin(async move {
                     trace!(
@@ -108,6 +242,12 @@ async fn main() {
             },
             post_command: |ctx| {
                 Box::pin(async move {
+                    ctx.data().metrics.increment("commands_total", 1);
+                    ctx.data()
+                        .metrics
+                        .increment_labeled("command_runs_total", &ctx.command().qualified_name, 1);
+                    audit_log::record(ctx, true).await;
+                    utils::aliases::notice(ctx).await;
                     info!(
                         "Command {} completed for {} in {}",
                         ctx.command().qualified_name,
@@ -121,6 +261,34 @@ async fn main() {
                 Box::pin(async move {
                     match error {
                         poise::FrameworkError::Command { error, ctx, .. } => {
+                            // Bad arguments get guidance rather than being logged as failures
+                            if let Some(invalid) = error.downcast_ref::<Invalid>() {
+                                let reply = poise::CreateReply::default()
+                                    .content(format!("❌ {}", invalid))
+                                    .ephemeral(true);
+                                if let Err(e) = ctx.send(reply).await {
+                                    error!("Failed to send validation error: {}", e);
+                                }
+                                return;
+                            }
+
+                            ctx.data().metrics.increment("command_errors_total", 1);
+                            audit_log::record(ctx, false).await;
+                            let mut report = ErrorReport::new(
+                                format!("/{}", ctx.command().qualified_name),
+                                &error,
+                            )
+                            .tag("command", &ctx.command().qualified_name)
+                            .tag("user", ctx.author().id)
+                            .backtrace(std::backtrace::Backtrace::capture());
+                            if let Some(guild_id) = ctx.guild_id() {
+                                report = report.tag("guild", guild_id);
+                            }
+                            errors::report(report);
+                            operators::notify(
+                                Severity::Warning,
+                                format!("Command `/{}` failed: {}", ctx.command().qualified_name, error),
+                            );
                             error!(
                                 "Command {} failed for {} in {}: {:?}",
                                 ctx.command().qualified_name,
@@ -130,6 +298,8 @@ async fn main() {
                                 error
                             );
                         }
+                        // Already explained to the user by `command_check`
+                        poise::FrameworkError::CommandCheckFailed { error: None, .. } => {}
                         err => error!("Other framework error: {:?}", err),
                     }
                 })
@@ -142,25 +312,71 @@ async fn main() {
             },
             ..Default::default()
         })
-        .setup(|ctx, _ready, framework| {
+        .setup(move |ctx, _ready, framework| {
             Box::pin(async move {
-                info!("registering commands");
-                poise::builtins::register_globally(ctx, &framework.options().commands).await?;
+                let dbs = setup_dbs;
+                let task_manager = setup_task_manager;
+
+                let commands = &framework.options().commands;
+                if config.discord.dev_guilds.is_empty() {
+                    info!("registering commands globally");
+                    poise::builtins::register_globally(ctx, commands).await?;
+                } else {
+                    for guild_id in &config.discord.dev_guilds {
+                        info!("registering commands in development guild {}", guild_id);
+                        poise::builtins::register_in_guild(
+                            ctx,
+                            commands,
+                            serenity::GuildId::new(*guild_id),
+                        )
+                        .await?;
+                    }
+                }
 
-                let dbs = Arc::new(Databases::default().await?);
-                let task_manager = Arc::new(tasks::TaskManager::new());
                 let event_manager = Arc::new(events::EventManager::new());
-                let master_key = std::env::var("MASTER_KEY").expect("missing MASTER_KEY");
+
+                let metrics = Arc::new(MetricsRegistry::new());
+                metrics.register_counter("commands_total", "Commands completed since startup");
+                metrics.register_counter("command_errors_total", "Commands that failed since startup");
+                metrics.register_labeled_counter(
+                    "command_runs_total",
+                    "Commands completed since startup, by command",
+                    "command",
+                );
+                modules::register_metrics(&metrics, &dbs);
+                task_manager.register_metrics(&metrics);
+
+                let archon = Arc::new(if config.archon.mock {
+                    let stored = dbs.testing.server_ids().await;
+                    ArchonClient::mock(config.archon.mock_fail_every, &stored)
+                } else {
+                    ArchonClient::live(
+                        config.archon.master_key.clone(),
+                        config.archon.base_url.clone(),
+                    )
+                });
 
                 let data = Arc::new(Data {
                     dbs: dbs.clone(),
                     task_manager: task_manager.clone(),
                     event_manager: event_manager.clone(),
-                    config: Config { master_key },
+                    metrics,
+                    archon,
+                    shards: framework.shard_manager().clone(),
+                    config: Arc::new(LiveConfig::new(config)),
                 });
 
                 event_manager.init(&data).await;
                 data.init_tasks(ctx).await;
+                if let (Some(listen), Some(songbird)) =
+                    (data.config.get().server.listen, songbird::get(ctx).await)
+                {
+                    let state = server::State {
+                        data: data.clone(),
+                        songbird,
+                    };
+                    server::start(listen, state);
+                }
 
                 Ok((*data).clone())
             })
@@ -170,10 +386,72 @@ async fn main() {
     let songbird = songbird::Songbird::serenity();
     songbird.set_config(songbird::Config::default().decode_mode(songbird::driver::DecodeMode::Decode));
 
-    let client = serenity::ClientBuilder::new(token, intents)
+    let mut client = serenity::ClientBuilder::new(token, intents)
         .framework(framework)
-        .register_songbird_with(songbird)
-        .await;
+        .register_songbird_with(songbird.clone())
+        .await
+        .expect("failed to create client");
+    operators::start(operator_config, client.http.clone());
+    errors::start(error_config, client.http.clone());
+
+    let shard_manager = client.shard_manager.clone();
+    let shutdown_dbs = dbs.clone();
+    tokio::spawn(async move {
+        wait_for_shutdown_signal().await;
+        info!("shutting down");
+
+        // Waits for task runs still in progress, so their writes make the final save
+        task_manager.shutdown().await;
 
-    client.unwrap().start().await.unwrap();
+        // Leave voice calls so recordings end cleanly instead of being cut off
+        let guild_ids: Vec<_> = songbird.iter().map(|(guild_id, _)| guild_id).collect();
+        for guild_id in guild_ids {
+            if let Err(e) = songbird.remove(guild_id).await {
+                error!("Failed to leave voice call in {}: {:?}", guild_id.0, e);
+            }
+        }
+        if let Err(e) = shutdown_dbs
+            .recording
+            .transaction(|db| {
+                for channel in db.channels.values_mut() {
+                    channel.is_recording = false;
+                }
+                Ok(())
+            })
+            .await
+        {
+            error!("Failed to mark recordings as stopped: {}", e);
+        }
+
+        shard_manager.shutdown_all().await;
+    });
+
+    let started = match shard_count {
+        Some(total) => client.start_shards(total).await,
+        None => client.start_autosharded().await,
+    };
+    if let Err(e) = started {
+        error!("Client error: {:?}", e);
+    }
+    // Saved once the gateway is down so no event handler writes land after it
+    dbs.flush_all().await;
+    info!("stopped");
+}
+
+async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut sigterm = signal(SignalKind::terminate()).expect("failed to listen for SIGTERM");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = sigterm.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
 }
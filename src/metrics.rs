@@ -0,0 +1,237 @@
+use dashmap::DashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+type GaugeFuture = Pin<Box<dyn Future<Output = Result<f64, String>> + Send>>;
+type GaugeFn = Arc<dyn Fn(u64) -> GaugeFuture + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Gauge,
+    Counter,
+}
+
+struct Gauge {
+    description: String,
+    read: GaugeFn,
+    /// Ignores the guild id, so it can be exported for the whole bot.
+    global: bool,
+}
+
+struct Counter {
+    description: String,
+    value: AtomicU64,
+}
+
+/// A counter split by the value of one label, e.g. runs per command.
+struct LabeledCounter {
+    description: String,
+    label: String,
+    values: DashMap<String, AtomicU64>,
+}
+
+/// Crate-wide registry of bot-side metrics.
+///
+/// Gauges are computed on demand for a guild (global gauges ignore the guild id),
+/// counters are plain global totals that modules bump as things happen.
+#[derive(Default)]
+pub struct MetricsRegistry {
+    gauges: DashMap<String, Gauge>,
+    counters: DashMap<String, Counter>,
+    labeled: DashMap<String, LabeledCounter>,
+}
+
+impl fmt::Debug for MetricsRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetricsRegistry")
+            .field("gauges", &self.gauges.len())
+            .field("counters", &self.counters.len())
+            .finish()
+    }
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a gauge read for one guild at a time.
+    pub fn register_gauge<F, Fut>(&self, name: &str, description: &str, read: F)
+    where
+        F: Fn(u64) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<f64, String>> + Send + 'static,
+    {
+        self.insert_gauge(name, description, false, read);
+    }
+
+    /// Registers a gauge that ignores the guild id and is also exported on `/metrics`.
+    pub fn register_global_gauge<F, Fut>(&self, name: &str, description: &str, read: F)
+    where
+        F: Fn(u64) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<f64, String>> + Send + 'static,
+    {
+        self.insert_gauge(name, description, true, read);
+    }
+
+    fn insert_gauge<F, Fut>(&self, name: &str, description: &str, global: bool, read: F)
+    where
+        F: Fn(u64) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<f64, String>> + Send + 'static,
+    {
+        self.gauges.insert(
+            name.to_string(),
+            Gauge {
+                description: description.to_string(),
+                read: Arc::new(move |guild_id| Box::pin(read(guild_id))),
+                global,
+            },
+        );
+    }
+
+    pub fn register_counter(&self, name: &str, description: &str) {
+        self.counters
+            .entry(name.to_string())
+            .or_insert_with(|| Counter {
+                description: description.to_string(),
+                value: AtomicU64::new(0),
+            });
+    }
+
+    /// Bumps a counter, registering it on first use if needed.
+    pub fn increment(&self, name: &str, by: u64) {
+        if let Some(counter) = self.counters.get(name) {
+            counter.value.fetch_add(by, Ordering::Relaxed);
+            return;
+        }
+
+        self.register_counter(name, "");
+        if let Some(counter) = self.counters.get(name) {
+            counter.value.fetch_add(by, Ordering::Relaxed);
+        }
+    }
+
+    pub fn register_labeled_counter(&self, name: &str, description: &str, label: &str) {
+        self.labeled
+            .entry(name.to_string())
+            .or_insert_with(|| LabeledCounter {
+                description: description.to_string(),
+                label: label.to_string(),
+                values: DashMap::new(),
+            });
+    }
+
+    /// Bumps the series of a labeled counter for `value`. Unregistered counters are ignored.
+    pub fn increment_labeled(&self, name: &str, value: &str, by: u64) {
+        if let Some(counter) = self.labeled.get(name) {
+            counter
+                .values
+                .entry(value.to_string())
+                .or_default()
+                .fetch_add(by, Ordering::Relaxed);
+        }
+    }
+
+    /// Whether a counter or gauge is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.counters.contains_key(name) || self.gauges.contains_key(name)
+    }
+
+    pub async fn read(&self, name: &str, guild_id: u64) -> Result<f64, String> {
+        if let Some(counter) = self.counters.get(name) {
+            return Ok(counter.value.load(Ordering::Relaxed) as f64);
+        }
+
+        // Clone the callback out so the map isn't locked while the gauge runs
+        let read = self
+            .gauges
+            .get(name)
+            .map(|gauge| gauge.read.clone())
+            .ok_or_else(|| format!("Unknown metric `{}`", name))?;
+        read(guild_id).await
+    }
+
+    /// All registered metrics as `(name, description, kind)`, sorted by name.
+    pub fn list(&self) -> Vec<(String, String, MetricKind)> {
+        let mut metrics: Vec<_> = self
+            .gauges
+            .iter()
+            .map(|g| (g.key().clone(), g.description.clone(), MetricKind::Gauge))
+            .chain(
+                self.counters
+                    .iter()
+                    .map(|c| (c.key().clone(), c.description.clone(), MetricKind::Counter)),
+            )
+            .collect();
+        metrics.sort_by(|a, b| a.0.cmp(&b.0));
+        metrics
+    }
+
+    /// Counters, labeled counters and global gauges in the Prometheus text format, with
+    /// `prefix` in front of every name. Gauges that fail to read are left out.
+    pub async fn render(&self, prefix: &str) -> String {
+        let mut out = String::new();
+        for (name, description, kind) in self.list() {
+            let value = match kind {
+                MetricKind::Counter => self.read(&name, 0).await,
+                MetricKind::Gauge => {
+                    let global = self.gauges.get(&name).map(|gauge| gauge.global);
+                    if global != Some(true) {
+                        continue;
+                    }
+                    self.read(&name, 0).await
+                }
+            };
+            let Ok(value) = value else {
+                continue;
+            };
+            let kind = match kind {
+                MetricKind::Counter => "counter",
+                MetricKind::Gauge => "gauge",
+            };
+            write_header(&mut out, &format!("{prefix}{name}"), &description, kind);
+            out.push_str(&format!("{prefix}{name} {value}\n"));
+        }
+
+        let mut labeled: Vec<_> = self.labeled.iter().collect();
+        labeled.sort_by(|a, b| a.key().cmp(b.key()));
+        for counter in labeled {
+            let name = format!("{prefix}{}", counter.key());
+            write_header(&mut out, &name, &counter.description, "counter");
+            let mut values: Vec<_> = counter
+                .values
+                .iter()
+                .map(|entry| (entry.key().clone(), entry.value().load(Ordering::Relaxed)))
+                .collect();
+            values.sort();
+            for (value, count) in values {
+                out.push_str(&format!(
+                    "{}{{{}=\"{}\"}} {}\n",
+                    name,
+                    counter.label,
+                    escape_label(&value),
+                    count
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// Writes the `# HELP` and `# TYPE` lines that start a metric family.
+pub fn write_header(out: &mut String, name: &str, description: &str, kind: &str) {
+    if !description.is_empty() {
+        out.push_str(&format!("# HELP {} {}\n", name, description.replace('\n', " ")));
+    }
+    out.push_str(&format!("# TYPE {} {}\n", name, kind));
+}
+
+/// Escapes a label value for the Prometheus text format.
+pub fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,113 @@
+use crate::modules::system::api_tokens::ApiScope;
+use crate::utils::validate;
+use crate::{Context, Error};
+use poise::{command, ChoiceParameter};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// Issue and revoke this server's HTTP API tokens
+#[command(
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    rename = "apitoken",
+    subcommands("create", "revoke", "list")
+)]
+pub async fn api_token(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Issue an API token for this server; it's only shown once
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD", ephemeral)]
+pub async fn create(
+    ctx: Context<'_>,
+    #[description = "What the token may do"] scope: ApiScope,
+    #[description = "What the token is for, e.g. the tool using it"]
+    #[max_length = 60]
+    label: String,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    match ctx
+        .data()
+        .dbs
+        .system
+        .create_api_token(guild_id, scope, label, ctx.author().id.get(), now)
+        .await
+    {
+        Ok((issued, token)) => {
+            info!(
+                "API token {} ({}) issued for guild {} by {}",
+                issued.id,
+                issued.scope.name(),
+                guild_id,
+                ctx.author().tag()
+            );
+            ctx.say(format!(
+                "🔑 **{}** token `{}` created for *{}*:\n```\n{}\n```\
+                Copy it now, it won't be shown again. Revoke it with `/settings apitoken revoke {}`.",
+                issued.scope.name(),
+                issued.id,
+                issued.label,
+                token,
+                issued.id
+            ))
+            .await?;
+        }
+        Err(e) => {
+            ctx.say(format!("❌ {}", e)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Revoke one of this server's API tokens
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD", ephemeral)]
+pub async fn revoke(
+    ctx: Context<'_>,
+    #[description = "Token ID, the part after `pyro_`"] id: String,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    match ctx.data().dbs.system.revoke_api_token(guild_id, &id).await? {
+        Some(revoked) => {
+            info!("API token {} revoked for guild {}", revoked.id, guild_id);
+            ctx.say(format!("🗑️ Revoked token `{}` (*{}*).", revoked.id, revoked.label))
+                .await?;
+        }
+        None => {
+            ctx.say(format!("❌ This server has no token `{}`.", id.trim())).await?;
+        }
+    }
+    Ok(())
+}
+
+/// List this server's API tokens
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD", ephemeral)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let tokens = ctx.data().dbs.system.api_tokens(guild_id).await;
+    if tokens.is_empty() {
+        ctx.say("⚪ This server has no API tokens.").await?;
+        return Ok(());
+    }
+
+    let lines: Vec<_> = tokens
+        .iter()
+        .map(|token| {
+            format!(
+                "`{}` **{}** — *{}*, by <@{}> <t:{}:R>",
+                token.id,
+                token.scope.name(),
+                token.label,
+                token.created_by,
+                token.created_at
+            )
+        })
+        .collect();
+    ctx.say(format!("🔑 **API tokens**\n{}", lines.join("\n"))).await?;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use crate::{config::data_path, databases::Databases, tasks::Task};
+use async_trait::async_trait;
+use poise::serenity_prelude::Context;
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+use tracing::{error, info};
+
+/// Where snapshots are written, one timestamped directory each.
+pub fn backups_dir() -> PathBuf {
+    data_path("backups")
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub dir: PathBuf,
+    pub files: usize,
+    pub pruned: usize,
+}
+
+/// Snapshots every database into `<data dir>/backups/<timestamp>/`, then deletes all but the
+/// newest `keep` snapshots.
+pub async fn take_snapshot(dbs: &Databases, keep: usize) -> Result<Snapshot, String> {
+    let dir = backups_dir().join(chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string());
+    let files = dbs.snapshot(&dir).await.map_err(|e| e.to_string())?;
+    let pruned = prune(keep).await.map_err(|e| e.to_string())?;
+
+    Ok(Snapshot {
+        dir,
+        files: files.len(),
+        pruned,
+    })
+}
+
+/// Removes the oldest snapshot directories beyond `keep`. Names sort chronologically.
+async fn prune(keep: usize) -> std::io::Result<usize> {
+    let mut entries = tokio::fs::read_dir(backups_dir()).await?;
+    let mut snapshots = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_dir() {
+            snapshots.push(entry.path());
+        }
+    }
+    snapshots.sort();
+
+    let excess = snapshots.len().saturating_sub(keep.max(1));
+    for dir in &snapshots[..excess] {
+        tokio::fs::remove_dir_all(dir).await?;
+    }
+    Ok(excess)
+}
+
+#[derive(Clone, Debug)]
+pub struct BackupTask {
+    dbs: Arc<Databases>,
+    interval: Duration,
+    keep: usize,
+}
+
+impl BackupTask {
+    pub fn new(dbs: Arc<Databases>, interval: Duration, keep: usize) -> Self {
+        Self {
+            dbs,
+            interval,
+            keep,
+        }
+    }
+}
+
+#[async_trait]
+impl Task for BackupTask {
+    fn name(&self) -> &str {
+        "Backup"
+    }
+
+    fn schedule(&self) -> Option<Duration> {
+        Some(self.interval)
+    }
+
+    async fn execute(
+        &mut self,
+        _ctx: &Context,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match take_snapshot(&self.dbs, self.keep).await {
+            Ok(snapshot) => {
+                info!(
+                    "Backed up {} database file(s) to {} ({} old snapshot(s) pruned)",
+                    snapshot.files,
+                    snapshot.dir.display(),
+                    snapshot.pruned
+                );
+                Ok(())
+            }
+            Err(e) => {
+                error!("Database backup failed: {}", e);
+                Err(e.into())
+            }
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Task> {
+        Box::new(self.clone())
+    }
+}
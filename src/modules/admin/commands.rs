@@ -0,0 +1,457 @@
+use crate::modules::preferences::notify::send_dm;
+use super::backup::take_snapshot;
+use super::export::{export_guild, import_guild, GuildExport};
+use crate::health::{overall, HealthState};
+use crate::tasks::shard_of;
+use crate::utils::reply::truncate_message;
+use crate::utils::validate;
+use crate::{Context, Error};
+use poise::serenity_prelude::{
+    Attachment, ButtonStyle, Command, ConnectionStage, CreateActionRow, CreateAttachment,
+    CreateButton, CreateMessage, GuildId,
+};
+use poise::{command, CreateReply};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+/// Register all commands globally and clear them from the development guilds
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn promote_commands(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    let commands = &ctx.framework().options().commands;
+    poise::builtins::register_globally(ctx.http(), commands).await?;
+    info!("Promoted {} commands to global", commands.len());
+
+    // Drop the guild copies so commands don't show up twice in the dev guilds
+    let config = ctx.data().config.get();
+    let dev_guilds = &config.discord.dev_guilds;
+    for guild_id in dev_guilds {
+        if let Err(e) = GuildId::new(*guild_id)
+            .set_commands(ctx.http(), Vec::new())
+            .await
+        {
+            warn!("Failed to clear commands in dev guild {}: {}", guild_id, e);
+        }
+    }
+
+    let global = Command::get_global_commands(ctx.http()).await?;
+    ctx.say(format!(
+        "🚀 Registered {} commands globally. It can take up to an hour for Discord to show them everywhere.{}",
+        global.len(),
+        if dev_guilds.is_empty() {
+            String::new()
+        } else {
+            format!("\nCleared guild commands from {} development guild(s).", dev_guilds.len())
+        }
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Delay between broadcast DMs, well under Discord's DM rate limits.
+const BROADCAST_INTERVAL: Duration = Duration::from_millis(1500);
+
+/// How many DMs to send between progress updates.
+const PROGRESS_EVERY: usize = 10;
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum BroadcastAudience {
+    #[name = "Guild owners"]
+    GuildOwners,
+    #[name = "Members with a role"]
+    RoleMembers,
+}
+
+/// Collects unique recipients across every guild the bot is in.
+async fn broadcast_recipients(
+    ctx: Context<'_>,
+    audience: BroadcastAudience,
+    role_name: Option<&str>,
+) -> Result<Vec<u64>, Error> {
+    let mut recipients = BTreeSet::new();
+
+    for guild_id in ctx.cache().guilds() {
+        let guild = guild_id.to_partial_guild(ctx.http()).await?;
+
+        match audience {
+            BroadcastAudience::GuildOwners => {
+                recipients.insert(guild.owner_id.get());
+            }
+            BroadcastAudience::RoleMembers => {
+                let Some(role_name) = role_name else { continue };
+                let Some(role_id) = guild
+                    .roles
+                    .values()
+                    .find(|role| role.name.eq_ignore_ascii_case(role_name))
+                    .map(|role| role.id)
+                else {
+                    continue;
+                };
+
+                let mut after = None;
+                loop {
+                    let members = guild_id.members(ctx.http(), Some(1000), after).await?;
+                    if members.is_empty() {
+                        break;
+                    }
+                    after = members.last().map(|m| m.user.id);
+
+                    recipients.extend(
+                        members
+                            .iter()
+                            .filter(|m| !m.user.bot && m.roles.contains(&role_id))
+                            .map(|m| m.user.id.get()),
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(recipients.into_iter().collect())
+}
+
+/// DM a maintenance notice to guild owners or role members across all guilds
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn broadcast(
+    ctx: Context<'_>,
+    #[description = "Message to send"] message: String,
+    #[description = "Who should receive it"] audience: BroadcastAudience,
+    #[description = "Role name to match in each guild (for role members)"] role: Option<String>,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    if matches!(audience, BroadcastAudience::RoleMembers) && role.is_none() {
+        ctx.say("❌ Please give a role name to broadcast to role members.")
+            .await?;
+        return Ok(());
+    }
+
+    let recipients = broadcast_recipients(ctx, audience, role.as_deref()).await?;
+    if recipients.is_empty() {
+        ctx.say("📭 Nobody matches that audience.").await?;
+        return Ok(());
+    }
+
+    let preview = format!(
+        "📣 **Broadcast preview**\nSending to **{}** {} (users who turned off DMs are skipped).\n\n>>> {}",
+        recipients.len(),
+        if recipients.len() == 1 { "user" } else { "users" },
+        message
+    );
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new("broadcast_confirm")
+            .style(ButtonStyle::Danger)
+            .label("Send"),
+        CreateButton::new("broadcast_cancel")
+            .style(ButtonStyle::Secondary)
+            .label("Cancel"),
+    ]);
+
+    let handle = ctx
+        .send(CreateReply::default().content(preview).components(vec![buttons]))
+        .await?;
+
+    let interaction = handle
+        .message()
+        .await?
+        .await_component_interaction(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .timeout(Duration::from_secs(60))
+        .await;
+
+    let confirmed = match &interaction {
+        Some(interaction) => {
+            interaction.defer(ctx.http()).await?;
+            interaction.data.custom_id == "broadcast_confirm"
+        }
+        None => false,
+    };
+
+    if !confirmed {
+        handle
+            .edit(ctx, CreateReply::default().content("❌ Broadcast cancelled.").components(vec![]))
+            .await?;
+        return Ok(());
+    }
+
+    info!(
+        "Broadcast by {} to {} users started",
+        ctx.author().tag(),
+        recipients.len()
+    );
+
+    let preferences = &ctx.data().dbs.preferences;
+    let total = recipients.len();
+    let (mut sent, mut skipped, mut failed) = (0, 0, 0);
+
+    for (i, user_id) in recipients.into_iter().enumerate() {
+        if !preferences.allows_dms(user_id).await {
+            skipped += 1;
+        } else {
+            let dm = CreateMessage::new().content(format!("📣 **Message from the bot team**\n\n{}", message));
+            if send_dm(ctx.serenity_context(), preferences, user_id, dm).await {
+                sent += 1;
+            } else {
+                failed += 1;
+            }
+            sleep(BROADCAST_INTERVAL).await;
+        }
+
+        if (i + 1) % PROGRESS_EVERY == 0 && i + 1 < total {
+            let _ = handle
+                .edit(
+                    ctx,
+                    CreateReply::default().content(format!(
+                        "📨 Broadcasting... {}/{} processed ({} sent, {} opted out, {} failed)",
+                        i + 1,
+                        total,
+                        sent,
+                        skipped,
+                        failed
+                    )),
+                )
+                .await;
+        }
+    }
+
+    info!(
+        "Broadcast finished: {} sent, {} opted out, {} failed",
+        sent, skipped, failed
+    );
+
+    // The interaction token may have expired on very long broadcasts, so don't fail on this
+    let _ = handle
+        .edit(
+            ctx,
+            CreateReply::default().content(format!(
+                "✅ Broadcast finished: {} sent, {} opted out, {} failed.",
+                sent, skipped, failed
+            )),
+        )
+        .await;
+    Ok(())
+}
+
+/// Database backups
+#[command(slash_command, owners_only, subcommands("backup_now"))]
+pub async fn backup(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Snapshot every database right now
+#[command(slash_command, owners_only, ephemeral, rename = "now")]
+pub async fn backup_now(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    let keep = ctx.data().config.get().tasks.backup_keep;
+    match take_snapshot(&ctx.data().dbs, keep).await {
+        Ok(snapshot) => {
+            ctx.say(format!(
+                "💾 Backed up {} database file(s) to `{}`.{}",
+                snapshot.files,
+                snapshot.dir.display(),
+                if snapshot.pruned > 0 {
+                    format!("\nPruned {} old snapshot(s), keeping the newest {}.", snapshot.pruned, keep)
+                } else {
+                    String::new()
+                }
+            ))
+            .await?;
+        }
+        Err(e) => {
+            ctx.say(format!("❌ Backup failed: {}", e)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Show the health of every task and event handler
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn health(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let mut components = data.task_manager.health();
+    components.extend(data.event_manager.health().await);
+
+    let state = overall(&components);
+    let mut lines = vec![format!("{} **Overall: {}**", state.emoji(), state)];
+    for component in &components {
+        let mut line = format!(
+            "{} {} `{}`",
+            component.health.state.emoji(),
+            component.kind,
+            component.name
+        );
+        if component.health.state != HealthState::Ok && !component.health.message.is_empty() {
+            line.push_str(&format!(" — {}", component.health.message));
+        }
+        lines.push(line);
+    }
+
+    let content = truncate_message(lines.join("\n"));
+
+    ctx.say(content).await?;
+    Ok(())
+}
+
+/// Show each gateway shard's connection, latency and guild count
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn shards(ctx: Context<'_>) -> Result<(), Error> {
+    let total = ctx.cache().shard_count();
+    let mut guilds: BTreeMap<u32, usize> = BTreeMap::new();
+    for guild_id in ctx.cache().guilds() {
+        *guilds.entry(shard_of(guild_id.get(), total)).or_default() += 1;
+    }
+
+    let runners = ctx.data().shards.runners.lock().await;
+    let mut shards: Vec<_> = runners.iter().collect();
+    shards.sort_by_key(|(id, _)| id.0);
+
+    let mut lines = vec![format!(
+        "🧩 **{} of {} shards in this process**",
+        shards.len(),
+        total
+    )];
+    for (id, runner) in shards {
+        let emoji = if runner.stage == ConnectionStage::Connected { "🟢" } else { "🟡" };
+        let latency = runner
+            .latency
+            .map_or_else(|| "no heartbeat yet".to_string(), |l| format!("{}ms", l.as_millis()));
+        lines.push(format!(
+            "{} Shard {} — {} · {} · {} guilds{}",
+            emoji,
+            id.0,
+            runner.stage,
+            latency,
+            guilds.get(&id.0).copied().unwrap_or_default(),
+            if id.0 == ctx.serenity_context().shard_id.0 { " (this one)" } else { "" }
+        ));
+    }
+    drop(runners);
+
+    let content = truncate_message(lines.join("\n"));
+
+    ctx.say(content).await?;
+    Ok(())
+}
+
+/// Download this server's data from every module as JSON
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD", ephemeral)]
+pub async fn export(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?;
+    let members: HashSet<u64> = ctx
+        .guild()
+        .map(|guild| guild.members.keys().map(|id| id.get()).collect())
+        .unwrap_or_default();
+
+    let export = export_guild(&ctx.data().dbs, guild_id.get(), &members).await;
+    let summary = export.summary();
+    let json = serde_json::to_vec_pretty(&export)?;
+
+    info!("Guild {} exported by {}", guild_id, ctx.author().tag());
+    ctx.send(
+        CreateReply::default()
+            .content(if summary.is_empty() {
+                "📦 This server has no stored data; the export is empty.".to_string()
+            } else {
+                format!("📦 Exported: {}.", summary.join(", "))
+            })
+            .attachment(CreateAttachment::bytes(json, format!("guild-{}.json", guild_id))),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Replace this server's data with a file from /settings export
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD", ephemeral)]
+pub async fn import(
+    ctx: Context<'_>,
+    #[description = "JSON file from /settings export"] file: Attachment,
+    #[description = "Import even though the file was exported from another server"]
+    allow_other_guild: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    ctx.defer_ephemeral().await?;
+
+    let export = match GuildExport::from_json(&file.download().await?) {
+        Ok(export) => export,
+        Err(e) => {
+            ctx.say(format!("❌ That isn't a valid export file: {}", e)).await?;
+            return Ok(());
+        }
+    };
+
+    if export.guild_id != guild_id && !allow_other_guild.unwrap_or(false) {
+        ctx.say(format!(
+            "❌ This file was exported from server `{}`. Channel and role IDs won't match here; \
+            set `allow_other_guild` to import it anyway.",
+            export.guild_id
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let summary = export.summary();
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new("import_confirm")
+            .style(ButtonStyle::Danger)
+            .label("Replace data"),
+        CreateButton::new("import_cancel")
+            .style(ButtonStyle::Secondary)
+            .label("Cancel"),
+    ]);
+    let handle = ctx
+        .send(
+            CreateReply::default()
+                .content(format!(
+                    "⚠️ This replaces **all** of this server's data with the export from {}:\n{}",
+                    export.exported_at.format("%Y-%m-%d %H:%M UTC"),
+                    if summary.is_empty() {
+                        "• nothing (all data will be cleared)".to_string()
+                    } else {
+                        summary.iter().map(|line| format!("• {}", line)).collect::<Vec<_>>().join("\n")
+                    }
+                ))
+                .components(vec![buttons]),
+        )
+        .await?;
+
+    let interaction = handle
+        .message()
+        .await?
+        .await_component_interaction(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .timeout(Duration::from_secs(60))
+        .await;
+
+    let confirmed = match &interaction {
+        Some(interaction) => {
+            interaction.defer(ctx.http()).await?;
+            interaction.data.custom_id == "import_confirm"
+        }
+        None => false,
+    };
+
+    if !confirmed {
+        handle
+            .edit(ctx, CreateReply::default().content("❌ Import cancelled.").components(vec![]))
+            .await?;
+        return Ok(());
+    }
+
+    let content = match import_guild(&ctx.data().dbs, guild_id, export).await {
+        Ok(()) => {
+            info!("Guild {} imported by {}", guild_id, ctx.author().tag());
+            // Restart per-guild tasks to pick up the imported state
+            ctx.data().task_manager.sync_guild(guild_id).await;
+            "✅ Import complete.".to_string()
+        }
+        Err(e) => format!("❌ Import failed: {}", e),
+    };
+    handle
+        .edit(ctx, CreateReply::default().content(content).components(vec![]))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,291 @@
+use crate::{
+    databases::Databases,
+    modules::{
+        cooldowns::database::Cooldown,
+        lorax::database::{LoraxEvent, LoraxSettings, ScheduledLorax, MAX_SCHEDULES},
+        recording::database::RecordingChannel,
+        stats::{
+            computed::MAX_NAMED_QUERIES,
+            database::{GuildSettings, StatBar},
+        },
+        system::database::GuildConfig,
+        toggles::database::Module,
+    },
+    utils::json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Bumped whenever the export layout changes incompatibly.
+pub const EXPORT_FORMAT: u32 = 1;
+
+/// A guild's configuration in every module, with its running and scheduled Lorax events,
+/// as written by `/settings export`. Records of past activity and API tokens stay behind.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildExport {
+    pub format: u32,
+    pub guild_id: u64,
+    pub exported_at: DateTime<Utc>,
+    pub lorax_settings: Option<LoraxSettings>,
+    pub lorax_event: Option<LoraxEvent>,
+    #[serde(default)]
+    pub lorax_schedules: Vec<ScheduledLorax>,
+    pub stats_settings: Option<GuildSettings>,
+    #[serde(default)]
+    pub stat_bars: HashMap<u64, StatBar>,
+    #[serde(default)]
+    pub named_queries: BTreeMap<String, String>,
+    pub recording: Option<RecordingChannel>,
+    pub system: Option<GuildConfig>,
+    #[serde(default)]
+    pub disabled_modules: HashSet<Module>,
+    /// Cooldowns set with `/settings cooldown`, by command path.
+    #[serde(default)]
+    pub cooldowns: HashMap<String, Cooldown>,
+    pub audit_channel: Option<u64>,
+    /// Testing server limits of the guild's members. Limits are per user, so only members
+    /// known at export time are included.
+    #[serde(default)]
+    pub testing_limits: HashMap<u64, usize>,
+}
+
+impl GuildExport {
+    /// Reads an export file, checking each section's values before anything uses them.
+    /// Errors give the path to the offending field, e.g. `lorax_settings.max_submissions`.
+    pub fn from_json(bytes: &[u8]) -> Result<Self, String> {
+        let export: Self = json::parse(bytes)?;
+        if let Some(settings) = &export.lorax_settings {
+            settings.check().map_err(|e| format!("lorax_settings.{}", e))?;
+        }
+        if let Some(event) = &export.lorax_event {
+            event.settings.check().map_err(|e| format!("lorax_event.settings.{}", e))?;
+        }
+        if export.lorax_schedules.len() > MAX_SCHEDULES {
+            return Err(format!("lorax_schedules: at most {} are allowed", MAX_SCHEDULES));
+        }
+        if let Some(settings) = &export.stats_settings {
+            settings.check().map_err(|e| format!("stats_settings.{}", e))?;
+        }
+        if export.named_queries.len() > MAX_NAMED_QUERIES {
+            return Err(format!("named_queries: at most {} are allowed", MAX_NAMED_QUERIES));
+        }
+        Ok(export)
+    }
+
+    /// One line per section that has data, for confirmation messages.
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.lorax_settings.is_some() {
+            lines.push("Lorax settings".to_string());
+        }
+        if let Some(event) = &self.lorax_event {
+            lines.push(format!("Lorax event ({} submissions)", event.submission_count()));
+        }
+        if !self.lorax_schedules.is_empty() {
+            lines.push(format!("{} scheduled Lorax event(s)", self.lorax_schedules.len()));
+        }
+        if self.stats_settings.is_some() {
+            lines.push("Stats settings".to_string());
+        }
+        if !self.stat_bars.is_empty() {
+            lines.push(format!("{} stat bar(s)", self.stat_bars.len()));
+        }
+        if !self.named_queries.is_empty() {
+            lines.push(format!("{} named query(s)", self.named_queries.len()));
+        }
+        if self.recording.is_some() {
+            lines.push("Recording configuration".to_string());
+        }
+        if self.system.is_some() {
+            lines.push("Server settings".to_string());
+        }
+        if !self.disabled_modules.is_empty() {
+            lines.push(format!("{} disabled module(s)", self.disabled_modules.len()));
+        }
+        if !self.cooldowns.is_empty() {
+            lines.push(format!("{} cooldown override(s)", self.cooldowns.len()));
+        }
+        if self.audit_channel.is_some() {
+            lines.push("Audit log channel".to_string());
+        }
+        if !self.testing_limits.is_empty() {
+            lines.push(format!("{} testing limit(s)", self.testing_limits.len()));
+        }
+        lines
+    }
+}
+
+/// Collects a guild's records from every module database.
+pub async fn export_guild(dbs: &Databases, guild_id: u64, members: &HashSet<u64>) -> GuildExport {
+    let (lorax_settings, lorax_event, lorax_schedules) = dbs
+        .lorax
+        .read(|db| {
+            (
+                db.settings.get(&guild_id).cloned(),
+                db.events.get(&guild_id).cloned(),
+                db.schedules.get(&guild_id).cloned().unwrap_or_default(),
+            )
+        })
+        .await;
+    let (stats_settings, stat_bars, named_queries) = dbs
+        .stats
+        .read(|db| {
+            (
+                db.guild_settings.get(&guild_id).cloned(),
+                db.stat_bars.get(&guild_id).cloned().unwrap_or_default(),
+                db.named_queries.get(&guild_id).cloned().unwrap_or_default(),
+            )
+        })
+        .await;
+
+    GuildExport {
+        format: EXPORT_FORMAT,
+        guild_id,
+        exported_at: Utc::now(),
+        lorax_settings,
+        lorax_event,
+        lorax_schedules,
+        stats_settings,
+        stat_bars,
+        named_queries,
+        recording: dbs.recording.read(|db| db.channels.get(&guild_id).cloned()).await,
+        system: dbs.system.read(|db| db.guilds.get(&guild_id).cloned()).await,
+        disabled_modules: dbs
+            .modules
+            .read(|db| db.disabled.get(&guild_id).cloned().unwrap_or_default())
+            .await,
+        cooldowns: dbs
+            .cooldowns
+            .read(|db| {
+                db.guilds
+                    .get(&guild_id)
+                    .map(|cooldowns| cooldowns.overrides.clone())
+                    .unwrap_or_default()
+            })
+            .await,
+        audit_channel: dbs.audit.read(|db| db.channels.get(&guild_id).copied()).await,
+        testing_limits: dbs
+            .testing
+            .read(|db| {
+                db.user_limits
+                    .iter()
+                    .filter(|(user_id, _)| members.contains(user_id))
+                    .map(|(user_id, limit)| (*user_id, *limit))
+                    .collect()
+            })
+            .await,
+    }
+}
+
+/// Replaces the guild's records with those in `export`. Sections missing from the export
+/// are cleared so the result matches the source instance.
+pub async fn import_guild(dbs: &Databases, guild_id: u64, export: GuildExport) -> Result<(), String> {
+    if export.format > EXPORT_FORMAT {
+        return Err(format!(
+            "This export was made by a newer version of the bot (format {})",
+            export.format
+        ));
+    }
+
+    dbs.lorax
+        .transaction(|db| {
+            set_or_remove(&mut db.settings, guild_id, export.lorax_settings);
+            set_or_remove(&mut db.events, guild_id, export.lorax_event);
+            set_or_remove(
+                &mut db.schedules,
+                guild_id,
+                Some(export.lorax_schedules).filter(|schedules| !schedules.is_empty()),
+            );
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    dbs.stats
+        .transaction(|db| {
+            set_or_remove(&mut db.guild_settings, guild_id, export.stats_settings);
+            set_or_remove(
+                &mut db.stat_bars,
+                guild_id,
+                Some(export.stat_bars).filter(|bars| !bars.is_empty()),
+            );
+            set_or_remove(
+                &mut db.named_queries,
+                guild_id,
+                Some(export.named_queries).filter(|queries| !queries.is_empty()),
+            );
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    dbs.recording
+        .transaction(|db| {
+            let recording = export.recording.map(|mut channel| {
+                channel.guild_id = guild_id;
+                channel.is_recording = false;
+                channel
+            });
+            set_or_remove(&mut db.channels, guild_id, recording);
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    dbs.system
+        .transaction(|db| {
+            set_or_remove(&mut db.guilds, guild_id, export.system);
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    dbs.modules
+        .transaction(|db| {
+            set_or_remove(
+                &mut db.disabled,
+                guild_id,
+                Some(export.disabled_modules).filter(|modules| !modules.is_empty()),
+            );
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Only the overrides move; when commands were last used stays with this instance
+    dbs.cooldowns
+        .transaction(|db| {
+            db.guilds.entry(guild_id).or_default().overrides = export.cooldowns;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    dbs.audit
+        .transaction(|db| {
+            set_or_remove(&mut db.channels, guild_id, export.audit_channel);
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !export.testing_limits.is_empty() {
+        dbs.testing
+            .transaction(|db| {
+                db.user_limits.extend(export.testing_limits);
+                Ok(())
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn set_or_remove<V>(map: &mut HashMap<u64, V>, guild_id: u64, value: Option<V>) {
+    match value {
+        Some(value) => map.insert(guild_id, value),
+        None => map.remove(&guild_id),
+    };
+}
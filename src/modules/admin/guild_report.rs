@@ -0,0 +1,98 @@
+//! `/admin guildreport`: one guild's usage across modules, for support and capacity planning.
+
+use crate::modules::recording::database::MAX_SESSIONS;
+use crate::modules::toggles::database::Module;
+use crate::utils::duration::format_duration;
+use crate::{Context, Error};
+use poise::{command, serenity_prelude::GuildId};
+
+/// Summarize a guild's usage across modules
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn guildreport(
+    ctx: Context<'_>,
+    #[description = "ID of the guild to report on"] guild_id: String,
+) -> Result<(), Error> {
+    let Ok(guild_id) = guild_id.trim().parse::<u64>() else {
+        ctx.say("❌ That isn't a guild ID.").await?;
+        return Ok(());
+    };
+    let dbs = &ctx.data().dbs;
+
+    let guild = match ctx.cache().guild(GuildId::new(guild_id)) {
+        Some(guild) => format!("**{}** (`{}`, {} members)", guild.name, guild_id, guild.member_count),
+        None => format!("`{}` (not in this process's cache)", guild_id),
+    };
+
+    let mut disabled = Vec::new();
+    for module in Module::ALL {
+        if !dbs.modules.is_enabled(guild_id, module).await {
+            disabled.push(format!("{:?}", module));
+        }
+    }
+    let modules = if disabled.is_empty() {
+        "all enabled".to_string()
+    } else {
+        format!("disabled: {}", disabled.join(", "))
+    };
+
+    let bars = dbs.stats.get_stat_bars(guild_id).await.unwrap_or_default();
+    let failing = bars.iter().filter(|bar| bar.error_count > 0).count();
+    let stats = format!(
+        "{} stat bars ({} failing), {} named queries",
+        bars.len(),
+        failing,
+        dbs.stats.named_queries(guild_id).await.len()
+    );
+
+    let lorax = match dbs.lorax.get_event(guild_id).await {
+        Some(event) => format!(
+            "event running ({:?}), started <t:{}:R>",
+            event.stage, event.start_time
+        ),
+        None => match dbs.lorax.history(guild_id).await.last() {
+            Some(last) if last.winners.is_empty() => {
+                format!("last event ended <t:{}:R>, won by nobody", last.ended_at)
+            }
+            Some(last) => {
+                let trees: Vec<&str> = last.winners.iter().map(|(tree, _)| tree.as_str()).collect();
+                format!("last event ended <t:{}:R>, won by {}", last.ended_at, trees.join(", "))
+            }
+            None => "no events yet".to_string(),
+        },
+    };
+
+    let month = chrono::Utc::now().format("%Y-%m").to_string();
+    let testing = format!(
+        "{} servers created this month",
+        dbs.testing.creations_in(guild_id, &month).await
+    );
+
+    let sessions = dbs.recording.recent_sessions(guild_id, MAX_SESSIONS).await;
+    let recorded: i64 = sessions.iter().map(|session| session.duration().num_seconds()).sum();
+    let recording = match sessions.first() {
+        Some(latest) => format!(
+            "{} recordings stored ({} in total), latest <t:{}:R>",
+            sessions.len(),
+            format_duration(recorded.max(0) as u64),
+            latest.started_at.timestamp()
+        ),
+        None => "no recordings stored".to_string(),
+    };
+
+    ctx.say(format!(
+        "📋 **Guild Report** for {}\n\
+        🧩 **Modules:** {}\n\
+        📊 **Stats:** {}\n\
+        🌳 **Lorax:** {}\n\
+        🧪 **Testing:** {}\n\
+        🎙️ **Recording:** {}",
+        guild,
+        modules,
+        stats,
+        lorax,
+        testing,
+        recording
+    ))
+    .await?;
+    Ok(())
+}
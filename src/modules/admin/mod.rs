@@ -0,0 +1,17 @@
+pub mod api_tokens;
+pub mod backup;
+pub mod commands;
+pub mod export;
+pub mod guild_report;
+pub mod task_control;
+
+use commands::*;
+use guild_report::guildreport;
+use task_control::{tasks, taskstats};
+use poise::command;
+
+/// 🛠️ Bot operator tools
+#[command(slash_command, subcommands("promote_commands", "broadcast", "backup", "health", "shards", "tasks", "taskstats", "guildreport"), owners_only)]
+pub async fn admin(_ctx: crate::Context<'_>) -> Result<(), crate::Error> {
+    Ok(())
+}
@@ -0,0 +1,242 @@
+//! `/admin tasks`: inspect, pause, resume and trigger background tasks at runtime.
+
+use crate::modules::system::task_runs::TaskRun;
+use crate::tasks::TaskStatus;
+use crate::utils::reply::truncate_message;
+use crate::{Context, Error};
+use poise::{command, serenity_prelude as serenity};
+use std::collections::BTreeSet;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn relative(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    format!("<t:{}:R>", secs)
+}
+
+fn describe(task: &TaskStatus) -> String {
+    let mut line = format!("{} `{}`", task.health.state.emoji(), task.name);
+    line.push_str(&match task.last_run {
+        Some(time) => format!(" — ran {}", relative(time)),
+        None => " — not run yet".to_string(),
+    });
+    if task.paused {
+        line.push_str(", ⏸️ paused");
+    } else if let Some(time) = task.next_run {
+        line.push_str(&format!(", next {}", relative(time)));
+    } else {
+        line.push_str(", running");
+    }
+    if let Some((time, error)) = &task.last_error {
+        line.push_str(&format!("\n  ↳ last error {}: {}", relative(*time), error));
+    }
+    line
+}
+
+fn duration(duration: Duration) -> String {
+    if duration < Duration::from_secs(1) {
+        format!("{}ms", duration.as_millis())
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
+
+fn describe_stats(task: &TaskStatus) -> String {
+    let stats = &task.stats;
+    let mut line = format!("`{}` — {} runs, {} failed", task.name, stats.runs, stats.failures);
+    if let Some(mean) = stats.mean() {
+        let p95 = match stats.quantile(0.95) {
+            Some(bound) => format!("≤ {}", duration(bound)),
+            None => format!("≤ {}", duration(stats.max)),
+        };
+        line.push_str(&format!(
+            "\n  ↳ avg {} · p95 {} · max {}",
+            duration(mean),
+            p95,
+            duration(stats.max)
+        ));
+        if let Some(last) = stats.last {
+            line.push_str(&format!(" · last {}", duration(last)));
+        }
+    }
+    line
+}
+
+fn describe_run(name: &str, instance: &str, run: &TaskRun) -> String {
+    let mut line = format!(
+        "{} <t:{}:f> · {}",
+        run.outcome.emoji(),
+        run.started_at,
+        duration(Duration::from_millis(run.duration_ms))
+    );
+    if instance != name {
+        line.push_str(&format!(" · `{}`", instance));
+    }
+    if let Some(error) = &run.error {
+        line.push_str(&format!("\n  ↳ {}", error));
+    }
+    line
+}
+
+async fn autocomplete_task<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Iterator<Item = serenity::AutocompleteChoice> {
+    let partial = partial.to_lowercase();
+
+    // Guild task instances are also offered by their shared name, which targets them all
+    let mut names = BTreeSet::new();
+    for task in ctx.data().task_manager.list_tasks() {
+        if let Some((base, _)) = task.name.split_once(" (") {
+            names.insert(base.to_string());
+        }
+        names.insert(task.name);
+    }
+
+    names
+        .into_iter()
+        .filter(|name| name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|name| serenity::AutocompleteChoice::new(name.clone(), name))
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// Inspect and control background tasks
+#[command(
+    slash_command,
+    owners_only,
+    subcommands("list", "pause", "resume", "run", "history")
+)]
+pub async fn tasks(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show when each task last ran, its last error and its next run
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let tasks = ctx.data().task_manager.list_tasks();
+    if tasks.is_empty() {
+        ctx.say("⚪ No tasks are running.").await?;
+        return Ok(());
+    }
+
+    let content = truncate_message(format!(
+        "⚙️ **Tasks**\n{}",
+        tasks.iter().map(describe).collect::<Vec<_>>().join("\n")
+    ));
+
+    ctx.say(content).await?;
+    Ok(())
+}
+
+/// Stop a task's scheduled runs until it's resumed
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn pause(
+    ctx: Context<'_>,
+    #[description = "Task name; a guild task's name pauses every guild"]
+    #[autocomplete = "autocomplete_task"]
+    name: String,
+) -> Result<(), Error> {
+    match ctx.data().task_manager.pause(&name) {
+        Ok(count) => {
+            ctx.say(format!("⏸️ Paused `{}` ({} running).", name, count))
+                .await?
+        }
+        Err(e) => ctx.say(format!("❌ {}.", e)).await?,
+    };
+    Ok(())
+}
+
+/// Resume a paused task's schedule
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn resume(
+    ctx: Context<'_>,
+    #[description = "Task name"]
+    #[autocomplete = "autocomplete_task"]
+    name: String,
+) -> Result<(), Error> {
+    match ctx.data().task_manager.resume(&name) {
+        Ok(count) => {
+            ctx.say(format!("▶️ Resumed `{}` ({} running).", name, count))
+                .await?
+        }
+        Err(e) => ctx.say(format!("❌ {}.", e)).await?,
+    };
+    Ok(())
+}
+
+/// Run a task right away, even if it's paused
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn run(
+    ctx: Context<'_>,
+    #[description = "Task name"]
+    #[autocomplete = "autocomplete_task"]
+    name: String,
+) -> Result<(), Error> {
+    match ctx.data().task_manager.trigger_now(&name) {
+        Ok(count) => {
+            ctx.say(format!(
+                "🚀 Triggered `{}` ({} running). Check `/admin tasks list` for the result.",
+                name, count
+            ))
+            .await?
+        }
+        Err(e) => ctx.say(format!("❌ {}.", e)).await?,
+    };
+    Ok(())
+}
+
+/// Show how often each task has run, how often it failed and how long it takes
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn taskstats(ctx: Context<'_>) -> Result<(), Error> {
+    let mut tasks = ctx.data().task_manager.list_tasks();
+    if tasks.is_empty() {
+        ctx.say("⚪ No tasks are running.").await?;
+        return Ok(());
+    }
+
+    // Slowest first, since that's usually what you're looking for
+    tasks.sort_by(|a, b| b.stats.mean().cmp(&a.stats.mean()));
+    let content = truncate_message(format!(
+        "⏱️ **Task timings** since startup\n{}",
+        tasks.iter().map(describe_stats).collect::<Vec<_>>().join("\n")
+    ));
+
+    ctx.say(content).await?;
+    Ok(())
+}
+
+/// Show a task's recent runs, including those from before the last restart
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn history(
+    ctx: Context<'_>,
+    #[description = "Task name; a guild task's name shows every guild"]
+    #[autocomplete = "autocomplete_task"]
+    name: String,
+) -> Result<(), Error> {
+    let runs = ctx.data().dbs.system.task_runs(&name).await;
+    if runs.is_empty() {
+        ctx.say(format!("⚪ No runs of `{}` have been recorded.", name))
+            .await?;
+        return Ok(());
+    }
+
+    let total: u64 = runs.iter().map(|(_, run)| run.duration_ms).sum();
+    let slowest = runs.iter().map(|(_, run)| run.duration_ms).max().unwrap_or_default();
+    let failed = runs.iter().filter(|(_, run)| run.error.is_some()).count();
+    let content = truncate_message(format!(
+        "📜 **`{}` history** — {} runs, {} failed · avg {} · max {}\n{}",
+        name,
+        runs.len(),
+        failed,
+        duration(Duration::from_millis(total / runs.len() as u64)),
+        duration(Duration::from_millis(slowest)),
+        runs.iter()
+            .map(|(instance, run)| describe_run(&name, instance, run))
+            .collect::<Vec<_>>()
+            .join("\n")
+    ));
+
+    ctx.say(content).await?;
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use super::log::describe;
+use crate::utils::reply::{say, truncate_message};
+use crate::utils::validate;
+use crate::{Context, Error};
+use poise::{command, serenity_prelude as serenity};
+
+/// Show the latest privileged commands used in this server
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD", ephemeral)]
+pub async fn recent(
+    ctx: Context<'_>,
+    #[description = "Only show commands used by this member"] user: Option<serenity::User>,
+    #[description = "How many entries to show (default 15)"]
+    #[min = 1]
+    #[max = 50]
+    limit: Option<usize>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let entries = ctx
+        .data()
+        .dbs
+        .audit
+        .recent(guild_id, user.as_ref().map(|u| u.id.get()), limit.unwrap_or(15))
+        .await;
+
+    if entries.is_empty() {
+        say(ctx, "⚪ No privileged commands have been recorded yet.").await?;
+        return Ok(());
+    }
+
+    let content = truncate_message(format!(
+        "🛡️ **Audit log**\n{}",
+        entries.iter().map(describe).collect::<Vec<_>>().join("\n")
+    ));
+    say(ctx, content).await?;
+    Ok(())
+}
+
+/// Set the channel privileged commands are logged in
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn channel(
+    ctx: Context<'_>,
+    #[description = "Channel for the audit log (leave empty to stop posting)"]
+    channel: Option<serenity::GuildChannel>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    ctx.data()
+        .dbs
+        .audit
+        .set_channel(guild_id, channel.as_ref().map(|c| c.id.get()))
+        .await?;
+
+    match channel {
+        Some(channel) => {
+            say(ctx, format!("✅ Privileged commands will be logged in <#{}>.", channel.id))
+                .await?
+        }
+        None => {
+            say(
+                ctx,
+                "✅ Privileged commands are no longer posted. `/audit recent` still lists them.",
+            )
+            .await?
+        }
+    };
+    Ok(())
+}
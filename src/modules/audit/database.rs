@@ -0,0 +1,133 @@
+use crate::database::{Database, Rows};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Entries kept per guild; older ones are dropped.
+const MAX_ENTRIES: usize = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix timestamp.
+    pub at: u64,
+    pub user_id: u64,
+    pub channel_id: u64,
+    /// The command as typed, including its arguments.
+    pub invocation: String,
+    pub succeeded: bool,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct AuditDatabase {
+    /// Channel each guild's audit entries are posted in.
+    pub channels: HashMap<u64, u64>,
+    /// Newest last.
+    pub entries: HashMap<u64, VecDeque<AuditEntry>>,
+}
+
+impl Rows for AuditDatabase {}
+
+impl Database<AuditDatabase> {
+    /// Stores `entry`, returning the guild's audit channel to post it in.
+    pub async fn record(&self, guild_id: u64, entry: AuditEntry) -> Result<Option<u64>, String> {
+        self.transaction(|db| {
+            let entries = db.entries.entry(guild_id).or_default();
+            entries.push_back(entry);
+            while entries.len() > MAX_ENTRIES {
+                entries.pop_front();
+            }
+            Ok(db.channels.get(&guild_id).copied())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Drops entries recorded before `cutoff` (unix seconds) in every guild, returning how
+    /// many were dropped.
+    pub async fn prune_before(&self, cutoff: u64) -> Result<usize, String> {
+        self.transaction(|db| {
+            let mut pruned = 0;
+            for entries in db.entries.values_mut() {
+                while entries.front().is_some_and(|entry| entry.at < cutoff) {
+                    entries.pop_front();
+                    pruned += 1;
+                }
+            }
+            db.entries.retain(|_, entries| !entries.is_empty());
+            Ok(pruned)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// The guild's latest `limit` entries, newest first, optionally only those by `user_id`.
+    pub async fn recent(
+        &self,
+        guild_id: u64,
+        user_id: Option<u64>,
+        limit: usize,
+    ) -> Vec<AuditEntry> {
+        self.read(|db| {
+            db.entries
+                .get(&guild_id)
+                .map(|entries| {
+                    entries
+                        .iter()
+                        .rev()
+                        .filter(|entry| user_id.map_or(true, |id| entry.user_id == id))
+                        .take(limit)
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+        .await
+    }
+
+    pub async fn set_channel(&self, guild_id: u64, channel_id: Option<u64>) -> Result<(), String> {
+        self.transaction(|db| {
+            match channel_id {
+                Some(channel_id) => db.channels.insert(guild_id, channel_id),
+                None => db.channels.remove(&guild_id),
+            };
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Removes the user's audit entries in every guild, describing what was removed.
+    pub async fn forget_user(&self, user_id: u64) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            let mut removed = 0;
+            for entries in db.entries.values_mut() {
+                let before = entries.len();
+                entries.retain(|entry| entry.user_id != user_id);
+                removed += before - entries.len();
+            }
+            Ok(if removed > 0 {
+                vec![format!("{} audit log entries", removed)]
+            } else {
+                Vec::new()
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn forget_channel(
+        &self,
+        guild_id: u64,
+        channel_id: u64,
+    ) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            if db.channels.get(&guild_id) == Some(&channel_id) {
+                db.channels.remove(&guild_id);
+                Ok(vec!["Audit channel unset".to_string()])
+            } else {
+                Ok(Vec::new())
+            }
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,61 @@
+//! Records privileged commands as they're used. Called by the framework's `post_command`
+//! and `on_error` hooks, so commands don't need to do anything themselves.
+
+use super::database::AuditEntry;
+use crate::Context;
+use poise::serenity_prelude::ChannelId;
+use tracing::{error, warn};
+
+/// Top-level commands audited even though they don't require a permission.
+const ALWAYS_AUDITED: [&str; 1] = ["recording"];
+
+/// Whether the running command needs a permission, is owner-only or is always audited.
+fn is_privileged(ctx: Context<'_>) -> bool {
+    let command = ctx.command();
+    let root = command.qualified_name.split(' ').next().unwrap_or_default();
+    ALWAYS_AUDITED.contains(&root)
+        || std::iter::once(command)
+            .chain(ctx.parent_commands().iter().copied())
+            .any(|command| !command.required_permissions.is_empty() || command.owners_only)
+}
+
+pub fn describe(entry: &AuditEntry) -> String {
+    format!(
+        "{} <@{}> used `{}` in <#{}> <t:{}:R>",
+        if entry.succeeded { "🛡️" } else { "⚠️" },
+        entry.user_id,
+        entry.invocation,
+        entry.channel_id,
+        entry.at
+    ) + if entry.succeeded { "" } else { " (failed)" }
+}
+
+/// Stores the running command in the guild's audit log and posts it to the audit channel,
+/// if it's privileged and used in a guild.
+pub async fn record(ctx: Context<'_>, succeeded: bool) {
+    let Some(guild_id) = ctx.guild_id() else {
+        return;
+    };
+    if !is_privileged(ctx) {
+        return;
+    }
+
+    let entry = AuditEntry {
+        at: chrono::Utc::now().timestamp() as u64,
+        user_id: ctx.author().id.get(),
+        channel_id: ctx.channel_id().get(),
+        invocation: ctx.invocation_string(),
+        succeeded,
+    };
+    let line = describe(&entry);
+
+    match ctx.data().dbs.audit.record(guild_id.get(), entry).await {
+        Ok(Some(channel_id)) => {
+            if let Err(e) = ChannelId::new(channel_id).say(ctx.http(), line).await {
+                warn!("Failed to post to audit channel {} in {}: {}", channel_id, guild_id, e);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => error!("Failed to record audit entry in {}: {}", guild_id, e),
+    }
+}
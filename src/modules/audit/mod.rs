@@ -0,0 +1,17 @@
+pub mod commands;
+pub mod database;
+pub mod log;
+
+use commands::*;
+use poise::command;
+
+/// 🛡️ Review who used privileged commands in this server
+#[command(
+    slash_command,
+    subcommands("recent", "channel"),
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn audit(_ctx: crate::Context<'_>) -> Result<(), crate::Error> {
+    Ok(())
+}
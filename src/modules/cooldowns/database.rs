@@ -0,0 +1,132 @@
+use crate::database::{Database, Rows};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How often a command may be used. Zero turns that limit off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cooldown {
+    /// Seconds between uses by the same member.
+    pub user_secs: u64,
+    /// Seconds between uses by anyone in the guild.
+    pub guild_secs: u64,
+}
+
+impl Cooldown {
+    pub fn is_off(&self) -> bool {
+        self.user_secs == 0 && self.guild_secs == 0
+    }
+}
+
+/// Cooldowns that apply until a guild sets its own, by command path.
+pub const DEFAULTS: [(&str, Cooldown); 3] = [
+    ("lorax submit", Cooldown { user_secs: 30, guild_secs: 0 }),
+    ("modrinth link", Cooldown { user_secs: 60, guild_secs: 0 }),
+    ("servers create", Cooldown { user_secs: 300, guild_secs: 0 }),
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GuildCooldowns {
+    /// Cooldowns set with `/settings cooldown`, by command path.
+    pub overrides: HashMap<String, Cooldown>,
+    /// Unix time of the latest use of each command, by anyone.
+    pub last_use: HashMap<String, u64>,
+    /// Unix time of each member's latest use of each command.
+    pub last_use_by: HashMap<String, HashMap<u64, u64>>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct CooldownDatabase {
+    pub guilds: HashMap<u64, GuildCooldowns>,
+}
+
+impl Rows for CooldownDatabase {}
+
+impl Database<CooldownDatabase> {
+    /// The cooldown of `command` in a guild: its own setting, else the default.
+    pub async fn cooldown(&self, guild_id: u64, command: &str) -> Cooldown {
+        let configured = self
+            .read(|db| db.guilds.get(&guild_id)?.overrides.get(command).copied())
+            .await;
+        configured.unwrap_or_else(|| default_cooldown(command))
+    }
+
+    pub async fn set_cooldown(
+        &self,
+        guild_id: u64,
+        command: &str,
+        cooldown: Option<Cooldown>,
+    ) -> Result<(), String> {
+        self.transaction(|db| {
+            let guild = db.guilds.entry(guild_id).or_default();
+            match cooldown {
+                Some(cooldown) => guild.overrides.insert(command.to_string(), cooldown),
+                None => guild.overrides.remove(command),
+            };
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Records a use of `command` at `now` if its cooldown allows one. Otherwise returns
+    /// the unix time the member may use it again.
+    pub async fn try_use(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        command: &str,
+        cooldown: Cooldown,
+        now: u64,
+    ) -> Result<Option<u64>, String> {
+        self.transaction(|db| {
+            let guild = db.guilds.entry(guild_id).or_default();
+            let guild_ready = guild.last_use.get(command).map_or(0, |at| at + cooldown.guild_secs);
+            let user_ready = guild
+                .last_use_by
+                .get(command)
+                .and_then(|users| users.get(&user_id))
+                .map_or(0, |at| at + cooldown.user_secs);
+
+            let ready = guild_ready.max(user_ready);
+            if ready > now {
+                return Ok(Some(ready));
+            }
+
+            guild.last_use.insert(command.to_string(), now);
+            let users = guild.last_use_by.entry(command.to_string()).or_default();
+            users.insert(user_id, now);
+            // Entries past the longest cooldown no longer matter
+            users.retain(|_, at| *at + cooldown.user_secs > now || *at == now);
+            Ok(None)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Removes the user's command history in every guild, describing what was removed.
+    pub async fn forget_user(&self, user_id: u64) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            let mut removed = false;
+            for guild in db.guilds.values_mut() {
+                for users in guild.last_use_by.values_mut() {
+                    removed |= users.remove(&user_id).is_some();
+                }
+            }
+            Ok(if removed {
+                vec!["Command cooldown history".to_string()]
+            } else {
+                Vec::new()
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+}
+
+pub fn default_cooldown(command: &str) -> Cooldown {
+    DEFAULTS
+        .iter()
+        .find(|(name, _)| *name == command)
+        .map(|(_, cooldown)| *cooldown)
+        .unwrap_or_default()
+}
@@ -0,0 +1,63 @@
+//! Rate limits for commands: a short global limit per user from `[cooldowns]` in the
+//! config, and per-command cooldowns per member and per guild that guild admins can change
+//! with `/settings cooldown`.
+
+pub mod database;
+
+use crate::{Context, Error};
+use dashmap::DashMap;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+/// When each user last ran any command, for the global limit.
+static LAST_COMMAND: LazyLock<DashMap<u64, Instant>> = LazyLock::new(DashMap::new);
+
+/// Whether the running command may run now. If not, the user is told when to try again.
+pub async fn check(ctx: Context<'_>) -> Result<bool, Error> {
+    let user_id = ctx.author().id.get();
+
+    let global = Duration::from_millis(ctx.data().config.get().cooldowns.global_per_user_ms);
+    if !global.is_zero() {
+        let now = Instant::now();
+        let last = LAST_COMMAND.get(&user_id).map(|last| *last);
+        if last.is_some_and(|last| now.duration_since(last) < global) {
+            reply(ctx, "⏳ You're using commands too quickly. Please wait a moment.").await?;
+            return Ok(false);
+        }
+        LAST_COMMAND.insert(user_id, now);
+    }
+
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(true);
+    };
+    let command = &ctx.command().qualified_name;
+    let cooldowns = &ctx.data().dbs.cooldowns;
+    let cooldown = cooldowns.cooldown(guild_id.get(), command).await;
+    if cooldown.is_off() {
+        return Ok(true);
+    }
+
+    let now = chrono::Utc::now().timestamp() as u64;
+    match cooldowns
+        .try_use(guild_id.get(), user_id, command, cooldown, now)
+        .await?
+    {
+        None => Ok(true),
+        Some(ready) => {
+            reply(
+                ctx,
+                format!("⏳ `/{}` is on cooldown. Try again <t:{}:R>.", command, ready),
+            )
+            .await?;
+            Ok(false)
+        }
+    }
+}
+
+async fn reply(ctx: Context<'_>, content: impl Into<String>) -> Result<(), Error> {
+    let reply = poise::CreateReply::default()
+        .content(content)
+        .ephemeral(true);
+    ctx.send(reply).await?;
+    Ok(())
+}
@@ -0,0 +1,292 @@
+//! `/help`: one page per module listing the commands the user can actually run, so members
+//! don't see admin commands and nobody sees commands of modules their server turned off.
+
+use crate::modules::system::database::Theme;
+use crate::modules::toggles::database::Module;
+use crate::utils::aliases::renamed;
+use crate::utils::embed::titled;
+use crate::utils::reply::send;
+use crate::{Context, Data, Error};
+use poise::serenity_prelude::{
+    ComponentInteractionDataKind, CreateActionRow, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateSelectMenu, CreateSelectMenuKind,
+    CreateSelectMenuOption, Permissions,
+};
+use poise::{command, ChoiceParameter, CreateReply};
+use std::time::Duration;
+
+/// How long the page picker keeps working.
+const PICKER_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ChoiceParameter)]
+pub enum HelpPage {
+    Lorax,
+    Stats,
+    Testing,
+    Modrinth,
+    Recording,
+    Server,
+}
+
+impl HelpPage {
+    const ALL: [HelpPage; 6] = [
+        Self::Lorax,
+        Self::Stats,
+        Self::Testing,
+        Self::Modrinth,
+        Self::Recording,
+        Self::Server,
+    ];
+
+    fn emoji(&self) -> &'static str {
+        match self {
+            Self::Lorax => "🌳",
+            Self::Stats => "📊",
+            Self::Testing => "🧪",
+            Self::Modrinth => "📦",
+            Self::Recording => "🎙️",
+            Self::Server => "⚙️",
+        }
+    }
+
+    fn blurb(&self) -> &'static str {
+        match self {
+            Self::Lorax => "Tree naming events: submissions, voting and winners",
+            Self::Stats => "Live Prometheus stats in channel names, with alerts",
+            Self::Testing => "Temporary test servers",
+            Self::Modrinth => "Linking Modrinth projects",
+            Self::Recording => "Voice channel recording",
+            Self::Server => "Settings, modules, auditing and your own preferences",
+        }
+    }
+
+    /// Top-level commands shown on this page.
+    fn commands(&self) -> &'static [&'static str] {
+        match self {
+            Self::Lorax => &["lorax"],
+            Self::Stats => &["stats"],
+            Self::Testing => &["servers"],
+            Self::Modrinth => &["modrinth"],
+            Self::Recording => &["recording"],
+            Self::Server => &[
+                "help",
+                "settings",
+                "modules",
+                "audit",
+                "preferences",
+                "privacy",
+                "server_costs",
+                "register",
+                "admin",
+                "owner",
+            ],
+        }
+    }
+
+    /// The toggleable module behind this page, if any.
+    fn module(&self) -> Option<Module> {
+        match self {
+            Self::Lorax => Some(Module::Lorax),
+            Self::Stats => Some(Module::Stats),
+            Self::Testing => Some(Module::Testing),
+            Self::Recording => Some(Module::Recording),
+            Self::Modrinth | Self::Server => None,
+        }
+    }
+}
+
+/// What the user running `/help` is allowed to use.
+struct Viewer {
+    is_owner: bool,
+    /// `None` outside a guild.
+    permissions: Option<Permissions>,
+}
+
+impl Viewer {
+    fn of(ctx: Context<'_>) -> Self {
+        let permissions = match ctx {
+            poise::Context::Application(ctx) => ctx
+                .interaction
+                .member
+                .as_ref()
+                .and_then(|member| member.permissions),
+            poise::Context::Prefix(_) => None,
+        };
+        Self {
+            is_owner: ctx.framework().options().owners.contains(&ctx.author().id),
+            permissions,
+        }
+    }
+
+    /// Whether `command` would pass its checks, given the permissions its parents need.
+    fn can_run(&self, command: &poise::Command<Data, Error>, inherited: Permissions) -> bool {
+        if command.owners_only && !self.is_owner {
+            return false;
+        }
+        let required = inherited | command.required_permissions;
+        match self.permissions {
+            Some(permissions) => permissions.contains(required),
+            None => !command.guild_only && required.is_empty(),
+        }
+    }
+}
+
+/// Adds a line per runnable leaf command under `command`.
+fn collect(
+    viewer: &Viewer,
+    command: &poise::Command<Data, Error>,
+    inherited: Permissions,
+    lines: &mut Vec<String>,
+) {
+    // Old names of renamed commands are listed under their new name
+    if !viewer.can_run(command, inherited) || renamed(&command.qualified_name).is_some() {
+        return;
+    }
+    if command.subcommands.is_empty() {
+        lines.push(format!(
+            "`/{}` — {}",
+            command.qualified_name,
+            command.description.as_deref().unwrap_or("No description")
+        ));
+        return;
+    }
+    let inherited = inherited | command.required_permissions;
+    for subcommand in &command.subcommands {
+        collect(viewer, subcommand, inherited, lines);
+    }
+}
+
+/// The commands `viewer` can run on `page`, one line each.
+fn page_lines(ctx: Context<'_>, viewer: &Viewer, page: HelpPage) -> Vec<String> {
+    let mut lines = Vec::new();
+    for name in page.commands() {
+        let command = ctx
+            .framework()
+            .options()
+            .commands
+            .iter()
+            .find(|command| command.name == *name);
+        if let Some(command) = command {
+            collect(viewer, command, Permissions::empty(), &mut lines);
+        }
+    }
+    lines
+}
+
+/// Pages with at least one command the user can run, each with its lines.
+async fn visible_pages(ctx: Context<'_>) -> Vec<(HelpPage, Vec<String>)> {
+    let viewer = Viewer::of(ctx);
+    let mut pages = Vec::new();
+    for page in HelpPage::ALL {
+        if let (Some(guild_id), Some(module)) = (ctx.guild_id(), page.module()) {
+            if !ctx.data().dbs.modules.is_enabled(guild_id.get(), module).await {
+                continue;
+            }
+        }
+        let lines = page_lines(ctx, &viewer, page);
+        if !lines.is_empty() {
+            pages.push((page, lines));
+        }
+    }
+    pages
+}
+
+fn render(theme: &Theme, page: HelpPage, lines: &[String]) -> CreateEmbed {
+    let mut description = format!("*{}*\n\n{}", page.blurb(), lines.join("\n"));
+    if description.chars().count() > 4000 {
+        description = description.chars().take(3990).collect::<String>() + "\n…";
+    }
+    titled(theme, format!("{} {}", page.emoji(), page.name())).description(description)
+}
+
+fn picker(pages: &[(HelpPage, Vec<String>)], current: HelpPage) -> Vec<CreateActionRow> {
+    let options = pages
+        .iter()
+        .map(|(page, lines)| {
+            CreateSelectMenuOption::new(page.name(), page.name())
+                .emoji(page.emoji().chars().next().unwrap_or('❔'))
+                .description(format!("{} command(s)", lines.len()))
+                .default_selection(*page == current)
+        })
+        .collect();
+    vec![CreateActionRow::SelectMenu(
+        CreateSelectMenu::new("help_page", CreateSelectMenuKind::String { options })
+            .placeholder("Choose a module"),
+    )]
+}
+
+/// ❓ Show the commands you can use
+#[command(slash_command, ephemeral)]
+pub async fn help(
+    ctx: Context<'_>,
+    #[description = "Module to start on"] page: Option<HelpPage>,
+) -> Result<(), Error> {
+    let pages = visible_pages(ctx).await;
+    let Some((first, _)) = pages.first() else {
+        send(ctx, CreateReply::default().content("🤷 There are no commands you can use here."))
+            .await?;
+        return Ok(());
+    };
+    let mut current = page
+        .filter(|page| pages.iter().any(|(visible, _)| visible == page))
+        .unwrap_or(*first);
+
+    let theme = match ctx.guild_id() {
+        Some(guild_id) => ctx.data().dbs.system.get_theme(guild_id.get()).await,
+        None => Default::default(),
+    };
+    let lines_of = |page: HelpPage| {
+        pages
+            .iter()
+            .find(|(visible, _)| *visible == page)
+            .map(|(_, lines)| lines.as_slice())
+            .unwrap_or_default()
+    };
+
+    let reply = CreateReply::default()
+        .embed(render(&theme, current, lines_of(current)))
+        .components(picker(&pages, current));
+    let message = send(ctx, reply).await?;
+    if pages.len() < 2 {
+        return Ok(());
+    }
+
+    while let Some(interaction) = message
+        .message()
+        .await?
+        .await_component_interaction(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .timeout(PICKER_TIMEOUT)
+        .await
+    {
+        let ComponentInteractionDataKind::StringSelect { values, .. } = &interaction.data.kind
+        else {
+            continue;
+        };
+        let Some(selected) = values
+            .first()
+            .and_then(|value| HelpPage::ALL.into_iter().find(|page| page.name() == value))
+        else {
+            continue;
+        };
+        current = selected;
+
+        let update = CreateInteractionResponseMessage::new()
+            .embed(render(&theme, current, lines_of(current)))
+            .components(picker(&pages, current));
+        interaction
+            .create_response(ctx.http(), CreateInteractionResponse::UpdateMessage(update))
+            .await?;
+    }
+
+    // Leave the last page up without a picker that no longer responds
+    message
+        .edit(
+            ctx,
+            CreateReply::default()
+                .embed(render(&theme, current, lines_of(current)))
+                .components(Vec::new()),
+        )
+        .await?;
+    Ok(())
+}
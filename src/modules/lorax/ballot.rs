@@ -0,0 +1,187 @@
+//! Reaction voting: each voting round posts a ballot with one number per tree, and reacting
+//! with a number casts or moves the member's vote through the same checks as `/lorax vote`.
+
+use super::database::{LoraxEvent, LoraxHandler, LoraxStage};
+use super::pitch::campaign_link;
+use super::task::{announcement_channel, get_current_timestamp};
+use crate::events::EventHandler;
+use crate::modules::system::database::Theme;
+use crate::modules::toggles::database::Module;
+use crate::utils::embed;
+use async_trait::async_trait;
+use poise::serenity_prelude::{Context, CreateMessage, FullEvent, Reaction, ReactionType};
+
+/// Reactions on the ballot, one per tree. Trees past the last one vote with `/lorax vote`.
+pub const BALLOT_EMOJI: [&str; 10] =
+    ["1️⃣", "2️⃣", "3️⃣", "4️⃣", "5️⃣", "6️⃣", "7️⃣", "8️⃣", "9️⃣", "🔟"];
+
+/// Posts the round's ballot where the stage was announced, if the event votes by reaction.
+pub async fn post_ballot(ctx: &Context, event: &mut LoraxEvent, theme: &Theme, guild_id: u64) {
+    if !event.settings.reaction_voting
+        || !matches!(event.stage, LoraxStage::Voting | LoraxStage::Tiebreaker(_))
+    {
+        return;
+    }
+    let Some(channel_id) = announcement_channel(ctx, event).await else {
+        return;
+    };
+
+    let ballot: Vec<String> =
+        event.current_trees.iter().take(BALLOT_EMOJI.len()).cloned().collect();
+    let mut lines: Vec<String> = ballot
+        .iter()
+        .zip(BALLOT_EMOJI)
+        .map(|(tree, emoji)| match campaign_link(event, guild_id, tree) {
+            Some(link) => format!("{} **{}** · [campaign]({})", emoji, tree, link),
+            None => format!("{} **{}**", emoji, tree),
+        })
+        .collect();
+    if event.current_trees.len() > ballot.len() {
+        lines.push(format!(
+            "\nThe other {}s are on `/lorax vote`.",
+            event.settings.noun()
+        ));
+    }
+    let embed = embed::themed(theme).title("🗳️ React to vote").description(format!(
+        "React with a number to vote. Picking another number moves your vote.\n\n{}",
+        lines.join("\n")
+    ));
+
+    let message = CreateMessage::default().embed(embed);
+    let message = match channel_id.send_message(ctx, message).await {
+        Ok(message) => message,
+        Err(e) => {
+            tracing::warn!("Failed to post the Lorax ballot in {}: {}", channel_id, e);
+            return;
+        }
+    };
+    for emoji in BALLOT_EMOJI.iter().take(ballot.len()) {
+        if let Err(e) = message.react(ctx, ReactionType::Unicode(emoji.to_string())).await {
+            tracing::warn!("Failed to add ballot reactions in {}: {}", channel_id, e);
+            break;
+        }
+    }
+    event.ballot_message_id = Some(message.id.get());
+    event.ballot = ballot;
+}
+
+/// Turns reactions on the current ballot into votes, keeping one reaction per member.
+#[derive(Debug, Clone)]
+pub struct BallotHandler {
+    pub db: LoraxHandler,
+}
+
+impl BallotHandler {
+    /// The event and the tree a reaction picks, if it's a member's reaction on the ballot.
+    async fn choice(&self, ctx: &Context, reaction: &Reaction) -> Option<(LoraxEvent, String)> {
+        let guild_id = reaction.guild_id?;
+        let user_id = reaction.user_id?;
+        if user_id == ctx.cache.current_user().id {
+            return None;
+        }
+        let event = self.db.get_event(guild_id.get()).await?;
+        if event.ballot_message_id != Some(reaction.message_id.get()) {
+            return None;
+        }
+        let ReactionType::Unicode(emoji) = &reaction.emoji else {
+            return None;
+        };
+        let index = BALLOT_EMOJI.iter().position(|number| number == emoji)?;
+        let tree = event.ballot.get(index)?.clone();
+        Some((event, tree))
+    }
+
+    async fn vote(
+        &self,
+        ctx: &Context,
+        reaction: &Reaction,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some((event, tree)) = self.choice(ctx, reaction).await else {
+            return Ok(());
+        };
+        let (Some(guild_id), Some(user_id)) = (reaction.guild_id, reaction.user_id) else {
+            return Ok(());
+        };
+        let roles: Vec<u64> = reaction
+            .member
+            .as_ref()
+            .map(|member| member.roles.iter().map(|role| role.get()).collect())
+            .unwrap_or_default();
+
+        let own = event.get_tree_submitter(&tree) == Some(user_id.get());
+        let vote = if own || !event.settings.can_vote(&roles) {
+            Err("not allowed".to_string())
+        } else {
+            let weight = event.settings.vote_weight(&roles);
+            let now = get_current_timestamp();
+            self.db
+                .vote_tree(guild_id.get(), tree.clone(), user_id.get(), weight, now)
+                .await
+        };
+
+        match vote {
+            // One reaction per member: the old pick's reaction goes
+            Ok(Some(old)) if old != tree => {
+                if let Some(index) = event.ballot.iter().position(|t| *t == old) {
+                    let emoji = ReactionType::Unicode(BALLOT_EMOJI[index].to_string());
+                    let _ = reaction
+                        .channel_id
+                        .delete_reaction(ctx, reaction.message_id, Some(user_id), emoji)
+                        .await;
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::debug!("Ignored ballot reaction from {}: {}", user_id, e);
+                reaction.delete(ctx).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn unvote(
+        &self,
+        ctx: &Context,
+        reaction: &Reaction,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some((_, tree)) = self.choice(ctx, reaction).await else {
+            return Ok(());
+        };
+        let (Some(guild_id), Some(user_id)) = (reaction.guild_id, reaction.user_id) else {
+            return Ok(());
+        };
+        self.db
+            .withdraw_vote(guild_id.get(), user_id.get(), &tree, get_current_timestamp())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventHandler for BallotHandler {
+    fn name(&self) -> &str {
+        "LoraxBallot"
+    }
+
+    async fn handle(
+        &self,
+        ctx: &Context,
+        event: &FullEvent,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match event {
+            FullEvent::ReactionAdd { add_reaction } => self.vote(ctx, add_reaction).await,
+            FullEvent::ReactionRemove { removed_reaction } => {
+                self.unvote(ctx, removed_reaction).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn EventHandler> {
+        Box::new(self.clone())
+    }
+
+    fn module(&self) -> Option<Module> {
+        Some(Module::Lorax)
+    }
+}
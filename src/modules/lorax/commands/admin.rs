@@ -1,24 +1,47 @@
 //! Commands for managing Lorax events.
 
-use std::sync::Arc;
-
-use crate::modules::lorax::database::LoraxEvent;
-use crate::modules::lorax::{database::LoraxStage, task::LoraxEventTask};
+use crate::modules::lorax::{
+    database::{LoraxEvent, LoraxStage, PendingSubmission, RoundResult, MAX_WINNERS},
+    schedule,
+    task::{
+        announcement_channel, get_current_timestamp, role_ping, LoraxEventTask,
+        FORUM_POST_TITLE,
+    },
+};
+use crate::utils::{
+    duration::{format_duration, parse_duration_secs, DurationUnit},
+    time::format_timestamp,
+};
+use crate::modules::preferences::notify::send_dm;
+use crate::utils::embed;
+use crate::utils::plan::Plan;
+use crate::utils::validate;
+use crate::utils::reply::{defer, say, send};
 use crate::{Context, Error};
-use poise::command;
-use poise::serenity_prelude::{self as serenity, ChannelId, EditMessage, Mentionable};
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ChannelId, ChannelType, CreateActionRow,
+    CreateAllowedMentions, CreateButton, CreateEmbed, CreateMessage, EditMessage, Mentionable,
+};
+use poise::{command, CreateReply};
+use std::time::Duration;
 use tracing::error;
 
 /// Kick off a new Lorax event for your community!
 #[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
-pub async fn start(ctx: Context<'_>) -> Result<(), Error> {
-    ctx.defer().await?;
+pub async fn start(
+    ctx: Context<'_>,
+    #[description = "How many trees win, to name several nodes at once (default 1)"]
+    #[min = 1]
+    #[max = 10]
+    winner_count: Option<usize>,
+) -> Result<(), Error> {
+    defer(ctx).await?;
 
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
 
     if let Some(event) = ctx.data().dbs.lorax.get_event(guild_id).await {
         if event.stage != LoraxStage::Inactive {
-            ctx.say("❌ There is already an active event!").await?;
+            say(ctx, "❌ There is already an active event!").await?;
             return Ok(());
         }
     }
@@ -26,71 +49,132 @@ pub async fn start(ctx: Context<'_>) -> Result<(), Error> {
     let settings = ctx.data().dbs.lorax.get_settings(guild_id).await?;
 
     if settings.lorax_channel.is_none() {
-        ctx.say("❌ Please set a Lorax channel first using `/lorax channel`")
+        say(ctx, "❌ Please set a Lorax channel first using `/lorax channel`")
             .await?;
         return Ok(());
     }
 
-    let mut lorax_task = LoraxEventTask::new(guild_id, Arc::new(ctx.data().dbs.lorax.clone()));
+    let mut lorax_task =
+        LoraxEventTask::new(guild_id, ctx.data().dbs.clone(), ctx.data().config.clone());
 
     lorax_task
-        .start_event(settings, ctx.serenity_context())
+        .start_event(settings, winner_count.unwrap_or(1), ctx.serenity_context())
         .await;
+    ctx.data().task_manager.sync_guild(guild_id).await;
 
-    ctx.say("🎉 The Lorax event has begun! Let the naming commence!")
+    say(ctx, "🎉 The Lorax event has begun! Let the naming commence!")
         .await?;
     Ok(())
 }
 
-/// Wrap up the current Lorax event
-#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
-pub async fn end(ctx: Context<'_>) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
-    let mut lorax_task = LoraxEventTask::new(guild_id, Arc::new(ctx.data().dbs.lorax.clone()));
+/// See what an event's announcements will look like, without starting one
+#[command(slash_command, guild_only, ephemeral, required_permissions = "MANAGE_GUILD")]
+pub async fn preview(
+    ctx: Context<'_>,
+    #[description = "How many trees win (default 1)"]
+    #[min = 1]
+    #[max = 10]
+    winner_count: Option<usize>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let dbs = &ctx.data().dbs;
+    let settings = dbs.lorax.get_settings(guild_id).await?;
+    let tz = dbs.guild_config().timezone(guild_id).await;
+    let theme = dbs.system.get_theme(guild_id).await;
+    let task = LoraxEventTask::new(guild_id, dbs.clone(), ctx.data().config.clone());
+
+    // Sample entries stand in for submissions, all credited to whoever asked
+    let mut names: Vec<String> =
+        settings.sample_names().iter().take(3).map(|n| n.to_string()).collect();
+    if names.is_empty() {
+        names = (1..=3).map(|i| format!("Example {}", i)).collect();
+    }
+    let now = get_current_timestamp();
+    let mut event = LoraxEvent::new(settings, now);
+    event.winner_count = winner_count.unwrap_or(1).clamp(1, MAX_WINNERS);
+    event.tree_submissions.insert(ctx.author().id.get(), names.clone());
+    event.current_trees = names.clone();
+    event.round_results.push(RoundResult {
+        stage: LoraxStage::Voting,
+        tally: names.iter().cloned().zip((1..=names.len()).rev()).collect(),
+        eliminated: Vec::new(),
+        ended_at: now,
+    });
 
-    match lorax_task.end_event(ctx.serenity_context()).await {
-        Ok(_) => {
-            ctx.say("🛑 The Lorax event has been ended. Thanks for participating!")
-                .await?;
-        }
-        Err(e) => {
-            ctx.say(format!("❌ {}", e)).await?;
+    let mut embeds = Vec::new();
+    for (stage, label) in [
+        (LoraxStage::Submission, "Submissions open"),
+        (LoraxStage::Voting, "Voting opens"),
+        (LoraxStage::Completed, "Results"),
+    ] {
+        event.stage = stage;
+        if let Some(content) = task.stage_content(&event, tz) {
+            embeds.push(
+                embed::themed(&theme)
+                    .title(format!("Preview: {}", label))
+                    .description(content),
+            );
         }
     }
 
+    let is_forum = match event.settings.lorax_channel {
+        Some(id) => ChannelId::new(id)
+            .to_channel(ctx)
+            .await
+            .ok()
+            .and_then(|channel| channel.guild())
+            .is_some_and(|channel| channel.kind == ChannelType::Forum),
+        None => false,
+    };
+    let channel = match event.settings.lorax_channel {
+        Some(id) if is_forum => format!(
+            "📣 Posted in one forum post in <#{}> titled **{}**. Campaigning happens in that post and the results are pinned there.",
+            id, FORUM_POST_TITLE
+        ),
+        Some(id) => format!(
+            "📣 Posted in <#{}>. The submission announcement gets a **Submit** button, and the voting announcement opens a public **Campaign Thread**.",
+            id
+        ),
+        None => "⚠️ No Lorax channel is set, so nothing would be posted. Set one with `/lorax channel`."
+            .to_string(),
+    };
+    let ping = match role_ping(&event).as_str() {
+        "" => "🔕 No role is pinged.".to_string(),
+        mention => format!("🔔 Each announcement pings {}.", mention),
+    };
+
+    send(
+        ctx,
+        CreateReply::default()
+            .content(format!(
+                "👀 **Lorax preview** with the current settings. Nothing was posted or saved.\n{}\n{}",
+                channel, ping
+            ))
+            .embeds(embeds)
+            .allowed_mentions(CreateAllowedMentions::new())
+            .ephemeral(true),
+    )
+    .await?;
     Ok(())
 }
 
-async fn handle_winner_roles(
-    ctx: &serenity::Context,
-    guild_id: u64,
-    event: &LoraxEvent,
-    winner_tree: &str,
-) -> Result<(), Error> {
-    let settings = &event.settings;
-    
-    // Get winner and previous winner roles
-    if let (Some(winner_role_id), Some(alumni_role_id)) = (settings.winner_role, settings.alumni_role) {
-        let winner_role = serenity::RoleId::from(winner_role_id);
-        let alumni_role = serenity::RoleId::from(alumni_role_id);
-
-        // Move current winners to alumni
-        if let Ok(guild) = ctx.http.get_guild(guild_id.into()).await {
-            if let Ok(members) = guild.members(ctx, None, None).await {
-                for member in members {
-                    if member.roles.contains(&winner_role) {
-                        let _ = member.remove_role(ctx, winner_role).await;
-                        let _ = member.add_role(ctx, alumni_role).await;
-                    }
-                }
+/// Wrap up the current Lorax event
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn end(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let mut lorax_task =
+        LoraxEventTask::new(guild_id, ctx.data().dbs.clone(), ctx.data().config.clone());
 
-                // Assign winner role to new winner
-                if let Some(winner_id) = event.get_tree_submitter(winner_tree) {
-                    if let Ok(winner) = guild.member(ctx, winner_id).await {
-                        let _ = winner.add_role(ctx, winner_role).await;
-                    }
-                }
-            }
+    let result = lorax_task.end_event(ctx.serenity_context()).await;
+    ctx.data().task_manager.sync_guild(guild_id).await;
+
+    match result {
+        Ok(_) => {
+            say(ctx, "🛑 The Lorax event has been ended. Thanks for participating!")
+                .await?;
+        }
+        Err(e) => {
+            say(ctx, format!("❌ {}", e)).await?;
         }
     }
 
@@ -100,99 +184,118 @@ async fn handle_winner_roles(
 /// Skip to the next event stage
 #[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
 pub async fn force_advance(ctx: Context<'_>) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
 
     let event = match ctx.data().dbs.lorax.get_event(guild_id).await {
         Some(event) => event,
         None => {
-            ctx.say("⚪ No active Lorax event is running.").await?;
+            say(ctx, "⚪ No active Lorax event is running.").await?;
             return Ok(());
         }
     };
 
     if matches!(event.stage, LoraxStage::Completed | LoraxStage::Inactive) {
-        ctx.say("❌ Cannot advance a completed or inactive event.")
+        say(ctx, "❌ Cannot advance a completed or inactive event.")
             .await?;
         return Ok(());
     }
 
-    let mut updated_event = event.clone();
-    let mut lorax_task = LoraxEventTask::new(guild_id, Arc::new(ctx.data().dbs.lorax.clone()));
+    let mut lorax_task =
+        LoraxEventTask::new(guild_id, ctx.data().dbs.clone(), ctx.data().config.clone());
 
-    if matches!(updated_event.stage, LoraxStage::Voting) {
-        // Handle role assignments before advancing
-        if let Some(winner_tree) = updated_event.get_winner() {
-            handle_winner_roles(ctx.serenity_context(), guild_id, &updated_event, &winner_tree).await?;
+    match lorax_task.advance(ctx.serenity_context(), true).await {
+        Ok(Some(LoraxStage::Inactive)) => {
+            say(ctx, "⏹️ Nobody took part, so the event has ended.").await?;
         }
-    }
-
-    lorax_task
-        .advance_stage(ctx.serenity_context(), &mut updated_event)
-        .await;
-
-    if !matches!(updated_event.stage, LoraxStage::Inactive) {
-        if let Err(e) = ctx
-            .data()
-            .dbs
-            .lorax
-            .update_event(guild_id, updated_event)
-            .await
-        {
-            error!("Failed to update event for guild {}: {}", guild_id, e);
-            ctx.say("❌ Failed to update event stage. Please try again later.")
+        Ok(Some(_)) => {
+            say(ctx, "⏩ Advanced to the next stage!").await?;
+        }
+        Ok(None) => {
+            say(ctx, "❌ The event has nothing left to advance.").await?;
+        }
+        Err(e) => {
+            error!("Failed to advance event for guild {}: {}", guild_id, e);
+            say(ctx, "❌ Failed to update event stage. Please try again later.")
                 .await?;
-        } else {
-            ctx.say("⏩ Advanced to the next stage!").await?;
         }
     }
 
     Ok(())
 }
 
-/// Adjust the current stage duration
+/// Extend or shorten the current stage
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn extend(
+    ctx: Context<'_>,
+    #[description = "Time to add or remove, e.g. 30m, 1h30m or -15m (plain numbers are minutes)"]
+    change: String,
+) -> Result<(), Error> {
+    adjust_stage(ctx, change).await
+}
+
+/// ⚠️ Renamed to /lorax extend
 #[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
 pub async fn duration(
     ctx: Context<'_>,
-    #[description = "Minutes to add or remove (negative to reduce)"] minutes: i64,
+    #[description = "Time to add or remove, e.g. 30m, 1h30m or -15m (plain numbers are minutes)"]
+    change: String,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    adjust_stage(ctx, change).await
+}
+
+async fn adjust_stage(ctx: Context<'_>, change: String) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    let change_secs = match parse_duration_secs(&change, DurationUnit::Minutes) {
+        Ok(secs) => secs,
+        Err(e) => {
+            say(ctx, format!("❌ {}", e)).await?;
+            return Ok(());
+        }
+    };
+
+    let lorax_task =
+        LoraxEventTask::new(guild_id, ctx.data().dbs.clone(), ctx.data().config.clone());
+    // Hold the event lock so the stage can't advance underneath the adjustment
+    let _guard = lorax_task.lock().await;
 
     let mut event = match ctx.data().dbs.lorax.get_event(guild_id).await {
         Some(event) => event,
         None => {
-            ctx.say("⚪ No active Lorax event is running.").await?;
+            say(ctx, "⚪ No active Lorax event is running.").await?;
             return Ok(());
         }
     };
 
     if matches!(event.stage, LoraxStage::Completed | LoraxStage::Inactive) {
-        ctx.say("❌ Cannot modify duration of a completed or inactive event.")
+        say(ctx, "❌ Cannot modify duration of a completed or inactive event.")
             .await?;
         return Ok(());
     }
 
-    let lorax_task = LoraxEventTask::new(guild_id, Arc::new(ctx.data().dbs.lorax.clone()));
     let current_duration = lorax_task.calculate_stage_duration(&event);
 
-    let adjusted_duration = (current_duration as i64) + (minutes * 60);
+    let adjusted_duration = (current_duration as i64) + change_secs;
     if adjusted_duration < 0 {
-        ctx.say("❌ Duration cannot be negative.").await?;
+        say(ctx, "❌ Duration cannot be negative.").await?;
         return Ok(());
     }
 
     let new_duration = adjusted_duration as u64;
     lorax_task.adjust_stage_duration(&mut event, new_duration);
 
-    if let Some(channel_id) = event.settings.lorax_channel {
-        let change_type = if minutes > 0 { "extended" } else { "reduced" };
+    if let Some(channel) = announcement_channel(ctx.serenity_context(), &event).await {
+        let change_type = if change_secs > 0 { "extended" } else { "reduced" };
+        let new_end = event.get_stage_end_timestamp(new_duration);
+        let tz = ctx.data().dbs.guild_config().timezone(guild_id).await;
         let msg = format!(
-            "⏰ Event stage has been {} by {} minutes! New end time: <t:{}:R>",
+            "⏰ Event stage has been {} by {}! New end time: <t:{}:R> ({})",
             change_type,
-            minutes.abs(),
-            event.get_stage_end_timestamp(new_duration)
+            format_duration(change_secs.unsigned_abs()),
+            new_end,
+            format_timestamp(new_end, tz)
         );
 
-        let channel = ChannelId::new(channel_id);
         channel.say(&ctx.serenity_context().http, &msg).await?;
 
         if let Some(msg_id) = match event.stage {
@@ -202,100 +305,200 @@ pub async fn duration(
             _ => None,
         } {
             if let Ok(mut message) = channel.message(&ctx.serenity_context().http, msg_id).await {
-                let new_content = message.content.replace(
-                    r"<t:\d+:R>",
-                    &format!("<t:{}:R>", event.get_stage_end_timestamp(new_duration)),
-                );
-                let _ = message
-                    .edit(
-                        &ctx.serenity_context().http,
-                        EditMessage::new().content(new_content),
-                    )
-                    .await;
+                // Announcements carry their text in a themed embed
+                if let Some(announcement) = message.embeds.first().cloned() {
+                    let new_description = announcement.description.clone().unwrap_or_default().replace(
+                        r"<t:\d+:R>",
+                        &format!("<t:{}:R>", event.get_stage_end_timestamp(new_duration)),
+                    );
+                    let _ = message
+                        .edit(
+                            &ctx.serenity_context().http,
+                            EditMessage::new()
+                                .embed(CreateEmbed::from(announcement).description(new_description)),
+                        )
+                        .await;
+                }
             }
         }
     }
 
-    let _ = ctx.data().dbs.lorax.update_event(guild_id, event).await;
-    ctx.say(format!(
-        "⏳ Stage duration adjusted by {} minutes.",
-        minutes
+    schedule::sync(ctx.serenity_context(), guild_id, &mut event).await;
+
+    let settings = event.settings.clone();
+    let scheduled_event_id = event.scheduled_event_id;
+    let _ = ctx
+        .data()
+        .dbs
+        .lorax
+        .modify_event(guild_id, |event| {
+            event.settings = settings;
+            event.scheduled_event_id = scheduled_event_id;
+            Ok(())
+        })
+        .await;
+    say(ctx, format!(
+        "⏳ Stage duration adjusted by {}{}.",
+        if change_secs < 0 { "-" } else { "" },
+        format_duration(change_secs.unsigned_abs())
     ))
     .await?;
     Ok(())
 }
 
-/// Set event phase durations
-#[command(slash_command, guild_only)]
-pub async fn durations(
+/// Lists what `/lorax reset` would remove in a guild.
+async fn plan_reset(ctx: Context<'_>, guild_id: u64) -> Plan {
+    ctx.data()
+        .dbs
+        .lorax
+        .read(|db| {
+            let mut plan = Plan::new();
+            if let Some(event) = db.events.get(&guild_id) {
+                let stage = match event.stage {
+                    LoraxStage::Submission => "submission",
+                    LoraxStage::Voting => "voting",
+                    LoraxStage::Tiebreaker(_) => "tiebreaker",
+                    LoraxStage::Completed => "completed",
+                    LoraxStage::Inactive => "inactive",
+                };
+                let submissions: usize = event.tree_submissions.values().map(Vec::len).sum();
+                plan.add(format!(
+                    "Delete the {} event with {} submission(s) from {} member(s), {} vote(s) and {} pitch(es)",
+                    stage,
+                    submissions,
+                    event.tree_submissions.len(),
+                    event.tree_votes.len(),
+                    event.pitches.len()
+                ));
+                if event.scheduled_event_id.is_some() {
+                    plan.add("Close its Discord scheduled event");
+                }
+            }
+            if db.settings.contains_key(&guild_id) {
+                plan.add("Clear the Lorax settings (they stay in the settings history)");
+            }
+            plan
+        })
+        .await
+}
+
+/// Reset Lorax settings and events
+#[command(slash_command, guild_only, required_permissions = "ADMINISTRATOR")]
+pub async fn reset(
     ctx: Context<'_>,
-    #[description = "Minutes for submissions"] submission: Option<u64>,
-    #[description = "Minutes for voting"] voting: Option<u64>,
-    #[description = "Minutes for tiebreakers"] tiebreaker: Option<u64>,
+    #[description = "Only show what would be removed"] dry_run: Option<bool>,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
 
-    if submission.is_none() && voting.is_none() && tiebreaker.is_none() {
-        ctx.say("❌ Please specify at least one duration to update.")
-            .await?;
+    let plan = plan_reset(ctx, guild_id).await;
+    if plan.preview(ctx, dry_run).await? {
+        return Ok(());
+    }
+    if plan.is_empty() {
+        say(ctx, "⚪ There is nothing to reset.").await?;
         return Ok(());
     }
 
-    match ctx
-        .data()
-        .dbs
-        .lorax
-        .transaction(|db| {
-            let settings = db.settings.entry(guild_id).or_default();
-            if let Some(mins) = submission {
-                settings.submission_duration = mins;
-            }
-            if let Some(mins) = voting {
-                settings.voting_duration = mins;
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new("lorax_reset_confirm")
+            .style(ButtonStyle::Danger)
+            .label("Reset"),
+        CreateButton::new("lorax_reset_cancel")
+            .style(ButtonStyle::Secondary)
+            .label("Cancel"),
+    ]);
+    let handle = send(
+        ctx,
+        CreateReply::default()
+            .content(format!(
+                "⚠️ **Reset Lorax?** This will:\n{}\nUndo it with `/lorax restore`.",
+                plan.describe()
+            ))
+            .components(vec![buttons]),
+    )
+    .await?;
+
+    let interaction = handle
+        .message()
+        .await?
+        .await_component_interaction(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .timeout(Duration::from_secs(60))
+        .await;
+
+    let confirmed = match &interaction {
+        Some(interaction) => {
+            interaction.defer(ctx.http()).await?;
+            interaction.data.custom_id == "lorax_reset_confirm"
+        }
+        None => false,
+    };
+    let content = if !confirmed {
+        "❌ Nothing was reset.".to_string()
+    } else {
+        let now = chrono::Utc::now().timestamp() as u64;
+        match ctx.data().dbs.lorax.reset(guild_id, now).await {
+            Ok(event) => {
+                // Don't leave the event's Discord scheduled event behind
+                if let Some(mut event) = event {
+                    event.stage = LoraxStage::Inactive;
+                    schedule::sync(ctx.serenity_context(), guild_id, &mut event).await;
+                }
+                ctx.data().task_manager.sync_guild(guild_id).await;
+                "🔄 Lorax has been reset for this server. Changed your mind? Use `/lorax restore`."
+                    .to_string()
             }
-            if let Some(mins) = tiebreaker {
-                settings.tiebreaker_duration = mins;
+            Err(e) => {
+                error!("Failed to reset Lorax for guild {}: {}", guild_id, e);
+                "❌ Failed to reset Lorax settings. Please try again later.".to_string()
             }
-            Ok(())
-        })
-        .await
-    {
-        Ok(_) => {
-            ctx.say("⏱️ Durations updated!").await?;
         }
-        Err(e) => {
-            error!("Failed to update durations for guild {}: {}", guild_id, e);
-            ctx.say("❌ Failed to update durations. Please try again later.")
-                .await?;
-        }
-    }
+    };
 
+    handle
+        .edit(ctx, CreateReply::default().content(content).components(vec![]))
+        .await?;
     Ok(())
 }
 
-/// Reset Lorax settings and events
+/// Undo the most recent `/lorax reset`
 #[command(slash_command, guild_only, required_permissions = "ADMINISTRATOR")]
-pub async fn reset(ctx: Context<'_>) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+pub async fn restore(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
 
-    match ctx
-        .data()
-        .dbs
-        .lorax
-        .transaction(|db| {
-            db.events.remove(&guild_id);
-            db.settings.remove(&guild_id);
-            Ok(())
-        })
-        .await
-    {
-        Ok(_) => {
-            ctx.say("🔄 Lorax has been reset for this server.").await?;
+    if let Some(event) = ctx.data().dbs.lorax.get_event(guild_id).await {
+        if !matches!(event.stage, LoraxStage::Inactive | LoraxStage::Completed) {
+            say(ctx, "❌ An event is running. End it with `/lorax end` before restoring.")
+                .await?;
+            return Ok(());
+        }
+    }
+
+    let now = chrono::Utc::now().timestamp() as u64;
+    match ctx.data().dbs.lorax.restore(guild_id, now).await {
+        Ok(Some(reset)) => {
+            ctx.data().task_manager.sync_guild(guild_id).await;
+
+            let mut restored = Vec::new();
+            if reset.settings.is_some() {
+                restored.push("settings");
+            }
+            if reset.event.is_some() {
+                restored.push("event");
+            }
+            say(ctx, format!(
+                "♻️ Restored the Lorax {} removed by the reset <t:{}:R>.",
+                restored.join(" and "),
+                reset.reset_at
+            ))
+            .await?;
+        }
+        Ok(None) => {
+            say(ctx, "❌ There is no reset to undo.").await?;
         }
         Err(e) => {
-            error!("Failed to reset Lorax for guild {}: {}", guild_id, e);
-            ctx.say("❌ Failed to reset Lorax settings. Please try again later.")
-                .await?;
+            error!("Failed to restore Lorax for guild {}: {}", guild_id, e);
+            say(ctx, "❌ Failed to restore Lorax. Please try again later.").await?;
         }
     }
 
@@ -310,13 +513,13 @@ pub async fn submissions(
     ctx: Context<'_>,
     #[description = "Page number to view"] page: Option<usize>,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
     let page = page.unwrap_or(1).max(1);
 
     let event = match ctx.data().dbs.lorax.get_event(guild_id).await {
         Some(event) => event,
         None => {
-            ctx.say("⚪ No active Lorax event is running.").await?;
+            say(ctx, "⚪ No active Lorax event is running.").await?;
             return Ok(());
         }
     };
@@ -329,21 +532,20 @@ pub async fn submissions(
     });
 
     if matches!(event.stage, LoraxStage::Submission) && !has_manage_messages {
-        ctx.say("❌ Cannot view submissions during the submission phase.")
+        say(ctx, "❌ Cannot view submissions during the submission phase.")
             .await?;
         return Ok(());
     }
 
     let mut submissions: Vec<_> = event
-        .tree_submissions
-        .iter()
-        .map(|(user_id, tree)| (tree.clone(), *user_id))
+        .submissions()
+        .map(|(user_id, tree)| (tree.clone(), user_id))
         .collect();
     submissions.sort_by(|a, b| a.0.cmp(&b.0));
 
     let total_pages = (submissions.len() + ITEMS_PER_PAGE - 1) / ITEMS_PER_PAGE;
     if total_pages == 0 {
-        ctx.say("📝 No submissions yet!").await?;
+        say(ctx, "📝 No submissions yet!").await?;
         return Ok(());
     }
 
@@ -364,7 +566,7 @@ pub async fn submissions(
         entries.join("\n")
     );
 
-    ctx.say(msg).await?;
+    say(ctx, msg).await?;
     Ok(())
 }
 
@@ -374,13 +576,13 @@ pub async fn votes(
     ctx: Context<'_>,
     #[description = "Page number to view"] page: Option<usize>,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
     let page = page.unwrap_or(1).max(1);
 
     let event = match ctx.data().dbs.lorax.get_event(guild_id).await {
         Some(event) => event,
         None => {
-            ctx.say("⚪ No active Lorax event is running.").await?;
+            say(ctx, "⚪ No active Lorax event is running.").await?;
             return Ok(());
         }
     };
@@ -393,27 +595,28 @@ pub async fn votes(
     });
 
     if !matches!(event.stage, LoraxStage::Completed) && !has_manage_messages {
-        ctx.say("❌ Votes can only be viewed after the event is completed.")
+        say(ctx, "❌ Votes can only be viewed after the event is completed.")
             .await?;
         return Ok(());
     }
 
-    let total_votes = event.tree_votes.len();
+    let total_votes: usize =
+        event.tree_votes.keys().map(|user_id| event.vote_weight(*user_id)).sum();
 
     let mut vote_counts: std::collections::HashMap<String, (usize, Option<u64>)> =
         std::collections::HashMap::new();
     
-    // Count votes and track submitters
-    for tree in event.tree_votes.values() {
+    // Count (weighted) votes and track submitters
+    for (user_id, tree) in &event.tree_votes {
         let entry = vote_counts.entry(tree.clone()).or_insert((0, event.get_tree_submitter(tree)));
-        entry.0 += 1;
+        entry.0 += event.vote_weight(*user_id);
     }
 
     let mut vote_counts: Vec<_> = vote_counts.into_iter().collect();
     vote_counts.sort_by(|a, b| b.1.0.cmp(&a.1.0).then_with(|| a.0.cmp(&b.0)));
 
     if vote_counts.is_empty() {
-        ctx.say("📝 No votes cast yet!").await?;
+        say(ctx, "📝 No votes cast yet!").await?;
         return Ok(());
     }
 
@@ -453,7 +656,7 @@ pub async fn votes(
         entries.join("\n")
     );
 
-    ctx.say(msg).await?;
+    say(ctx, msg).await?;
     Ok(())
 }
 
@@ -463,28 +666,29 @@ pub async fn remove_submission(
     ctx: Context<'_>,
     #[description = "Tree name to remove"] tree: String,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
 
-    let mut event = match ctx.data().dbs.lorax.get_event(guild_id).await {
-        Some(event) => event,
-        None => {
-            ctx.say("⚪ No active Lorax event is running.").await?;
-            return Ok(());
-        }
-    };
+    if ctx.data().dbs.lorax.get_event(guild_id).await.is_none() {
+        say(ctx, "⚪ No active Lorax event is running.").await?;
+        return Ok(());
+    }
 
     let tree = tree.to_lowercase();
-    let submitter = event
-        .get_tree_submitter(&tree)
-        .ok_or("That tree name was not found")?;
-
-    event.tree_submissions.remove(&submitter);
-    event.eliminated_trees.insert(tree.clone());
+    ctx.data()
+        .dbs
+        .lorax
+        .modify_event(guild_id, |event| {
+            event
+                .remove_submission(&tree)
+                .ok_or("That tree name was not found")?;
 
-    event.tree_votes.retain(|_, voted_tree| voted_tree != &tree);
+            event.eliminated_trees.insert(tree.clone());
+            event.tree_votes.retain(|_, voted_tree| voted_tree != &tree);
+            Ok(())
+        })
+        .await?;
 
-    let _ = ctx.data().dbs.lorax.update_event(guild_id, event).await;
-    ctx.say(format!(
+    say(ctx, format!(
         "🗑️ Removed submission \"{}\" and any related votes.",
         tree
     ))
@@ -498,23 +702,190 @@ pub async fn remove_vote(
     ctx: Context<'_>,
     #[description = "User to remove vote from"] user: serenity::User,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
 
-    let mut event = match ctx.data().dbs.lorax.get_event(guild_id).await {
-        Some(event) => event,
-        None => {
-            ctx.say("⚪ No active Lorax event is running.").await?;
-            return Ok(());
-        }
-    };
+    if ctx.data().dbs.lorax.get_event(guild_id).await.is_none() {
+        say(ctx, "⚪ No active Lorax event is running.").await?;
+        return Ok(());
+    }
+
+    let removed = ctx
+        .data()
+        .dbs
+        .lorax
+        .modify_event(guild_id, |event| {
+            Ok(event.tree_votes.remove(&user.id.get()).is_some())
+        })
+        .await?;
 
-    if event.tree_votes.remove(&user.id.get()).is_some() {
-        let _ = ctx.data().dbs.lorax.update_event(guild_id, event).await;
-        ctx.say(format!("🗑️ Removed vote from {}.", user.mention()))
+    if removed {
+        say(ctx, format!("🗑️ Removed vote from {}.", user.mention()))
             .await?;
     } else {
-        ctx.say(format!("❌ {} has not voted.", user.mention()))
+        say(ctx, format!("❌ {} has not voted.", user.mention()))
             .await?;
     }
     Ok(())
 }
+
+#[derive(Debug, poise::Modal)]
+#[name = "Reject submission"]
+struct RejectModal {
+    #[name = "Why is this name rejected?"]
+    #[min_length = 3]
+    #[max_length = 200]
+    reason: String,
+}
+
+/// Queued names shown after the one being reviewed.
+const QUEUE_PREVIEW: usize = 10;
+
+fn render_queue(pending: &[PendingSubmission]) -> String {
+    let Some(next) = pending.first() else {
+        return "📭 No submissions are waiting for approval.".to_string();
+    };
+    let replacing = next
+        .replace
+        .as_ref()
+        .map(|old| format!(", replacing \"{}\"", old))
+        .unwrap_or_default();
+    let mut text = format!(
+        "📋 **{} submission(s) awaiting approval**\n\nNext: **{}** by <@{}>{}, submitted <t:{}:R>",
+        pending.len(),
+        next.tree,
+        next.user_id,
+        replacing,
+        next.submitted_at
+    );
+    let later: Vec<&str> = pending
+        .iter()
+        .skip(1)
+        .take(QUEUE_PREVIEW)
+        .map(|queued| queued.tree.as_str())
+        .collect();
+    if !later.is_empty() {
+        text.push_str(&format!("\nThen: {}", later.join(", ")));
+    }
+    text
+}
+
+fn queue_buttons(pending: &[PendingSubmission]) -> Vec<CreateActionRow> {
+    if pending.is_empty() {
+        return Vec::new();
+    }
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new("queue_approve")
+            .style(ButtonStyle::Success)
+            .label("Approve"),
+        CreateButton::new("queue_reject")
+            .style(ButtonStyle::Danger)
+            .label("Reject"),
+    ])]
+}
+
+async fn pending_submissions(ctx: Context<'_>, guild_id: u64) -> Vec<PendingSubmission> {
+    ctx.data()
+        .dbs
+        .lorax
+        .get_event(guild_id)
+        .await
+        .map(|event| event.pending_submissions)
+        .unwrap_or_default()
+}
+
+/// Review submissions waiting for approval
+#[command(slash_command, guild_only, ephemeral, required_permissions = "MANAGE_MESSAGES")]
+pub async fn queue(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let mut pending = pending_submissions(ctx, guild_id).await;
+
+    let handle = send(
+        ctx,
+        CreateReply::default()
+            .content(render_queue(&pending))
+            .components(queue_buttons(&pending)),
+    )
+    .await?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    while let Some(interaction) = handle
+        .message()
+        .await?
+        .await_component_interaction(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .timeout(Duration::from_secs(300))
+        .await
+    {
+        let Some(tree) = pending.first().map(|next| next.tree.clone()) else {
+            break;
+        };
+        let lorax = &ctx.data().dbs.lorax;
+        let (result, reason) = match interaction.data.custom_id.as_str() {
+            "queue_approve" => {
+                interaction.defer(ctx.http()).await?;
+                (lorax.approve_submission(guild_id, &tree).await, None)
+            }
+            "queue_reject" => {
+                let modal = poise::execute_modal_on_component_interaction::<RejectModal>(
+                    ctx,
+                    interaction.clone(),
+                    None,
+                    Some(Duration::from_secs(300)),
+                )
+                .await?;
+                let Some(modal) = modal else {
+                    continue;
+                };
+                let reason = modal.reason.trim().to_string();
+                let result = lorax.reject_submission(guild_id, &tree, reason.clone()).await;
+                (result, Some(reason))
+            }
+            _ => continue,
+        };
+
+        let status = match result {
+            Ok(reviewed) => {
+                let (status, dm) = match &reason {
+                    None => (
+                        format!("✅ Approved **{}**.", tree),
+                        format!("🌳 Your tree name \"**{}**\" was approved!", tree),
+                    ),
+                    Some(reason) => (
+                        format!("🚫 Rejected **{}**.", tree),
+                        format!("🚫 Your tree name \"**{}**\" was rejected: {}", tree, reason),
+                    ),
+                };
+                let dm = CreateMessage::new().content(dm);
+                let preferences = &ctx.data().dbs.preferences;
+                send_dm(ctx.serenity_context(), preferences, reviewed.user_id, dm).await;
+                status
+            }
+            Err(e) => format!("❌ {}", e),
+        };
+
+        pending = pending_submissions(ctx, guild_id).await;
+        handle
+            .edit(
+                ctx,
+                CreateReply::default()
+                    .content(format!("{}\n\n{}", status, render_queue(&pending)))
+                    .components(queue_buttons(&pending)),
+            )
+            .await?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+    }
+
+    handle
+        .edit(
+            ctx,
+            CreateReply::default()
+                .content(render_queue(&pending))
+                .components(Vec::new()),
+        )
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,342 @@
+//! Browsing completed Lorax events.
+
+use crate::modules::lorax::database::{ArchivedEvent, MemberStats};
+use crate::modules::system::database::Theme;
+use crate::utils::embed::titled;
+use crate::utils::reply::{say, send};
+use crate::utils::validate;
+use crate::{Context, Error};
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, CreateActionRow, CreateButton, CreateEmbed,
+    CreateInteractionResponse, CreateInteractionResponseMessage,
+};
+use poise::{command, ChoiceParameter, CreateReply};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// Events shown per `/lorax history` page.
+const PAGE_SIZE: usize = 3;
+
+/// Winners listed by `/lorax winners`.
+const HALL_OF_FAME_SIZE: usize = 20;
+
+/// Winning names listed by `/lorax profile`.
+const PROFILE_WINS: usize = 10;
+
+/// A winning tree and, when known, who submitted it.
+fn credit(tree: &str, submitter: Option<u64>) -> String {
+    match submitter {
+        Some(user_id) => format!("**{}** by <@{}>", tree, user_id),
+        None => format!("**{}**", tree),
+    }
+}
+
+/// Every winning tree of the guild's history, oldest first, with its submitter and when
+/// it won.
+fn all_wins(history: &[ArchivedEvent]) -> Vec<(&str, Option<u64>, u64)> {
+    history
+        .iter()
+        .flat_map(|archived| {
+            archived
+                .winners
+                .iter()
+                .map(move |(tree, submitter)| (tree.as_str(), *submitter, archived.ended_at))
+        })
+        .collect()
+}
+
+fn describe(archived: &ArchivedEvent) -> String {
+    let winner = if archived.winners.is_empty() {
+        "No winner".to_string()
+    } else {
+        let credits: Vec<String> =
+            archived.winners.iter().map(|(tree, submitter)| credit(tree, *submitter)).collect();
+        format!("🥇 {}", credits.join(", "))
+    };
+    let votes: usize = archived
+        .rounds
+        .iter()
+        .map(|round| round.tally.iter().map(|(_, votes)| votes).sum::<usize>())
+        .sum();
+
+    let mut text = format!("**<t:{}:D>** — {}", archived.ended_at, winner);
+    let runners_up: Vec<&str> = archived
+        .standings
+        .iter()
+        .skip(archived.winners.len())
+        .take(4)
+        .map(String::as_str)
+        .collect();
+    if !runners_up.is_empty() {
+        text.push_str(&format!("\nRunners-up: {}", runners_up.join(", ")));
+    }
+    text.push_str(&format!(
+        "\n{} name(s) submitted, {} vote(s) over {} round(s)",
+        archived.submissions.len(),
+        votes,
+        archived.rounds.len()
+    ));
+    text
+}
+
+fn render(theme: &Theme, history: &[ArchivedEvent], page: usize, pages: usize) -> CreateEmbed {
+    let entries: Vec<String> = history
+        .iter()
+        .rev()
+        .skip(page * PAGE_SIZE)
+        .take(PAGE_SIZE)
+        .map(describe)
+        .collect();
+    titled(theme, format!("📜 Lorax History ({}/{})", page + 1, pages))
+        .description(entries.join("\n\n"))
+}
+
+fn buttons(page: usize, pages: usize) -> Vec<CreateActionRow> {
+    if pages < 2 {
+        return Vec::new();
+    }
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new("history_prev")
+            .emoji('◀')
+            .style(ButtonStyle::Secondary)
+            .disabled(page == 0),
+        CreateButton::new("history_next")
+            .emoji('▶')
+            .style(ButtonStyle::Secondary)
+            .disabled(page + 1 >= pages),
+    ])]
+}
+
+/// Browse this server's past Lorax events
+#[command(slash_command, guild_only)]
+pub async fn history(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let history = ctx.data().dbs.lorax.history(guild_id).await;
+    if history.is_empty() {
+        say(ctx, "⚪ No Lorax events have finished here yet.").await?;
+        return Ok(());
+    }
+
+    let theme = ctx.data().dbs.system.get_theme(guild_id).await;
+    let pages = history.len().div_ceil(PAGE_SIZE);
+    let mut page = 0;
+
+    let reply = CreateReply::default()
+        .embed(render(&theme, &history, page, pages))
+        .components(buttons(page, pages));
+    let message = send(ctx, reply).await?;
+    if pages < 2 {
+        return Ok(());
+    }
+
+    while let Some(interaction) = message
+        .message()
+        .await?
+        .await_component_interaction(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .timeout(Duration::from_secs(120))
+        .await
+    {
+        match interaction.data.custom_id.as_str() {
+            "history_prev" => page = page.saturating_sub(1),
+            "history_next" => page = (page + 1).min(pages - 1),
+            _ => continue,
+        }
+        let update = CreateInteractionResponseMessage::new()
+            .embed(render(&theme, &history, page, pages))
+            .components(buttons(page, pages));
+        interaction
+            .create_response(ctx.http(), CreateInteractionResponse::UpdateMessage(update))
+            .await?;
+    }
+
+    message
+        .edit(
+            ctx,
+            CreateReply::default()
+                .embed(render(&theme, &history, page, pages))
+                .components(Vec::new()),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Hall of fame: every winning tree name and who submitted it
+#[command(slash_command, guild_only)]
+pub async fn winners(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let history = ctx.data().dbs.lorax.history(guild_id).await;
+    let won = all_wins(&history);
+    if won.is_empty() {
+        say(ctx, "⚪ No Lorax event has had a winner here yet.").await?;
+        return Ok(());
+    }
+
+    let mut lines: Vec<String> = won
+        .iter()
+        .rev()
+        .take(HALL_OF_FAME_SIZE)
+        .map(|(tree, submitter, ended_at)| {
+            format!("🏆 {} — <t:{}:D>", credit(tree, *submitter), ended_at)
+        })
+        .collect();
+    if won.len() > HALL_OF_FAME_SIZE {
+        lines.push(format!("…and {} earlier winners", won.len() - HALL_OF_FAME_SIZE));
+    }
+
+    let mut wins: HashMap<u64, usize> = HashMap::new();
+    for user_id in won.iter().filter_map(|(_, submitter, _)| *submitter) {
+        *wins.entry(user_id).or_default() += 1;
+    }
+    let mut champions: Vec<(u64, usize)> = wins.into_iter().filter(|(_, n)| *n > 1).collect();
+    champions.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    if !champions.is_empty() {
+        lines.push(String::new());
+        lines.push("**Most wins**".to_string());
+        lines.extend(
+            champions
+                .iter()
+                .take(3)
+                .map(|(user_id, n)| format!("- <@{}>: {} wins", user_id, n)),
+        );
+    }
+
+    let theme = ctx.data().dbs.system.get_theme(guild_id).await;
+    let embed = titled(&theme, "🌳 Lorax Hall of Fame").description(lines.join("\n"));
+    send(ctx, CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// What `/lorax leaderboard` ranks members by.
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum Ranking {
+    Events,
+    Submissions,
+    Votes,
+    Wins,
+    #[name = "Best streak"]
+    Streak,
+}
+
+impl Ranking {
+    fn of(&self, events: u32, stats: &MemberStats) -> u32 {
+        match self {
+            Self::Events => events,
+            Self::Submissions => stats.submissions,
+            Self::Votes => stats.votes,
+            Self::Wins => stats.wins,
+            Self::Streak => stats.best_streak,
+        }
+    }
+
+    fn unit(&self) -> &'static str {
+        match self {
+            Self::Events => "event(s)",
+            Self::Submissions => "name(s) submitted",
+            Self::Votes => "vote(s)",
+            Self::Wins => "win(s)",
+            Self::Streak => "event(s) in a row",
+        }
+    }
+}
+
+/// Members who have done the most in this server's Lorax events
+#[command(slash_command, guild_only)]
+pub async fn leaderboard(
+    ctx: Context<'_>,
+    #[description = "What to rank members by (default: events)"] by: Option<Ranking>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let by = by.unwrap_or(Ranking::Events);
+    let participation = ctx.data().dbs.lorax.participation(guild_id).await;
+    let stats = ctx.data().dbs.lorax.member_stats(guild_id).await;
+
+    let empty = MemberStats::default();
+    let mut scores: Vec<(u64, u32)> = participation
+        .counts
+        .keys()
+        .chain(stats.keys())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .map(|user_id| {
+            let events = participation.counts.get(user_id).copied().unwrap_or_default();
+            (*user_id, by.of(events, stats.get(user_id).unwrap_or(&empty)))
+        })
+        .filter(|(_, score)| *score > 0)
+        .collect();
+    if scores.is_empty() {
+        say(ctx, "⚪ Nobody has taken part in a completed Lorax event here yet.").await?;
+        return Ok(());
+    }
+
+    scores.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let lines: Vec<String> = scores
+        .iter()
+        .take(HALL_OF_FAME_SIZE)
+        .enumerate()
+        .map(|(i, (user_id, score))| {
+            format!("{}. <@{}> — {} {}", i + 1, user_id, score, by.unit())
+        })
+        .collect();
+
+    let theme = ctx.data().dbs.system.get_theme(guild_id).await;
+    let title = format!("🎟️ Lorax Leaderboard: {}", by.name());
+    let embed = titled(&theme, title).description(lines.join("\n"));
+    send(ctx, CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// A member's Lorax record in this server: totals, streaks and wins
+#[command(slash_command, guild_only)]
+pub async fn profile(
+    ctx: Context<'_>,
+    #[description = "Member to show (default: you)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let user = user.as_ref().unwrap_or_else(|| ctx.author());
+    let lorax = &ctx.data().dbs.lorax;
+    let events = lorax.participation(guild_id).await.counts.get(&user.id.get()).copied();
+    let stats = lorax.member_stats(guild_id).await.remove(&user.id.get());
+    if events.is_none() && stats.is_none() {
+        say(ctx, format!("⚪ {} hasn't taken part in a Lorax event here yet.", user.name))
+            .await?;
+        return Ok(());
+    }
+    let events = events.unwrap_or_default();
+    let stats = stats.unwrap_or_default();
+
+    let history = lorax.history(guild_id).await;
+    let won: Vec<(&str, u64)> = all_wins(&history)
+        .into_iter()
+        .filter(|(_, submitter, _)| *submitter == Some(user.id.get()))
+        .map(|(tree, _, ended_at)| (tree, ended_at))
+        .collect();
+
+    let mut lines = vec![
+        format!("🎟️ **Events:** {}", events),
+        format!("📝 **Names submitted:** {}", stats.submissions),
+        format!("🗳️ **Votes cast:** {}", stats.votes),
+        format!("🏆 **Wins:** {}", stats.wins),
+        format!(
+            "🔥 **Streak:** {} (best {})",
+            stats.current_streak(history.len()),
+            stats.best_streak
+        ),
+    ];
+    if !won.is_empty() {
+        lines.push(String::new());
+        lines.push("**Winning names**".to_string());
+        lines.extend(
+            won.iter()
+                .rev()
+                .take(PROFILE_WINS)
+                .map(|(tree, ended_at)| format!("- **{}** — <t:{}:D>", tree, ended_at)),
+        );
+    }
+
+    let theme = ctx.data().dbs.system.get_theme(guild_id).await;
+    let embed = titled(&theme, format!("🌳 {}'s Lorax Profile", user.name))
+        .description(lines.join("\n"));
+    send(ctx, CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
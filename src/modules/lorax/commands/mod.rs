@@ -1,6 +1,9 @@
 use poise::command;
 
 pub mod admin;
+pub mod history;
+pub mod names;
+pub mod schedule;
 pub mod settings;
 pub mod users;
 
@@ -9,21 +12,44 @@ pub mod users;
     slash_command,
     subcommands(
         "admin::start",
+        "admin::preview",
         "admin::end",
+        "admin::extend",
         "admin::duration",
         "admin::force_advance",
         "admin::reset",
+        "admin::restore",
         "admin::submissions",
         "admin::votes",
         "admin::remove_submission",
         "admin::remove_vote",
+        "admin::queue",
+        "schedule::schedule",
         "settings::channel",
         "settings::roles",
         "settings::durations",
+        "settings::max_submissions",
+        "settings::approval",
+        "settings::live_tally",
+        "settings::reaction_voting",
+        "settings::vote_lock",
+        "settings::voter_role",
+        "settings::vote_weight",
+        "settings::theme",
+        "settings::participant_role",
+        "settings::reminders",
         "settings::view",
+        "names::names",
         "users::submit",
+        "users::withdraw",
+        "users::pitch",
         "users::vote",
         "users::check",
+        "users::stats",
+        "history::history",
+        "history::winners",
+        "history::leaderboard",
+        "history::profile",
     )
 )]
 pub async fn lorax(_ctx: crate::Context<'_>) -> Result<(), crate::Error> {
@@ -0,0 +1,168 @@
+//! Commands for the rules tree names have to follow.
+
+use crate::modules::lorax::database::{NameCharacters, NameRules};
+use crate::utils::reply::say;
+use crate::utils::validate;
+use crate::{Context, Error};
+use poise::command;
+
+/// Most reserved names or extra banned words per guild.
+const MAX_LIST_ENTRIES: usize = 100;
+
+/// Set the rules tree names have to follow
+#[command(
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("length", "characters", "reserve", "unreserve", "ban", "unban", "rules")
+)]
+pub async fn names(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Adds `entry` to a rules list, failing if it's already there or the list is full.
+fn add_entry(list: &mut Vec<String>, entry: &str) -> Result<(), String> {
+    if list.iter().any(|existing| existing == entry) {
+        return Err(format!("**{}** is already on the list.", entry));
+    }
+    if list.len() >= MAX_LIST_ENTRIES {
+        return Err(format!("The list is full ({} entries).", MAX_LIST_ENTRIES));
+    }
+    list.push(entry.to_string());
+    Ok(())
+}
+
+fn remove_entry(list: &mut Vec<String>, entry: &str) -> Result<(), String> {
+    let before = list.len();
+    list.retain(|existing| existing != entry);
+    if list.len() == before {
+        return Err(format!("**{}** isn't on the list.", entry));
+    }
+    Ok(())
+}
+
+/// Applies a rules change and replies with `done` or the reason it was refused.
+async fn update(
+    ctx: Context<'_>,
+    change: impl Fn(&mut NameRules) -> Result<(), String>,
+    done: String,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    match ctx.data().dbs.lorax.update_name_rules(guild_id, change).await {
+        Ok(_) => say(ctx, done).await?,
+        Err(e) => say(ctx, format!("❌ {}", e)).await?,
+    };
+    Ok(())
+}
+
+/// Set how long tree names can be
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn length(
+    ctx: Context<'_>,
+    #[description = "Shortest allowed name"]
+    #[min = 1]
+    #[max = 64]
+    min: usize,
+    #[description = "Longest allowed name"]
+    #[min = 1]
+    #[max = 64]
+    max: usize,
+) -> Result<(), Error> {
+    if min > max {
+        say(ctx, "❌ The shortest length can't be more than the longest.").await?;
+        return Ok(());
+    }
+    let change = |rules: &mut NameRules| {
+        rules.min_length = min;
+        rules.max_length = max;
+        Ok(())
+    };
+    let done = format!("📏 Tree names must now be {} to {} characters long.", min, max);
+    update(ctx, change, done).await
+}
+
+/// Set which characters tree names can use
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn characters(
+    ctx: Context<'_>,
+    #[description = "Allowed characters"] allowed: NameCharacters,
+) -> Result<(), Error> {
+    let change = |rules: &mut NameRules| {
+        rules.characters = allowed;
+        Ok(())
+    };
+    let done = format!("🔤 Tree names can now use {}.", allowed.describe());
+    update(ctx, change, done).await
+}
+
+/// Reserve a tree name so nobody can submit it
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn reserve(
+    ctx: Context<'_>,
+    #[description = "Name to reserve"] name: String,
+) -> Result<(), Error> {
+    let name = name.trim().to_lowercase();
+    let done = format!("🌲 **{}** is now reserved.", name);
+    update(ctx, |rules| add_entry(&mut rules.reserved, &name), done).await
+}
+
+/// Allow a reserved tree name again
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn unreserve(
+    ctx: Context<'_>,
+    #[description = "Reserved name to allow"] name: String,
+) -> Result<(), Error> {
+    let name = name.trim().to_lowercase();
+    let done = format!("🌲 **{}** can be submitted again.", name);
+    update(ctx, |rules| remove_entry(&mut rules.reserved, &name), done).await
+}
+
+/// Ban a word from tree names, on top of the built-in list
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD", ephemeral)]
+pub async fn ban(
+    ctx: Context<'_>,
+    #[description = "Word to ban"] word: String,
+) -> Result<(), Error> {
+    let word = word.trim().to_lowercase();
+    let done = "🚫 Banned the word.".to_string();
+    update(ctx, |rules| add_entry(&mut rules.banned_words, &word), done).await
+}
+
+/// Allow a word you banned again
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD", ephemeral)]
+pub async fn unban(
+    ctx: Context<'_>,
+    #[description = "Banned word to allow"] word: String,
+) -> Result<(), Error> {
+    let word = word.trim().to_lowercase();
+    let done = "✅ Unbanned the word.".to_string();
+    update(ctx, |rules| remove_entry(&mut rules.banned_words, &word), done).await
+}
+
+/// Show the rules tree names have to follow
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD", ephemeral)]
+pub async fn rules(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let rules = ctx.data().dbs.lorax.get_settings(guild_id).await?.name_rules;
+
+    // Banned words are only counted, so the list isn't repeated back in the channel
+    let reserved = if rules.reserved.is_empty() {
+        "None".to_string()
+    } else {
+        rules.reserved.join(", ")
+    };
+    say(ctx, format!(
+        "📋 **Tree Name Rules**\n\
+        📏 **Length:** {} to {} characters\n\
+        🔤 **Characters:** {}\n\
+        🌲 **Reserved:** {}\n\
+        🚫 **Extra Banned Words:** {}",
+        rules.min_length,
+        rules.max_length,
+        rules.characters.describe(),
+        reserved,
+        rules.banned_words.len()
+    ))
+    .await?;
+    Ok(())
+}
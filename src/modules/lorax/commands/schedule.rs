@@ -0,0 +1,118 @@
+//! Commands for scheduling Lorax events ahead of time.
+
+use crate::modules::lorax::{database::Recurrence, task::get_current_timestamp};
+use crate::utils::reply::say;
+use crate::utils::time::{format_timestamp, parse_datetime};
+use crate::utils::validate;
+use crate::{Context, Error};
+use poise::{command, ChoiceParameter};
+
+/// Start Lorax events automatically at set times
+#[command(
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("add", "list", "cancel")
+)]
+pub async fn schedule(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Schedule a Lorax event; it's announced a day before it starts
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "When it starts, e.g. 2025-06-01 18:00 (server timezone)"] start: String,
+    #[description = "How often it repeats"] recurrence: Recurrence,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let tz = ctx.data().dbs.guild_config().timezone(guild_id).await;
+
+    let start_at = match parse_datetime(&start, tz) {
+        Ok(start_at) => start_at,
+        Err(e) => {
+            say(ctx, format!("❌ {}", e)).await?;
+            return Ok(());
+        }
+    };
+    if start_at <= get_current_timestamp() {
+        say(ctx, "❌ That time has already passed.").await?;
+        return Ok(());
+    }
+
+    let settings = ctx.data().dbs.lorax.get_settings(guild_id).await?;
+    let scheduled = match ctx
+        .data()
+        .dbs
+        .lorax
+        .add_schedule(guild_id, start_at, recurrence, ctx.author().id.get())
+        .await
+    {
+        Ok(scheduled) => scheduled,
+        Err(e) => {
+            say(ctx, format!("❌ {}", e)).await?;
+            return Ok(());
+        }
+    };
+    ctx.data().task_manager.sync_guild(guild_id).await;
+
+    let mut reply = format!(
+        "📅 Scheduled event `{}` starts <t:{}:R> ({}), repeating: {}.",
+        scheduled.id,
+        start_at,
+        format_timestamp(start_at, tz),
+        recurrence.name()
+    );
+    if settings.lorax_channel.is_none() {
+        reply.push_str("\n⚠️ Set a Lorax channel with `/lorax channel` or it won't start.");
+    }
+    say(ctx, reply).await?;
+    Ok(())
+}
+
+/// List this server's scheduled Lorax events
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let schedules = ctx.data().dbs.lorax.schedules(guild_id).await;
+    if schedules.is_empty() {
+        say(ctx, "⚪ No Lorax events are scheduled.").await?;
+        return Ok(());
+    }
+
+    let tz = ctx.data().dbs.guild_config().timezone(guild_id).await;
+    let lines: Vec<_> = schedules
+        .iter()
+        .map(|scheduled| {
+            format!(
+                "`{}` <t:{}:R> ({}) — {}, by <@{}>",
+                scheduled.id,
+                scheduled.start_at,
+                format_timestamp(scheduled.start_at, tz),
+                scheduled.recurrence.name(),
+                scheduled.created_by
+            )
+        })
+        .collect();
+    say(ctx, format!("📅 **Scheduled Lorax events**\n{}", lines.join("\n"))).await?;
+    Ok(())
+}
+
+/// Cancel a scheduled Lorax event, including its repeats
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn cancel(
+    ctx: Context<'_>,
+    #[description = "ID from /lorax schedule list"] id: u64,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    match ctx.data().dbs.lorax.cancel_schedule(guild_id, id).await? {
+        Some(_) => {
+            ctx.data().task_manager.sync_guild(guild_id).await;
+            say(ctx, format!("🗑️ Cancelled scheduled event `{}`.", id)).await?;
+        }
+        None => {
+            say(ctx, format!("❌ No scheduled event `{}`.", id)).await?;
+        }
+    }
+    Ok(())
+}
@@ -1,8 +1,19 @@
-use crate::{Context, Error};
+use crate::modules::lorax::database::EventTheme;
+use crate::modules::roles::database::RoleChange;
+use crate::utils::reply::say;
+use crate::{
+    utils::{
+        duration::{format_duration, DurationUnit},
+        validate::{self, Invalid},
+    },
+    Context, Error,
+};
 use poise::{
     command,
-    serenity_prelude::{self as serenity, Mentionable},
+    serenity_prelude::{self as serenity, ChannelType, Mentionable},
 };
+use std::collections::BTreeMap;
+use std::time::Duration;
 use tracing::error;
 
 /// Configure Lorax settings for your server
@@ -16,67 +27,73 @@ pub async fn config(_ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-/// Set the announcement channel
+/// Set the announcement channel (a text, news or forum channel)
 #[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
 pub async fn channel(
     ctx: Context<'_>,
     #[description = "Channel for Lorax announcements"] channel: serenity::Channel,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
 
-    let text_channel = match channel.guild() {
-        Some(channel) => channel,
-        None => {
-            ctx.say("❌ Please select a text channel.").await?;
-            return Ok(());
-        }
-    };
+    let target = validate::channel(
+        ctx,
+        channel.id(),
+        &[ChannelType::Text, ChannelType::News, ChannelType::Forum],
+        "Lorax announcements are posted there",
+    )
+    .await?;
+    let is_forum = target.kind == ChannelType::Forum;
 
     let bot_permissions = match {
-        let guild = ctx.guild().unwrap();
+        let guild = validate::cached_guild(ctx.guild())?;
         let bot_member = guild.members.get(&ctx.framework().bot_id);
         if let Some(bot_member) = bot_member {
-            Ok(guild.user_permissions_in(&text_channel, bot_member))
+            Ok(guild.user_permissions_in(&target, bot_member))
         } else {
             Err(())
         }
     } {
         Ok(perms) => perms,
         Err(_) => {
-            ctx.say("❌ Failed to verify bot permissions. Please try again.")
+            say(ctx, "❌ Failed to verify bot permissions. Please try again.")
                 .await?;
             return Ok(());
         }
     };
 
     if !bot_permissions.send_messages() || !bot_permissions.embed_links() {
-        ctx.say("❌ I need permission to send messages and embed links in that channel.")
+        say(ctx, "❌ I need permission to send messages and embed links in that channel.")
+            .await?;
+        return Ok(());
+    }
+    // Every stage after the first is posted inside the event's forum post
+    if is_forum && !bot_permissions.send_messages_in_threads() {
+        say(ctx, "❌ I need permission to send messages in posts in that forum.")
             .await?;
         return Ok(());
     }
 
-    let channel_id = text_channel.id.get();
+    let channel_id = target.id.get();
 
     match ctx
         .data()
         .dbs
         .lorax
-        .transaction(|db| {
-            let settings = db.settings.entry(guild_id).or_default();
+        .update_settings(guild_id, |settings| {
             settings.lorax_channel = Some(channel_id);
-            Ok(())
         })
         .await
     {
         Ok(_) => {
-            ctx.say(format!(
-                "✅ Lorax announcements will be in {}!",
-                text_channel.mention()
-            ))
-            .await?;
+            let msg = if is_forum {
+                format!("✅ Each Lorax event will get its own post in {}!", target.mention())
+            } else {
+                format!("✅ Lorax announcements will be in {}!", target.mention())
+            };
+            say(ctx, msg).await?;
         }
         Err(_e) => {
-            ctx.say("❌ Failed to save channel settings. Please try again later.")
+            say(ctx, "❌ Failed to save channel settings. Please try again later.")
                 .await?;
         }
     }
@@ -92,10 +109,10 @@ pub async fn roles(
     #[description = "Role awarded to winners"] winner_role: Option<serenity::Role>,
     #[description = "Role for previous winners"] alumni_role: Option<serenity::Role>,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
 
     let bot_top_role = {
-        let guild = ctx.guild().unwrap();
+        let guild = validate::cached_guild(ctx.guild())?;
         let bot_member = guild.members.get(&ctx.framework().bot_id);
         if let Some(bot_member) = bot_member {
             let bot_roles: Vec<_> = bot_member
@@ -118,7 +135,7 @@ pub async fn roles(
     if let Some(top_role) = bot_top_role {
         for role in &roles_to_validate {
             if role.position >= top_role.position {
-                ctx.say("One or more roles are positioned higher than the bot's highest role.")
+                say(ctx, "One or more roles are positioned higher than the bot's highest role.")
                     .await?;
                 return Ok(());
             }
@@ -131,8 +148,7 @@ pub async fn roles(
     ctx.data()
         .dbs
         .lorax
-        .transaction(|db| {
-            let settings = db.settings.entry(guild_id).or_default();
+        .update_settings(guild_id, |settings| {
             if let Some(role) = event_role {
                 settings.lorax_role = Some(role.id.get());
             }
@@ -142,7 +158,6 @@ pub async fn roles(
             if let Some(role) = alumni_role {
                 settings.alumni_role = Some(role.id.get());
             }
-            Ok(())
         })
         .await?;
 
@@ -152,32 +167,48 @@ pub async fn roles(
         response.push_str("\n⚠️ Warning: Winner role is set but no alumni role is configured. Previous winners will lose their status.");
     }
 
-    ctx.say(response).await?;
+    say(ctx, response).await?;
     Ok(())
 }
 
+/// Parses an optional phase duration into whole minutes.
+fn parse_phase_minutes(input: Option<&String>) -> Result<Option<u64>, Invalid> {
+    let Some(input) = input else {
+        return Ok(None);
+    };
+
+    let duration = validate::duration(input, DurationUnit::Minutes, Duration::from_secs(60), None)?;
+    Ok(Some(duration.as_secs() / 60))
+}
+
 /// Set event phase durations
 #[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
 pub async fn durations(
     ctx: Context<'_>,
-    #[description = "Minutes for submissions"] submission: Option<u64>,
-    #[description = "Minutes for voting"] voting: Option<u64>,
-    #[description = "Minutes for tiebreakers"] tiebreaker: Option<u64>,
+    #[description = "Submission phase, e.g. 1h or 2d (plain numbers are minutes)"]
+    submission: Option<String>,
+    #[description = "Voting phase, e.g. 30m or 1d (plain numbers are minutes)"]
+    voting: Option<String>,
+    #[description = "Tiebreaker rounds, e.g. 15m (plain numbers are minutes)"]
+    tiebreaker: Option<String>,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
 
     if submission.is_none() && voting.is_none() && tiebreaker.is_none() {
-        ctx.say("❌ Please specify at least one duration to update.")
+        say(ctx, "❌ Please specify at least one duration to update.")
             .await?;
         return Ok(());
     }
 
+    let submission = parse_phase_minutes(submission.as_ref())?;
+    let voting = parse_phase_minutes(voting.as_ref())?;
+    let tiebreaker = parse_phase_minutes(tiebreaker.as_ref())?;
+
     match ctx
         .data()
         .dbs
         .lorax
-        .transaction(|db| {
-            let settings = db.settings.entry(guild_id).or_default();
+        .update_settings(guild_id, |settings| {
             if let Some(mins) = submission {
                 settings.submission_duration = mins;
             }
@@ -187,16 +218,15 @@ pub async fn durations(
             if let Some(mins) = tiebreaker {
                 settings.tiebreaker_duration = mins;
             }
-            Ok(())
         })
         .await
     {
         Ok(_) => {
-            ctx.say("⏱️ Durations updated!").await?;
+            say(ctx, "⏱️ Durations updated!").await?;
         }
         Err(e) => {
             error!("Failed to update durations for guild {}: {}", guild_id, e);
-            ctx.say("❌ Failed to update durations. Please try again later.")
+            say(ctx, "❌ Failed to update durations. Please try again later.")
                 .await?;
         }
     }
@@ -204,10 +234,390 @@ pub async fn durations(
     Ok(())
 }
 
+/// Set how many tree names each user can submit per event
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn max_submissions(
+    ctx: Context<'_>,
+    #[description = "Names per user (default 1)"]
+    #[min = 1]
+    #[max = 10]
+    limit: usize,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    ctx.data()
+        .dbs
+        .lorax
+        .update_settings(guild_id, |settings| settings.max_submissions = limit)
+        .await?;
+
+    say(ctx, format!(
+        "🌳 Users can now submit up to **{}** tree {} per event. This applies from the next event.",
+        limit,
+        if limit == 1 { "name" } else { "names" }
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Most example names a custom theme can have.
+const MAX_SAMPLES: usize = 10;
+
+/// Choose what Lorax events name, e.g. trees or mountains
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn theme(
+    ctx: Context<'_>,
+    #[description = "What events name"] theme: EventTheme,
+    #[description = "Custom: what's being named, e.g. planet"]
+    #[max_length = 20]
+    noun: Option<String>,
+    #[description = "Custom: emoji used in announcements"]
+    #[max_length = 8]
+    emoji: Option<String>,
+    #[description = "Custom: example names, comma separated"] examples: Option<String>,
+    #[description = "Custom: whether names can't match existing node names"]
+    check_node_names: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    let samples = examples.map(|examples| {
+        examples
+            .split(',')
+            .map(|sample| sample.trim().to_string())
+            .filter(|sample| !sample.is_empty())
+            .take(MAX_SAMPLES)
+            .collect::<Vec<_>>()
+    });
+    let settings = ctx
+        .data()
+        .dbs
+        .lorax
+        .update_settings(guild_id, |settings| {
+            settings.theme = theme;
+            let custom = &mut settings.custom_theme;
+            if let Some(noun) = noun.map(|noun| noun.trim().to_lowercase()) {
+                custom.noun = noun;
+            }
+            if let Some(emoji) = emoji {
+                custom.emoji = emoji.trim().to_string();
+            }
+            if let Some(samples) = samples {
+                custom.samples = samples;
+            }
+            if let Some(check) = check_node_names {
+                custom.check_node_names = check;
+            }
+        })
+        .await?;
+
+    say(ctx, format!(
+        "{} Lorax events will now name **{}s**. This applies from the next event.",
+        settings.theme_emoji(),
+        settings.noun()
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Make new submissions wait for a moderator's approval
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn approval(
+    ctx: Context<'_>,
+    #[description = "Whether submissions need approval in /lorax queue"] enabled: bool,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    ctx.data()
+        .dbs
+        .lorax
+        .update_settings(guild_id, |settings| settings.require_approval = enabled)
+        .await?;
+
+    let response = if enabled {
+        "🛂 New submissions will wait in `/lorax queue` for a moderator. This applies from the \
+        next event."
+    } else {
+        "🛂 Submissions will join the event straight away. This applies from the next event."
+    };
+    say(ctx, response).await?;
+    Ok(())
+}
+
+/// Show a live scoreboard of vote counts while voting is open
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn live_tally(
+    ctx: Context<'_>,
+    #[description = "Whether voting shows a public, live-updating vote count"] enabled: bool,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let lorax = &ctx.data().dbs.lorax;
+
+    lorax
+        .update_settings(guild_id, |settings| settings.live_tally = enabled)
+        .await?;
+    // Unlike most settings this also applies to a running event, so a tally that's
+    // causing bandwagoning can be switched off straight away
+    let _ = lorax
+        .modify_event(guild_id, |event| {
+            event.settings.live_tally = enabled;
+            Ok(())
+        })
+        .await;
+
+    let response = if enabled {
+        "📊 Voting will show a live tally of vote counts, updated every minute."
+    } else {
+        "📊 Vote counts will stay hidden until the results are announced."
+    };
+    say(ctx, response).await?;
+    Ok(())
+}
+
+/// Let members vote by reacting to a numbered ballot
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn reaction_voting(
+    ctx: Context<'_>,
+    #[description = "Whether each voting round also posts a ballot to vote on by reacting"]
+    enabled: bool,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    ctx.data()
+        .dbs
+        .lorax
+        .update_settings(guild_id, |settings| settings.reaction_voting = enabled)
+        .await?;
+
+    let response = if enabled {
+        "🔢 Voting rounds will post a numbered ballot; reacting with a number casts a vote. \
+        This applies from the next event."
+    } else {
+        "🔢 Votes will only be cast with `/lorax vote`. This applies from the next event."
+    };
+    say(ctx, response).await?;
+    Ok(())
+}
+
+/// Stop votes from changing in the final minutes of each voting round
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn vote_lock(
+    ctx: Context<'_>,
+    #[description = "Minutes before a round ends when votes lock (0 turns it off)"]
+    #[max = 1440]
+    minutes: u64,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let lorax = &ctx.data().dbs.lorax;
+
+    lorax
+        .update_settings(guild_id, |settings| settings.vote_lock = minutes)
+        .await?;
+    // Also applies to a running event, so a flipping war can be stopped mid-round
+    let _ = lorax
+        .modify_event(guild_id, |event| {
+            event.settings.vote_lock = minutes;
+            Ok(())
+        })
+        .await;
+
+    let response = if minutes == 0 {
+        "🔓 Votes can now be changed until each round ends.".to_string()
+    } else {
+        format!(
+            "🔒 Votes will lock in the final {} of each round. New votes are still taken, \
+            but existing ones can't change.",
+            format_duration(minutes * 60)
+        )
+    };
+    say(ctx, response).await?;
+    Ok(())
+}
+
+/// Only let members with a role vote, or clear it to let everyone vote
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn voter_role(
+    ctx: Context<'_>,
+    #[description = "Role members need to vote; leave empty to let everyone vote"]
+    role: Option<serenity::Role>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let role_id = role.as_ref().map(|role| role.id.get());
+    let lorax = &ctx.data().dbs.lorax;
+
+    lorax
+        .update_settings(guild_id, |settings| settings.voter_role = role_id)
+        .await?;
+    // Votes already cast stay; the role is checked as members vote
+    let _ = lorax
+        .modify_event(guild_id, |event| {
+            event.settings.voter_role = role_id;
+            Ok(())
+        })
+        .await;
+
+    let response = match role {
+        Some(role) => format!("🗳️ Only members with {} can vote now.", role.mention()),
+        None => "🗳️ Everyone can vote now.".to_string(),
+    };
+    say(ctx, response).await?;
+    Ok(())
+}
+
+/// Most roles with a vote weight a guild can have.
+const MAX_VOTE_WEIGHTS: usize = 10;
+
+/// Make votes from members with a role count more than once, e.g. for boosters
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn vote_weight(
+    ctx: Context<'_>,
+    #[description = "Role whose members' votes count more"] role: serenity::Role,
+    #[description = "How many votes they cast (1 removes the weight)"]
+    #[min = 1]
+    #[max = 10]
+    weight: u32,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let role_id = role.id.get();
+    let lorax = &ctx.data().dbs.lorax;
+
+    let settings = lorax.get_settings(guild_id).await.unwrap_or_default();
+    if weight > 1
+        && !settings.vote_weights.contains_key(&role_id)
+        && settings.vote_weights.len() >= MAX_VOTE_WEIGHTS
+    {
+        return Err(Invalid(format!(
+            "Only {} roles can have a vote weight; remove one first.",
+            MAX_VOTE_WEIGHTS
+        ))
+        .into());
+    }
+
+    let set = |weights: &mut BTreeMap<u64, u32>| {
+        if weight > 1 {
+            weights.insert(role_id, weight);
+        } else {
+            weights.remove(&role_id);
+        }
+    };
+    lorax
+        .update_settings(guild_id, |settings| set(&mut settings.vote_weights))
+        .await?;
+    // Applies to votes cast from now on; earlier votes keep the weight they had
+    let _ = lorax
+        .modify_event(guild_id, |event| {
+            set(&mut event.settings.vote_weights);
+            Ok(())
+        })
+        .await;
+
+    let response = match weight {
+        1 => format!("⚖️ Votes from {} count once again.", role.mention()),
+        weight => format!("⚖️ Votes from {} now count {} times.", role.mention(), weight),
+    };
+    say(ctx, response).await?;
+    Ok(())
+}
+
+/// Most reminders a guild can set per stage.
+const MAX_REMINDERS: usize = 5;
+
+/// Set when to remind the Lorax channel before a stage ends
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn reminders(
+    ctx: Context<'_>,
+    #[description = "Times before the end, e.g. \"1h, 10m\", or \"off\""] times: String,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    let mut minutes = Vec::new();
+    if !times.trim().eq_ignore_ascii_case("off") {
+        for part in times.split(',').filter(|part| !part.trim().is_empty()) {
+            let duration =
+                validate::duration(part, DurationUnit::Minutes, Duration::from_secs(60), None)?;
+            minutes.push(duration.as_secs() / 60);
+        }
+    }
+    minutes.sort_unstable_by(|a, b| b.cmp(a));
+    minutes.dedup();
+    if minutes.len() > MAX_REMINDERS {
+        say(ctx, format!("❌ At most {} reminders, please.", MAX_REMINDERS)).await?;
+        return Ok(());
+    }
+
+    let response = if minutes.is_empty() {
+        "⏰ Stage reminders turned off.".to_string()
+    } else {
+        let list: Vec<String> = minutes
+            .iter()
+            .map(|m| format_duration(m * 60))
+            .collect();
+        format!(
+            "⏰ The Lorax channel will be reminded {} before each stage ends.",
+            list.join(", ")
+        )
+    };
+    ctx.data()
+        .dbs
+        .lorax
+        .update_settings(guild_id, |settings| settings.reminders = minutes)
+        .await?;
+
+    say(ctx, response).await?;
+    Ok(())
+}
+
+/// Set the role everyone who took part in the last event gets, or clear it to turn it off
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn participant_role(
+    ctx: Context<'_>,
+    #[description = "Role for the last event's participants; leave empty to turn it off"]
+    role: Option<serenity::Role>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let role_id = role.as_ref().map(|role| role.id.get());
+
+    let previous = ctx
+        .data()
+        .dbs
+        .lorax
+        .set_participant_role(guild_id, role_id)
+        .await?;
+
+    // The old role comes off the members who got it; the new one goes out next time
+    if let Some((old_role, holders)) = previous.filter(|(_, holders)| !holders.is_empty()) {
+        let mut holders: Vec<u64> = holders.into_iter().collect();
+        holders.sort_unstable();
+        let changes = holders
+            .into_iter()
+            .map(|user_id| RoleChange {
+                user_id,
+                add: Vec::new(),
+                remove: vec![old_role],
+            })
+            .collect();
+        let admin_channel = ctx.data().dbs.guild_config().admin_channel(guild_id).await;
+        ctx.data()
+            .dbs
+            .roles
+            .enqueue(guild_id, "Lorax participant role changed", changes, admin_channel)
+            .await?;
+    }
+
+    let response = match role {
+        Some(role) => format!(
+            "🎟️ Everyone who submits or votes will get {} when an event completes, until \
+            the next one does.",
+            role.mention()
+        ),
+        None => "🎟️ Participant role turned off.".to_string(),
+    };
+    say(ctx, response).await?;
+    Ok(())
+}
+
 /// View current Lorax settings
 #[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
 pub async fn view(ctx: Context<'_>) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
 
     let settings = ctx
         .data()
@@ -216,6 +626,7 @@ pub async fn view(ctx: Context<'_>) -> Result<(), Error> {
         .get_settings(guild_id)
         .await
         .unwrap_or_default();
+    let participation = ctx.data().dbs.lorax.participation(guild_id).await;
 
     let msg = format!(
         "⚙️ **Lorax Settings**\n\
@@ -225,7 +636,17 @@ pub async fn view(ctx: Context<'_>) -> Result<(), Error> {
         🏅 **Alumni Role:** {}\n\
         ⏳ **Submission Duration:** {} minutes\n\
         ⏳ **Voting Duration:** {} minutes\n\
-        ⏳ **Tiebreaker Duration:** {} minutes",
+        ⏳ **Tiebreaker Duration:** {} minutes\n\
+        🌳 **Submissions Per User:** {}\n\
+        🎨 **Theme:** {}\n\
+        🛂 **Approval:** {}\n\
+        📊 **Live Tally:** {}\n\
+        🔢 **Reaction Voting:** {}\n\
+        🔒 **Vote Lock:** {}\n\
+        🗳️ **Voter Role:** {}\n\
+        ⚖️ **Vote Weights:** {}\n\
+        🎟️ **Participant Role:** {}\n\
+        ⏰ **Reminders:** {}",
         settings
             .lorax_channel
             .map_or("Not set".into(), |id| format!("<#{}>", id)),
@@ -240,9 +661,44 @@ pub async fn view(ctx: Context<'_>) -> Result<(), Error> {
             .map_or("Not set".into(), |id| format!("<@&{}>", id)),
         settings.submission_duration,
         settings.voting_duration,
-        settings.tiebreaker_duration
+        settings.tiebreaker_duration,
+        settings.max_submissions,
+        format!("{} {}s", settings.theme_emoji(), settings.noun()),
+        if settings.require_approval { "Required" } else { "Off" },
+        if settings.live_tally { "On" } else { "Off" },
+        if settings.reaction_voting { "On" } else { "Off" },
+        match settings.vote_lock {
+            0 => "Off".to_string(),
+            minutes => format!("Final {}", format_duration(minutes * 60)),
+        },
+        settings
+            .voter_role
+            .map_or("Everyone".into(), |id| format!("<@&{}>", id)),
+        if settings.vote_weights.is_empty() {
+            "Off".to_string()
+        } else {
+            settings
+                .vote_weights
+                .iter()
+                .map(|(id, weight)| format!("<@&{}> ×{}", id, weight))
+                .collect::<Vec<_>>()
+                .join(", ")
+        },
+        participation
+            .role_id
+            .map_or("Not set".into(), |id| format!("<@&{}>", id)),
+        if settings.reminders.is_empty() {
+            "Off".to_string()
+        } else {
+            settings
+                .reminders
+                .iter()
+                .map(|m| format_duration(m * 60))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
     );
 
-    ctx.say(msg).await?;
+    say(ctx, msg).await?;
     Ok(())
 }
@@ -1,6 +1,13 @@
+use crate::utils::{reply::{defer, say, send}, validate};
 use crate::{
-    modules::lorax::database::{LoraxEvent, LoraxStage},
-    Context, Error,
+    databases::Databases,
+    modules::lorax::{
+        database::{LoraxEvent, LoraxStage, NameRules, Pitch, PitchImage},
+        node_names,
+        pitch::{campaign_link, pitch_excerpt, show_pitch},
+        task::{format_rounds, get_current_timestamp},
+    },
+    ApplicationContext, Context, Error,
 };
 use poise::{
     command,
@@ -9,170 +16,155 @@ use poise::{
     },
     CreateReply,
 };
+use std::collections::HashMap;
+use std::time::Duration;
 use tracing::{error, info};
 
-const RESERVED_TREES: [&str; 10] = [
-    "maple", "sakura", "baobab", "sequoia", "oak", "pine", "palm", "willow", "cherry", "redwood",
-];
-
-async fn fetch_node_names() -> Result<Vec<String>, String> {
-    let client = reqwest::Client::new();
-    let response = client
-        .get("https://metrics.pyro.host/api/v1/query")
-        .query(&[("query", "node_uname_info")])
-        .send()
-        .await
-        .map_err(|e| format!("Failed to fetch metrics: {}", e))?;
-
-    #[derive(serde::Deserialize)]
-    struct PrometheusResponse {
-        data: Data,
-    }
-
-    #[derive(serde::Deserialize)]
-    struct Data {
-        result: Vec<Result>,
-    }
-
-    #[derive(serde::Deserialize)]
-    struct Result {
-        metric: Metric,
-    }
-
-    #[derive(serde::Deserialize)]
-    struct Metric {
-        nodename: String,
-    }
-
-    let data: PrometheusResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-    Ok(data
-        .data
-        .result
-        .into_iter()
-        .map(|r| r.metric.nodename.to_lowercase())
-        .collect())
-}
-
 #[command(slash_command, guild_only, ephemeral)]
 pub async fn submit(
     ctx: Context<'_>,
     #[description = "Your awesome tree name"] name: String,
+    #[description = "One of your submissions to replace (when you're at the limit)"]
+    #[autocomplete = "autocomplete_own_submission"]
+    replace: Option<String>,
 ) -> Result<(), Error> {
-    ctx.defer_ephemeral().await?;
+    defer(ctx).await?;
+
+    let config = ctx.data().config.get();
+    let message = submit_name(
+        &ctx.data().dbs,
+        &config.prometheus.node_names_url,
+        validate::guild(ctx)?.get(),
+        ctx.author().id.get(),
+        name,
+        replace,
+    )
+    .await;
+    say(ctx, message).await?;
 
-    let guild_id = ctx.guild_id().unwrap().get();
-    let user_id = ctx.author().id.get();
+    Ok(())
+}
 
-    let event = match ctx.data().dbs.lorax.get_event(guild_id).await {
+/// Validates and submits a tree name for `user_id`, returning the reply to show them. Shared by
+/// `/lorax submit` and the "Submit a name" button on the submission announcement.
+pub async fn submit_name(
+    dbs: &Databases,
+    node_names_url: &str,
+    guild_id: u64,
+    user_id: u64,
+    name: String,
+    replace: Option<String>,
+) -> String {
+    let event = match dbs.lorax.get_event(guild_id).await {
         Some(event) => event,
-        None => {
-            ctx.say("🛑 Oops! There's no Lorax event happening right now.")
-                .await?;
-            return Ok(());
-        }
+        None => return "🛑 Oops! There's no Lorax event happening right now.".to_string(),
     };
 
+    let noun = event.settings.noun();
+    let emoji = event.settings.theme_emoji();
+
     if event.stage == LoraxStage::Voting {
-        ctx.say("🗳️ Submission period has ended, but voting is open!\n💡 Use `/lorax vote` to pick your favorite tree name.").await?;
-        return Ok(());
+        return format!(
+            "🗳️ Submission period has ended, but voting is open!\n💡 Use `/lorax vote` to pick your favorite {} name.",
+            noun
+        );
     }
 
     if event.stage != LoraxStage::Submission {
-        ctx.say("🚫 Submissions are closed at the moment. Stay tuned for the next event!")
-            .await?;
-        return Ok(());
+        return "🚫 Submissions are closed at the moment. Stay tuned for the next event!"
+            .to_string();
     }
 
     let name = name.to_lowercase().trim().to_string();
+    let rules = &event.settings.name_rules;
 
-    if !is_appropriate_name(&name) {
-        ctx.say("❌ Invalid tree name. Please ensure that the name is appropriate!")
-            .await?;
-
-        info!(
-            "Inappropriate name \"{}\" submitted by {}",
-            name,
-            ctx.author().tag()
-        );
-        return Ok(());
+    if !is_appropriate_name(&name, rules) {
+        info!("Inappropriate name \"{}\" submitted by {}", name, user_id);
+        return format!("❌ Invalid {} name. Please ensure that the name is appropriate!", noun);
     }
 
-    if !is_valid_tree_name(&name) {
-        ctx.say(
-            "❌ Invalid tree name. Please ensure it is between 3 and 32 alphabetic characters.",
-        )
-        .await?;
-        return Ok(());
+    if !rules.is_valid(&name) {
+        return format!(
+            "❌ Invalid {} name. Please ensure it is between {} and {} characters, using only {}.",
+            noun,
+            rules.min_length,
+            rules.max_length,
+            rules.characters.describe()
+        );
     }
 
-    match fetch_node_names().await {
-        Ok(node_names) => {
-            if node_names.contains(&name) {
-                ctx.say(
-                    "🌲 That tree name is already in use as a node name. Please choose another!",
-                )
-                .await?;
-                return Ok(());
+    if event.settings.checks_node_names() {
+        match node_names::fetch(node_names_url).await {
+            Ok(node_names) => {
+                if node_names.contains(&name) {
+                    return format!(
+                        "{} That {} name is already in use as a node name. Please choose another!",
+                        emoji, noun
+                    );
+                }
+            }
+            Err(e) => {
+                error!("Failed to fetch node names: {}", e);
             }
-        }
-        Err(e) => {
-            error!("Failed to fetch node names: {}", e);
         }
     }
 
-    if RESERVED_TREES.contains(&name.as_str()) || name == "lorax" {
-        ctx.say("🌲 That tree name is reserved. Try coming up with something unique! 🍃")
-            .await?;
-        return Ok(());
+    if rules.is_reserved(&name) {
+        return format!(
+            "{} That {} name is reserved. Try coming up with something unique!",
+            emoji, noun
+        );
     }
 
-    if event.tree_submissions.values().any(|t| t == &name) {
-        ctx.say("🌳 Someone already suggested that name! How about a different one?")
-            .await?;
-        return Ok(());
+    if event.submissions().any(|(_, t)| t == &name) {
+        return format!("{} Someone already suggested that name! How about a different one?", emoji);
     }
 
-    match ctx
-        .data()
-        .dbs
+    if event.settings.require_approval {
+        let now = chrono::Utc::now().timestamp() as u64;
+        return match dbs
+            .lorax
+            .queue_submission(guild_id, name.clone(), user_id, replace, now)
+            .await
+        {
+            Ok(()) => format!(
+                "📨 Your {} name \"**{}**\" is waiting for a moderator's approval. \
+                It joins the event once it's approved.",
+                noun, name
+            ),
+            Err(e) => format!("❌ Unable to submit: {}", e),
+        };
+    }
+
+    match dbs
         .lorax
-        .submit_tree(guild_id, name.clone(), user_id)
+        .submit_tree(guild_id, name.clone(), user_id, replace)
         .await
     {
-        Ok((is_update, old_submission)) => {
-            let msg = if is_update {
-                format!(
-                    "🔄 Updated your submission from \"**{}**\" to \"**{}**\"!\n⏳ Stay tuned for the voting phase.",
-                    old_submission.unwrap_or_default(),
-                    name
-                )
-            } else {
-                format!(
-                    "🌳 Your tree name \"**{}**\" has been submitted!\n⏳ Stay tuned for the voting phase.",
-                    name
-                )
-            };
-            ctx.say(msg).await?;
-        }
-        Err(e) => {
-            ctx.say(format!("❌ Unable to submit: {}", e)).await?;
-        }
+        Ok((true, old_submission)) => format!(
+            "🔄 Updated your submission from \"**{}**\" to \"**{}**\"!\n⏳ Stay tuned for the voting phase.",
+            old_submission.unwrap_or_default(),
+            name
+        ),
+        Ok((false, _)) => format!(
+            "{} Your {} name \"**{}**\" has been submitted!\n⏳ Stay tuned for the voting phase.",
+            emoji,
+            noun,
+            name
+        ),
+        Err(e) => format!("❌ Unable to submit: {}", e),
     }
-
-    Ok(())
 }
 
 const FORBIDDEN_LIST: &str = include_str!("../../../../extra/banned_words.txt");
 
-fn is_appropriate_name(name: &str) -> bool {
+/// Checks `name` against the built-in word list and the guild's extra banned words.
+fn is_appropriate_name(name: &str, rules: &NameRules) -> bool {
     let name = name.to_lowercase();
     let words: Vec<&str> = name.split_whitespace().collect();
 
-    for forbidden in FORBIDDEN_LIST.lines() {
+    let extra = rules.banned_words.iter().map(String::as_str);
+    for forbidden in FORBIDDEN_LIST.lines().chain(extra) {
         let forbidden = forbidden.trim().to_lowercase();
         if forbidden.is_empty() {
             continue;
@@ -187,55 +179,74 @@ fn is_appropriate_name(name: &str) -> bool {
     true
 }
 
-fn is_valid_tree_name(name: &str) -> bool {
-    let name = name.trim();
-    let length = name.len();
-    (3..=32).contains(&length) && name.chars().all(|c| c.is_ascii_alphabetic())
-}
-
 #[command(slash_command, guild_only, ephemeral)]
 pub async fn vote(ctx: Context<'_>) -> Result<(), Error> {
-    ctx.defer_ephemeral().await?;
+    defer(ctx).await?;
 
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
     let user_id = ctx.author().id.get();
 
     let event = match ctx.data().dbs.lorax.get_event(guild_id).await {
         Some(event) => event,
         None => {
-            ctx.say("❌ There is no active event at the moment!")
+            say(ctx, "❌ There is no active event at the moment!")
                 .await?;
             return Ok(());
         }
     };
 
     if !is_voting_stage(&event.stage) {
-        ctx.say("🚫 Voting is not active at the moment.").await?;
+        say(ctx, "🚫 Voting is not active at the moment.").await?;
+        return Ok(());
+    }
+
+    let roles: Vec<u64> = match ctx.author_member().await {
+        Some(member) => member.roles.iter().map(|role| role.get()).collect(),
+        None => Vec::new(),
+    };
+    if !event.settings.can_vote(&roles) {
+        say(ctx, voter_role_notice(&event)).await?;
+        return Ok(());
+    }
+
+    let locked = event.vote_lock_time().is_some_and(|lock| get_current_timestamp() >= lock);
+    if locked && event.tree_votes.contains_key(&user_id) {
+        say(ctx, format!(
+            "🔒 Votes are locked for the final {} minute(s) of the round, so yours can't change.",
+            event.settings.vote_lock
+        ))
+        .await?;
         return Ok(());
     }
 
     let mut trees = get_available_trees(&event, user_id);
     if trees.is_empty() {
-        ctx.say("🤔 There's nothing to vote on yet. Wait for more submissions!")
+        say(ctx, "🤔 There's nothing to vote on yet. Wait for more submissions!")
             .await?;
         return Ok(());
     }
 
     trees.sort();
 
+    let excerpts: HashMap<String, String> = trees
+        .iter()
+        .filter_map(|tree| pitch_excerpt(&event, tree).map(|excerpt| (tree.clone(), excerpt)))
+        .collect();
+
     let page_size = 25;
     let total_pages = (trees.len() as f32 / page_size as f32).ceil() as usize;
     let mut current_page = 0;
+    let page_trees =
+        |page: usize| &trees[page * page_size..(page * page_size + page_size).min(trees.len())];
 
     let create_reply = |page: usize| {
         let mut components = vec![CreateActionRow::SelectMenu(
             CreateSelectMenu::new(
                 "vote_tree",
                 CreateSelectMenuKind::String {
-                    options: trees
-                        [page * page_size..(page * page_size + page_size).min(trees.len())]
+                    options: page_trees(page)
                         .iter()
-                        .map(|tree| CreateSelectMenuOption::new(tree, tree))
+                        .map(|tree| select_option(tree, &excerpts))
                         .collect(),
                 },
             )
@@ -257,9 +268,11 @@ pub async fn vote(ctx: Context<'_>) -> Result<(), Error> {
 
         CreateReply::default()
             .content(format!(
-                "🗳️ **Vote for your favorite tree name!** (Page {}/{})\nNote: You can't vote for your own.",
+                "🗳️ **Vote for your favorite {} name!** (Page {}/{})\nNote: You can't vote for your own.{}",
+                event.settings.noun(),
                 page + 1,
-                total_pages
+                total_pages,
+                campaign_links(&event, guild_id, page_trees(page))
             ))
             .components(components)
     };
@@ -269,10 +282,9 @@ pub async fn vote(ctx: Context<'_>) -> Result<(), Error> {
             CreateSelectMenu::new(
                 "vote_tree",
                 CreateSelectMenuKind::String {
-                    options: trees
-                        [page * page_size..(page * page_size + page_size).min(trees.len())]
+                    options: page_trees(page)
                         .iter()
-                        .map(|tree| CreateSelectMenuOption::new(tree, tree))
+                        .map(|tree| select_option(tree, &excerpts))
                         .collect(),
                 },
             )
@@ -294,14 +306,16 @@ pub async fn vote(ctx: Context<'_>) -> Result<(), Error> {
 
         CreateInteractionResponseMessage::new()
             .content(format!(
-                "🗳️ Pick your favorite tree name: (Page {}/{})\nNote: You can't vote for your own.",
+                "🗳️ Pick your favorite {} name: (Page {}/{})\nNote: You can't vote for your own.{}",
+                event.settings.noun(),
                 page + 1,
-                total_pages
+                total_pages,
+                campaign_links(&event, guild_id, page_trees(page))
             ))
             .components(components)
     };
 
-    let msg = ctx.send(create_reply(current_page)).await?;
+    let msg = send(ctx, create_reply(current_page)).await?;
 
     while let Some(interaction) = msg
         .message()
@@ -346,44 +360,315 @@ pub async fn vote(ctx: Context<'_>) -> Result<(), Error> {
         }
     }
 
-    ctx.say("⌛ Time's up! Feel free to `/lorax vote` again anytime.")
+    say(ctx, "⌛ Time's up! Feel free to `/lorax vote` again anytime.")
         .await?;
     Ok(())
 }
 
+fn select_option(tree: &str, excerpts: &HashMap<String, String>) -> CreateSelectMenuOption {
+    let option = CreateSelectMenuOption::new(tree, tree);
+    match excerpts.get(tree) {
+        Some(excerpt) => option.description(excerpt),
+        None => option,
+    }
+}
+
+/// Most characters of campaign links shown with the voting menu, to stay within a message.
+const CAMPAIGN_LINKS_LIMIT: usize = 1500;
+
+/// Largest pitch image accepted. Images are stored with the event, so keep them small.
+const MAX_PITCH_IMAGE_BYTES: u32 = 4 * 1024 * 1024;
+
+/// Links to the campaign messages of `trees`, as a line for the voting menu.
+fn campaign_links(event: &LoraxEvent, guild_id: u64, trees: &[String]) -> String {
+    let mut links = String::new();
+    for tree in trees {
+        let Some(link) = campaign_link(event, guild_id, tree) else {
+            continue;
+        };
+        let separator = if links.is_empty() { "" } else { " · " };
+        let entry = format!("{}[{}]({})", separator, tree, link);
+        if links.len() + entry.len() > CAMPAIGN_LINKS_LIMIT {
+            links.push_str(" …");
+            break;
+        }
+        links.push_str(&entry);
+    }
+    if links.is_empty() {
+        links
+    } else {
+        format!("\n📣 **Campaigns:** {}", links)
+    }
+}
+
+#[derive(Debug, poise::Modal)]
+#[name = "Campaign pitch"]
+struct PitchModal {
+    #[name = "Why should people vote for your tree name?"]
+    #[paragraph]
+    #[min_length = 10]
+    #[max_length = 300]
+    pitch: String,
+}
+
+/// Suggests the user's own submissions in the current event.
+/// Your submissions and any name of yours waiting for approval.
+async fn autocomplete_withdrawable<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Iterator<Item = String> {
+    let partial = partial.to_lowercase();
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get());
+    let user_id = ctx.author().id.get();
+
+    let mut trees = Vec::new();
+    if let Some(event) = ctx.data().dbs.lorax.get_event(guild_id).await {
+        trees.extend(event.user_submissions(user_id).iter().cloned());
+        trees.extend(
+            event
+                .pending_submissions
+                .iter()
+                .filter(|pending| pending.user_id == user_id)
+                .map(|pending| pending.tree.clone()),
+        );
+    }
+    trees.into_iter().filter(move |tree| tree.contains(&partial))
+}
+
+/// Take back one of your tree name submissions
+#[command(slash_command, guild_only, ephemeral)]
+pub async fn withdraw(
+    ctx: Context<'_>,
+    #[description = "Which of your submissions to withdraw"]
+    #[autocomplete = "autocomplete_withdrawable"]
+    tree: String,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let user_id = ctx.author().id.get();
+
+    match ctx
+        .data()
+        .dbs
+        .lorax
+        .withdraw_submission(guild_id, user_id, &tree)
+        .await
+    {
+        Ok(()) => {
+            say(ctx, format!(
+                "↩️ Withdrew \"**{}**\". You can submit another name with `/lorax submit`.",
+                tree.trim().to_lowercase()
+            ))
+            .await?;
+        }
+        Err(e) => {
+            say(ctx, format!("❌ Unable to withdraw: {}", e)).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn autocomplete_own_submission<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Iterator<Item = String> {
+    let partial = partial.to_lowercase();
+    let guild_id = ctx.guild_id().map_or(0, |id| id.get());
+
+    ctx.data()
+        .dbs
+        .lorax
+        .get_event(guild_id)
+        .await
+        .map(|event| event.user_submissions(ctx.author().id.get()).to_vec())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(move |tree| tree.contains(&partial))
+}
+
+/// Name to upload a pitch image under, keeping the original extension so Discord shows it.
+fn pitch_image_name(filename: &str) -> String {
+    let extension = std::path::Path::new(filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .filter(|ext| !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric()))
+        .unwrap_or("png");
+    format!("pitch.{}", extension.to_ascii_lowercase())
+}
+
+/// Add a short campaign pitch to your submission
+#[command(slash_command, guild_only, ephemeral)]
+pub async fn pitch(
+    app_ctx: ApplicationContext<'_>,
+    #[description = "Which of your submissions to pitch (if you have several)"]
+    #[autocomplete = "autocomplete_own_submission"]
+    tree: Option<String>,
+    #[description = "Optional image to show with your pitch"] image: Option<serenity::Attachment>,
+) -> Result<(), Error> {
+    let ctx: Context<'_> = app_ctx.into();
+    let guild_id = validate::guild(ctx)?.get();
+    let user_id = ctx.author().id.get();
+
+    let Some(event) = ctx.data().dbs.lorax.get_event(guild_id).await else {
+        say(ctx, "🛑 Oops! There's no Lorax event happening right now.")
+            .await?;
+        return Ok(());
+    };
+
+    let own = event.user_submissions(user_id);
+    let tree = match tree.map(|t| t.trim().to_lowercase()) {
+        Some(tree) if own.contains(&tree) => tree,
+        Some(tree) => {
+            say(ctx, format!("❌ You haven't submitted \"{}\".", tree)).await?;
+            return Ok(());
+        }
+        None => match own {
+            [] => {
+                say(ctx, "🌱 Submit a tree name with `/lorax submit` before adding a pitch.")
+                    .await?;
+                return Ok(());
+            }
+            [tree] => tree.clone(),
+            _ => {
+                say(ctx, "🌳 You have several submissions — pick one with the `tree` option.")
+                    .await?;
+                return Ok(());
+            }
+        },
+    };
+
+    if let Some(image) = &image {
+        if !image
+            .content_type
+            .as_deref()
+            .is_some_and(|kind| kind.starts_with("image/"))
+        {
+            say(ctx, "❌ Pitch attachments must be images.").await?;
+            return Ok(());
+        }
+        if image.size > MAX_PITCH_IMAGE_BYTES {
+            say(
+                ctx,
+                format!(
+                    "❌ Pitch images can be at most {} MB.",
+                    MAX_PITCH_IMAGE_BYTES / 1024 / 1024
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+    }
+
+    let defaults = event.pitches.get(&tree).map(|pitch| PitchModal {
+        pitch: pitch.text.clone(),
+    });
+    let Some(modal) = poise::execute_modal(app_ctx, defaults, Some(Duration::from_secs(300))).await?
+    else {
+        return Ok(());
+    };
+
+    // Attachment links expire, so keep a copy of the image to upload with the campaign
+    let image = match image {
+        Some(image) => match image.download().await {
+            Ok(data) => Some(PitchImage { filename: pitch_image_name(&image.filename), data }),
+            Err(e) => {
+                say(ctx, format!("❌ Unable to download your image: {}", e)).await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    let pitch = Pitch {
+        text: modal.pitch.trim().to_string(),
+        image,
+    };
+
+    match ctx
+        .data()
+        .dbs
+        .lorax
+        .set_pitch(guild_id, user_id, Some(tree), pitch)
+        .await
+    {
+        Ok(tree) => {
+            // Once voting has started the campaign thread exists, so show the pitch straight
+            // away. Forum posts exist from submission on but only get pitches once voting opens
+            let campaigning = !matches!(event.stage, LoraxStage::Submission);
+            let event = ctx.data().dbs.lorax.get_event(guild_id).await;
+            if let Some(event) = event.filter(|_| campaigning) {
+                let theme = ctx.data().dbs.system.get_theme(guild_id).await;
+                show_pitch(ctx.serenity_context(), &theme, &event, &tree, user_id).await;
+            }
+            say(ctx, format!("📣 Your pitch for \"**{}**\" has been saved!", tree))
+                .await?;
+        }
+        Err(e) => {
+            say(ctx, format!("❌ Unable to save your pitch: {}", e)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Show how the current event's rounds have played out
+#[command(slash_command, guild_only)]
+pub async fn stats(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    let Some(event) = ctx.data().dbs.lorax.get_event(guild_id).await else {
+        say(ctx, "⚪ No active Lorax event is running.").await?;
+        return Ok(());
+    };
+
+    let rounds = if event.round_results.is_empty() {
+        "No voting rounds have finished yet.".to_string()
+    } else {
+        format_rounds(&event)
+    };
+
+    say(ctx, format!(
+        "📊 **Lorax Stats**\n- Stage: {:?}\n- Names Submitted: {}\n- Votes This Round: {}\n\n**Rounds**\n{}",
+        event.stage,
+        event.submission_count(),
+        event.tree_votes.len(),
+        rounds
+    ))
+    .await?;
+    Ok(())
+}
+
 /// Checks if the given tree name is taken by a node in the cluster.
 #[command(slash_command, guild_only, ephemeral)]
 pub async fn check(
     ctx: Context<'_>,
     #[description = "Tree name to check"] name: String,
 ) -> Result<(), Error> {
-    ctx.defer_ephemeral().await?;
+    defer(ctx).await?;
 
     let name = name.to_lowercase().trim().to_string();
 
     if !is_valid_tree_name(&name) {
-        ctx.say(
+        say(ctx, 
             "❌ Invalid tree name. Please ensure it is between 3 and 32 alphabetic characters.",
         )
         .await?;
         return Ok(());
     }
 
-    match fetch_node_names().await {
+    match node_names::fetch(&ctx.data().config.get().prometheus.node_names_url).await {
         Ok(node_names) => {
             if node_names.contains(&name) {
-                ctx.say(
+                say(ctx, 
                     "🌲 That tree name is already in use as a node name. Please choose another!",
                 )
                 .await?;
             } else {
-                ctx.say("🌳 That tree name is available! Go ahead and submit it.")
+                say(ctx, "🌳 That tree name is available! Go ahead and submit it.")
                     .await?;
             }
         }
         Err(e) => {
             error!("Failed to fetch node names: {}", e);
-            ctx.say("❌ Failed to fetch node names. Please try again later.")
+            say(ctx, "❌ Failed to fetch node names. Please try again later.")
                 .await?;
         }
     }
@@ -391,16 +676,19 @@ pub async fn check(
     Ok(())
 }
 
+fn voter_role_notice(event: &LoraxEvent) -> String {
+    format!(
+        "🚫 Only members with <@&{}> can vote in this event.",
+        event.settings.voter_role.unwrap_or_default()
+    )
+}
+
 fn is_voting_stage(stage: &LoraxStage) -> bool {
     matches!(stage, LoraxStage::Voting | LoraxStage::Tiebreaker(_))
 }
 
 fn get_available_trees(event: &LoraxEvent, _user_id: u64) -> Vec<String> {
-    event
-        .tree_submissions
-        .iter()
-        .map(|(_, tree)| tree.clone())
-        .collect()
+    event.submissions().map(|(_, tree)| tree.clone()).collect()
 }
 
 async fn handle_vote_selection(
@@ -412,8 +700,40 @@ async fn handle_vote_selection(
 ) -> Result<(), Error> {
     let selected_tree = values.first().ok_or("No selection made")?;
 
+    // The event may have ended or been cancelled while the menu was open
+    let Some(event) = ctx.data().dbs.lorax.get_event(guild_id).await else {
+        interaction
+            .create_response(
+                &ctx.serenity_context().http,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content("❌ Voting has ended.")
+                        .components(vec![]),
+                ),
+            )
+            .await?;
+        return Ok(());
+    };
+    let roles: Vec<u64> = interaction
+        .member
+        .as_ref()
+        .map(|member| member.roles.iter().map(|role| role.get()).collect())
+        .unwrap_or_default();
+    if !event.settings.can_vote(&roles) {
+        interaction
+            .create_response(
+                &ctx.serenity_context().http,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(voter_role_notice(&event))
+                        .components(vec![]),
+                ),
+            )
+            .await?;
+        return Ok(());
+    }
+
     // Check if user is trying to vote for their own submission
-    let event = ctx.data().dbs.lorax.get_event(guild_id).await.unwrap();
     if let Some(submitter_id) = event.get_tree_submitter(selected_tree) {
         if submitter_id == user_id {
             interaction
@@ -430,29 +750,24 @@ async fn handle_vote_selection(
         }
     }
 
-    match ctx
+    let weight = event.settings.vote_weight(&roles);
+    let vote = ctx
         .data()
         .dbs
         .lorax
-        .transaction(|db| {
-            let event = db
-                .events
-                .get_mut(&guild_id)
-                .ok_or_else(|| "No active event".to_string())?;
-
-            let old_vote = event.tree_votes.insert(user_id, selected_tree.to_string());
-
-            if let Some(old) = old_vote {
-                Ok(format!(
-                    "Changed vote from \"{}\" to \"{}\"",
-                    old, selected_tree
-                ))
-            } else {
-                Ok("Vote recorded!".to_string())
-            }
-        })
+        .vote_tree(guild_id, selected_tree.to_string(), user_id, weight, get_current_timestamp())
         .await
-    {
+        .map(|old_vote| {
+            let msg = match old_vote {
+                Some(old) => format!("Changed vote from \"{}\" to \"{}\"", old, selected_tree),
+                None => "Vote recorded!".to_string(),
+            };
+            match weight {
+                1 => msg,
+                weight => format!("{} Thanks to your roles it counts {} times.", msg, weight),
+            }
+        });
+    match vote {
         Ok(msg) => {
             interaction
                 .create_response(
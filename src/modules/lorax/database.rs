@@ -1,7 +1,13 @@
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use crate::{database::Database, default_struct};
+use crate::{
+    database::{put_guild_rows, take_guild_rows, Database, DbError, Migration, Rows},
+    default_struct,
+    utils::history::SettingsHistory,
+};
+
+use super::migrations;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum LoraxStage {
@@ -12,6 +18,133 @@ pub enum LoraxStage {
     Inactive,
 }
 
+impl LoraxStage {
+    /// Numeric stage code for dashboards: 0 inactive, 1 submission, 2 voting,
+    /// 3 tiebreaker, 4 completed.
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Inactive => 0,
+            Self::Submission => 1,
+            Self::Voting => 2,
+            Self::Tiebreaker(_) => 3,
+            Self::Completed => 4,
+        }
+    }
+}
+
+/// Characters tree names may use.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, poise::ChoiceParameter,
+)]
+pub enum NameCharacters {
+    #[default]
+    #[name = "Letters"]
+    Letters,
+    #[name = "Letters and digits"]
+    Alphanumeric,
+    /// Valid in a hostname, for trees that become node names.
+    #[name = "Letters, digits and hyphens"]
+    Hostname,
+}
+
+impl NameCharacters {
+    pub fn allows(self, c: char) -> bool {
+        match self {
+            Self::Letters => c.is_ascii_alphabetic(),
+            Self::Alphanumeric => c.is_ascii_alphanumeric(),
+            Self::Hostname => c.is_ascii_alphanumeric() || c == '-',
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Letters => "letters",
+            Self::Alphanumeric => "letters and digits",
+            Self::Hostname => "letters, digits and hyphens",
+        }
+    }
+}
+
+/// Names no guild starts out allowing.
+pub const DEFAULT_RESERVED_TREES: [&str; 10] = [
+    "maple", "sakura", "baobab", "sequoia", "oak", "pine", "palm", "willow", "cherry", "redwood",
+];
+
+/// Longest tree name any guild can allow.
+pub const MAX_NAME_LENGTH: usize = 64;
+
+default_struct! {
+/// What a guild accepts as a tree name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NameRules {
+    pub min_length: usize = 3,
+    pub max_length: usize = 32,
+    pub characters: NameCharacters,
+    /// Names nobody may submit, lowercase.
+    pub reserved: Vec<String> = DEFAULT_RESERVED_TREES.iter().map(|s| s.to_string()).collect(),
+    /// Words banned on top of the built-in list, lowercase.
+    pub banned_words: Vec<String>,
+}
+}
+
+impl NameRules {
+    /// Whether `name` has an allowed length and only allowed characters.
+    pub fn is_valid(&self, name: &str) -> bool {
+        let name = name.trim();
+        (self.min_length..=self.max_length).contains(&name.chars().count())
+            && name.chars().all(|c| self.characters.allows(c))
+    }
+
+    pub fn is_reserved(&self, name: &str) -> bool {
+        name == "lorax" || self.reserved.iter().any(|reserved| reserved == name)
+    }
+
+    /// Checks limits the `/lorax names` commands enforce, for rules that came from
+    /// elsewhere. Errors name the offending field.
+    pub fn check(&self) -> Result<(), String> {
+        if self.min_length == 0 {
+            return Err("min_length: must be at least 1".to_string());
+        }
+        if self.max_length > MAX_NAME_LENGTH {
+            return Err(format!("max_length: must be at most {}", MAX_NAME_LENGTH));
+        }
+        if self.min_length > self.max_length {
+            return Err(format!(
+                "min_length: {} is more than max_length ({})",
+                self.min_length, self.max_length
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// What an event names, which sets its wording, example names and whether names may clash
+/// with existing node names.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, poise::ChoiceParameter,
+)]
+pub enum EventTheme {
+    #[default]
+    Trees,
+    Constellations,
+    Mountains,
+    Custom,
+}
+
+default_struct! {
+/// Wording for the custom theme.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomTheme {
+    /// What's being named, singular and lowercase, e.g. "planet".
+    pub noun: String = "name".to_string(),
+    pub emoji: String = "✨".to_string(),
+    /// Example names for announcements.
+    pub samples: Vec<String>,
+    /// Whether names can't match existing node names.
+    pub check_node_names: bool = true,
+}
+}
+
 default_struct! {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoraxSettings {
@@ -27,14 +160,161 @@ pub struct LoraxSettings {
     pub submission_duration: u64 = 60,
     pub voting_duration: u64 = 30,
     pub tiebreaker_duration: u64 = 15,
+
+    /// How many tree names each user may submit per event.
+    pub max_submissions: usize = 1,
+
+    /// Minutes before a stage ends to remind the Lorax channel.
+    pub reminders: Vec<u64> = vec![60, 10],
+
+    /// Whether new submissions wait in `/lorax queue` for a moderator.
+    pub require_approval: bool,
+
+    /// Whether voting shows a public scoreboard of vote counts, updated every minute.
+    pub live_tally: bool,
+
+    pub name_rules: NameRules,
+
+    pub theme: EventTheme,
+    /// Used when `theme` is [`EventTheme::Custom`].
+    pub custom_theme: CustomTheme,
+
+    /// Minutes before a voting round ends during which votes can no longer be changed.
+    /// Zero turns the lock off.
+    pub vote_lock: u64,
+
+    /// Only members with this role can vote. Unset lets everyone vote.
+    pub voter_role: Option<u64>,
+    /// How many votes a member with each role casts; the highest of their roles applies.
+    pub vote_weights: BTreeMap<u64, u32>,
+
+    /// Whether each voting round also posts a ballot members vote on by reacting.
+    pub reaction_voting: bool,
 }
 }
 
+impl LoraxSettings {
+    /// Whether a member with these roles may vote.
+    pub fn can_vote(&self, roles: &[u64]) -> bool {
+        self.voter_role.is_none_or(|role| roles.contains(&role))
+    }
+
+    /// How many votes a member with these roles casts.
+    pub fn vote_weight(&self, roles: &[u64]) -> u32 {
+        roles.iter().filter_map(|role| self.vote_weights.get(role)).copied().max().unwrap_or(1)
+    }
+
+    /// Checks limits the settings commands enforce, for settings that came from elsewhere,
+    /// such as an import. Errors name the offending field.
+    pub fn check(&self) -> Result<(), String> {
+        let durations = [
+            ("submission_duration", self.submission_duration),
+            ("voting_duration", self.voting_duration),
+            ("tiebreaker_duration", self.tiebreaker_duration),
+        ];
+        for (field, minutes) in durations {
+            if minutes == 0 {
+                return Err(format!("{}: must be at least 1 minute", field));
+            }
+        }
+        if self.max_submissions == 0 {
+            return Err("max_submissions: must be at least 1".to_string());
+        }
+        if let Some((role_id, _)) = self.vote_weights.iter().find(|(_, weight)| **weight == 0) {
+            return Err(format!("vote_weights.{}: must be at least 1", role_id));
+        }
+        self.name_rules.check().map_err(|e| format!("name_rules.{}", e))
+    }
+
+    /// What the event names, singular and lowercase, e.g. "tree".
+    pub fn noun(&self) -> &str {
+        match self.theme {
+            EventTheme::Trees => "tree",
+            EventTheme::Constellations => "constellation",
+            EventTheme::Mountains => "mountain",
+            EventTheme::Custom => &self.custom_theme.noun,
+        }
+    }
+
+    pub fn theme_emoji(&self) -> &str {
+        match self.theme {
+            EventTheme::Trees => "🌳",
+            EventTheme::Constellations => "🌌",
+            EventTheme::Mountains => "⛰️",
+            EventTheme::Custom => &self.custom_theme.emoji,
+        }
+    }
+
+    /// Example names for announcements.
+    pub fn sample_names(&self) -> Vec<&str> {
+        match self.theme {
+            EventTheme::Trees => vec!["Willow", "Sequoia", "Maple", "Oak", "Pine"],
+            EventTheme::Constellations => vec!["Orion", "Lyra", "Cygnus", "Draco", "Perseus"],
+            EventTheme::Mountains => vec!["Everest", "Denali", "Matterhorn", "Fuji", "Elbrus"],
+            EventTheme::Custom => self.custom_theme.samples.iter().map(String::as_str).collect(),
+        }
+    }
+
+    /// Whether submitted names are checked against the existing node names.
+    pub fn checks_node_names(&self) -> bool {
+        match self.theme {
+            EventTheme::Custom => self.custom_theme.check_node_names,
+            _ => true,
+        }
+    }
+}
+
+/// Final standings of one voting or tiebreaker round.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundResult {
+    pub stage: LoraxStage,
+    /// Trees in the round and their votes, most votes first.
+    pub tally: Vec<(String, usize)>,
+    /// Trees that didn't make it past this round.
+    pub eliminated: Vec<String>,
+    pub ended_at: u64,
+}
+
+/// A submission waiting in the moderation queue.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingSubmission {
+    pub user_id: u64,
+    pub tree: String,
+    /// Which of the user's names it replaces once approved.
+    pub replace: Option<String>,
+    /// Unix time.
+    pub submitted_at: u64,
+}
+
+/// A submitter's campaign pitch for their tree name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pitch {
+    pub text: String,
+    pub image: Option<PitchImage>,
+}
+
+/// A pitch's image, kept as a copy because Discord attachment links expire.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PitchImage {
+    pub filename: String,
+    pub data: Vec<u8>,
+}
+
+impl std::fmt::Debug for PitchImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PitchImage")
+            .field("filename", &self.filename)
+            .field("bytes", &self.data.len())
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoraxEvent {
     pub stage: LoraxStage,
     pub settings: LoraxSettings,
-    pub tree_submissions: HashMap<u64, String>,
+    /// Submitted tree names keyed by submitter.
+    pub tree_submissions: HashMap<u64, Vec<String>>,
     pub tree_votes: HashMap<u64, String>,
     pub eliminated_trees: HashSet<String>,
     pub start_time: u64,
@@ -44,8 +324,41 @@ pub struct LoraxEvent {
     pub voting_message_id: Option<u64>,
     pub tiebreaker_message_id: Option<u64>,
     pub campaign_thread_id: Option<u64>,
+    /// Campaign pitches keyed by tree name.
+    pub pitches: HashMap<String, Pitch>,
+    pub round_results: Vec<RoundResult>,
+    /// The Discord scheduled event covering voting, if one could be created.
+    pub scheduled_event_id: Option<u64>,
+    /// Whether submitters who hadn't voted were reminded during this event's voting.
+    pub vote_reminder_sent: bool,
+    /// Reminders (minutes before the end) already sent for the current stage.
+    pub reminders_sent: Vec<u64>,
+    /// How many trees win, for naming several nodes at once.
+    pub winner_count: usize,
+    /// Trees that won outright while a tiebreaker decides the remaining places.
+    pub secured_winners: Vec<String>,
+    /// Submissions waiting for approval, oldest first.
+    pub pending_submissions: Vec<PendingSubmission>,
+    /// Why each rejected tree (also in `eliminated_trees`) was rejected.
+    pub rejection_reasons: HashMap<String, String>,
+    /// The live tally message of the current voting or tiebreaker round.
+    pub tally_message_id: Option<u64>,
+    /// This round's voters whose vote counts more than once, with its weight.
+    pub vote_weights: HashMap<u64, u32>,
+    /// The current round's reaction ballot, when reaction voting is on.
+    pub ballot_message_id: Option<u64>,
+    /// Trees on the ballot, in the order of their reaction emoji.
+    pub ballot: Vec<String>,
+    /// Each shortlisted tree's campaign message in the campaign thread or forum post.
+    pub campaign_posts: HashMap<String, u64>,
 }
 
+/// Why names found to be node names after submission were rejected.
+const TAKEN_REASON: &str = "A node now uses that name";
+
+/// Most trees one event can pick.
+pub const MAX_WINNERS: usize = 10;
+
 impl LoraxEvent {
     pub fn new(settings: LoraxSettings, start_time: u64) -> Self {
         Self {
@@ -61,25 +374,226 @@ impl LoraxEvent {
             voting_message_id: None,
             tiebreaker_message_id: None,
             campaign_thread_id: None,
+            pitches: HashMap::new(),
+            round_results: Vec::new(),
+            scheduled_event_id: None,
+            vote_reminder_sent: false,
+            reminders_sent: Vec::new(),
+            winner_count: 1,
+            secured_winners: Vec::new(),
+            pending_submissions: Vec::new(),
+            rejection_reasons: HashMap::new(),
+            tally_message_id: None,
+            vote_weights: HashMap::new(),
+            ballot_message_id: None,
+            ballot: Vec::new(),
+            campaign_posts: HashMap::new(),
         }
     }
 
+    /// The winning trees of a completed event, best first.
+    pub fn winners(&self) -> &[String] {
+        &self.current_trees[..self.winner_count.min(self.current_trees.len())]
+    }
+
     pub fn get_stage_end_timestamp(&self, duration: u64) -> u64 {
         self.start_time + duration
     }
 
+    /// When votes in the current round stop being changeable, if the vote lock is on and
+    /// a round is open.
+    pub fn vote_lock_time(&self) -> Option<u64> {
+        let duration = match self.stage {
+            LoraxStage::Voting => self.settings.voting_duration,
+            LoraxStage::Tiebreaker(_) => self.settings.tiebreaker_duration,
+            _ => return None,
+        };
+        if self.settings.vote_lock == 0 {
+            return None;
+        }
+        let end = self.get_stage_end_timestamp(duration * 60);
+        Some(end.saturating_sub(self.settings.vote_lock * 60))
+    }
+
     pub fn get_tree_submitter(&self, tree_name: &str) -> Option<u64> {
+        self.submissions()
+            .find(|(_, name)| name.as_str() == tree_name)
+            .map(|(uid, _)| uid)
+    }
+
+    /// Every submission as `(submitter, tree name)`.
+    pub fn submissions(&self) -> impl Iterator<Item = (u64, &String)> {
         self.tree_submissions
             .iter()
-            .find(|(_, name)| name.as_str() == tree_name)
-            .map(|(uid, _)| *uid)
+            .flat_map(|(uid, trees)| trees.iter().map(move |tree| (*uid, tree)))
+    }
+
+    pub fn submission_count(&self) -> usize {
+        self.tree_submissions.values().map(Vec::len).sum()
+    }
+
+    pub fn user_submissions(&self, user_id: u64) -> &[String] {
+        self.tree_submissions
+            .get(&user_id)
+            .map_or(&[], |trees| trees.as_slice())
+    }
+
+    /// Removes a submitted tree and its pitch, returning who submitted it.
+    pub fn remove_submission(&mut self, tree_name: &str) -> Option<u64> {
+        let submitter = self.get_tree_submitter(tree_name)?;
+        if let Some(trees) = self.tree_submissions.get_mut(&submitter) {
+            trees.retain(|tree| tree != tree_name);
+            if trees.is_empty() {
+                self.tree_submissions.remove(&submitter);
+            }
+        }
+        self.pitches.remove(tree_name);
+        Some(submitter)
+    }
+
+    /// Removes the user's submissions (with their pitches and the votes cast for them),
+    /// queued submissions and vote, describing each removal.
+    pub fn forget_user(&mut self, user_id: u64) -> Vec<String> {
+        let mut removed = Vec::new();
+        self.pending_submissions.retain(|pending| {
+            let own = pending.user_id == user_id;
+            if own {
+                removed.push(format!("Lorax submission \"{}\" awaiting approval", pending.tree));
+            }
+            !own
+        });
+        let trees = self.user_submissions(user_id).to_vec();
+        for tree in &trees {
+            self.remove_submission(tree);
+            self.current_trees.retain(|t| t != tree);
+            self.tree_votes.retain(|_, voted| voted != tree);
+            removed.push(format!("Lorax submission \"{}\"", tree));
+        }
+        self.vote_weights.remove(&user_id);
+        if let Some(vote) = self.tree_votes.remove(&user_id) {
+            removed.push(format!("Lorax vote for \"{}\"", vote));
+        }
+        removed
+    }
+
+    /// Withdraws submissions whose names nodes now use, rejecting them so they can't be
+    /// submitted again. Returns `(submitter, tree)` for each.
+    pub fn withdraw_taken(&mut self, taken: &HashSet<String>) -> Vec<(u64, String)> {
+        let withdrawn: Vec<(u64, String)> = self
+            .submissions()
+            .filter(|(_, tree)| taken.contains(*tree))
+            .map(|(user_id, tree)| (user_id, tree.clone()))
+            .collect();
+        for (_, tree) in &withdrawn {
+            self.remove_submission(tree);
+            self.eliminated_trees.insert(tree.clone());
+            self.rejection_reasons.insert(tree.clone(), TAKEN_REASON.to_string());
+        }
+        withdrawn
+    }
+
+    /// Drops names nodes now use from the final standings, so the next name up wins
+    /// instead. Returns `(submitter, tree)` for each.
+    pub fn drop_taken_standings(&mut self, taken: &HashSet<String>) -> Vec<(u64, String)> {
+        let dropped: Vec<String> = self
+            .current_trees
+            .iter()
+            .filter(|tree| taken.contains(*tree))
+            .cloned()
+            .collect();
+        self.current_trees.retain(|tree| !taken.contains(tree));
+        dropped
+            .into_iter()
+            .filter_map(|tree| self.get_tree_submitter(&tree).map(|user_id| (user_id, tree)))
+            .collect()
+    }
+
+    /// Checks the user may submit `tree`, returning which of their names it replaces. Once
+    /// the user is at the submission limit, `replace` picks which of their names to swap
+    /// out (with a limit of one it's implied).
+    pub fn check_submission(
+        &self,
+        user_id: u64,
+        tree: &str,
+        replace: Option<String>,
+    ) -> Result<Option<String>, String> {
+        let taken = self.submissions().any(|(_, t)| t.eq_ignore_ascii_case(tree))
+            || self
+                .pending_submissions
+                .iter()
+                .any(|pending| pending.tree.eq_ignore_ascii_case(tree));
+        if taken {
+            return Err(format!("That {} name has already been submitted", self.settings.noun()));
+        }
+
+        let tree = tree.to_lowercase();
+        if self.eliminated_trees.contains(&tree) {
+            return Err(match self.rejection_reasons.get(&tree) {
+                Some(reason) => {
+                    format!("That {} name was rejected: {}", self.settings.noun(), reason)
+                }
+                None => format!("That {} name has been disqualified", self.settings.noun()),
+            });
+        }
+
+        let max_submissions = self.settings.max_submissions.max(1);
+        let own = self.user_submissions(user_id);
+
+        if let Some(replace) = replace {
+            let replace = replace.trim().to_lowercase();
+            if !own.contains(&replace) {
+                return Err(format!("You haven't submitted \"{}\"", replace));
+            }
+            Ok(Some(replace))
+        } else if own.len() >= max_submissions {
+            if max_submissions == 1 {
+                Ok(own.first().cloned())
+            } else {
+                Err(format!(
+                    "You've already submitted {} names; choose one to replace",
+                    max_submissions
+                ))
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Adds a submission after checking it, returning the name it replaced.
+    pub fn add_submission(
+        &mut self,
+        user_id: u64,
+        tree: String,
+        replace: Option<String>,
+    ) -> Result<Option<String>, String> {
+        let old_submission = self.check_submission(user_id, &tree, replace)?;
+        if let Some(old) = &old_submission {
+            // Also drops the pitch, which was written for the old name
+            self.remove_submission(old);
+        }
+        self.tree_submissions.entry(user_id).or_default().push(tree);
+        Ok(old_submission)
+    }
+
+    /// How much the user's vote counts this round.
+    pub fn vote_weight(&self, user_id: u64) -> usize {
+        self.vote_weights.get(&user_id).map_or(1, |weight| *weight as usize)
+    }
+
+    /// This round's weighted votes for a tree.
+    pub fn votes_for(&self, tree: &str) -> usize {
+        self.tree_votes
+            .iter()
+            .filter(|(_, vote)| vote.eq_ignore_ascii_case(tree))
+            .map(|(user_id, _)| self.vote_weight(*user_id))
+            .sum()
     }
 
     pub fn get_winner(&self) -> Option<String> {
         let mut vote_counts: std::collections::HashMap<&String, usize> = std::collections::HashMap::new();
         
-        for voted_tree in self.tree_votes.values() {
-            *vote_counts.entry(voted_tree).or_insert(0) += 1;
+        for (user_id, voted_tree) in &self.tree_votes {
+            *vote_counts.entry(voted_tree).or_insert(0) += self.vote_weight(*user_id);
         }
 
         if vote_counts.is_empty() {
@@ -93,33 +607,373 @@ impl LoraxEvent {
     }
 }
 
+/// What `/lorax reset` removed, kept so `/lorax restore` can put it back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoraxReset {
+    pub event: Option<LoraxEvent>,
+    pub settings: Option<LoraxSettings>,
+    /// Unix time of the reset.
+    pub reset_at: u64,
+}
+
+/// Members holding a guild's winner role, kept current from member updates so rotating
+/// winners doesn't have to scan the whole member list.
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
+pub struct WinnerHolders {
+    /// The winner role these members hold; a different configured role means unknown.
+    pub role_id: u64,
+    pub users: HashSet<u64>,
+}
+
+/// A completed event as kept for `/lorax history` and `/lorax winners`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedEvent {
+    /// Unix time the results were announced.
+    pub ended_at: u64,
+    /// Every winning tree, best first, with its submitter.
+    pub winners: Vec<(String, Option<u64>)>,
+    /// Every submission as `(submitter, tree name)`.
+    pub submissions: Vec<(u64, String)>,
+    /// Final standings, winner first.
+    pub standings: Vec<String>,
+    pub rounds: Vec<RoundResult>,
+}
+
+impl ArchivedEvent {
+    pub fn of(event: &LoraxEvent, ended_at: u64) -> Self {
+        let mut submissions: Vec<(u64, String)> = event
+            .submissions()
+            .map(|(user_id, tree)| (user_id, tree.clone()))
+            .collect();
+        submissions.sort();
+        Self {
+            ended_at,
+            winners: event
+                .winners()
+                .iter()
+                .map(|tree| (tree.clone(), event.get_tree_submitter(tree)))
+                .collect(),
+            submissions,
+            standings: event.current_trees.clone(),
+            rounds: event.round_results.clone(),
+        }
+    }
+}
+
+/// Participation rewards: a role for everyone who took part in the latest completed event,
+/// and how many events each member has taken part in.
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
+pub struct Participation {
+    /// Role given to participants until the next event completes. Unset turns the role off.
+    pub role_id: Option<u64>,
+    /// Members given the role, so it can be taken back.
+    pub holders: HashSet<u64>,
+    /// Members who voted in a finished round of the running event.
+    pub voters: HashSet<u64>,
+    /// Completed events each member submitted or voted in.
+    pub counts: HashMap<u64, u32>,
+}
+
+/// A member's lifetime Lorax record in one guild.
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
+pub struct MemberStats {
+    /// Names submitted to completed events.
+    pub submissions: u32,
+    /// Votes cast in closed rounds.
+    pub votes: u32,
+    pub wins: u32,
+    /// Completed events in a row the member took part in, ending at `last_event`.
+    pub streak: u32,
+    pub best_streak: u32,
+    /// Index in the guild's history of the latest completed event they took part in.
+    pub last_event: Option<usize>,
+}
+
+impl MemberStats {
+    /// The streak as of the guild's latest completed event: zero once they missed one.
+    pub fn current_streak(&self, completed: usize) -> u32 {
+        match self.last_event {
+            Some(last) if last + 1 == completed => self.streak,
+            _ => 0,
+        }
+    }
+}
+
+/// How often a scheduled event repeats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum Recurrence {
+    Once,
+    Daily,
+    Weekly,
+    #[name = "Every two weeks"]
+    Biweekly,
+    Monthly,
+}
+
+impl Recurrence {
+    /// The occurrence after `start`, or `None` for one-off events.
+    pub fn next(&self, start: u64) -> Option<u64> {
+        const DAY: u64 = 24 * 60 * 60;
+        match self {
+            Self::Once => None,
+            Self::Daily => Some(start + DAY),
+            Self::Weekly => Some(start + 7 * DAY),
+            Self::Biweekly => Some(start + 14 * DAY),
+            Self::Monthly => chrono::DateTime::from_timestamp(start as i64, 0)
+                .and_then(|time| time.checked_add_months(chrono::Months::new(1)))
+                .map(|time| time.timestamp() as u64),
+        }
+    }
+}
+
+/// An event `LoraxEventTask` starts by itself when its time comes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledLorax {
+    pub id: u64,
+    /// Unix time of the next start.
+    pub start_at: u64,
+    pub recurrence: Recurrence,
+    /// Whether the 24 hour notice for the next start went out.
+    pub announced: bool,
+    pub created_by: u64,
+}
+
+/// Scheduled events a guild can have at once.
+pub const MAX_SCHEDULES: usize = 10;
+
 #[derive(Default, Serialize, Deserialize, Clone, Debug)]
 pub struct LoraxDatabase {
     pub events: HashMap<u64, LoraxEvent>,
     pub settings: HashMap<u64, LoraxSettings>,
+    pub settings_history: HashMap<u64, SettingsHistory<LoraxSettings>>,
+    /// Archived resets, newest last.
+    pub resets: HashMap<u64, SettingsHistory<LoraxReset>>,
+    pub winner_holders: HashMap<u64, WinnerHolders>,
+    /// Upcoming scheduled events, soonest first.
+    pub schedules: HashMap<u64, Vec<ScheduledLorax>>,
+    /// Completed events, oldest first.
+    pub history: HashMap<u64, Vec<ArchivedEvent>>,
+    pub participation: HashMap<u64, Participation>,
+    /// Lifetime stats of each guild's members.
+    pub member_stats: HashMap<u64, HashMap<u64, MemberStats>>,
+}
+
+impl Rows for LoraxDatabase {
+    const VERSION: u32 = 2;
+
+    fn migrations() -> Vec<Box<dyn Migration>> {
+        vec![Box::new(migrations::V1ToV2)]
+    }
+
+    fn to_rows(&self) -> Result<BTreeMap<String, Vec<u8>>, DbError> {
+        let mut rows = BTreeMap::new();
+        put_guild_rows(&mut rows, "events", &self.events)?;
+        put_guild_rows(&mut rows, "settings", &self.settings)?;
+        put_guild_rows(&mut rows, "settings_history", &self.settings_history)?;
+        put_guild_rows(&mut rows, "resets", &self.resets)?;
+        put_guild_rows(&mut rows, "winner_holders", &self.winner_holders)?;
+        put_guild_rows(&mut rows, "schedules", &self.schedules)?;
+        put_guild_rows(&mut rows, "history", &self.history)?;
+        put_guild_rows(&mut rows, "participation", &self.participation)?;
+        put_guild_rows(&mut rows, "member_stats", &self.member_stats)?;
+        Ok(rows)
+    }
+
+    fn from_rows(rows: BTreeMap<String, Vec<u8>>) -> Result<Self, DbError> {
+        Ok(Self {
+            events: take_guild_rows(&rows, "events")?,
+            settings: take_guild_rows(&rows, "settings")?,
+            settings_history: take_guild_rows(&rows, "settings_history")?,
+            resets: take_guild_rows(&rows, "resets")?,
+            winner_holders: take_guild_rows(&rows, "winner_holders")?,
+            schedules: take_guild_rows(&rows, "schedules")?,
+            history: take_guild_rows(&rows, "history")?,
+            participation: take_guild_rows(&rows, "participation")?,
+            member_stats: take_guild_rows(&rows, "member_stats")?,
+        })
+    }
 }
 
 pub type LoraxHandler = Database<LoraxDatabase>;
 
 impl LoraxHandler {
+    /// Removes the user's submissions (with their pitches and the votes cast for them)
+    /// and votes from every guild's event and archived resets, describing what was removed.
+    pub async fn forget_user(&self, user_id: u64) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            let mut removed = Vec::new();
+            for (guild_id, event) in db.events.iter_mut() {
+                for what in event.forget_user(user_id) {
+                    removed.push(format!("{} in server {}", what, guild_id));
+                }
+            }
+            // Resets can be restored, so their snapshots must not bring the entries back
+            for (guild_id, resets) in db.resets.iter_mut() {
+                let archived = resets
+                    .iter_mut()
+                    .filter_map(|reset| reset.event.as_mut())
+                    .map(|event| event.forget_user(user_id).len())
+                    .sum::<usize>();
+                if archived > 0 {
+                    removed.push(format!(
+                        "{} Lorax submission(s) and vote(s) in reset events in server {}",
+                        archived, guild_id
+                    ));
+                }
+            }
+            for (guild_id, participation) in db.participation.iter_mut() {
+                participation.holders.remove(&user_id);
+                participation.voters.remove(&user_id);
+                if let Some(count) = participation.counts.remove(&user_id) {
+                    removed.push(format!(
+                        "Lorax participation in {} event(s) in server {}",
+                        count, guild_id
+                    ));
+                }
+            }
+            for (guild_id, stats) in db.member_stats.iter_mut() {
+                if stats.remove(&user_id).is_some() {
+                    removed.push(format!("Lorax stats in server {}", guild_id));
+                }
+            }
+            for (guild_id, holders) in db.winner_holders.iter_mut() {
+                if holders.users.remove(&user_id) {
+                    removed.push(format!("Lorax winner role tracking in server {}", guild_id));
+                }
+            }
+            // Past results keep the names, just not who submitted them
+            for (guild_id, history) in db.history.iter_mut() {
+                let mut credited = 0;
+                for archived in history.iter_mut() {
+                    let before = archived.submissions.len();
+                    archived.submissions.retain(|(submitter, _)| *submitter != user_id);
+                    credited += before - archived.submissions.len();
+                    for (_, winner_id) in archived.winners.iter_mut() {
+                        if *winner_id == Some(user_id) {
+                            *winner_id = None;
+                        }
+                    }
+                }
+                if credited > 0 {
+                    removed.push(format!(
+                        "{} past Lorax submission(s) in server {}",
+                        credited, guild_id
+                    ));
+                }
+            }
+            Ok(removed)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Clears a deleted channel from the guild's settings and running event, returning a
+    /// description of each change.
+    pub async fn forget_channel(&self, guild_id: u64, channel_id: u64) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            let mut changed = Vec::new();
+            let settings = db
+                .settings
+                .get_mut(&guild_id)
+                .into_iter()
+                .chain(db.events.get_mut(&guild_id).map(|event| &mut event.settings));
+            for settings in settings {
+                if settings.lorax_channel == Some(channel_id) {
+                    settings.lorax_channel = None;
+                    changed.push("Lorax announcement channel unset".to_string());
+                }
+            }
+            changed.dedup();
+            Ok(changed)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Clears a deleted role from the guild's settings and running event, returning a
+    /// description of each change.
+    pub async fn forget_role(&self, guild_id: u64, role_id: u64) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            let mut changed = Vec::new();
+            let settings = db
+                .settings
+                .get_mut(&guild_id)
+                .into_iter()
+                .chain(db.events.get_mut(&guild_id).map(|event| &mut event.settings));
+            for settings in settings {
+                for (label, role) in [
+                    ("event", &mut settings.lorax_role),
+                    ("winner", &mut settings.winner_role),
+                    ("alumni", &mut settings.alumni_role),
+                ] {
+                    if *role == Some(role_id) {
+                        *role = None;
+                        changed.push(format!("Lorax {} role unset", label));
+                    }
+                }
+            }
+            if db.winner_holders.get(&guild_id).is_some_and(|h| h.role_id == role_id) {
+                db.winner_holders.remove(&guild_id);
+            }
+            if let Some(participation) = db.participation.get_mut(&guild_id) {
+                if participation.role_id == Some(role_id) {
+                    participation.role_id = None;
+                    participation.holders.clear();
+                    changed.push("Lorax participant role unset".to_string());
+                }
+            }
+            changed.dedup();
+            Ok(changed)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
     pub async fn get_event(&self, guild_id: u64) -> Option<LoraxEvent> {
         self.get_data().await.events.get(&guild_id).cloned()
     }
 
+    /// Takes back one of the user's submissions, or their name waiting for approval, while
+    /// submissions are open. The name can be submitted again afterwards.
+    pub async fn withdraw_submission(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        tree: &str,
+    ) -> Result<(), String> {
+        let tree = tree.trim().to_lowercase();
+        self.transaction(|db| {
+            let event = db.events.get_mut(&guild_id).ok_or("No active event")?;
+            if !matches!(event.stage, LoraxStage::Submission) {
+                return Err("Submissions can only be withdrawn while they're open".to_string());
+            }
+
+            if event.user_submissions(user_id).contains(&tree) {
+                event.remove_submission(&tree);
+                return Ok(());
+            }
+            let before = event.pending_submissions.len();
+            event
+                .pending_submissions
+                .retain(|pending| !(pending.user_id == user_id && pending.tree == tree));
+            if event.pending_submissions.len() == before {
+                return Err("You haven't submitted that name".to_string());
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Submits a tree name. Once the user is at the submission limit, `replace` picks
+    /// which of their names to swap out (with a limit of one it's implied).
     pub async fn submit_tree(
         &self,
         guild_id: u64,
         tree: String,
         user_id: u64,
+        replace: Option<String>,
     ) -> Result<(bool, Option<String>), String> {
-        if tree.trim().is_empty() {
-            return Err("Tree name cannot be empty".to_string());
-        }
-
-        if tree.len() > 32 {
-            return Err("Tree name cannot be longer than 32 characters".to_string());
-        }
-
         let tree = tree.trim().to_owned();
 
         self.transaction(|db| {
@@ -129,30 +983,126 @@ impl LoraxHandler {
             if !matches!(event.stage, LoraxStage::Submission) {
                 return Err("Submissions are not currently open".to_string());
             }
+            if tree.is_empty() {
+                return Err(format!("The {} name cannot be empty", event.settings.noun()));
+            }
+            if tree.chars().count() > MAX_NAME_LENGTH {
+                return Err(format!(
+                    "The {} name cannot be longer than {} characters",
+                    event.settings.noun(),
+                    MAX_NAME_LENGTH
+                ));
+            }
+
+            let old_submission = event.add_submission(user_id, tree, replace)?;
+            Ok((old_submission.is_some(), old_submission))
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
 
-            // Check for duplicate names
-            if event.tree_submissions.values().any(|t| t.eq_ignore_ascii_case(&tree)) {
-                return Err("That tree name has already been submitted".to_string());
+    /// Puts a submission in the moderation queue after the same checks `submit_tree` makes.
+    /// Users have one name waiting at a time.
+    pub async fn queue_submission(
+        &self,
+        guild_id: u64,
+        tree: String,
+        user_id: u64,
+        replace: Option<String>,
+        now: u64,
+    ) -> Result<(), String> {
+        let tree = tree.trim().to_owned();
+
+        self.transaction(|db| {
+            let event = db.events.get_mut(&guild_id).ok_or("No active event")?;
+            if !matches!(event.stage, LoraxStage::Submission) {
+                return Err("Submissions are not currently open".into());
             }
+            if tree.is_empty() {
+                return Err(format!("The {} name cannot be empty", event.settings.noun()));
+            }
+            if let Some(waiting) = event
+                .pending_submissions
+                .iter()
+                .find(|pending| pending.user_id == user_id)
+            {
+                return Err(format!("\"{}\" is still waiting for approval", waiting.tree));
+            }
+
+            let replace = event.check_submission(user_id, &tree, replace)?;
+            event.pending_submissions.push(PendingSubmission {
+                user_id,
+                tree,
+                replace,
+                submitted_at: now,
+            });
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
 
-            if event.eliminated_trees.contains(&tree.to_lowercase()) {
-                return Err("That tree name has been disqualified".to_string());
+    /// Moves a queued submission into the event, returning it.
+    pub async fn approve_submission(
+        &self,
+        guild_id: u64,
+        tree: &str,
+    ) -> Result<PendingSubmission, String> {
+        self.transaction(|db| {
+            let event = db.events.get_mut(&guild_id).ok_or("No active event")?;
+            if !matches!(event.stage, LoraxStage::Submission) {
+                return Err("Submissions are closed".into());
             }
+            let index = event
+                .pending_submissions
+                .iter()
+                .position(|pending| pending.tree == tree)
+                .ok_or("That submission is no longer waiting")?;
 
-            let is_update = event.tree_submissions.contains_key(&user_id);
-            let old_submission = event.tree_submissions.insert(user_id, tree);
-            Ok((is_update, old_submission))
+            let pending = event.pending_submissions.remove(index);
+            event.add_submission(pending.user_id, pending.tree.clone(), pending.replace.clone())?;
+            Ok(pending)
         })
         .await
         .map_err(|e| e.to_string())
     }
 
+    /// Drops a queued submission and disqualifies the name for the rest of the event.
+    pub async fn reject_submission(
+        &self,
+        guild_id: u64,
+        tree: &str,
+        reason: String,
+    ) -> Result<PendingSubmission, String> {
+        self.transaction(|db| {
+            let event = db.events.get_mut(&guild_id).ok_or("No active event")?;
+            let index = event
+                .pending_submissions
+                .iter()
+                .position(|pending| pending.tree == tree)
+                .ok_or("That submission is no longer waiting")?;
+
+            let pending = event.pending_submissions.remove(index);
+            let name = pending.tree.to_lowercase();
+            event.eliminated_trees.insert(name.clone());
+            event.rejection_reasons.insert(name, reason);
+            Ok(pending)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Casts or changes the user's vote, counting `weight` times, and returns the vote it
+    /// replaced. Once the round's vote lock starts, new votes are still taken but existing
+    /// ones can't change.
     pub async fn vote_tree(
         &self,
         guild_id: u64,
         tree: String,
         user_id: u64,
-    ) -> Result<bool, String> {
+        weight: u32,
+        now: u64,
+    ) -> Result<Option<String>, String> {
         self.transaction(|db| {
             let event = db.events.get_mut(&guild_id)
                 .ok_or("No active event")?;
@@ -162,12 +1112,99 @@ impl LoraxHandler {
             }
 
             if !event.current_trees.iter().any(|t| t.eq_ignore_ascii_case(&tree)) {
-                return Err("Invalid tree selection".to_string());
+                return Err(format!("Invalid {} selection", event.settings.noun()));
             }
 
-            let is_update = event.tree_votes.contains_key(&user_id);
-            event.tree_votes.insert(user_id, tree);
-            Ok(is_update)
+            let locked = event.vote_lock_time().is_some_and(|lock| now >= lock);
+            if locked && event.tree_votes.contains_key(&user_id) {
+                return Err(format!(
+                    "Votes are locked for the final {} minute(s) of the round",
+                    event.settings.vote_lock
+                ));
+            }
+
+            if weight > 1 {
+                event.vote_weights.insert(user_id, weight);
+            } else {
+                event.vote_weights.remove(&user_id);
+            }
+            Ok(event.tree_votes.insert(user_id, tree))
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Takes back the user's vote if it's for `tree` and votes aren't locked. Returns
+    /// whether a vote was taken back.
+    pub async fn withdraw_vote(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        tree: &str,
+        now: u64,
+    ) -> Result<bool, String> {
+        self.transaction(|db| {
+            let Some(event) = db.events.get_mut(&guild_id) else {
+                return Ok(false);
+            };
+            let locked = event.vote_lock_time().is_some_and(|lock| now >= lock);
+            if locked || event.tree_votes.get(&user_id).is_none_or(|vote| vote != tree) {
+                return Ok(false);
+            }
+            event.tree_votes.remove(&user_id);
+            event.vote_weights.remove(&user_id);
+            Ok(true)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Sets the campaign pitch for one of the user's submissions. `tree` can be left
+    /// out when the user only has one; returns the tree name the pitch belongs to.
+    pub async fn set_pitch(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        tree: Option<String>,
+        pitch: Pitch,
+    ) -> Result<String, String> {
+        self.transaction(|db| {
+            let event = db.events.get_mut(&guild_id).ok_or("No active event")?;
+
+            if !matches!(
+                event.stage,
+                LoraxStage::Submission | LoraxStage::Voting | LoraxStage::Tiebreaker(_)
+            ) {
+                return Err("Campaigning is closed".to_string());
+            }
+
+            let own = event.user_submissions(user_id);
+            let tree = match tree {
+                Some(tree) => {
+                    let tree = tree.trim().to_lowercase();
+                    if !own.contains(&tree) {
+                        return Err(format!("You haven't submitted \"{}\"", tree));
+                    }
+                    tree
+                }
+                None => match own {
+                    [] => {
+                        return Err(format!(
+                            "You haven't submitted a {} name",
+                            event.settings.noun()
+                        ))
+                    }
+                    [tree] => tree.clone(),
+                    _ => {
+                        return Err(format!(
+                            "You have several submissions; choose which {} to pitch",
+                            event.settings.noun()
+                        ))
+                    }
+                },
+            };
+            event.pitches.insert(tree.clone(), pitch);
+            Ok(tree)
         })
         .await
         .map_err(|e| e.to_string())
@@ -182,6 +1219,19 @@ impl LoraxHandler {
         .map_err(|e| e.to_string())
     }
 
+    /// Mutates the stored event in place, so concurrent votes and submissions aren't overwritten.
+    pub async fn modify_event<F, R>(&self, guild_id: u64, f: F) -> Result<R, String>
+    where
+        F: FnOnce(&mut LoraxEvent) -> Result<R, String>,
+    {
+        self.transaction(|db| {
+            let event = db.events.get_mut(&guild_id).ok_or("No active event")?;
+            f(event)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
     pub async fn get_settings(&self, guild_id: u64) -> Result<LoraxSettings, String> {
         Ok(self
             .get_data()
@@ -197,4 +1247,423 @@ impl LoraxHandler {
             .await
             .map_err(|e| e.to_string())
     }
+
+    /// Edits the guild's name rules, for the running event too since they only affect new
+    /// submissions. `f` runs once per copy and can refuse the change.
+    pub async fn update_name_rules<F>(&self, guild_id: u64, f: F) -> Result<NameRules, String>
+    where
+        F: Fn(&mut NameRules) -> Result<(), String>,
+    {
+        self.transaction(|db| {
+            let settings = db.settings.entry(guild_id).or_default();
+            let before = settings.clone();
+            f(&mut settings.name_rules)?;
+            let rules = settings.name_rules.clone();
+            db.settings_history.entry(guild_id).or_default().record(before);
+
+            if let Some(event) = db.events.get_mut(&guild_id) {
+                f(&mut event.settings.name_rules)?;
+            }
+            Ok(rules)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Applies a settings change, recording the previous value for `/settings undo`.
+    pub async fn update_settings<F>(&self, guild_id: u64, f: F) -> Result<LoraxSettings, String>
+    where
+        F: FnOnce(&mut LoraxSettings),
+    {
+        self.transaction(|db| {
+            let settings = db.settings.entry(guild_id).or_default();
+            let before = settings.clone();
+            f(settings);
+            let after = settings.clone();
+
+            db.settings_history.entry(guild_id).or_default().record(before);
+            Ok(after)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Removes the guild's event and settings, archiving them for [`Self::restore`].
+    /// Returns the removed event.
+    pub async fn reset(&self, guild_id: u64, now: u64) -> Result<Option<LoraxEvent>, String> {
+        self.transaction(|db| {
+            let event = db.events.remove(&guild_id);
+            let settings = db.settings.remove(&guild_id);
+            if let Some(settings) = &settings {
+                db.settings_history.entry(guild_id).or_default().record(settings.clone());
+            }
+            if event.is_some() || settings.is_some() {
+                // The reset closes the Discord scheduled event, so the archive forgets it
+                let archived = event.clone().map(|mut event| {
+                    event.scheduled_event_id = None;
+                    event
+                });
+                db.resets.entry(guild_id).or_default().record(LoraxReset {
+                    event: archived,
+                    settings,
+                    reset_at: now,
+                });
+            }
+            Ok(event)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Puts back what the most recent reset removed. A restored event resumes its stage
+    /// with the time it spent archived added back. Refuses while an event is running,
+    /// since restoring would replace it.
+    pub async fn restore(&self, guild_id: u64, now: u64) -> Result<Option<LoraxReset>, String> {
+        self.transaction(|db| {
+            let running = db.events.get(&guild_id).is_some_and(|event| {
+                !matches!(event.stage, LoraxStage::Inactive | LoraxStage::Completed)
+            });
+            if running {
+                return Err("An event is running; end it before restoring".to_string());
+            }
+
+            let Some(reset) = db.resets.get_mut(&guild_id).and_then(|resets| resets.pop())
+            else {
+                return Ok(None);
+            };
+            if let Some(event) = &reset.event {
+                let mut event = event.clone();
+                event.start_time += now.saturating_sub(reset.reset_at);
+                db.events.insert(guild_id, event);
+            }
+            if let Some(settings) = &reset.settings {
+                if let Some(current) = db.settings.insert(guild_id, settings.clone()) {
+                    db.settings_history.entry(guild_id).or_default().record(current);
+                }
+            }
+            Ok(Some(reset))
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Members known to hold `role_id` as the guild's winner role, or `None` if they
+    /// haven't been collected for that role yet.
+    pub async fn winner_holders(&self, guild_id: u64, role_id: u64) -> Option<HashSet<u64>> {
+        self.read(|db| {
+            db.winner_holders
+                .get(&guild_id)
+                .filter(|holders| holders.role_id == role_id)
+                .map(|holders| holders.users.clone())
+        })
+        .await
+    }
+
+    pub async fn set_winner_holders(
+        &self,
+        guild_id: u64,
+        role_id: u64,
+        users: HashSet<u64>,
+    ) -> Result<(), String> {
+        self.transaction(|db| {
+            db.winner_holders
+                .insert(guild_id, WinnerHolders { role_id, users });
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Records whether a member holds the tracked winner role after their roles changed.
+    /// Only writes when that changes something.
+    pub async fn update_winner_holder(
+        &self,
+        guild_id: u64,
+        user_id: u64,
+        roles: &[u64],
+    ) -> Result<(), String> {
+        let changed = self
+            .read(|db| {
+                db.winner_holders.get(&guild_id).is_some_and(|holders| {
+                    roles.contains(&holders.role_id) != holders.users.contains(&user_id)
+                })
+            })
+            .await;
+        if !changed {
+            return Ok(());
+        }
+
+        self.transaction(|db| {
+            if let Some(holders) = db.winner_holders.get_mut(&guild_id) {
+                if roles.contains(&holders.role_id) {
+                    holders.users.insert(user_id);
+                } else {
+                    holders.users.remove(&user_id);
+                }
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Keeps only the newest `keep` archived resets per guild, returning how many were
+    /// dropped.
+    pub async fn trim_resets(&self, keep: usize) -> Result<usize, String> {
+        self.transaction(|db| {
+            let pruned = db.resets.values_mut().map(|resets| resets.keep_newest(keep)).sum();
+            db.resets.retain(|_, resets| !resets.is_empty());
+            Ok(pruned)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Reverts the most recent settings change, returning the restored settings.
+    pub async fn undo_settings(&self, guild_id: u64) -> Result<Option<LoraxSettings>, String> {
+        self.transaction(|db| {
+            let previous = db
+                .settings_history
+                .get_mut(&guild_id)
+                .and_then(|history| history.pop());
+
+            if let Some(previous) = &previous {
+                db.settings.insert(guild_id, previous.clone());
+            }
+            Ok(previous)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Schedules an event, returning it. Fails once the guild has [`MAX_SCHEDULES`].
+    pub async fn add_schedule(
+        &self,
+        guild_id: u64,
+        start_at: u64,
+        recurrence: Recurrence,
+        created_by: u64,
+    ) -> Result<ScheduledLorax, String> {
+        self.transaction(|db| {
+            let schedules = db.schedules.entry(guild_id).or_default();
+            if schedules.len() >= MAX_SCHEDULES {
+                return Err(format!(
+                    "This server already has {} scheduled events; cancel one first",
+                    MAX_SCHEDULES
+                ));
+            }
+            let scheduled = ScheduledLorax {
+                id: schedules.iter().map(|s| s.id).max().unwrap_or(0) + 1,
+                start_at,
+                recurrence,
+                announced: false,
+                created_by,
+            };
+            schedules.push(scheduled.clone());
+            schedules.sort_by_key(|s| s.start_at);
+            Ok(scheduled)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Removes a scheduled event, returning it if there was one.
+    pub async fn cancel_schedule(
+        &self,
+        guild_id: u64,
+        id: u64,
+    ) -> Result<Option<ScheduledLorax>, String> {
+        self.transaction(|db| {
+            let Some(schedules) = db.schedules.get_mut(&guild_id) else {
+                return Ok(None);
+            };
+            let cancelled = schedules
+                .iter()
+                .position(|s| s.id == id)
+                .map(|index| schedules.remove(index));
+            if schedules.is_empty() {
+                db.schedules.remove(&guild_id);
+            }
+            Ok(cancelled)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn schedules(&self, guild_id: u64) -> Vec<ScheduledLorax> {
+        self.read(|db| db.schedules.get(&guild_id).cloned().unwrap_or_default())
+            .await
+    }
+
+    /// Moves a scheduled event that just came up to its next occurrence, or removes it if
+    /// it doesn't repeat.
+    pub async fn reschedule(&self, guild_id: u64, id: u64, now: u64) -> Result<(), String> {
+        self.transaction(|db| {
+            let Some(schedules) = db.schedules.get_mut(&guild_id) else {
+                return Ok(());
+            };
+            for scheduled in schedules.iter_mut().filter(|s| s.id == id) {
+                // Skip occurrences missed while the bot was down
+                let mut next = scheduled.recurrence.next(scheduled.start_at);
+                while let Some(start) = next.filter(|start| *start <= now) {
+                    next = scheduled.recurrence.next(start);
+                }
+                match next {
+                    Some(start) => {
+                        scheduled.start_at = start;
+                        scheduled.announced = false;
+                    }
+                    None => scheduled.start_at = 0,
+                }
+            }
+            schedules.retain(|s| s.start_at != 0);
+            schedules.sort_by_key(|s| s.start_at);
+            if schedules.is_empty() {
+                db.schedules.remove(&guild_id);
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Marks the 24 hour notice for a scheduled event as sent.
+    pub async fn mark_schedule_announced(&self, guild_id: u64, id: u64) -> Result<(), String> {
+        self.transaction(|db| {
+            let schedules = db.schedules.get_mut(&guild_id).into_iter().flatten();
+            for scheduled in schedules.filter(|s| s.id == id) {
+                scheduled.announced = true;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn archive_event(
+        &self,
+        guild_id: u64,
+        archived: ArchivedEvent,
+    ) -> Result<(), String> {
+        self.transaction(|db| {
+            let stats = db.member_stats.entry(guild_id).or_default();
+            for (user_id, _) in &archived.submissions {
+                stats.entry(*user_id).or_default().submissions += 1;
+            }
+            for winner_id in archived.winners.iter().filter_map(|(_, id)| *id) {
+                stats.entry(winner_id).or_default().wins += 1;
+            }
+            db.history.entry(guild_id).or_default().push(archived);
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// The guild's completed events, oldest first.
+    pub async fn history(&self, guild_id: u64) -> Vec<ArchivedEvent> {
+        self.read(|db| db.history.get(&guild_id).cloned().unwrap_or_default())
+            .await
+    }
+
+    /// Sets or clears the participant role, returning the old role and the members holding
+    /// it, if it changed.
+    pub async fn set_participant_role(
+        &self,
+        guild_id: u64,
+        role_id: Option<u64>,
+    ) -> Result<Option<(u64, HashSet<u64>)>, String> {
+        self.transaction(|db| {
+            let participation = db.participation.entry(guild_id).or_default();
+            if participation.role_id == role_id {
+                return Ok(None);
+            }
+            let old = std::mem::replace(&mut participation.role_id, role_id);
+            let holders = std::mem::take(&mut participation.holders);
+            Ok(old.map(|old| (old, holders)))
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn participation(&self, guild_id: u64) -> Participation {
+        self.read(|db| db.participation.get(&guild_id).cloned().unwrap_or_default())
+            .await
+    }
+
+    /// Lifetime stats of the guild's members.
+    pub async fn member_stats(&self, guild_id: u64) -> HashMap<u64, MemberStats> {
+        self.read(|db| db.member_stats.get(&guild_id).cloned().unwrap_or_default())
+            .await
+    }
+
+    /// Forgets voters left over from an event that ended without completing.
+    pub async fn clear_voters(&self, guild_id: u64) -> Result<(), String> {
+        self.transaction(|db| {
+            if let Some(participation) = db.participation.get_mut(&guild_id) {
+                participation.voters.clear();
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Remembers who voted in a round that just closed and counts their votes.
+    pub async fn record_voters(
+        &self,
+        guild_id: u64,
+        voters: impl IntoIterator<Item = u64>,
+    ) -> Result<(), String> {
+        self.transaction(|db| {
+            let voters: Vec<u64> = voters.into_iter().collect();
+            let stats = db.member_stats.entry(guild_id).or_default();
+            for user_id in &voters {
+                stats.entry(*user_id).or_default().votes += 1;
+            }
+            db.participation.entry(guild_id).or_default().voters.extend(voters);
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Credits everyone who submitted or voted in the event that just completed, extending
+    /// their streaks, and makes them the participant role's holders. Returns the role with
+    /// the members to take it from and to give it to, if the role is on.
+    pub async fn reward_participants(
+        &self,
+        guild_id: u64,
+        submitters: impl IntoIterator<Item = u64>,
+    ) -> Result<Option<(u64, Vec<u64>, Vec<u64>)>, String> {
+        self.transaction(|db| {
+            let participation = db.participation.entry(guild_id).or_default();
+            let mut participants = std::mem::take(&mut participation.voters);
+            participants.extend(submitters);
+            // Not archived yet, so this event goes at the end of the history
+            let index = db.history.get(&guild_id).map_or(0, Vec::len);
+            let stats = db.member_stats.entry(guild_id).or_default();
+            for user_id in &participants {
+                *participation.counts.entry(*user_id).or_default() += 1;
+                let member = stats.entry(*user_id).or_default();
+                member.streak = member.current_streak(index) + 1;
+                member.best_streak = member.best_streak.max(member.streak);
+                member.last_event = Some(index);
+            }
+
+            let Some(role_id) = participation.role_id else {
+                return Ok(None);
+            };
+            let mut remove: Vec<u64> =
+                participation.holders.difference(&participants).copied().collect();
+            let mut add: Vec<u64> =
+                participants.difference(&participation.holders).copied().collect();
+            remove.sort_unstable();
+            add.sort_unstable();
+            participation.holders = participants;
+            Ok(Some((role_id, remove, add)))
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
 }
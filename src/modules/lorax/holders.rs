@@ -0,0 +1,48 @@
+use super::database::LoraxHandler;
+use crate::events::EventHandler;
+use crate::modules::toggles::database::Module;
+use async_trait::async_trait;
+use poise::serenity_prelude::{Context, FullEvent};
+
+/// Keeps the tracked winner role holders current as members gain or lose the role or leave.
+#[derive(Debug, Clone)]
+pub struct WinnerHoldersHandler {
+    pub db: LoraxHandler,
+}
+
+#[async_trait]
+impl EventHandler for WinnerHoldersHandler {
+    fn name(&self) -> &str {
+        "LoraxWinnerHolders"
+    }
+
+    async fn handle(
+        &self,
+        _ctx: &Context,
+        event: &FullEvent,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match event {
+            FullEvent::GuildMemberUpdate { event, .. } => {
+                let roles: Vec<u64> = event.roles.iter().map(|role| role.get()).collect();
+                self.db
+                    .update_winner_holder(event.guild_id.get(), event.user.id.get(), &roles)
+                    .await?;
+            }
+            FullEvent::GuildMemberRemoval { guild_id, user, .. } => {
+                self.db
+                    .update_winner_holder(guild_id.get(), user.id.get(), &[])
+                    .await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn box_clone(&self) -> Box<dyn EventHandler> {
+        Box::new(self.clone())
+    }
+
+    fn module(&self) -> Option<Module> {
+        Some(Module::Lorax)
+    }
+}
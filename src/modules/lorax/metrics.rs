@@ -0,0 +1,49 @@
+use crate::{database::Database, metrics::MetricsRegistry};
+
+use super::database::LoraxDatabase;
+
+pub fn register(registry: &MetricsRegistry, db: Database<LoraxDatabase>) {
+    let stage_db = db.clone();
+    registry.register_gauge(
+        "lorax_stage",
+        "Current Lorax stage (0 inactive, 1 submission, 2 voting, 3 tiebreaker, 4 completed)",
+        move |guild_id| {
+            let db = stage_db.clone();
+            async move {
+                Ok::<_, String>(db
+                    .get_event(guild_id)
+                    .await
+                    .map_or(0.0, |event| event.stage.code() as f64))
+            }
+        },
+    );
+
+    let submissions_db = db.clone();
+    registry.register_gauge(
+        "lorax_submissions",
+        "Tree names submitted to the current Lorax event",
+        move |guild_id| {
+            let db = submissions_db.clone();
+            async move {
+                Ok::<_, String>(db
+                    .get_event(guild_id)
+                    .await
+                    .map_or(0.0, |event| event.submission_count() as f64))
+            }
+        },
+    );
+
+    registry.register_gauge(
+        "lorax_votes",
+        "Votes cast in the current Lorax stage",
+        move |guild_id| {
+            let db = db.clone();
+            async move {
+                Ok::<_, String>(db
+                    .get_event(guild_id)
+                    .await
+                    .map_or(0.0, |event| event.tree_votes.len() as f64))
+            }
+        },
+    );
+}
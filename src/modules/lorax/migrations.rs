@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::database as live;
+use crate::database::{decode, encode, DbError, Migration};
+
+/// The Lorax schema as first released, before rows were versioned. Frozen: never change
+/// these types.
+mod v1 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum LoraxStage {
+        Submission,
+        Voting,
+        Tiebreaker(usize),
+        Completed,
+        Inactive,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct LoraxSettings {
+        pub lorax_channel: Option<u64>,
+        pub lorax_role: Option<u64>,
+        pub winner_role: Option<u64>,
+        pub alumni_role: Option<u64>,
+        pub submission_duration: u64,
+        pub voting_duration: u64,
+        pub tiebreaker_duration: u64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct LoraxEvent {
+        pub stage: LoraxStage,
+        pub settings: LoraxSettings,
+        pub tree_submissions: HashMap<u64, String>,
+        pub tree_votes: HashMap<u64, String>,
+        pub eliminated_trees: HashSet<String>,
+        pub start_time: u64,
+        pub current_trees: Vec<String>,
+        pub campaign_message_id: Option<u64>,
+        pub stage_message_id: Option<u64>,
+        pub voting_message_id: Option<u64>,
+        pub tiebreaker_message_id: Option<u64>,
+        pub campaign_thread_id: Option<u64>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct LoraxDatabase {
+        pub events: HashMap<u64, LoraxEvent>,
+        pub settings: HashMap<u64, LoraxSettings>,
+    }
+}
+
+impl From<v1::LoraxStage> for live::LoraxStage {
+    fn from(old: v1::LoraxStage) -> Self {
+        match old {
+            v1::LoraxStage::Submission => Self::Submission,
+            v1::LoraxStage::Voting => Self::Voting,
+            v1::LoraxStage::Tiebreaker(round) => Self::Tiebreaker(round),
+            v1::LoraxStage::Completed => Self::Completed,
+            v1::LoraxStage::Inactive => Self::Inactive,
+        }
+    }
+}
+
+impl From<v1::LoraxSettings> for live::LoraxSettings {
+    fn from(old: v1::LoraxSettings) -> Self {
+        Self {
+            lorax_channel: old.lorax_channel,
+            lorax_role: old.lorax_role,
+            winner_role: old.winner_role,
+            alumni_role: old.alumni_role,
+            submission_duration: old.submission_duration,
+            voting_duration: old.voting_duration,
+            tiebreaker_duration: old.tiebreaker_duration,
+            ..Self::default()
+        }
+    }
+}
+
+impl From<v1::LoraxEvent> for live::LoraxEvent {
+    fn from(old: v1::LoraxEvent) -> Self {
+        Self {
+            stage: old.stage.into(),
+            tree_submissions: old
+                .tree_submissions
+                .into_iter()
+                .map(|(user_id, tree)| (user_id, vec![tree]))
+                .collect(),
+            tree_votes: old.tree_votes,
+            eliminated_trees: old.eliminated_trees,
+            current_trees: old.current_trees,
+            campaign_message_id: old.campaign_message_id,
+            stage_message_id: old.stage_message_id,
+            voting_message_id: old.voting_message_id,
+            tiebreaker_message_id: old.tiebreaker_message_id,
+            campaign_thread_id: old.campaign_thread_id,
+            ..Self::new(old.settings.into(), old.start_time)
+        }
+    }
+}
+
+/// v1 → v2: submissions become a list per user, and everything added since (history,
+/// schedules, stats, pitches, the newer settings) starts out empty or at its default.
+pub struct V1ToV2;
+
+impl Migration for V1ToV2 {
+    fn from_version(&self) -> u32 {
+        1
+    }
+
+    fn migrate(&self, key: &str, bytes: Vec<u8>) -> Result<Vec<u8>, DbError> {
+        // v1 predates partitioned storage, so it only ever exists as the single row
+        if !key.is_empty() {
+            return Err(DbError::Codec(format!("unexpected v1 row {:?}", key)));
+        }
+        let old: v1::LoraxDatabase = decode(&bytes)?;
+        encode(&live::LoraxDatabase {
+            events: old.events.into_iter().map(|(id, e)| (id, e.into())).collect(),
+            settings: old.settings.into_iter().map(|(id, s)| (id, s.into())).collect(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Rows;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn loads_baseline_at_current_version() {
+        let settings = || v1::LoraxSettings {
+            lorax_channel: Some(10),
+            lorax_role: None,
+            winner_role: Some(11),
+            alumni_role: None,
+            submission_duration: 120,
+            voting_duration: 30,
+            tiebreaker_duration: 15,
+        };
+        let event = v1::LoraxEvent {
+            stage: v1::LoraxStage::Tiebreaker(2),
+            settings: settings(),
+            tree_submissions: HashMap::from([(7, "willow".to_string())]),
+            tree_votes: HashMap::from([(8, "willow".to_string())]),
+            eliminated_trees: HashSet::new(),
+            start_time: 1000,
+            current_trees: vec!["willow".to_string()],
+            campaign_message_id: Some(12),
+            stage_message_id: None,
+            voting_message_id: None,
+            tiebreaker_message_id: None,
+            campaign_thread_id: None,
+        };
+        let baseline = v1::LoraxDatabase {
+            events: HashMap::from([(1, event)]),
+            settings: HashMap::from([(1, settings())]),
+        };
+        let rows = BTreeMap::from([(String::new(), encode(&baseline).unwrap())]);
+
+        let rows = live::LoraxDatabase::upgrade(rows).unwrap();
+        let db: live::LoraxDatabase = decode(&rows[""]).unwrap();
+
+        let event = &db.events[&1];
+        assert_eq!(event.stage, live::LoraxStage::Tiebreaker(2));
+        assert_eq!(event.user_submissions(7), ["willow".to_string()]);
+        assert_eq!(event.tree_votes[&8], "willow");
+        assert_eq!(event.start_time, 1000);
+        assert_eq!(event.campaign_message_id, Some(12));
+        assert_eq!(event.winner_count, 1);
+        assert_eq!(event.settings.submission_duration, 120);
+        assert_eq!(db.settings[&1].winner_role, Some(11));
+        assert_eq!(db.settings[&1].max_submissions, 1);
+        assert!(db.history.is_empty());
+    }
+}
@@ -1,3 +1,11 @@
+pub mod ballot;
 pub mod commands;
 pub mod database;
+pub mod holders;
+pub mod metrics;
+pub mod migrations;
+pub mod node_names;
+pub mod pitch;
+pub mod schedule;
+pub mod submit_button;
 pub mod task;
@@ -0,0 +1,48 @@
+//! Names of the cluster's nodes, which event names mustn't clash with.
+
+use crate::utils::http;
+
+/// Every node's name, lowercase, from the `node_uname_info` metric.
+pub async fn fetch(prometheus_url: &str) -> Result<Vec<String>, String> {
+    let client = http::client();
+    let response = client
+        .send(
+            client
+                .get(format!("{}/api/v1/query", prometheus_url.trim_end_matches('/')))
+                .query(&[("query", "node_uname_info")]),
+        )
+        .await
+        .map_err(|e| format!("Failed to fetch metrics: {}", e))?;
+
+    #[derive(serde::Deserialize)]
+    struct PrometheusResponse {
+        data: Data,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Data {
+        result: Vec<Result>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Result {
+        metric: Metric,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Metric {
+        nodename: String,
+    }
+
+    let data: PrometheusResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    Ok(data
+        .data
+        .result
+        .into_iter()
+        .map(|r| r.metric.nodename.to_lowercase())
+        .collect())
+}
@@ -0,0 +1,132 @@
+use poise::serenity_prelude::{
+    ChannelId, Context, CreateAttachment, CreateEmbed, CreateMessage, EditAttachments, EditMessage,
+    MessageId,
+};
+use tracing::warn;
+
+use super::database::LoraxEvent;
+use crate::{modules::system::database::Theme, utils::embed};
+
+/// Longest pitch excerpt that fits in a select menu option description.
+const DESCRIPTION_LIMIT: usize = 100;
+
+/// A shortlisted tree's campaign embed: its pitch, or a prompt to add one, its submitter,
+/// and an invitation to discuss it in replies.
+pub fn campaign_embed(
+    theme: &Theme,
+    event: &LoraxEvent,
+    tree: &str,
+    submitter: u64,
+) -> CreateEmbed {
+    let pitch = event.pitches.get(tree);
+    let text = match pitch {
+        Some(pitch) => pitch.text.clone(),
+        None => "*No pitch yet. The submitter can add one with `/lorax pitch`.*".to_string(),
+    };
+    let embed = embed::themed(theme)
+        .title(format!("{} {}", event.settings.theme_emoji(), tree))
+        .description(format!(
+            "{}\n\n— <@{}>\n\n💬 Reply to this message to discuss **{}**.",
+            text, submitter, tree
+        ));
+    match pitch.and_then(|pitch| pitch.image.as_ref()) {
+        Some(image) => embed.image(format!("attachment://{}", image.filename)),
+        None => embed,
+    }
+}
+
+/// The pitch image to upload alongside the tree's campaign embed.
+fn campaign_image(event: &LoraxEvent, tree: &str) -> Option<CreateAttachment> {
+    let image = event.pitches.get(tree)?.image.as_ref()?;
+    Some(CreateAttachment::bytes(image.data.clone(), image.filename.clone()))
+}
+
+fn campaign_message(
+    theme: &Theme,
+    event: &LoraxEvent,
+    tree: &str,
+    submitter: u64,
+) -> CreateMessage {
+    let message = CreateMessage::new().embed(campaign_embed(theme, event, tree, submitter));
+    match campaign_image(event, tree) {
+        Some(image) => message.add_file(image),
+        None => message,
+    }
+}
+
+/// Posts a campaign message for every shortlisted tree in the event's campaign thread,
+/// remembering each so voting options can link to it.
+pub async fn post_campaign(ctx: &Context, theme: &Theme, event: &mut LoraxEvent) {
+    let Some(thread_id) = event.campaign_thread_id else {
+        return;
+    };
+
+    for tree in event.current_trees.clone() {
+        let Some(submitter) = event.get_tree_submitter(&tree) else {
+            continue;
+        };
+        let message = campaign_message(theme, event, &tree, submitter);
+        match ChannelId::new(thread_id).send_message(ctx, message).await {
+            Ok(message) => {
+                event.campaign_posts.insert(tree, message.id.get());
+            }
+            Err(e) => warn!("Failed to post campaign for {} in {}: {}", tree, thread_id, e),
+        }
+    }
+}
+
+/// Shows a new or changed pitch in the tree's campaign message, or in a new message when
+/// the tree has none.
+pub async fn show_pitch(
+    ctx: &Context,
+    theme: &Theme,
+    event: &LoraxEvent,
+    tree: &str,
+    submitter: u64,
+) {
+    let Some(thread_id) = event.campaign_thread_id else {
+        return;
+    };
+    let thread = ChannelId::new(thread_id);
+
+    let result = match event.campaign_posts.get(tree) {
+        Some(message_id) => {
+            // Replaces the old image, if any, with the new one
+            let images = campaign_image(event, tree).into_iter();
+            let attachments = images.fold(EditAttachments::new(), EditAttachments::add);
+            let edit = EditMessage::new()
+                .embed(campaign_embed(theme, event, tree, submitter))
+                .attachments(attachments);
+            thread.edit_message(ctx, MessageId::new(*message_id), edit).await.map(|_| ())
+        }
+        None => thread
+            .send_message(ctx, campaign_message(theme, event, tree, submitter))
+            .await
+            .map(|_| ()),
+    };
+    if let Err(e) = result {
+        warn!("Failed to show pitch for {} in {}: {}", tree, thread_id, e);
+    }
+}
+
+/// Link to the tree's campaign message, once voting has posted it.
+pub fn campaign_link(event: &LoraxEvent, guild_id: u64, tree: &str) -> Option<String> {
+    let thread_id = event.campaign_thread_id?;
+    let message_id = event.campaign_posts.get(tree)?;
+    Some(format!(
+        "https://discord.com/channels/{}/{}/{}",
+        guild_id, thread_id, message_id
+    ))
+}
+
+/// Short pitch excerpt for the tree, for the voting menu.
+pub fn pitch_excerpt(event: &LoraxEvent, tree: &str) -> Option<String> {
+    let text = &event.pitches.get(tree)?.text;
+
+    if text.chars().count() <= DESCRIPTION_LIMIT {
+        Some(text.clone())
+    } else {
+        let excerpt: String = text.chars().take(DESCRIPTION_LIMIT - 1).collect();
+        Some(format!("{}…", excerpt))
+    }
+}
@@ -0,0 +1,128 @@
+//! Mirrors a Lorax event as a Discord scheduled event covering the voting window, so
+//! members get Discord's own "starting soon" reminders.
+
+use crate::modules::lorax::{
+    database::{LoraxEvent, LoraxStage},
+    task::get_current_timestamp,
+};
+use poise::serenity_prelude::{
+    Context, CreateScheduledEvent, EditScheduledEvent, GuildId, ScheduledEventId,
+    ScheduledEventStatus, ScheduledEventType, Timestamp,
+};
+
+const EVENT_NAME: &str = "🌳 Lorax Voting";
+
+/// When voting starts and ends for `event`, as unix timestamps.
+fn voting_window(event: &LoraxEvent) -> Option<(u64, u64)> {
+    let settings = &event.settings;
+    match event.stage {
+        LoraxStage::Submission => {
+            // Discord only accepts start times in the future
+            let start = (event.start_time + settings.submission_duration * 60)
+                .max(get_current_timestamp() + 60);
+            Some((start, start + (settings.voting_duration * 60).max(60)))
+        }
+        LoraxStage::Voting => Some((
+            event.start_time,
+            event.start_time + settings.voting_duration * 60,
+        )),
+        LoraxStage::Tiebreaker(_) => Some((
+            event.start_time,
+            event.start_time + settings.tiebreaker_duration * 60,
+        )),
+        LoraxStage::Completed | LoraxStage::Inactive => None,
+    }
+}
+
+fn timestamp(secs: u64) -> Option<Timestamp> {
+    Timestamp::from_unix_timestamp(secs as i64).ok()
+}
+
+/// Brings the guild's scheduled event in line with `event`: created when submissions
+/// open, retimed when durations change, started with voting and completed (or cancelled,
+/// if voting never happened) at the end. Failures are logged and otherwise ignored, e.g.
+/// when the bot lacks Manage Events.
+pub async fn sync(ctx: &Context, guild_id: u64, event: &mut LoraxEvent) {
+    let guild = GuildId::new(guild_id);
+    let window = voting_window(event);
+
+    let Some(id) = event.scheduled_event_id.map(ScheduledEventId::new) else {
+        if event.stage == LoraxStage::Submission {
+            if let Some((start, end)) = window {
+                event.scheduled_event_id =
+                    create(ctx, guild, event, start, end).await.map(|id| id.get());
+            }
+        }
+        return;
+    };
+
+    let status = match guild.scheduled_event(ctx, id, false).await {
+        Ok(scheduled) => scheduled.status,
+        Err(e) => {
+            // Most likely deleted by hand; don't keep trying to edit it
+            tracing::warn!("Lorax scheduled event {} in guild {} is gone: {}", id, guild_id, e);
+            event.scheduled_event_id = None;
+            return;
+        }
+    };
+
+    let times = window.and_then(|(start, end)| Some((timestamp(start)?, timestamp(end)?)));
+    let edit = match (&event.stage, status, times) {
+        (LoraxStage::Submission, ScheduledEventStatus::Scheduled, Some((start, end))) => {
+            EditScheduledEvent::new().start_time(start).end_time(end)
+        }
+        (
+            LoraxStage::Voting | LoraxStage::Tiebreaker(_),
+            ScheduledEventStatus::Scheduled,
+            Some((_, end)),
+        ) => EditScheduledEvent::new()
+            .status(ScheduledEventStatus::Active)
+            .end_time(end),
+        (
+            LoraxStage::Voting | LoraxStage::Tiebreaker(_),
+            ScheduledEventStatus::Active,
+            Some((_, end)),
+        ) => EditScheduledEvent::new().end_time(end),
+        (LoraxStage::Completed | LoraxStage::Inactive, ScheduledEventStatus::Active, _) => {
+            EditScheduledEvent::new().status(ScheduledEventStatus::Completed)
+        }
+        (LoraxStage::Completed | LoraxStage::Inactive, ScheduledEventStatus::Scheduled, _) => {
+            EditScheduledEvent::new().status(ScheduledEventStatus::Canceled)
+        }
+        _ => return,
+    };
+
+    if let Err(e) = guild.edit_scheduled_event(ctx, id, edit).await {
+        tracing::warn!("Failed to update Lorax scheduled event in guild {}: {}", guild_id, e);
+    }
+}
+
+async fn create(
+    ctx: &Context,
+    guild: GuildId,
+    event: &LoraxEvent,
+    start: u64,
+    end: u64,
+) -> Option<ScheduledEventId> {
+    let location = match event.settings.lorax_channel {
+        Some(channel_id) => format!("https://discord.com/channels/{}/{}", guild, channel_id),
+        None => "Discord".to_string(),
+    };
+
+    let scheduled = CreateScheduledEvent::new(
+        ScheduledEventType::External,
+        EVENT_NAME,
+        timestamp(start)?,
+    )
+    .end_time(timestamp(end)?)
+    .location(location)
+    .description("Help pick the new node's name! Vote with `/lorax vote` once voting opens.");
+
+    match guild.create_scheduled_event(ctx, scheduled).await {
+        Ok(scheduled) => Some(scheduled.id),
+        Err(e) => {
+            tracing::warn!("Failed to create Lorax scheduled event in guild {}: {}", guild, e);
+            None
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! The "Submit a name" button on submission announcements, which opens a modal so members can
+//! submit without typing `/lorax submit`.
+
+use super::commands::users::submit_name;
+use crate::config::LiveConfig;
+use crate::databases::Databases;
+use crate::events::EventHandler;
+use crate::modules::toggles::database::Module;
+use async_trait::async_trait;
+use poise::serenity_prelude::{
+    ActionRowComponent, ButtonStyle, ComponentInteraction, Context, CreateActionRow, CreateButton,
+    CreateInputText, CreateInteractionResponse, CreateInteractionResponseMessage, CreateModal,
+    EditInteractionResponse, FullEvent, InputTextStyle, Interaction, ModalInteraction,
+};
+use std::sync::Arc;
+
+const BUTTON_ID: &str = "lorax_submit";
+const MODAL_ID: &str = "lorax_submit_modal";
+const NAME_INPUT_ID: &str = "name";
+
+pub fn button_row() -> CreateActionRow {
+    CreateActionRow::Buttons(vec![CreateButton::new(BUTTON_ID)
+        .label("Submit a name")
+        .style(ButtonStyle::Success)])
+}
+
+/// Opens the submission modal from the button and submits what comes back.
+#[derive(Debug, Clone)]
+pub struct SubmitButtonHandler {
+    pub dbs: Arc<Databases>,
+    pub config: Arc<LiveConfig>,
+}
+
+impl SubmitButtonHandler {
+    async fn open_modal(
+        &self,
+        ctx: &Context,
+        interaction: &ComponentInteraction,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let settings = match interaction.guild_id {
+            Some(guild_id) => self.dbs.lorax.get_event(guild_id.get()).await,
+            None => None,
+        }
+        .map(|event| event.settings)
+        .unwrap_or_default();
+        let rules = &settings.name_rules;
+        let input = CreateInputText::new(InputTextStyle::Short, "Name", NAME_INPUT_ID)
+            .placeholder(format!("Your awesome {} name", settings.noun()))
+            .min_length(rules.min_length as u16)
+            .max_length(rules.max_length as u16);
+        let title = format!("{} Submit a {} name", settings.theme_emoji(), settings.noun());
+        let modal = CreateModal::new(MODAL_ID, title)
+            .components(vec![CreateActionRow::InputText(input)]);
+        interaction
+            .create_response(ctx, CreateInteractionResponse::Modal(modal))
+            .await?;
+        Ok(())
+    }
+
+    async fn submit(
+        &self,
+        ctx: &Context,
+        interaction: &ModalInteraction,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(guild_id) = interaction.guild_id else {
+            return Ok(());
+        };
+        let name = interaction
+            .data
+            .components
+            .iter()
+            .flat_map(|row| &row.components)
+            .find_map(|component| match component {
+                ActionRowComponent::InputText(input) if input.custom_id == NAME_INPUT_ID => {
+                    input.value.clone()
+                }
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        // Fetching node names can outlast the interaction deadline, so acknowledge first
+        interaction
+            .create_response(
+                ctx,
+                CreateInteractionResponse::Defer(
+                    CreateInteractionResponseMessage::new().ephemeral(true),
+                ),
+            )
+            .await?;
+
+        let config = self.config.get();
+        let message = submit_name(
+            &self.dbs,
+            &config.prometheus.node_names_url,
+            guild_id.get(),
+            interaction.user.id.get(),
+            name,
+            None,
+        )
+        .await;
+        interaction
+            .edit_response(ctx, EditInteractionResponse::new().content(message))
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventHandler for SubmitButtonHandler {
+    fn name(&self) -> &str {
+        "LoraxSubmitButton"
+    }
+
+    async fn handle(
+        &self,
+        ctx: &Context,
+        event: &FullEvent,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match event {
+            FullEvent::InteractionCreate {
+                interaction: Interaction::Component(interaction),
+            } if interaction.data.custom_id == BUTTON_ID => {
+                self.open_modal(ctx, interaction).await
+            }
+            FullEvent::InteractionCreate {
+                interaction: Interaction::Modal(interaction),
+            } if interaction.data.custom_id == MODAL_ID => self.submit(ctx, interaction).await,
+            _ => Ok(()),
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn EventHandler> {
+        Box::new(self.clone())
+    }
+
+    fn module(&self) -> Option<Module> {
+        Some(Module::Lorax)
+    }
+}
@@ -1,15 +1,42 @@
 use crate::{
+    config::LiveConfig,
     database::Database,
-    modules::lorax::database::{LoraxDatabase, LoraxEvent, LoraxSettings, LoraxStage},
-    tasks::Task,
+    databases::Databases,
+    modules::{
+        lorax::{
+            database::{
+                ArchivedEvent, LoraxDatabase, LoraxEvent, LoraxSettings, LoraxStage, RoundResult,
+                ScheduledLorax, MAX_WINNERS,
+            },
+            ballot, node_names, pitch, schedule, submit_button,
+        },
+        preferences::notify::send_dm,
+        roles::database::RoleChange,
+        system::database::Theme,
+        toggles::database::Module,
+    },
+    tasks::{GuildTask, Task},
+    utils::{
+        embed,
+        live::{self, LiveUpdate},
+        time::format_timestamp,
+    },
 };
+use chrono_tz::Tz;
 use poise::serenity_prelude::{
-    AutoArchiveDuration, ChannelId, ChannelType, Context, CreateAllowedMentions, CreateMessage,
-    CreateThread, EditThread, RoleId,
+    AutoArchiveDuration, ChannelId, ChannelType, Context, CreateAllowedMentions,
+    CreateForumPost, CreateMessage, CreateThread, EditMessage, EditThread, GuildChannel, GuildId,
+    MessageId, RoleId,
 };
+use dashmap::DashMap;
 use rand::seq::SliceRandom;
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::sync::{Arc, LazyLock};
 use std::time::Duration;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// Per-guild locks serialising stage changes between the periodic task and admin commands.
+static EVENT_LOCKS: LazyLock<DashMap<u64, Arc<Mutex<()>>>> = LazyLock::new(DashMap::new);
 
 // Helper functions at top level
 pub fn get_current_timestamp() -> u64 {
@@ -19,15 +46,86 @@ pub fn get_current_timestamp() -> u64 {
         .as_secs()
 }
 
+/// A line for the voting message saying when votes lock, if the lock is on.
+fn vote_lock_notice(event: &LoraxEvent) -> String {
+    event
+        .vote_lock_time()
+        .map(|lock| format!("\n🔒 Votes lock <t:{}:R> and can't be changed after that.", lock))
+        .unwrap_or_default()
+}
+
+/// Tiebreakers after which the event ends even if names are still tied.
+const MAX_TIEBREAKER_ROUNDS: usize = 3;
+
+/// How long before a scheduled event starts it's announced.
+const SCHEDULE_NOTICE: u64 = 24 * 60 * 60;
+
+/// Title of the post opened for each event when the Lorax channel is a forum.
+pub const FORUM_POST_TITLE: &str = "🌳 Node Naming";
+
+/// One line per finished voting/tiebreaker round, with who was knocked out.
+pub fn format_rounds(event: &LoraxEvent) -> String {
+    let last = event.round_results.len().saturating_sub(1);
+    let mut rounds = Vec::new();
+
+    for (i, result) in event.round_results.iter().enumerate() {
+        let label = match result.stage {
+            LoraxStage::Tiebreaker(round) => format!("Tiebreaker {}", round),
+            _ => "Voting".to_string(),
+        };
+        let standings = result
+            .tally
+            .iter()
+            .map(|(tree, votes)| format!("{} ({})", tree, votes))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut line = format!("- **{}**: {}", label, standings);
+        // The final round's losers are already on the podium
+        if i != last && !result.eliminated.is_empty() {
+            line.push_str(&format!(" — out: {}", result.eliminated.join(", ")));
+        }
+        rounds.push(line);
+    }
+    rounds.join("\n")
+}
+
 #[derive(Clone, Debug)]
 pub struct LoraxEventTask {
     pub guild_id: u64,
     pub db: Arc<Database<LoraxDatabase>>,
+    pub dbs: Arc<Databases>,
+    pub config: Arc<LiveConfig>,
+    /// When the live tally was last posted or edited.
+    tally_updated_at: u64,
 }
 
+/// Seconds between live tally updates.
+const TALLY_INTERVAL_SECS: u64 = 60;
+/// Width of the bars in the live tally.
+const TALLY_BAR_WIDTH: usize = 10;
+
 impl LoraxEventTask {
-    pub fn new(guild_id: u64, db: Arc<Database<LoraxDatabase>>) -> Self {
-        Self { guild_id, db }
+    pub fn new(guild_id: u64, dbs: Arc<Databases>, config: Arc<LiveConfig>) -> Self {
+        Self {
+            guild_id,
+            db: Arc::new(dbs.lorax.clone()),
+            dbs,
+            config,
+            tally_updated_at: 0,
+        }
+    }
+
+    fn guild_name(&self, ctx: &Context) -> String {
+        ctx.cache
+            .guild(self.guild_id)
+            .map(|guild| guild.name.clone())
+            .unwrap_or_else(|| "your server".to_string())
+    }
+
+    fn format_deadline(&self, event: &LoraxEvent, tz: Tz) -> String {
+        let end = event.get_stage_end_timestamp(self.calculate_stage_duration(event));
+        format!("<t:{}:R> ({})", end, format_timestamp(end, tz))
     }
 
     pub fn calculate_stage_duration(&self, event: &LoraxEvent) -> u64 {
@@ -49,90 +147,257 @@ impl LoraxEventTask {
         }
     }
 
-    pub async fn start_event(&mut self, settings: LoraxSettings, ctx: &Context) {
-        let event = LoraxEvent::new(settings, get_current_timestamp());
-        if let Err(e) = self.db.update_event(self.guild_id, event).await {
+    /// Holds the guild's event lock; every stage mutation goes through it.
+    pub async fn lock(&self) -> OwnedMutexGuard<()> {
+        let lock = EVENT_LOCKS
+            .entry(self.guild_id)
+            .or_insert_with(|| Arc::new(Mutex::new(())))
+            .clone();
+        lock.lock_owned().await
+    }
+
+    /// Starts an event picking `winner_count` trees.
+    pub async fn start_event(
+        &mut self,
+        settings: LoraxSettings,
+        winner_count: usize,
+        ctx: &Context,
+    ) {
+        let _guard = self.lock().await;
+
+        let mut event = LoraxEvent::new(settings, get_current_timestamp());
+        event.stage = LoraxStage::Submission;
+        event.winner_count = winner_count.clamp(1, MAX_WINNERS);
+
+        if let Err(e) = self.db.clear_voters(self.guild_id).await {
+            tracing::error!("Failed to clear Lorax voters: {}", e);
+        }
+
+        if let Err(e) = self.db.update_event(self.guild_id, event.clone()).await {
             tracing::error!("Failed to update event: {}", e);
             return;
         }
 
-        if let Some(mut event) = self.db.get_event(self.guild_id).await {
-            event.stage = LoraxStage::Submission;
-
-            let _ = self.db.update_event(self.guild_id, event.clone()).await;
+        self.send_stage_message(ctx, &mut event).await;
+        schedule::sync(ctx, self.guild_id, &mut event).await;
+        self.save_messages(&event).await;
+    }
 
-            self.send_stage_message(ctx, &mut event).await;
-        }
+    /// Weighted votes for each tree still in the running, most votes first (ties by name).
+    fn tally(event: &LoraxEvent) -> Vec<(String, usize)> {
+        let mut tally: Vec<(String, usize)> = event
+            .current_trees
+            .iter()
+            .map(|tree| (tree.clone(), event.votes_for(tree)))
+            .collect();
+        tally.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        tally
     }
 
-    fn get_winners(&self, event: &LoraxEvent) -> Vec<(String, usize)> {
-        let mut vote_counts: std::collections::HashMap<String, usize> =
-            std::collections::HashMap::new();
-        for tree in event.tree_votes.values() {
-            *vote_counts.entry(tree.clone()).or_insert(0) += 1;
-        }
+    /// Ends the current voting or tiebreaker round, recording its tally.
+    fn close_round(event: &mut LoraxEvent, round: usize) {
+        let tally = Self::tally(event);
+        // Places still open; trees that won outright before a tiebreaker keep theirs
+        let open = event.winner_count.saturating_sub(event.secured_winners.len()).max(1);
+        let cutoff = tally.get(open - 1).map_or(0, |(_, votes)| *votes);
+        let ahead: Vec<String> = tally
+            .iter()
+            .filter(|(_, votes)| *votes > cutoff)
+            .map(|(tree, _)| tree.clone())
+            .collect();
+        let tied: Vec<String> = tally
+            .iter()
+            .filter(|(_, votes)| *votes == cutoff)
+            .map(|(tree, _)| tree.clone())
+            .collect();
 
-        let mut winners: Vec<_> = vote_counts.into_iter().collect();
-        winners.sort_by(|a, b| b.1.cmp(&a.1));
-        winners
+        let (next_stage, next_trees) = if tied.len() >= 2
+            && ahead.len() + tied.len() > open
+            && round < MAX_TIEBREAKER_ROUNDS
+        {
+            // Trees clear of the tie win now; the tied ones play off for the places left
+            event.secured_winners.extend(ahead);
+            (LoraxStage::Tiebreaker(round + 1), tied)
+        } else {
+            // Final standings: outright winners, this round's order, then earlier rounds'
+            // runner-ups
+            let mut standings = event.secured_winners.clone();
+            let earlier = event.round_results.iter().rev().flat_map(|result| &result.tally);
+            for (tree, _) in tally.iter().chain(earlier) {
+                if !standings.contains(tree) {
+                    standings.push(tree.clone());
+                }
+            }
+            (LoraxStage::Completed, standings)
+        };
+
+        let still_in: Vec<&String> = match next_stage {
+            LoraxStage::Completed => next_trees.iter().take(event.winner_count).collect(),
+            _ => next_trees.iter().chain(&event.secured_winners).collect(),
+        };
+        let eliminated = tally
+            .iter()
+            .filter(|(tree, _)| !still_in.contains(&tree))
+            .map(|(tree, _)| tree.clone())
+            .collect();
+
+        event.round_results.push(RoundResult {
+            stage: event.stage.clone(),
+            tally,
+            eliminated,
+            ended_at: get_current_timestamp(),
+        });
+        event.stage = next_stage;
+        event.current_trees = next_trees;
+        // The next round, if any, gets its own tally message
+        event.tally_message_id = None;
     }
 
+    /// Gives the winners the winner role and moves its previous holders to the alumni role.
     async fn handle_winner_roles(&self, ctx: &Context, event: &LoraxEvent) {
-        let guild_id = self.guild_id.into();
+        let guild_id = GuildId::new(self.guild_id);
 
         let winner_role = event.settings.winner_role.map(RoleId::new);
         let alumni_role = event.settings.alumni_role.map(RoleId::new);
+        let (Some(winner_role), Some(alumni_role)) = (winner_role, alumni_role) else {
+            return;
+        };
 
-        if winner_role.is_none() && alumni_role.is_none() {
+        if event.winners().is_empty() {
             return;
         }
+        let winner_ids: HashSet<u64> = event
+            .winners()
+            .iter()
+            .filter_map(|tree| event.get_tree_submitter(tree))
+            .collect();
+
+        let holders = match self.db.winner_holders(self.guild_id, winner_role.get()).await {
+            Some(holders) => holders,
+            None => match Self::scan_winner_holders(ctx, guild_id, winner_role).await {
+                Ok(holders) => holders,
+                Err(e) => {
+                    tracing::error!("Failed to list winner role holders: {}", e);
+                    return;
+                }
+            },
+        };
 
-        let winners = self.get_winners(event);
-        if winners.is_empty() {
+        // Previous winners move to the alumni role; new winners who already hold it keep it
+        let changes: Vec<RoleChange> = holders
+            .difference(&winner_ids)
+            .map(|user_id| RoleChange {
+                user_id: *user_id,
+                add: vec![alumni_role.get()],
+                remove: vec![winner_role.get()],
+            })
+            .chain(winner_ids.difference(&holders).map(|user_id| RoleChange {
+                user_id: *user_id,
+                add: vec![winner_role.get()],
+                remove: Vec::new(),
+            }))
+            .collect();
+        if changes.is_empty() {
             return;
         }
 
-        if let (Some(winner_role), Some(alumni_role)) = (winner_role, alumni_role) {
-            if let Ok(guild) = ctx.http.get_guild(guild_id).await {
-                if let Some((winning_tree, _)) = winners.first() {
-                    if let Some(winner_id) = event.get_tree_submitter(winning_tree) {
-                        if let Ok(member) = guild.member(ctx, winner_id).await {
-                            if let Err(e) = member.add_role(ctx, winner_role).await {
-                                tracing::error!("Failed to add winner role: {}", e);
-                                return;
-                            }
-                        }
-                    }
-                }
+        let admin_channel = self.dbs.guild_config().admin_channel(self.guild_id).await;
+        if let Err(e) = self
+            .dbs
+            .roles
+            .enqueue(self.guild_id, "Lorax winner rotation", changes, admin_channel)
+            .await
+        {
+            tracing::error!("Failed to queue winner role changes: {}", e);
+            return;
+        }
 
-                let mut after = None;
-                while let Ok(members) = guild.members(ctx, Some(1000), after).await {
-                    if members.is_empty() {
-                        break;
-                    }
-                    after = members.last().map(|m| m.user.id);
+        // Member updates correct this if a queued change fails
+        if let Err(e) = self
+            .db
+            .set_winner_holders(self.guild_id, winner_role.get(), winner_ids)
+            .await
+        {
+            tracing::error!("Failed to save winner role holders: {}", e);
+        }
+    }
 
-                    for member in members {
-                        if member.roles.contains(&winner_role) {
-                            let _ = member.remove_role(ctx, winner_role).await;
-                            let _ = member.add_role(ctx, alumni_role).await;
-                        }
-                    }
-                }
+    /// Credits everyone who took part and moves the participant role, if set, from the
+    /// previous event's participants to this one's.
+    async fn reward_participants(&self, event: &LoraxEvent) {
+        let submitters = event.tree_submissions.keys().copied();
+        let rewards = self.db.reward_participants(self.guild_id, submitters).await;
+        let (role_id, remove, add) = match rewards {
+            Ok(Some(rewards)) => rewards,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::error!("Failed to reward Lorax participants: {}", e);
+                return;
             }
+        };
+
+        let changes: Vec<RoleChange> = remove
+            .into_iter()
+            .map(|user_id| RoleChange {
+                user_id,
+                add: Vec::new(),
+                remove: vec![role_id],
+            })
+            .chain(add.into_iter().map(|user_id| RoleChange {
+                user_id,
+                add: vec![role_id],
+                remove: Vec::new(),
+            }))
+            .collect();
+        if changes.is_empty() {
+            return;
+        }
+
+        let admin_channel = self.dbs.guild_config().admin_channel(self.guild_id).await;
+        if let Err(e) = self
+            .dbs
+            .roles
+            .enqueue(self.guild_id, "Lorax participant rewards", changes, admin_channel)
+            .await
+        {
+            tracing::error!("Failed to queue participant role changes: {}", e);
         }
     }
 
-    pub async fn advance_stage(&mut self, ctx: &Context, event: &mut LoraxEvent) {
-        let old_stage = event.stage.clone();
-        
+    /// Pages through the member list once to find who holds the winner role, for guilds
+    /// whose holders aren't tracked yet.
+    async fn scan_winner_holders(
+        ctx: &Context,
+        guild_id: GuildId,
+        winner_role: RoleId,
+    ) -> Result<HashSet<u64>, poise::serenity_prelude::Error> {
+        let mut holders = HashSet::new();
+        let mut after = None;
+        loop {
+            let members = guild_id.members(ctx, Some(1000), after).await?;
+            let Some(last) = members.last() else {
+                break;
+            };
+            after = Some(last.user.id);
+            holders.extend(
+                members
+                    .iter()
+                    .filter(|member| member.roles.contains(&winner_role))
+                    .map(|member| member.user.id.get()),
+            );
+        }
+        Ok(holders)
+    }
+
+    /// Moves the event to its next stage in place, returning false if there is nothing to advance.
+    fn next_stage(event: &mut LoraxEvent) -> bool {
         match event.stage {
             LoraxStage::Submission => {
                 if event.tree_submissions.is_empty() {
                     event.stage = LoraxStage::Inactive;
                 } else {
                     event.stage = LoraxStage::Voting;
-                    event.current_trees = event.tree_submissions.values().cloned().collect();
+                    event.current_trees = event.submissions().map(|(_, tree)| tree.clone()).collect();
                 }
                 event.start_time = get_current_timestamp();
             }
@@ -140,37 +405,73 @@ impl LoraxEventTask {
                 if event.tree_votes.is_empty() {
                     event.stage = LoraxStage::Inactive;
                 } else {
-                    let winners = self.get_winners(event);
-                    // Check for ties
-                    if winners.len() >= 2 && winners[0].1 == winners[1].1 {
-                        event.stage = LoraxStage::Tiebreaker(1);
-                        event.current_trees = winners
-                            .iter()
-                            .take_while(|(_, votes)| votes == &winners[0].1)
-                            .map(|(tree, _)| tree.clone())
-                            .collect();
-                    } else {
-                        event.stage = LoraxStage::Completed;
-                        event.current_trees = winners.into_iter().map(|(tree, _)| tree).collect();
-                        self.handle_winner_roles(ctx, event).await;
-                    }
+                    Self::close_round(event, 0);
                 }
                 event.start_time = get_current_timestamp();
-                event.tree_votes.clear(); // Reset votes for next stage
             }
             LoraxStage::Tiebreaker(round) => {
-                if round >= 3 {
-                    event.stage = LoraxStage::Completed;
-                } else {
-                    event.stage = LoraxStage::Tiebreaker(round + 1);
-                }
+                Self::close_round(event, round);
                 event.start_time = get_current_timestamp();
             }
             LoraxStage::Completed => {
                 event.stage = LoraxStage::Inactive;
             }
-            LoraxStage::Inactive => return,
+            LoraxStage::Inactive => return false,
         }
+        event.reminders_sent.clear();
+        true
+    }
+
+    /// Advances the event by one stage. Unless `force` is set, only advances once the
+    /// current stage has run its course. Returns the new stage if it changed.
+    pub async fn advance(&mut self, ctx: &Context, force: bool) -> Result<Option<LoraxStage>, String> {
+        let _guard = self.lock().await;
+
+        let current_time = get_current_timestamp();
+        let taken = self.taken_node_names(current_time, force).await;
+        let Some((old_stage, mut event, dropped)) = self
+            .db
+            .modify_event(self.guild_id, |event| {
+                if !force {
+                    let elapsed_time = current_time.saturating_sub(event.start_time);
+                    if elapsed_time <= self.calculate_stage_duration(event) {
+                        return Ok(None);
+                    }
+                }
+
+                let old_stage = event.stage.clone();
+                // Names can become node names after they're submitted
+                let mut dropped = match old_stage {
+                    LoraxStage::Submission => event.withdraw_taken(&taken),
+                    _ => Vec::new(),
+                };
+                // Winners are picked from this stage's votes, so keep them until the
+                // roles and results message are done
+                let votes = event.tree_votes.clone();
+                if !Self::next_stage(event) {
+                    return Ok(None);
+                }
+                if event.stage == LoraxStage::Completed {
+                    dropped.extend(event.drop_taken_standings(&taken));
+                }
+                // Each round posts its own ballot
+                event.ballot_message_id = None;
+                event.ballot.clear();
+                let snapshot = LoraxEvent {
+                    tree_votes: votes,
+                    ..event.clone()
+                };
+                if matches!(old_stage, LoraxStage::Voting | LoraxStage::Tiebreaker(_)) {
+                    event.tree_votes.clear(); // Reset votes for next round
+                    event.vote_weights.clear();
+                }
+                Ok(Some((old_stage, snapshot, dropped)))
+            })
+            .await?
+        else {
+            return Ok(None);
+        };
+        self.notify_taken(ctx, &event, dropped).await;
 
         tracing::info!(
             "Advanced Lorax event from {:?} to {:?} for guild {}",
@@ -179,16 +480,115 @@ impl LoraxEventTask {
             self.guild_id
         );
 
-        if let Err(e) = self.db.update_event(self.guild_id, event.clone()).await {
-            tracing::error!("Failed to update event stage: {}", e);
+        if matches!(old_stage, LoraxStage::Voting | LoraxStage::Tiebreaker(_)) {
+            let voters = event.tree_votes.keys().copied();
+            if let Err(e) = self.db.record_voters(self.guild_id, voters).await {
+                tracing::error!("Failed to record Lorax voters for guild {}: {}", self.guild_id, e);
+            }
+        }
+
+        if matches!(event.stage, LoraxStage::Completed)
+            && matches!(old_stage, LoraxStage::Voting | LoraxStage::Tiebreaker(_))
+        {
+            self.handle_winner_roles(ctx, &event).await;
+            self.reward_participants(&event).await;
+            let archived = ArchivedEvent::of(&event, current_time);
+            if let Err(e) = self.db.archive_event(self.guild_id, archived).await {
+                tracing::error!("Failed to archive Lorax event for guild {}: {}", self.guild_id, e);
+            }
+        }
+
+        self.send_stage_message(ctx, &mut event).await;
+        schedule::sync(ctx, self.guild_id, &mut event).await;
+        self.save_messages(&event).await;
+
+        Ok(Some(event.stage))
+    }
+
+    /// Node names the event's names may now clash with, looked up only when the stage is
+    /// due to move into voting or finish. Empty when the theme doesn't check node names
+    /// or the lookup fails, so a metrics outage never holds an event up.
+    async fn taken_node_names(&self, now: u64, force: bool) -> HashSet<String> {
+        let Some(event) = self.db.get_event(self.guild_id).await else {
+            return HashSet::new();
+        };
+        let due = force
+            || now.saturating_sub(event.start_time) > self.calculate_stage_duration(&event);
+        let rechecked = matches!(
+            event.stage,
+            LoraxStage::Submission | LoraxStage::Voting | LoraxStage::Tiebreaker(_)
+        );
+        if !due || !rechecked || !event.settings.checks_node_names() {
+            return HashSet::new();
+        }
+
+        let url = self.config.get().prometheus.node_names_url.clone();
+        match node_names::fetch(&url).await {
+            Ok(names) => names.into_iter().collect(),
+            Err(e) => {
+                tracing::warn!("Failed to re-check node names for guild {}: {}", self.guild_id, e);
+                HashSet::new()
+            }
+        }
+    }
+
+    /// Tells submitters their names were taken out because nodes now use them.
+    async fn notify_taken(&self, ctx: &Context, event: &LoraxEvent, dropped: Vec<(u64, String)>) {
+        if dropped.is_empty() {
+            return;
+        }
+        tracing::info!(
+            "Took {} Lorax name(s) now used by nodes out of the event in guild {}",
+            dropped.len(),
+            self.guild_id
+        );
+
+        let guild_name = self.guild_name(ctx);
+        for (user_id, tree) in dropped {
+            let message = CreateMessage::new().content(format!(
+                "{} Your {} name **{}** was taken out of the Lorax event in **{}** because a \
+                node now uses that name. Sorry!",
+                event.settings.theme_emoji(),
+                event.settings.noun(),
+                tree,
+                guild_name
+            ));
+            send_dm(ctx, &self.dbs.preferences, user_id, message).await;
+        }
+    }
+
+    /// Persists only the message, thread and scheduled event ids set while announcing a stage.
+    async fn save_messages(&self, announced: &LoraxEvent) {
+        let result = self
+            .db
+            .modify_event(self.guild_id, |event| {
+                if event.stage != announced.stage {
+                    return Ok(());
+                }
+                event.stage_message_id = announced.stage_message_id;
+                event.voting_message_id = announced.voting_message_id;
+                event.tiebreaker_message_id = announced.tiebreaker_message_id;
+                event.campaign_thread_id = announced.campaign_thread_id;
+                event.campaign_posts = announced.campaign_posts.clone();
+                event.scheduled_event_id = announced.scheduled_event_id;
+                event.ballot_message_id = announced.ballot_message_id;
+                event.ballot = announced.ballot.clone();
+                Ok(())
+            })
+            .await;
+
+        if let Err(e) = result {
+            tracing::error!("Failed to save stage messages for guild {}: {}", self.guild_id, e);
         }
-        self.send_stage_message(ctx, event).await;
     }
 
     pub async fn end_event(&mut self, ctx: &Context) -> Result<(), String> {
+        let _guard = self.lock().await;
+
         if let Some(mut event) = self.db.get_event(self.guild_id).await {
             event.stage = LoraxStage::Completed;
             self.send_stage_message(ctx, &mut event).await;
+            schedule::sync(ctx, self.guild_id, &mut event).await;
 
             self.db
                 .transaction(|db| {
@@ -204,115 +604,501 @@ impl LoraxEventTask {
     }
 
     pub async fn run(&mut self, ctx: &Context) {
-        let current_time = get_current_timestamp();
-        if let Some(event) = self.db.get_event(self.guild_id).await {
-            if matches!(event.stage, LoraxStage::Inactive) {
+        self.run_schedules(ctx).await;
+        if let Err(e) = self.advance(ctx, false).await {
+            tracing::debug!("Lorax event for guild {} not advanced: {}", self.guild_id, e);
+        }
+        self.remind_deadline(ctx).await;
+        self.remind_voters(ctx).await;
+        self.update_tally(ctx).await;
+    }
+
+    /// Publishes the tally at most once a minute while voting is open, and posts or edits
+    /// the live tally message if the guild turned it on. Only the votes per tree are
+    /// shown, never who voted for what.
+    async fn update_tally(&mut self, ctx: &Context) {
+        let now = get_current_timestamp();
+        if now.saturating_sub(self.tally_updated_at) < TALLY_INTERVAL_SECS {
+            return;
+        }
+        let Some(event) = self.db.get_event(self.guild_id).await else {
+            return;
+        };
+        if !matches!(event.stage, LoraxStage::Voting | LoraxStage::Tiebreaker(_)) {
+            return;
+        }
+        self.tally_updated_at = now;
+
+        let tally = Self::tally(&event);
+        live::publish(LiveUpdate::LoraxTally {
+            guild_id: self.guild_id,
+            stage: event.stage.clone(),
+            tally: tally.clone(),
+        });
+        if !event.settings.live_tally {
+            return;
+        }
+        let Some(channel_id) = announcement_channel(ctx, &event).await else {
+            return;
+        };
+        let total: usize = tally.iter().map(|(_, votes)| votes).sum();
+        let most = tally.first().map_or(0, |(_, votes)| *votes).max(1);
+        let lines: Vec<String> = tally
+            .iter()
+            .map(|(tree, votes)| {
+                let filled = votes * TALLY_BAR_WIDTH / most;
+                format!(
+                    "`{}{}` **{}**: {} {}",
+                    "█".repeat(filled),
+                    "░".repeat(TALLY_BAR_WIDTH - filled),
+                    tree,
+                    votes,
+                    if *votes == 1 { "vote" } else { "votes" }
+                )
+            })
+            .collect();
+        let theme = self.dbs.system.get_theme(self.guild_id).await;
+        let embed = embed::themed(&theme).title("📊 Live Tally").description(format!(
+            "{}\n\n🗳️ {} votes cast · updated <t:{}:R>",
+            lines.join("\n"),
+            total,
+            now
+        ));
+
+        if let Some(message_id) = event.tally_message_id {
+            let edit = EditMessage::new().embed(embed.clone());
+            if channel_id
+                .edit_message(ctx, MessageId::new(message_id), edit)
+                .await
+                .is_ok()
+            {
                 return;
             }
+        }
+
+        // Nothing posted this round yet, or the old message couldn't be edited (e.g. it was
+        // deleted), so post a new one
+        let message = match channel_id
+            .send_message(ctx, CreateMessage::default().embed(embed))
+            .await
+        {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::warn!("Failed to post the Lorax tally in {}: {}", channel_id, e);
+                return;
+            }
+        };
+        let result = self
+            .db
+            .modify_event(self.guild_id, |current| {
+                if current.stage == event.stage {
+                    current.tally_message_id = Some(message.id.get());
+                }
+                Ok(())
+            })
+            .await;
+        if let Err(e) = result {
+            tracing::error!("Failed to save the Lorax tally for guild {}: {}", self.guild_id, e);
+        }
+    }
+
+    /// Posts the configured reminders in the Lorax channel as the current stage's end
+    /// nears. When several come due at once, e.g. after the bot was down, only one is
+    /// posted. Reminders that would fire as soon as a stage starts are skipped.
+    async fn remind_deadline(&self, ctx: &Context) {
+        let current_time = get_current_timestamp();
+        let due = self
+            .db
+            .modify_event(self.guild_id, |event| {
+                let duration = self.calculate_stage_duration(event);
+                if duration == 0 {
+                    return Ok(None);
+                }
+                let end = event.get_stage_end_timestamp(duration);
+                let remaining = end.saturating_sub(current_time);
+                // Extending the stage makes passed reminders due again
+                event.reminders_sent.retain(|minutes| remaining <= minutes * 60);
+
+                let due: Vec<u64> = event
+                    .settings
+                    .reminders
+                    .iter()
+                    .copied()
+                    .filter(|minutes| minutes * 60 < duration && remaining <= minutes * 60)
+                    .filter(|minutes| !event.reminders_sent.contains(minutes))
+                    .collect();
+                if due.is_empty() || remaining == 0 {
+                    return Ok(None);
+                }
+                event.reminders_sent.extend(due);
+                Ok(Some((event.clone(), end)))
+            })
+            .await;
+        let Ok(Some((event, end))) = due else {
+            return;
+        };
+        let Some(channel_id) = announcement_channel(ctx, &event).await else {
+            return;
+        };
 
-            let stage_duration = self.calculate_stage_duration(&event);
-            let elapsed_time = current_time.saturating_sub(event.start_time);
+        let action = match event.stage {
+            LoraxStage::Submission => "Submissions close",
+            LoraxStage::Voting => "Voting ends",
+            LoraxStage::Tiebreaker(_) => "The tiebreaker ends",
+            _ => return,
+        };
+        let command = match event.stage {
+            LoraxStage::Submission => "/lorax submit",
+            _ => "/lorax vote",
+        };
+        let theme = self.dbs.system.get_theme(self.guild_id).await;
+        let message = CreateMessage::default()
+            .content(role_ping(&event))
+            .embed(embed::themed(&theme).description(format!(
+                "⏰ {} <t:{}:R>! Don't miss out: `{}`",
+                action, end, command
+            )))
+            .allowed_mentions(CreateAllowedMentions::new().roles(event.settings.lorax_role));
+        if let Err(e) = channel_id.send_message(ctx, message).await {
+            tracing::warn!("Failed to send Lorax reminder in {}: {}", channel_id, e);
+        }
+    }
 
-            if elapsed_time > stage_duration {
-                let mut updated_event = event.clone();
-                self.advance_stage(ctx, &mut updated_event).await;
-                let _ = self.db.update_event(self.guild_id, updated_event).await;
+    /// Announces scheduled events a day ahead and starts them when they come up. An
+    /// occurrence that comes up while another event is running is skipped.
+    async fn run_schedules(&mut self, ctx: &Context) {
+        let now = get_current_timestamp();
+        for scheduled in self.db.schedules(self.guild_id).await {
+            if scheduled.start_at <= now {
+                self.start_scheduled(ctx, &scheduled).await;
+                if let Err(e) = self.db.reschedule(self.guild_id, scheduled.id, now).await {
+                    tracing::error!("Failed to reschedule Lorax event {}: {}", scheduled.id, e);
+                }
+            } else if !scheduled.announced && scheduled.start_at <= now + SCHEDULE_NOTICE {
+                self.announce_scheduled(ctx, &scheduled).await;
+                if let Err(e) = self
+                    .db
+                    .mark_schedule_announced(self.guild_id, scheduled.id)
+                    .await
+                {
+                    tracing::error!("Failed to save Lorax schedule notice: {}", e);
+                }
             }
         }
     }
 
-    pub async fn send_stage_message(&mut self, ctx: &Context, event: &mut LoraxEvent) {
-        let channel_id = match event.settings.lorax_channel {
-            Some(id) => id,
-            None => {
-                tracing::error!("No Lorax channel configured for guild {}", self.guild_id);
+    async fn start_scheduled(&mut self, ctx: &Context, scheduled: &ScheduledLorax) {
+        let running = self
+            .db
+            .get_event(self.guild_id)
+            .await
+            .is_some_and(|event| event.stage != LoraxStage::Inactive);
+        if running {
+            tracing::info!(
+                "Skipping scheduled Lorax event {} in guild {}: an event is already running",
+                scheduled.id,
+                self.guild_id
+            );
+            return;
+        }
+
+        let settings = match self.db.get_settings(self.guild_id).await {
+            Ok(settings) if settings.lorax_channel.is_some() => settings,
+            Ok(_) => {
+                tracing::warn!(
+                    "Skipping scheduled Lorax event {} in guild {}: no Lorax channel is set",
+                    scheduled.id,
+                    self.guild_id
+                );
                 return;
             }
-        };
-
-        // Validate channel exists and is accessible
-        let channel = match ctx.http.get_channel(ChannelId::new(channel_id)).await {
-            Ok(channel) => channel,
             Err(e) => {
-                tracing::error!("Failed to fetch channel {}: {}", channel_id, e);
+                tracing::error!("Failed to load Lorax settings: {}", e);
                 return;
             }
         };
+        self.start_event(settings, 1, ctx).await;
+    }
 
-        let text_channel = match channel.guild() {
-            Some(tc) => tc,
-            None => {
-                tracing::error!("Channel {} is not a guild text channel", channel_id);
-                return;
-            }
+    /// Tells the Lorax channel a scheduled event starts within a day. Forum channels are
+    /// skipped, as the event's post only exists once it starts.
+    async fn announce_scheduled(&self, ctx: &Context, scheduled: &ScheduledLorax) {
+        let Ok(settings) = self.db.get_settings(self.guild_id).await else {
+            return;
         };
+        let Some(channel_id) = settings.lorax_channel.map(ChannelId::new) else {
+            return;
+        };
+        let is_forum = channel_id
+            .to_channel(ctx)
+            .await
+            .ok()
+            .and_then(|channel| channel.guild())
+            .is_some_and(|channel| channel.kind == ChannelType::Forum);
+        if is_forum {
+            return;
+        }
 
-        let role_ping = event
-            .settings
+        let tz = self.dbs.guild_config().timezone(self.guild_id).await;
+        let theme = self.dbs.system.get_theme(self.guild_id).await;
+        let role_ping = settings
             .lorax_role
-            .map(|id| format!("<@&{}> ", id))
+            .map(|id| format!("<@&{}>", id))
             .unwrap_or_default();
+        let content = format!(
+            "📅 The next node naming event starts <t:{}:R> ({}). Start thinking of {} names!",
+            scheduled.start_at,
+            format_timestamp(scheduled.start_at, tz),
+            settings.noun()
+        );
+        let message = CreateMessage::default()
+            .content(role_ping)
+            .embed(embed::themed(&theme).description(content))
+            .allowed_mentions(CreateAllowedMentions::new().roles(settings.lorax_role));
+        if let Err(e) = channel_id.send_message(ctx, message).await {
+            tracing::warn!("Failed to announce scheduled Lorax event in {}: {}", channel_id, e);
+        }
+    }
 
-        let sample_trees = vec!["Willow", "Sequoia", "Maple", "Oak", "Pine"];
-        let random_tree = sample_trees
+    /// Once voting is half over, reminds submitters who haven't voted yet. They're DMed
+    /// unless they opted out; those whose DMs are closed are mentioned in the campaign
+    /// thread instead. Sent once per event.
+    async fn remind_voters(&self, ctx: &Context) {
+        let current_time = get_current_timestamp();
+        let due = self
+            .db
+            .modify_event(self.guild_id, |event| {
+                let halfway = event.start_time + self.calculate_stage_duration(event) / 2;
+                if event.stage != LoraxStage::Voting
+                    || event.vote_reminder_sent
+                    || current_time < halfway
+                {
+                    return Ok(None);
+                }
+                event.vote_reminder_sent = true;
+                Ok(Some(event.clone()))
+            })
+            .await;
+        let Ok(Some(event)) = due else {
+            return;
+        };
+
+        let mut waiting: Vec<u64> = event
+            .tree_submissions
+            .keys()
+            .filter(|user_id| !event.tree_votes.contains_key(user_id))
+            .copied()
+            .collect();
+        waiting.sort_unstable();
+        if waiting.is_empty() {
+            return;
+        }
+
+        let end = event.get_stage_end_timestamp(self.calculate_stage_duration(&event));
+        let tz = self.dbs.guild_config().timezone(self.guild_id).await;
+        let guild_name = self.guild_name(ctx);
+        let voting_link = match (event.settings.lorax_channel, event.voting_message_id) {
+            (Some(channel), Some(message)) => format!(
+                " [Go to the vote](https://discord.com/channels/{}/{}/{})",
+                self.guild_id, channel, message
+            ),
+            _ => String::new(),
+        };
+
+        let mut unreachable = Vec::new();
+        for user_id in waiting {
+            if !self.dbs.preferences.allows_dms(user_id).await {
+                continue;
+            }
+            let end_at = self.dbs.preferences.format_time(user_id, end, tz).await;
+            let message = CreateMessage::new().content(format!(
+                "🗳️ Voting in the Lorax event in **{}** ends <t:{}:R> ({}) and you haven't \
+                voted yet! Use `/lorax vote` to pick your favourite.{}",
+                guild_name, end, end_at, voting_link
+            ));
+            if !send_dm(ctx, &self.dbs.preferences, user_id, message).await {
+                unreachable.push(user_id);
+            }
+        }
+
+        if let (Some(thread_id), false) = (event.campaign_thread_id, unreachable.is_empty()) {
+            let mentions: Vec<_> = unreachable.iter().map(|id| format!("<@{}>", id)).collect();
+            let message = CreateMessage::new()
+                .content(format!(
+                    "🗳️ {} voting ends <t:{}:R> and you haven't voted yet! Use `/lorax vote`.",
+                    mentions.join(" "),
+                    end
+                ))
+                .allowed_mentions(CreateAllowedMentions::new().users(unreachable.clone()));
+            if let Err(e) = ChannelId::new(thread_id).send_message(ctx, message).await {
+                tracing::warn!(
+                    "Failed to post vote reminder for guild {}: {}",
+                    self.guild_id,
+                    e
+                );
+            }
+        }
+    }
+
+    /// The announcement for `event`'s current stage, or `None` when there's nothing to
+    /// announce.
+    pub fn stage_content(&self, event: &LoraxEvent, tz: Tz) -> Option<String> {
+        let settings = &event.settings;
+        let example = settings
+            .sample_names()
             .choose(&mut rand::thread_rng())
-            .unwrap_or(&"Tree");
+            .map(|sample| format!(" like '{}'", sample))
+            .unwrap_or_default();
 
         let content = match event.stage {
             LoraxStage::Submission => format!(
-                "{role_ping}🌳 Help us name our new node! Submit a tree name like '{random_tree}' with `/lorax submit`.\nSubmissions close <t:{}:R>",
-                event.get_stage_end_timestamp(self.calculate_stage_duration(event))
+                "{} Help us name our {}! Submit a {} name{example} with `/lorax submit`.\nSubmissions close {}",
+                settings.theme_emoji(),
+                match event.winner_count {
+                    1 => "new node".to_string(),
+                    count => format!("{} new nodes", count),
+                },
+                settings.noun(),
+                self.format_deadline(event, tz)
             ),
             LoraxStage::Voting => {
                 if event.tree_submissions.is_empty() {
-                    event.stage = LoraxStage::Inactive;
-                    format!("{role_ping}😕 No tree names were submitted.")
+                    format!("😕 No {} names were submitted.", event.settings.noun())
                 } else {
                     format!(
-                        "{role_ping}🗳️ Time to vote! Use `/lorax vote` to choose the new node's name.\nVoting ends <t:{}:R>",
-                        event.get_stage_end_timestamp(self.calculate_stage_duration(event))
+                        "🗳️ Time to vote! Use `/lorax vote` to choose the new node's name.\nVoting ends {}{}",
+                        self.format_deadline(event, tz),
+                        vote_lock_notice(event)
                     )
                 }
             },
             LoraxStage::Tiebreaker(round) => format!(
-                "{role_ping}⚖️ Tiebreaker Round {round}! Vote again with `/lorax vote`.\nEnds <t:{}:R>",
-                event.get_stage_end_timestamp(self.calculate_stage_duration(event))
+                "⚖️ Tiebreaker Round {round}! Vote again with `/lorax vote`.\nEnds {}{}",
+                self.format_deadline(event, tz),
+                vote_lock_notice(event)
             ),
             LoraxStage::Completed => {
-                let mut podium = String::new();
+                let by = |tree: &str| {
+                    event
+                        .get_tree_submitter(tree)
+                        .map(|submitter_id| format!(" (by <@{}>)", submitter_id))
+                        .unwrap_or_default()
+                };
+
                 let total_entries = event.current_trees.len();
-                for (i, tree) in event.current_trees.iter().take(3).enumerate() {
-                    match i {
-                        0 => podium.push_str(&format!("🥇 **{}**", tree)),
-                        1 => podium.push_str(&format!("\n🥈 **{}**", tree)),
-                        2 => podium.push_str(&format!("\n🥉 **{}**", tree)),
-                        _ => unreachable!(),
-                    }
-                    if let Some(submitter_id) = event.get_tree_submitter(tree) {
-                        podium.push_str(&format!(" (by <@{}>)", submitter_id));
+                let (headline, mut podium, shown) = if event.winner_count > 1 {
+                    // Batch naming: every assigned name, then how many didn't make it
+                    let names: Vec<String> = event
+                        .winners()
+                        .iter()
+                        .enumerate()
+                        .map(|(i, tree)| format!("{}. **{}**{}", i + 1, tree, by(tree)))
+                        .collect();
+                    (
+                        format!("Our {} new nodes will be named:", names.len()),
+                        names.join("\n"),
+                        names.len(),
+                    )
+                } else {
+                    let mut podium = String::new();
+                    for (i, tree) in event.current_trees.iter().take(3).enumerate() {
+                        match i {
+                            0 => podium.push_str(&format!("🥇 **{}**", tree)),
+                            1 => podium.push_str(&format!("\n🥈 **{}**", tree)),
+                            2 => podium.push_str(&format!("\n🥉 **{}**", tree)),
+                            _ => unreachable!(),
+                        }
+                        podium.push_str(&by(tree));
                     }
-                }
-                if total_entries > 3 {
-                    podium.push_str(&format!("\n\nand {} runner ups...", total_entries - 3));
+                    let winner_name = event.current_trees.first()
+                        .map(|s| s.as_str())
+                        .unwrap_or("Unknown");
+                    (format!("Our new node will be named **{winner_name}**!"), podium, 3)
+                };
+                if total_entries > shown {
+                    podium.push_str(&format!("\n\nand {} runner ups...", total_entries - shown));
                 }
 
-                let winner_name = event.current_trees.first()
-                    .map(|s| s.as_str())
-                    .unwrap_or("Unknown");
+                let rounds = if event.round_results.len() > 1 {
+                    format!("\n\n📊 **Round by Round**\n{}", format_rounds(event))
+                } else {
+                    String::new()
+                };
+                let votes_cast: usize = event
+                    .round_results
+                    .iter()
+                    .map(|round| round.tally.iter().map(|(_, votes)| votes).sum::<usize>())
+                    .sum();
 
                 format!(
-                    "{role_ping}🎉 **Node Naming Results**\nOur new node will be named **{winner_name}**!\n\n{podium}\n\n🌲 **Event Stats**\n- Names Submitted: {}\n- Votes Cast: {}",
-                    event.tree_submissions.len(),
-                    event.tree_votes.len()
+                    "🎉 **Node Naming Results**\n{headline}\n\n{podium}{rounds}\n\n{} **Event Stats**\n- Names Submitted: {}\n- Votes Cast: {}",
+                    event.settings.theme_emoji(),
+                    event.submission_count(),
+                    votes_cast
                 )
             },
-            LoraxStage::Inactive => return,
+            LoraxStage::Inactive => return None,
+        };
+        Some(content)
+    }
+
+    pub async fn send_stage_message(&mut self, ctx: &Context, event: &mut LoraxEvent) {
+        let channel_id = match event.settings.lorax_channel {
+            Some(id) => id,
+            None => {
+                tracing::error!("No Lorax channel configured for guild {}", self.guild_id);
+                return;
+            }
+        };
+
+        // Validate channel exists and is accessible
+        let channel = match ctx.http.get_channel(ChannelId::new(channel_id)).await {
+            Ok(channel) => channel,
+            Err(e) => {
+                tracing::error!("Failed to fetch channel {}: {}", channel_id, e);
+                return;
+            }
+        };
+
+        let text_channel = match channel.guild() {
+            Some(tc) => tc,
+            None => {
+                tracing::error!("Channel {} is not a guild text channel", channel_id);
+                return;
+            }
+        };
+
+        let tz = self.dbs.guild_config().timezone(self.guild_id).await;
+        let theme = self.dbs.system.get_theme(self.guild_id).await;
+
+        let Some(content) = self.stage_content(event, tz) else {
+            return;
+        };
+        if event.stage == LoraxStage::Voting && event.tree_submissions.is_empty() {
+            event.stage = LoraxStage::Inactive;
+        }
+
+        // Mentions don't ping from inside an embed, so the role ping stays in the content
+        let announcement = CreateMessage::default()
+            .content(role_ping(event))
+            .embed(embed::themed(&theme).description(content))
+            .allowed_mentions(
+                CreateAllowedMentions::new()
+                    .roles(vec![event.settings.lorax_role.unwrap_or_default()]),
+            );
+        let announcement = if event.stage == LoraxStage::Submission {
+            announcement.components(vec![submit_button::button_row()])
+        } else {
+            announcement
         };
 
-        if let Ok(message) = text_channel.send_message(ctx, CreateMessage::default().content(&content).allowed_mentions(
-            CreateAllowedMentions::new()
-                .roles(vec![event.settings.lorax_role.unwrap_or_default()]),
-        )).await {
+        if text_channel.kind == ChannelType::Forum {
+            Self::announce_in_forum(ctx, &text_channel, event, &theme, announcement).await;
+            ballot::post_ballot(ctx, event, &theme, self.guild_id).await;
+            return;
+        }
+
+        if let Ok(message) = text_channel.send_message(ctx, announcement).await {
             match event.stage {
                 LoraxStage::Submission => {
                     event.stage_message_id = Some(message.id.get())
@@ -331,25 +1117,12 @@ impl LoraxEventTask {
                         .await
                     {
                         event.campaign_thread_id = Some(thread.id.get());
-                        let welcome_msg = CreateMessage::default()
-                            .content("🎭 Welcome to the campaign thread! Tree submitters can campaign for their entries here. Good luck!");
-                        let _ = thread.send_message(ctx, welcome_msg).await;
+                        pitch::post_campaign(ctx, &theme, event).await;
                     }
                 }
                 LoraxStage::Completed => {
                     if let Some(thread_id) = event.campaign_thread_id {
-                        if let Ok(thread) =
-                            ctx.http.get_channel(ChannelId::new(thread_id)).await
-                        {
-                            if let Some(mut thread) = thread.guild() {
-                                let _ = thread
-                                    .edit_thread(
-                                        ctx,
-                                        EditThread::new().locked(true).archived(true),
-                                    )
-                                    .await;
-                            }
-                        }
+                        close_thread(ctx, thread_id).await;
                     }
                 }
                 LoraxStage::Tiebreaker(_) => {
@@ -358,6 +1131,99 @@ impl LoraxEventTask {
                 _ => {}
             }
         }
+        ballot::post_ballot(ctx, event, &theme, self.guild_id).await;
+    }
+
+    /// Forum channels get one post per event: the first announcement opens it, and later
+    /// stages, campaigning and the pinned results all happen inside it.
+    async fn announce_in_forum(
+        ctx: &Context,
+        forum: &GuildChannel,
+        event: &mut LoraxEvent,
+        theme: &Theme,
+        announcement: CreateMessage,
+    ) {
+        let (post_id, message_id) = match event.campaign_thread_id {
+            Some(post_id) => {
+                let post = ChannelId::new(post_id);
+                match post.send_message(ctx, announcement).await {
+                    Ok(message) => (post, message.id),
+                    Err(e) => {
+                        tracing::error!("Failed to post in Lorax forum post {}: {}", post_id, e);
+                        return;
+                    }
+                }
+            }
+            None => {
+                let post = CreateForumPost::new(FORUM_POST_TITLE, announcement)
+                    .auto_archive_duration(AutoArchiveDuration::OneWeek);
+                match forum.create_forum_post(ctx, post).await {
+                    Ok(post) => {
+                        event.campaign_thread_id = Some(post.id.get());
+                        // The opening message of a forum post shares the post's id
+                        (post.id, MessageId::new(post.id.get()))
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to create Lorax forum post in {}: {}", forum.id, e);
+                        return;
+                    }
+                }
+            }
+        };
+
+        match event.stage {
+            LoraxStage::Submission => event.stage_message_id = Some(message_id.get()),
+            LoraxStage::Voting => {
+                event.voting_message_id = Some(message_id.get());
+                pitch::post_campaign(ctx, theme, event).await;
+            }
+            LoraxStage::Tiebreaker(_) => event.tiebreaker_message_id = Some(message_id.get()),
+            LoraxStage::Completed => {
+                if let Err(e) = post_id.pin(ctx, message_id).await {
+                    tracing::warn!("Failed to pin Lorax results in {}: {}", post_id, e);
+                }
+                close_thread(ctx, post_id.get()).await;
+            }
+            LoraxStage::Inactive => close_thread(ctx, post_id.get()).await,
+        }
+    }
+}
+
+/// The mention of the role pinged by stage announcements, if one is set.
+pub fn role_ping(event: &LoraxEvent) -> String {
+    event
+        .settings
+        .lorax_role
+        .map(|id| format!("<@&{}>", id))
+        .unwrap_or_default()
+}
+
+/// Locks and archives a finished event's campaign thread or forum post.
+async fn close_thread(ctx: &Context, thread_id: u64) {
+    if let Ok(thread) = ctx.http.get_channel(ChannelId::new(thread_id)).await {
+        if let Some(mut thread) = thread.guild() {
+            let _ = thread
+                .edit_thread(ctx, EditThread::new().locked(true).archived(true))
+                .await;
+        }
+    }
+}
+
+/// Where `event`'s stage announcements live: the Lorax channel, or the event's post when
+/// that channel is a forum.
+pub async fn announcement_channel(ctx: &Context, event: &LoraxEvent) -> Option<ChannelId> {
+    let channel_id = ChannelId::new(event.settings.lorax_channel?);
+    let is_forum = channel_id
+        .to_channel(ctx)
+        .await
+        .ok()
+        .and_then(|channel| channel.guild())
+        .is_some_and(|channel| channel.kind == ChannelType::Forum);
+
+    if is_forum {
+        event.campaign_thread_id.map(ChannelId::new)
+    } else {
+        Some(channel_id)
     }
 }
 
@@ -383,3 +1249,51 @@ impl Task for LoraxEventTask {
         Box::new(self.clone())
     }
 }
+
+/// Runs a [`LoraxEventTask`] for every guild with an event in progress or scheduled.
+#[derive(Clone, Debug)]
+pub struct LoraxGuildTask {
+    pub dbs: Arc<Databases>,
+    pub config: Arc<LiveConfig>,
+}
+
+#[async_trait::async_trait]
+impl GuildTask for LoraxGuildTask {
+    fn name(&self) -> &str {
+        "LoraxEvent"
+    }
+
+    async fn guilds(&self) -> Vec<u64> {
+        let disabled = self.dbs.modules.disabled_guilds(Module::Lorax).await;
+        self.dbs
+            .lorax
+            .read(|db| {
+                db.events
+                    .iter()
+                    .filter(|(_, event)| event.stage != LoraxStage::Inactive)
+                    .map(|(guild_id, _)| *guild_id)
+                    .chain(db.schedules.keys().copied())
+                    .filter(|guild_id| !disabled.contains(guild_id))
+                    .collect::<HashSet<_>>()
+                    .into_iter()
+                    .collect()
+            })
+            .await
+    }
+
+    async fn wanted(&self, guild_id: u64) -> bool {
+        self.dbs.modules.is_enabled(guild_id, Module::Lorax).await
+            && self.dbs.lorax.read(|db| {
+                db.schedules.contains_key(&guild_id)
+                    || db
+                        .events
+                        .get(&guild_id)
+                        .is_some_and(|event| event.stage != LoraxStage::Inactive)
+            })
+            .await
+    }
+
+    fn create(&self, guild_id: u64) -> Box<dyn Task> {
+        Box::new(LoraxEventTask::new(guild_id, self.dbs.clone(), self.config.clone()))
+    }
+}
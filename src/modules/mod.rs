@@ -1,7 +1,25 @@
+pub mod admin;
+pub mod audit;
+pub mod cooldowns;
+pub mod help;
 pub mod lorax;
 pub mod modrinth;
+pub mod preferences;
+pub mod privacy;
 pub mod recording;  // Add this
+pub mod roles;
 pub mod stats;
 pub mod system;
 pub mod testing;
+pub mod toggles;
 pub mod utils;
+
+use crate::{databases::Databases, metrics::MetricsRegistry};
+
+/// Registers every module's bot-side metrics with the crate-wide registry.
+pub fn register_metrics(registry: &MetricsRegistry, dbs: &Databases) {
+    lorax::metrics::register(registry, dbs.lorax.clone());
+    testing::metrics::register(registry, dbs.testing.clone());
+    recording::metrics::register(registry);
+    crate::utils::http::register_metrics(registry);
+}
@@ -1,3 +1,4 @@
+use crate::utils::{http, reply::say};
 use crate::{Context, Error};
 use poise::{command, CreateReply};
 use serde_json::Value;
@@ -9,7 +10,7 @@ const CHECK_INTERVAL: Duration = Duration::from_secs(10);
 const MAX_DURATION: Duration = Duration::from_secs(300);
 
 /// Link your Modrinth account
-#[command(slash_command, guild_only, ephemeral)]
+#[command(slash_command, ephemeral)]
 pub async fn link(
     ctx: Context<'_>,
     #[description = "Your Modrinth username or ID"] username: String,
@@ -17,15 +18,14 @@ pub async fn link(
     let discord_id = ctx.author().id.get();
 
     if let Some(_) = ctx.data().dbs.modrinth.get_modrinth_id(discord_id).await {
-        ctx.say("⚠️ Your account is already linked! Use `/modrinth unlink` first.")
+        say(ctx, "⚠️ Your account is already linked! Use `/modrinth unlink` first.")
             .await?;
         return Ok(());
     }
 
     let verification_code = format!("{}{}", VERIFICATION_CODE, discord_id);
 
-    let msg = ctx
-        .say(format!(
+    let msg = say(ctx, format!(
             "🔗 **Link your Modrinth Account**\n\n\
         1. Visit your [Modrinth profile settings](https://modrinth.com/settings/profile)\n\
         2. Add this code to your bio: `{}`\n\
@@ -63,11 +63,10 @@ async fn verify_code(
     username: &str,
 ) -> Result<(), Error> {
     let discord_id = ctx.author().id.get();
-    let client = reqwest::Client::new();
+    let client = http::client();
 
     let response = client
-        .get(format!("https://api.modrinth.com/v2/user/{}", username))
-        .send()
+        .send(client.get(format!("https://api.modrinth.com/v2/user/{}", username)))
         .await;
 
     let response = match response {
@@ -100,7 +99,7 @@ async fn verify_code(
 }
 
 /// Unlink your Modrinth account
-#[command(slash_command, guild_only, ephemeral)]
+#[command(slash_command, ephemeral)]
 pub async fn unlink(ctx: Context<'_>) -> Result<(), Error> {
     let discord_id = ctx.author().id.get();
 
@@ -112,12 +111,12 @@ pub async fn unlink(ctx: Context<'_>) -> Result<(), Error> {
         .await
         .is_none()
     {
-        ctx.say("❌ Your account is not linked!").await?;
+        say(ctx, "❌ Your account is not linked!").await?;
         return Ok(());
     }
 
     ctx.data().dbs.modrinth.unlink_account(discord_id).await?;
-    ctx.say("✅ Successfully unlinked your Modrinth account!")
+    say(ctx, "✅ Successfully unlinked your Modrinth account!")
         .await?;
     Ok(())
 }
@@ -1,4 +1,4 @@
-use crate::database::Database;
+use crate::database::{Database, Rows};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -7,6 +7,8 @@ pub struct ModrinthDatabase {
     pub linked_accounts: HashMap<u64, String>,
 }
 
+impl Rows for ModrinthDatabase {}
+
 impl Database<ModrinthDatabase> {
     pub async fn link_account(&self, discord_id: u64, modrinth_id: String) -> Result<(), String> {
         self.transaction(|db| {
@@ -26,6 +28,20 @@ impl Database<ModrinthDatabase> {
         .map_err(|e| e.to_string())
     }
 
+    /// Removes the user's linked account, describing what was removed.
+    pub async fn forget_user(&self, user_id: u64) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            Ok(db
+                .linked_accounts
+                .remove(&user_id)
+                .map(|id| format!("Modrinth link ({})", id))
+                .into_iter()
+                .collect())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
     pub async fn get_modrinth_id(&self, discord_id: u64) -> Option<String> {
         self.read(|db| db.linked_accounts.get(&discord_id).cloned())
             .await
@@ -8,7 +8,6 @@ use poise::command;
 #[command(
     slash_command,
     subcommands("link", "unlink"),
-    guild_only,
     category = "Account"
 )]
 pub async fn modrinth(_ctx: crate::Context<'_>) -> Result<(), crate::Error> {
@@ -0,0 +1,120 @@
+use crate::utils::reply::say;
+use crate::utils::time::parse_locale;
+use crate::{Context, Error};
+use chrono_tz::Tz;
+use poise::command;
+
+/// View your notification and display preferences
+#[command(slash_command, ephemeral)]
+pub async fn view(ctx: Context<'_>) -> Result<(), Error> {
+    let prefs = ctx
+        .data()
+        .dbs
+        .preferences
+        .get_preferences(ctx.author().id.get())
+        .await;
+
+    say(ctx, format!(
+        "⚙️ **Your Preferences**\n\
+        📬 **DM Notifications:** {}\n\
+        🌐 **Locale:** {}\n\
+        🕒 **Timezone:** {}",
+        if prefs.dm_notifications { "On" } else { "Off" },
+        prefs.locale.as_deref().unwrap_or("Default"),
+        prefs.timezone.as_deref().unwrap_or("Server default"),
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Turn direct message notifications from the bot on or off
+#[command(slash_command, ephemeral)]
+pub async fn dms(
+    ctx: Context<'_>,
+    #[description = "Whether the bot may DM you (reminders, event updates)"] enabled: bool,
+) -> Result<(), Error> {
+    ctx.data()
+        .dbs
+        .preferences
+        .update_preferences(ctx.author().id.get(), |prefs| {
+            prefs.dm_notifications = enabled
+        })
+        .await?;
+
+    say(ctx, if enabled {
+        "📬 DM notifications enabled."
+    } else {
+        "🔕 DM notifications disabled. You won't receive reminders by DM."
+    })
+    .await?;
+    Ok(())
+}
+
+/// Set how times in the bot's DMs are written
+#[command(slash_command, ephemeral)]
+pub async fn locale(
+    ctx: Context<'_>,
+    #[description = "Locale such as en-US or de (leave empty for the default)"]
+    locale: Option<String>,
+) -> Result<(), Error> {
+    let locale = locale.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+
+    if let Some(locale) = &locale {
+        if parse_locale(locale).is_none() {
+            say(ctx, "❌ Unknown locale. Try something like `en-US` or `de`.").await?;
+            return Ok(());
+        }
+    }
+
+    ctx.data()
+        .dbs
+        .preferences
+        .update_preferences(ctx.author().id.get(), |prefs| {
+            prefs.locale = locale.clone()
+        })
+        .await?;
+
+    match locale {
+        Some(locale) => say(ctx, format!("🌐 Locale set to **{}**.", locale)).await?,
+        None => say(ctx, "🌐 Locale reset to the default.").await?,
+    };
+    Ok(())
+}
+
+/// Set your personal timezone for displayed times
+#[command(slash_command, ephemeral)]
+pub async fn timezone(
+    ctx: Context<'_>,
+    #[description = "Timezone name, e.g. Europe/Berlin (leave empty to use the server's)"]
+    #[autocomplete = "crate::modules::system::commands::autocomplete_timezone"]
+    timezone: Option<String>,
+) -> Result<(), Error> {
+    let timezone = match timezone.map(|tz| tz.trim().to_string()).filter(|tz| !tz.is_empty()) {
+        Some(name) => match name.parse::<Tz>() {
+            Ok(tz) => Some(tz.name().to_string()),
+            Err(_) => {
+                say(ctx, format!(
+                    "❌ Unknown timezone `{}`. Try something like `Europe/Berlin` or `America/New_York`.",
+                    name
+                ))
+                .await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    ctx.data()
+        .dbs
+        .preferences
+        .update_preferences(ctx.author().id.get(), |prefs| {
+            prefs.timezone = timezone.clone()
+        })
+        .await?;
+
+    match timezone {
+        Some(tz) => say(ctx, format!("🕒 Your timezone is now **{}**.", tz)).await?,
+        None => say(ctx, "🕒 Timezone reset to the server default.").await?,
+    };
+    Ok(())
+}
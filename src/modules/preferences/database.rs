@@ -0,0 +1,82 @@
+use crate::{
+    database::{Database, Rows},
+    default_struct,
+    utils::time::{format_timestamp, format_timestamp_localized, parse_locale, parse_timezone},
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+default_struct! {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserPreferences {
+    pub dm_notifications: bool = true,
+    pub locale: Option<String>,
+    pub timezone: Option<String>,
+}
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct PreferencesDatabase {
+    pub users: HashMap<u64, UserPreferences>,
+}
+
+impl Rows for PreferencesDatabase {}
+
+impl Database<PreferencesDatabase> {
+    pub async fn get_preferences(&self, user_id: u64) -> UserPreferences {
+        self.read(|db| db.users.get(&user_id).cloned().unwrap_or_default())
+            .await
+    }
+
+    /// Removes the user's stored preferences, describing what was removed.
+    pub async fn forget_user(&self, user_id: u64) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            Ok(db
+                .users
+                .remove(&user_id)
+                .map(|_| "Personal preferences".to_string())
+                .into_iter()
+                .collect())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn allows_dms(&self, user_id: u64) -> bool {
+        self.get_preferences(user_id).await.dm_notifications
+    }
+
+    /// Renders a unix timestamp for the user, in their own timezone (or `fallback_tz`) and
+    /// written the way their locale does when they have set one.
+    pub async fn format_time(&self, user_id: u64, timestamp: u64, fallback_tz: Tz) -> String {
+        let prefs = self.get_preferences(user_id).await;
+        let tz = prefs
+            .timezone
+            .map(|tz| parse_timezone(&tz))
+            .unwrap_or(fallback_tz);
+        match prefs.locale.as_deref().and_then(parse_locale) {
+            Some(locale) => format_timestamp_localized(timestamp, tz, locale),
+            None => format_timestamp(timestamp, tz),
+        }
+    }
+
+    pub async fn update_preferences<F>(&self, user_id: u64, f: F) -> Result<UserPreferences, String>
+    where
+        F: FnOnce(&mut UserPreferences),
+    {
+        self.transaction(|db| {
+            let prefs = db.users.entry(user_id).or_default();
+            f(prefs);
+            let prefs = prefs.clone();
+
+            // Don't keep rows around for users who are back on the defaults
+            if prefs.dm_notifications && prefs.locale.is_none() && prefs.timezone.is_none() {
+                db.users.remove(&user_id);
+            }
+            Ok(prefs)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,12 @@
+pub mod commands;
+pub mod database;
+pub mod notify;
+
+use commands::*;
+use poise::command;
+
+/// 🔔 Your personal bot preferences
+#[command(slash_command, subcommands("view", "dms", "locale", "timezone"))]
+pub async fn preferences(_ctx: crate::Context<'_>) -> Result<(), crate::Error> {
+    Ok(())
+}
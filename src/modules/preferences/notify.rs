@@ -0,0 +1,36 @@
+use crate::database::Database;
+use poise::serenity_prelude::{Context, CreateMessage, UserId};
+use tracing::{debug, warn};
+
+use super::database::PreferencesDatabase;
+
+/// Sends a direct message unless the user has opted out of DM notifications.
+///
+/// Returns whether the message was actually delivered.
+pub async fn send_dm(
+    ctx: &Context,
+    preferences: &Database<PreferencesDatabase>,
+    user_id: u64,
+    message: CreateMessage,
+) -> bool {
+    if !preferences.allows_dms(user_id).await {
+        debug!("Skipping DM to {} - notifications disabled", user_id);
+        return false;
+    }
+
+    let channel = match UserId::new(user_id).create_dm_channel(ctx).await {
+        Ok(channel) => channel,
+        Err(e) => {
+            warn!("Failed to open DM channel with {}: {}", user_id, e);
+            return false;
+        }
+    };
+
+    match channel.send_message(ctx, message).await {
+        Ok(_) => true,
+        Err(e) => {
+            warn!("Failed to DM {}: {}", user_id, e);
+            false
+        }
+    }
+}
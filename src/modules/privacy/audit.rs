@@ -0,0 +1,29 @@
+use crate::config::data_path;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+
+/// Append-only record of data deletions, one JSON object per line.
+const AUDIT_LOG: &str = "privacy-audit.jsonl";
+
+#[derive(Debug, Serialize)]
+pub struct ForgetEntry<'a> {
+    pub at: DateTime<Utc>,
+    pub user_id: u64,
+    pub removed: &'a [String],
+}
+
+/// Records what a forget request removed.
+pub async fn record(entry: &ForgetEntry<'_>) -> Result<(), String> {
+    let mut line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    line.push('\n');
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(data_path(AUDIT_LOG))
+        .await
+        .map_err(|e| e.to_string())?;
+    file.write_all(line.as_bytes()).await.map_err(|e| e.to_string())?;
+    file.sync_data().await.map_err(|e| e.to_string())
+}
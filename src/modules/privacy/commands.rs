@@ -0,0 +1,98 @@
+use super::audit::{self, ForgetEntry};
+use crate::utils::reply::send;
+use crate::{Context, Error};
+use poise::serenity_prelude::{ButtonStyle, CreateActionRow, CreateButton};
+use poise::{command, CreateReply};
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Delete everything the bot stores about you
+#[command(slash_command, ephemeral)]
+pub async fn forget(ctx: Context<'_>) -> Result<(), Error> {
+    let user_id = ctx.author().id.get();
+
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new("forget_confirm")
+            .style(ButtonStyle::Danger)
+            .label("Delete my data"),
+        CreateButton::new("forget_cancel")
+            .style(ButtonStyle::Secondary)
+            .label("Cancel"),
+    ]);
+    let handle = send(
+        ctx,
+        CreateReply::default()
+            .content(
+                "🔒 This permanently deletes your Lorax submissions and votes, Modrinth link, \
+                test servers and preferences across every server. This can't be undone.",
+            )
+            .components(vec![buttons]),
+    )
+    .await?;
+
+    let interaction = handle
+        .message()
+        .await?
+        .await_component_interaction(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .timeout(Duration::from_secs(60))
+        .await;
+
+    let confirmed = match &interaction {
+        Some(interaction) => {
+            interaction.defer(ctx.http()).await?;
+            interaction.data.custom_id == "forget_confirm"
+        }
+        None => false,
+    };
+
+    if !confirmed {
+        handle
+            .edit(ctx, CreateReply::default().content("❌ Nothing was deleted.").components(vec![]))
+            .await?;
+        return Ok(());
+    }
+
+    // Running test servers are deleted from Archon before their records go
+    for server in ctx.data().dbs.testing.get_user_servers(user_id).await {
+        if let Err(e) = ctx.data().archon.delete_server(&server.server_id).await {
+            error!("Failed to delete test server {} while forgetting user: {}", server.server_id, e);
+        }
+    }
+
+    let content = match ctx.data().dbs.forget_user(user_id).await {
+        Ok(removed) => {
+            if let Err(e) = audit::record(&ForgetEntry {
+                at: chrono::Utc::now(),
+                user_id,
+                removed: &removed,
+            })
+            .await
+            {
+                error!("Failed to write privacy audit entry: {}", e);
+            }
+            info!("Forgot user {}: {} record(s) removed", user_id, removed.len());
+
+            if removed.is_empty() {
+                "🔒 The bot had no data stored about you.".to_string()
+            } else {
+                let list = removed.iter().map(|r| format!("• {}", r)).collect::<Vec<_>>().join("\n");
+                let content = format!("🔒 Deleted:\n{}", list);
+                if content.chars().count() > 2000 {
+                    format!("🔒 Deleted {} records.", removed.len())
+                } else {
+                    content
+                }
+            }
+        }
+        Err(e) => {
+            error!("Failed to forget user {}: {}", user_id, e);
+            "❌ Something went wrong; some data may not have been deleted. Please try again.".to_string()
+        }
+    };
+
+    handle
+        .edit(ctx, CreateReply::default().content(content).components(vec![]))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,11 @@
+pub mod audit;
+pub mod commands;
+
+use commands::*;
+use poise::command;
+
+/// 🔒 Your data and privacy
+#[command(slash_command, subcommands("forget"))]
+pub async fn privacy(_ctx: crate::Context<'_>) -> Result<(), crate::Error> {
+    Ok(())
+}
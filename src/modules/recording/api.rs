@@ -0,0 +1,48 @@
+//! Recording sessions as served by `GET /api/recordings`. IDs are strings so JavaScript
+//! dashboards don't lose precision.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::database::RecordingSession;
+
+/// Sessions returned when the request doesn't ask for a number.
+pub const DEFAULT_LIMIT: usize = 20;
+
+#[derive(Debug, Serialize)]
+pub struct SessionView {
+    pub title: String,
+    pub voice_channel_id: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub duration_seconds: i64,
+    pub participants: Vec<String>,
+    pub link: Option<String>,
+}
+
+impl From<&RecordingSession> for SessionView {
+    fn from(session: &RecordingSession) -> Self {
+        Self {
+            title: session.title.clone(),
+            voice_channel_id: session.voice_channel_id.to_string(),
+            started_at: session.started_at,
+            ended_at: session.ended_at,
+            duration_seconds: session.duration().num_seconds(),
+            participants: session.participants.iter().map(|id| id.to_string()).collect(),
+            link: session.link.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    recordings: Vec<SessionView>,
+}
+
+/// The JSON body listing `sessions`, newest first.
+pub fn render(sessions: &[RecordingSession]) -> Result<String, String> {
+    serde_json::to_string(&Response {
+        recordings: sessions.iter().map(SessionView::from).collect(),
+    })
+    .map_err(|e| e.to_string())
+}
@@ -1,7 +1,13 @@
+use crate::utils::duration::format_duration;
+use crate::utils::plan::Plan;
+use crate::utils::reply::say;
+use crate::utils::validate;
 use crate::Context;
 use poise::command;
 use poise::serenity_prelude::{ChannelId, ChannelType};
 use super::database::RecordingChannel;
+use super::summary;
+use super::webhook::{RecordingWebhook, SIGNATURE_HEADER, TIMESTAMP_HEADER};
 
 /// Enable voice channel recording
 #[command(slash_command, guild_only)]
@@ -9,13 +15,13 @@ pub async fn enable(
     ctx: Context<'_>,
     #[description = "Voice channel to record"] voice_channel: ChannelId,
 ) -> Result<(), crate::Error> {
-    let guild_id = ctx.guild_id().unwrap();
+    let guild_id = validate::guild(ctx)?;
     
     // Verify channel is voice channel
     let voice_channel_info = voice_channel.to_channel(&ctx).await?;
     
     if voice_channel_info.guild().map(|c| c.kind) != Some(ChannelType::Voice) {
-        ctx.say("The specified channel must be a voice channel!").await?;
+        say(ctx, "The specified channel must be a voice channel!").await?;
         return Ok(());
     }
     
@@ -26,7 +32,7 @@ pub async fn enable(
     if db.read(|data| {
         data.channels.contains_key(&guild_id.get())
     }).await {
-        ctx.say("This guild already has a recording channel set up! Use `/recording disable` first.").await?;
+        say(ctx, "This guild already has a recording channel set up! Use `/recording disable` first.").await?;
         return Ok(());
     }
     
@@ -39,13 +45,15 @@ pub async fn enable(
                 voice_channel_id: voice_channel.get(),
                 is_recording: false,
                 last_activity: None,
+                webhook: None,
+                summaries: false,
             },
         );
         Ok(())
     })
     .await?;
     
-    ctx.say("Voice channel recording enabled!").await?;
+    say(ctx, "Voice channel recording enabled!").await?;
     Ok(())
 }
 
@@ -53,9 +61,21 @@ pub async fn enable(
 #[command(slash_command, guild_only)]
 pub async fn disable(
     ctx: Context<'_>,
+    #[description = "Only show what would be removed"] dry_run: Option<bool>,
 ) -> Result<(), crate::Error> {
-    let guild_id = ctx.guild_id().unwrap();
+    let guild_id = validate::guild(ctx)?;
     let db = &ctx.data().dbs.recording;
+
+    let mut plan = Plan::new();
+    if let Some(channel) = db.read(|data| data.channels.get(&guild_id.get()).cloned()).await {
+        plan.add(format!("Stop recording <#{}>", channel.voice_channel_id));
+        if let Some(webhook) = channel.webhook {
+            plan.add(format!("Remove the recording webhook to {}", webhook.url));
+        }
+    }
+    if plan.preview(ctx, dry_run).await? {
+        return Ok(());
+    }
     
     db.transaction(|data| {
         if data.channels.remove(&guild_id.get()).is_some() {
@@ -66,14 +86,14 @@ pub async fn disable(
     })
     .await?;
     
-    ctx.say("Voice channel recording disabled!").await?;
+    say(ctx, "Voice channel recording disabled!").await?;
     Ok(())
 }
 
 /// List recording channels
 #[command(slash_command, guild_only)]
 pub async fn list(ctx: Context<'_>) -> Result<(), crate::Error> {
-    let guild_id = ctx.guild_id().unwrap();
+    let guild_id = validate::guild(ctx)?;
     let db = &ctx.data().dbs.recording;
     
     let channel = db.read(|data| {
@@ -89,15 +109,16 @@ pub async fn list(ctx: Context<'_>) -> Result<(), crate::Error> {
                 .map(|c| c.name().to_string())
                 .unwrap_or_else(|| "Unknown".to_string());
                 
-            ctx.say(format!(
-                "Recording configuration:\nVoice Channel: {}\nCurrently Recording: {}\nLast Activity: {}",
+            say(ctx, format!(
+                "Recording configuration:\nVoice Channel: {}\nCurrently Recording: {}\nLast Activity: {}\nSummaries: {}",
                 voice_name,
                 if channel.is_recording { "Yes" } else { "No" },
-                channel.last_activity.map(|t| t.to_rfc3339()).unwrap_or_else(|| "Never".to_string())
+                channel.last_activity.map(|t| t.to_rfc3339()).unwrap_or_else(|| "Never".to_string()),
+                if channel.summaries { "On" } else { "Off" }
             )).await?;
         }
         None => {
-            ctx.say("No recording channel configured for this guild.").await?;
+            say(ctx, "No recording channel configured for this guild.").await?;
         }
     }
     
@@ -110,7 +131,7 @@ pub async fn toggle(
     ctx: Context<'_>,
     #[description = "Voice channel to record (leave empty to disable)"] voice_channel: Option<ChannelId>,
 ) -> Result<(), crate::Error> {
-    let guild_id = ctx.guild_id().unwrap();
+    let guild_id = validate::guild(ctx)?;
     let db = &ctx.data().dbs.recording;
 
     match voice_channel {
@@ -120,12 +141,15 @@ pub async fn toggle(
             
             // Check channel type first
             if channel_info.clone().guild().map(|c| c.kind) != Some(ChannelType::Voice) {
-                ctx.say("The specified channel must be a voice channel!").await?;
+                say(ctx, "The specified channel must be a voice channel!").await?;
                 return Ok(());
             }
 
             // Update or create recording configuration
             db.transaction(|data| {
+                let existing = data.channels.get(&guild_id.get());
+                let webhook = existing.and_then(|existing| existing.webhook.clone());
+                let summaries = existing.is_some_and(|existing| existing.summaries);
                 data.channels.insert(
                     guild_id.get(),
                     RecordingChannel {
@@ -133,6 +157,8 @@ pub async fn toggle(
                         voice_channel_id: channel.get(),
                         is_recording: false,
                         last_activity: None,
+                        webhook,
+                        summaries,
                     },
                 );
                 Ok(())
@@ -141,7 +167,7 @@ pub async fn toggle(
 
             let channel_name = channel_info.guild().map(|c| c.name().to_string())
                 .unwrap_or_else(|| "Unknown".to_string());
-            ctx.say(format!("Voice recording configured for channel: {}", channel_name)).await?;
+            say(ctx, format!("Voice recording configured for channel: {}", channel_name)).await?;
         }
         None => {
             // Disable recording if it exists
@@ -154,9 +180,151 @@ pub async fn toggle(
             })
             .await?;
             
-            ctx.say("Voice recording disabled!").await?;
+            say(ctx, "Voice recording disabled!").await?;
         }
     }
     
     Ok(())
 }
+
+/// Send signed JSON webhooks when recordings start, stop and finish uploading
+#[command(slash_command, guild_only, ephemeral)]
+pub async fn webhook(
+    ctx: Context<'_>,
+    #[description = "URL to POST recording events to (leave empty to disable)"] url: Option<String>,
+) -> Result<(), crate::Error> {
+    let guild_id = validate::guild(ctx)?;
+    let db = &ctx.data().dbs.recording;
+
+    let hook = match url {
+        Some(url) => {
+            let url = url.trim().to_string();
+            if reqwest::Url::parse(&url)
+                .map(|parsed| !matches!(parsed.scheme(), "http" | "https"))
+                .unwrap_or(true)
+            {
+                say(ctx, "❌ Webhook URL must be a valid http(s) URL.").await?;
+                return Ok(());
+            }
+            Some(RecordingWebhook::new(url))
+        }
+        None => None,
+    };
+
+    let result = db
+        .transaction(|data| {
+            let channel = data
+                .channels
+                .get_mut(&guild_id.get())
+                .ok_or("No recording channel configured for this guild.")?;
+            channel.webhook = hook.clone();
+            Ok(())
+        })
+        .await;
+
+    if let Err(e) = result {
+        say(ctx, format!("❌ {}", e)).await?;
+        return Ok(());
+    }
+
+    match hook {
+        Some(hook) => {
+            say(ctx, format!(
+                "🔗 Recording webhooks will be sent to <{}>.\n\
+                Verify each request by computing an HMAC-SHA256 of `{{{}}}.{{body}}` with this secret \
+                and comparing it to the `{}` header:\n||`{}`||",
+                hook.url, TIMESTAMP_HEADER, SIGNATURE_HEADER, hook.secret
+            ))
+            .await?;
+        }
+        None => {
+            say(ctx, "🔕 Recording webhooks disabled.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Post a summary and action items next to each finished recording
+#[command(slash_command, guild_only, ephemeral)]
+pub async fn summaries(
+    ctx: Context<'_>,
+    #[description = "Whether finished recordings are summarized"] enabled: bool,
+) -> Result<(), crate::Error> {
+    let guild_id = validate::guild(ctx)?;
+
+    let result = ctx
+        .data()
+        .dbs
+        .recording
+        .transaction(|data| {
+            let channel = data
+                .channels
+                .get_mut(&guild_id.get())
+                .ok_or("No recording channel configured for this guild.")?;
+            channel.summaries = enabled;
+            Ok(())
+        })
+        .await;
+    if let Err(e) = result {
+        say(ctx, format!("❌ {}", e)).await?;
+        return Ok(());
+    }
+
+    let configured = summary::is_configured(&ctx.data().config.get().recording);
+    match (enabled, configured) {
+        (true, true) => say(ctx, "📝 Finished recordings will be summarized.").await?,
+        (true, false) => {
+            say(
+                ctx,
+                "📝 Summaries turned on, but the bot has no transcription or summary endpoint configured yet, \
+                so none will be posted until its operator sets one.",
+            )
+            .await?
+        }
+        (false, _) => say(ctx, "🔕 Recording summaries turned off.").await?,
+    };
+    Ok(())
+}
+
+/// List the server's most recent recordings
+#[command(slash_command, guild_only)]
+pub async fn recent(
+    ctx: Context<'_>,
+    #[description = "How many recordings to show (default 5)"]
+    #[min = 1]
+    #[max = 10]
+    count: Option<usize>,
+) -> Result<(), crate::Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let sessions = ctx
+        .data()
+        .dbs
+        .recording
+        .recent_sessions(guild_id, count.unwrap_or(5))
+        .await;
+
+    if sessions.is_empty() {
+        say(ctx, "📭 No recordings have finished in this server yet.").await?;
+        return Ok(());
+    }
+
+    let lines: Vec<String> = sessions
+        .iter()
+        .map(|session| {
+            let title = match &session.link {
+                Some(link) => format!("[{}]({})", session.title, link),
+                None => session.title.clone(),
+            };
+            format!(
+                "**{}** - <t:{}:R>, {} with {} participant(s)",
+                title,
+                session.started_at.timestamp(),
+                format_duration(session.duration().num_seconds().max(0) as u64),
+                session.participants.len()
+            )
+        })
+        .collect();
+    say(ctx, format!("🎙️ Recent recordings:\n{}", lines.join("\n"))).await?;
+    Ok(())
+}
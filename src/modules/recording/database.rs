@@ -1,9 +1,95 @@
+use crate::database::{Database, Migration, Rows};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+
+use super::webhook::RecordingWebhook;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct RecordingDatabase {
     pub channels: HashMap<u64, RecordingChannel>,
+    /// Finished recordings of each guild, oldest first.
+    pub sessions: HashMap<u64, VecDeque<RecordingSession>>,
+}
+
+impl Rows for RecordingDatabase {
+    const VERSION: u32 = 2;
+
+    fn migrations() -> Vec<Box<dyn Migration>> {
+        vec![Box::new(super::migrations::V1ToV2)]
+    }
+}
+
+/// Finished recordings kept per guild.
+pub const MAX_SESSIONS: usize = 50;
+
+impl Database<RecordingDatabase> {
+    /// Keeps a finished recording, dropping the guild's oldest once there are too many.
+    pub async fn record_session(
+        &self,
+        guild_id: u64,
+        session: RecordingSession,
+    ) -> Result<(), String> {
+        self.transaction(|db| {
+            let sessions = db.sessions.entry(guild_id).or_default();
+            sessions.push_back(session);
+            while sessions.len() > MAX_SESSIONS {
+                sessions.pop_front();
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// The guild's last `count` recordings, newest first.
+    pub async fn recent_sessions(&self, guild_id: u64, count: usize) -> Vec<RecordingSession> {
+        self.read(|db| {
+            db.sessions
+                .get(&guild_id)
+                .map(|sessions| sessions.iter().rev().take(count).cloned().collect())
+                .unwrap_or_default()
+        })
+        .await
+    }
+
+    /// Removes the user from the participants of past recordings. Audio isn't stored per
+    /// user, so there is nothing else to remove.
+    pub async fn forget_user(&self, user_id: u64) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            let mut removed = 0;
+            for session in db.sessions.values_mut().flatten() {
+                let before = session.participants.len();
+                session.participants.retain(|id| *id != user_id);
+                removed += before - session.participants.len();
+            }
+            Ok(if removed > 0 {
+                vec![format!("Participation in {} recording(s)", removed)]
+            } else {
+                Vec::new()
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Turns recording off for a deleted voice channel, returning a description of the change.
+    pub async fn forget_channel(&self, guild_id: u64, channel_id: u64) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            let configured = db
+                .channels
+                .get(&guild_id)
+                .is_some_and(|channel| channel.voice_channel_id == channel_id);
+            if configured {
+                db.channels.remove(&guild_id);
+                Ok(vec!["Recording disabled".to_string()])
+            } else {
+                Ok(Vec::new())
+            }
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -12,4 +98,26 @@ pub struct RecordingChannel {
     pub voice_channel_id: u64,
     pub is_recording: bool,
     pub last_activity: Option<chrono::DateTime<chrono::Utc>>,
+    pub webhook: Option<RecordingWebhook>,
+    /// Whether finished recordings are summarized, when the bot has a summary endpoint.
+    pub summaries: bool,
+}
+
+/// A finished recording, listed by `/recording recent` and `GET /api/recordings`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordingSession {
+    pub voice_channel_id: u64,
+    pub title: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    /// Members heard during the recording.
+    pub participants: Vec<u64>,
+    /// Link to the message announcing the recording stopped.
+    pub link: Option<String>,
+}
+
+impl RecordingSession {
+    pub fn duration(&self) -> chrono::Duration {
+        self.ended_at - self.started_at
+    }
 }
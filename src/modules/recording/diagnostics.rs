@@ -0,0 +1,147 @@
+use dashmap::DashMap;
+use std::{collections::HashMap, fmt, sync::Mutex};
+
+/// Songbird delivers one voice tick every 20ms.
+const TICK_MS: u64 = 20;
+
+/// Per-SSRC receive counters. A tick where the user was speaking but no packet arrived
+/// is counted as lost (network side); a packet that arrived but failed to decode is a
+/// decode failure (bot side).
+#[derive(Debug, Default, Clone)]
+struct StreamStats {
+    ticks: u64,
+    lost: u64,
+    decode_failures: u64,
+    current_gap: u64,
+    longest_gap: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct ReceiveDiagnostics {
+    streams: Mutex<HashMap<u32, StreamStats>>,
+    users: DashMap<u32, u64>,
+}
+
+impl ReceiveDiagnostics {
+    pub fn record_user(&self, ssrc: u32, user_id: u64) {
+        self.users.insert(ssrc, user_id);
+    }
+
+    pub fn record_tick(&self, ssrc: u32, has_packet: bool, decoded: bool) {
+        let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = streams.entry(ssrc).or_default();
+
+        stats.ticks += 1;
+        if !has_packet {
+            stats.lost += 1;
+        } else if !decoded {
+            stats.decode_failures += 1;
+        }
+
+        if has_packet && decoded {
+            stats.current_gap = 0;
+        } else {
+            stats.current_gap += 1;
+            stats.longest_gap = stats.longest_gap.max(stats.current_gap);
+        }
+    }
+
+    /// Users heard so far, each once.
+    pub fn user_ids(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.users.iter().map(|entry| *entry.value()).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    pub fn summary(&self) -> DiagnosticsSummary {
+        let streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut users: Vec<UserSummary> = streams
+            .iter()
+            .map(|(ssrc, stats)| UserSummary {
+                user_id: self.users.get(ssrc).map(|id| *id),
+                ssrc: *ssrc,
+                ticks: stats.ticks,
+                lost: stats.lost,
+                decode_failures: stats.decode_failures,
+                longest_gap_ms: stats.longest_gap * TICK_MS,
+            })
+            .collect();
+        users.sort_by(|a, b| (b.lost + b.decode_failures).cmp(&(a.lost + a.decode_failures)));
+
+        DiagnosticsSummary { users }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UserSummary {
+    pub user_id: Option<u64>,
+    pub ssrc: u32,
+    pub ticks: u64,
+    pub lost: u64,
+    pub decode_failures: u64,
+    pub longest_gap_ms: u64,
+}
+
+impl UserSummary {
+    fn loss_percent(&self) -> f64 {
+        if self.ticks == 0 {
+            return 0.0;
+        }
+        self.lost as f64 / self.ticks as f64 * 100.0
+    }
+}
+
+impl fmt::Display for UserSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.user_id {
+            Some(id) => write!(f, "<@{}>", id)?,
+            None => write!(f, "SSRC {}", self.ssrc)?,
+        }
+        write!(
+            f,
+            ": {} lost ({:.1}%), {} decode failures, longest gap {}ms",
+            self.lost,
+            self.loss_percent(),
+            self.decode_failures,
+            self.longest_gap_ms
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiagnosticsSummary {
+    pub users: Vec<UserSummary>,
+}
+
+impl DiagnosticsSummary {
+    pub fn has_issues(&self) -> bool {
+        self.users.iter().any(|u| u.lost > 0 || u.decode_failures > 0)
+    }
+
+    /// Rough attribution of the session's audio problems.
+    pub fn likely_cause(&self) -> &'static str {
+        let lost: u64 = self.users.iter().map(|u| u.lost).sum();
+        let decode: u64 = self.users.iter().map(|u| u.decode_failures).sum();
+        match (lost, decode) {
+            (0, 0) => "none",
+            (lost, decode) if lost >= decode => "network (packets never arrived)",
+            _ => "bot-side (packets arrived but failed to decode)",
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticsSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.has_issues() {
+            return write!(f, "📶 No packet loss or decode gaps.");
+        }
+
+        writeln!(f, "📶 **Receive diagnostics** — likely cause: {}", self.likely_cause())?;
+        for user in self.users.iter().filter(|u| u.lost > 0 || u.decode_failures > 0) {
+            writeln!(f, "• {}", user)?;
+        }
+        Ok(())
+    }
+}
@@ -1,53 +1,81 @@
-use std::{num::NonZero, sync::{Arc, atomic::AtomicBool}};
+use std::{num::NonZero, path::PathBuf, sync::Arc};
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use dashmap::DashMap;
-use poise::serenity_prelude::{ChannelId, Context, CreateMessage, FullEvent};
+use poise::serenity_prelude::{
+    ChannelId, Context, CreateAttachment, CreateMessage, FullEvent, Message, PremiumTier,
+};
 use songbird::{
     events::{EventContext, EventHandler as VoiceEventHandler}, 
     id::{ChannelId as SongbirdChannelId, GuildId as SongbirdGuildId}, 
     input::{codecs::*, Input}, 
     model::{id::UserId, payload::Speaking}, 
     tracks::Track, 
-    Call, CoreEvent, Event
+    CoreEvent, Event
 };
 use tokio::sync::Mutex;
-use tracing::{error, info};
+use tracing::{debug, error, info};
 use crate::{
+    config::LiveConfig,
+    modules::toggles::database::Module,
     database::Database,
     events::{self, EventHandler},
 };
-use super::database::{RecordingDatabase, RecordingChannel};
+use super::database::{RecordingDatabase, RecordingChannel, RecordingSession};
+use super::diagnostics::ReceiveDiagnostics;
+use super::storage;
+use super::summary;
+use super::webhook::{self, RecordingEvent};
+
+/// Decoded samples per 20ms tick and channel.
+const SAMPLES_PER_TICK: usize = (storage::SAMPLE_RATE / 50) as usize;
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 struct RecordingReceiver {
     inner: Arc<InnerReceiver>,
 }
 
+#[derive(Debug)]
 struct InnerReceiver {
-    last_tick_was_empty: AtomicBool,
     known_ssrcs: DashMap<u32, UserId>,
-    buffer: Arc<Mutex<Vec<f32>>>,
+    /// Where the mixed audio goes; `None` once writing has failed.
+    writer: Mutex<Option<storage::WavWriter>>,
+    diagnostics: Arc<ReceiveDiagnostics>,
 }
 
 impl InnerReceiver {
-    fn convert_samples(samples: &[i16]) -> Vec<f32> {
-        samples.iter()
-            .map(|&s| (s as f32) / (i16::MAX as f32))
+    /// Mixes the stereo voice of everyone speaking in a tick down to one mono frame.
+    /// Ticks where nobody spoke become silence, so the recording keeps real time.
+    fn mix<'a>(voices: impl Iterator<Item = &'a Vec<i16>>) -> Vec<i16> {
+        let mut frame = vec![0i32; SAMPLES_PER_TICK];
+        for voice in voices {
+            for (sample, pair) in frame.iter_mut().zip(voice.chunks(2)) {
+                *sample += pair.iter().map(|&s| i32::from(s)).sum::<i32>() / pair.len() as i32;
+            }
+        }
+        frame
+            .into_iter()
+            .map(|s| s.clamp(i16::MIN.into(), i16::MAX.into()) as i16)
             .collect()
     }
 }
 
 impl RecordingReceiver {
-    fn new() -> Self {
+    fn new(writer: storage::WavWriter, diagnostics: Arc<ReceiveDiagnostics>) -> Self {
         Self {
             inner: Arc::new(InnerReceiver {
-                last_tick_was_empty: AtomicBool::default(),
                 known_ssrcs: DashMap::new(),
-                buffer: Arc::new(Mutex::new(Vec::new())),
+                writer: Mutex::new(Some(writer)),
+                diagnostics,
             }),
         }
     }
+
+    /// Stops writing and completes the file, returning its path and size.
+    async fn finish(&self) -> Option<std::io::Result<(PathBuf, u64)>> {
+        let writer = self.inner.writer.lock().await.take()?;
+        Some(writer.finish())
+    }
 }
 
 #[async_trait]
@@ -57,24 +85,25 @@ impl VoiceEventHandler for RecordingReceiver {
             EventContext::SpeakingStateUpdate(Speaking { speaking: _, ssrc, user_id, .. }) => {
                 if let Some(user) = user_id {
                     self.inner.known_ssrcs.insert(*ssrc, *user);
+                    self.inner.diagnostics.record_user(*ssrc, user.0);
                 }
             },
             EventContext::VoiceTick(tick) => {
-                let speaking = tick.speaking.len();
-                if speaking > 0 {
-                    for (_ssrc, data) in &tick.speaking {
-                        if let Some(decoded_voice) = data.decoded_voice.as_ref() {
-                            let mut buffer = self.inner.buffer.lock().await;
-                            buffer.extend(InnerReceiver::convert_samples(decoded_voice));
-                        }
-                    }
-                } else if !tick.speaking.is_empty() {
-                    // Process accumulated audio when no one is speaking
-                    let buffer = self.inner.buffer.lock().await;
-                    if !buffer.is_empty() {
-                        info!("Received {} samples of audio data", buffer.len());
-                        // TODO: Save audio data to file
-                    }
+                for (ssrc, data) in &tick.speaking {
+                    self.inner.diagnostics.record_tick(
+                        *ssrc,
+                        data.packet.is_some(),
+                        data.decoded_voice.is_some(),
+                    );
+                }
+
+                let frame = InnerReceiver::mix(
+                    tick.speaking.values().filter_map(|data| data.decoded_voice.as_ref()),
+                );
+                let mut writer = self.inner.writer.lock().await;
+                if let Some(Err(e)) = writer.as_mut().map(|w| w.write(&frame)) {
+                    error!("Failed to write recording, the rest is lost: {}", e);
+                    *writer = None;
                 }
             },
             _ => {},
@@ -86,11 +115,20 @@ impl VoiceEventHandler for RecordingReceiver {
 #[derive(Debug)]
 pub struct RecordingHandler {
     db: Database<RecordingDatabase>,
+    http: reqwest::Client,
+    config: Arc<LiveConfig>,
+    /// Receivers of each guild's in-progress recording.
+    receivers: Arc<DashMap<u64, RecordingReceiver>>,
 }
 
 impl RecordingHandler {
-    pub fn new(db: Database<RecordingDatabase>) -> Self {
-        Self { db }
+    pub fn new(db: Database<RecordingDatabase>, config: Arc<LiveConfig>) -> Self {
+        Self {
+            db,
+            http: reqwest::Client::new(),
+            config,
+            receivers: Arc::new(DashMap::new()),
+        }
     }
 
     async fn create_track(bytes: Vec<u8>) -> Result<Track, Box<dyn std::error::Error + Send + Sync>> {
@@ -134,221 +172,421 @@ impl RecordingHandler {
         }
     }
 
-    async fn notify_channel(&self, ctx: &Context, channel: &RecordingChannel, msg: &str) {
+    /// Posts `msg` in the voice channel's chat, returning the message if it was sent.
+    async fn notify_channel(
+        &self,
+        ctx: &Context,
+        channel: &RecordingChannel,
+        msg: &str,
+    ) -> Option<Message> {
+        self.post(ctx, channel, CreateMessage::new().content(msg)).await
+    }
+
+    async fn post(
+        &self,
+        ctx: &Context,
+        channel: &RecordingChannel,
+        message: CreateMessage,
+    ) -> Option<Message> {
         let voice_channel = ChannelId::from(channel.voice_channel_id);
         if let Ok(channel) = voice_channel.to_channel(&ctx).await {
             if let Some(text_id) = channel.guild().and_then(|c| Some(c.id)) {
-                if let Err(e) = text_id.say(&ctx.http, msg).await {
-                    error!("Failed to send notification: {}", e);
+                match text_id.send_message(&ctx.http, message).await {
+                    Ok(message) => return Some(message),
+                    Err(e) => error!("Failed to send notification: {}", e),
                 }
             }
         }
+        None
     }
 
-    async fn handle_recording_stop(&self, ctx: &Context, channel: &RecordingChannel, handler_lock: Arc<Mutex<Call>>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let mut handler = handler_lock.lock().await;
-        
-        let receiver = RecordingReceiver::new();
-        handler.remove_all_global_events();
-        handler.add_global_event(CoreEvent::SpeakingStateUpdate.into(), receiver.clone());
-        handler.add_global_event(CoreEvent::VoiceTick.into(), receiver.clone());
-        
-        // Get text channel from voice channel
-        let voice_channel = ChannelId::from(channel.voice_channel_id);
-        if let Ok(channel) = voice_channel.to_channel(&ctx).await {
-            if let Some(text_id) = channel.guild().and_then(|c| c.parent_id) {
-                text_id.send_message(&ctx.http, CreateMessage::default().content("🔄 Uploading recording...")).await?;
+    /// Keeps the finished recording for `/recording recent` and the HTTP API.
+    async fn record_session(
+        &self,
+        ctx: &Context,
+        channel: &RecordingChannel,
+        started_at: Option<DateTime<Utc>>,
+        participants: Vec<u64>,
+        announcement: Option<&Message>,
+    ) -> Option<RecordingSession> {
+        let started_at = started_at?;
+        let name = ChannelId::from(channel.voice_channel_id)
+            .to_channel(&ctx)
+            .await
+            .ok()
+            .and_then(|c| c.guild())
+            .map(|c| c.name)
+            .unwrap_or_else(|| "Recording".to_string());
+
+        let session = RecordingSession {
+            voice_channel_id: channel.voice_channel_id,
+            title: format!("{} on {}", name, started_at.format("%Y-%m-%d %H:%M UTC")),
+            started_at,
+            ended_at: Utc::now(),
+            participants,
+            link: announcement.map(|message| message.link()),
+        };
+        if let Err(e) = self.db.record_session(channel.guild_id, session.clone()).await {
+            error!("Failed to save recording session in guild {}: {}", channel.guild_id, e);
+        }
+        Some(session)
+    }
+
+    /// Transcribes and summarizes the recording at `path` in the background and replies to
+    /// its announcement with the summary.
+    fn post_summary(&self, ctx: &Context, path: PathBuf, title: String, announcement: Message) {
+        let config = self.config.get().recording.clone();
+        if !summary::is_configured(&config) {
+            return;
+        }
+        let http = self.http.clone();
+        let discord = ctx.http.clone();
+
+        tokio::spawn(async move {
+            let transcript = match summary::transcribe(&http, &config, &path).await {
+                Some(Ok(transcript)) if !transcript.trim().is_empty() => transcript,
+                Some(Ok(_)) => {
+                    debug!("Transcript of {} is empty, not summarizing it", path.display());
+                    return;
+                }
+                Some(Err(e)) => {
+                    error!("Failed to transcribe {}: {}", path.display(), e);
+                    return;
+                }
+                None => return,
             };
+            match summary::summarize(&http, &config, &title, &transcript).await {
+                Some(Ok(summary)) => {
+                    if let Err(e) = announcement.reply(&discord, summary.render()).await {
+                        error!("Failed to post summary of {}: {}", title, e);
+                    }
+                }
+                Some(Err(e)) => error!("Failed to summarize {}: {}", title, e),
+                None => {}
+            }
+        });
+    }
+
+    async fn warn_if_low_on_space(&self, ctx: &Context, channel: &RecordingChannel) {
+        match storage::usage().await {
+            Ok(usage) if usage.is_low_on_space() => {
+                self.notify_channel(
+                    ctx,
+                    channel,
+                    &format!(
+                        "⚠️ Recording disk is {:.0}% full - recordings may fail once it runs out of space.",
+                        usage.disk_used_percent()
+                    ),
+                )
+                .await;
+            }
+            Ok(_) => {}
+            Err(e) => error!("Failed to check recording storage: {}", e),
         }
-        
+    }
+
+    async fn start_recording(
+        &self,
+        ctx: &Context,
+        mut channel: RecordingChannel,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let manager = songbird::get(ctx).await.expect("Songbird not initialized");
+        let guild_id = SongbirdGuildId(NonZero::new(channel.guild_id).unwrap());
+        let channel_id = SongbirdChannelId(NonZero::new(channel.voice_channel_id).unwrap());
+
+        let started_at = Utc::now();
+        let path = storage::recording_path(channel.guild_id, started_at);
+        let writer = match storage::WavWriter::create(path) {
+            Ok(writer) => writer,
+            Err(e) => {
+                error!("Failed to create recording file in guild {}: {}", channel.guild_id, e);
+                self.notify_channel(ctx, &channel, "❌ Couldn't start recording: the audio file can't be written.").await;
+                return Ok(());
+            }
+        };
+
+        let Ok(handler_lock) = manager.join(guild_id, channel_id).await else {
+            return Ok(());
+        };
+        channel.is_recording = true;
+        channel.last_activity = Some(started_at);
+
+        // Update database
+        self.db.transaction(|data| {
+            data.channels.insert(channel.guild_id, channel.clone());
+            Ok(())
+        }).await?;
+
+        self.play_intro_sounds(ctx, &channel).await;
+
+        // Start recording
+        let receiver = RecordingReceiver::new(writer, Arc::default());
+        self.receivers.insert(channel.guild_id, receiver.clone());
+        {
+            let mut handler = handler_lock.lock().await;
+            handler.add_global_event(CoreEvent::SpeakingStateUpdate.into(), receiver.clone());
+            handler.add_global_event(CoreEvent::VoiceTick.into(), receiver);
+        }
+
+        self.notify_channel(ctx, &channel, "🎙️ Recording started").await;
+        webhook::emit(&self.http, &channel, RecordingEvent::RecordingStarted, channel.last_activity);
+        self.warn_if_low_on_space(ctx, &channel).await;
         Ok(())
     }
 
-    async fn handle(
+    async fn stop_recording(
         &self,
-        ctx: &Context, 
-        event: &FullEvent,
+        ctx: &Context,
+        mut channel: RecordingChannel,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        match event {
-            FullEvent::VoiceStateUpdate { old, new } => {
-                // Check if this is for a recording channel
-                let channel = self.db.read(|data| {
-                    data.channels.values()
-                        .find(|c| c.voice_channel_id == new.channel_id.map(|c| c.get()).unwrap_or(0))
-                        .cloned()
-                }).await;
-
-                if let Some(mut channel) = channel {
-                    let manager = songbird::get(ctx).await.expect("Songbird not initialized");
-                    
-                    match (old, new) {
-                        // User joined - when going from no channel to a channel
-                        (vs_old, vs_new) if vs_new.channel_id.is_some() && vs_old.as_ref().and_then(|s| s.channel_id).is_none() => {
-                            if !channel.is_recording {
-                                let guild_id = SongbirdGuildId(NonZero::new(channel.guild_id).unwrap());
-                                let channel_id = SongbirdChannelId(NonZero::new(channel.voice_channel_id).unwrap());
-
-                                if let Some(handler_lock) = manager.join(guild_id, channel_id).await.ok() {
-                                    channel.is_recording = true;
-                                    channel.last_activity = Some(Utc::now());
-                                    
-                                    // Update database
-                                    self.db.transaction(|data| {
-                                        data.channels.insert(channel.guild_id, channel.clone());
-                                        Ok(())
-                                    }).await?;
-                                    
-                                    self.play_intro_sounds(ctx, &channel).await;
-                                    
-                                    // Start recording
-                                    let mut handler = handler_lock.lock().await;
-                                    let receiver = RecordingReceiver::new();
-                                    handler.add_global_event(CoreEvent::SpeakingStateUpdate.into(), receiver.clone());
-                                    handler.add_global_event(CoreEvent::VoiceTick.into(), receiver);
-                                    
-                                    self.notify_channel(ctx, &channel, "🎙️ Recording started").await;
-                                }
-                            }
-                        },
-                        // User left - when going from a channel to no channel
-                        (vs_old, vs_new) if vs_old.as_ref().and_then(|s| s.channel_id).is_some() && vs_new.channel_id.is_none() => {
-                            // Extract users count before await
-                            let users_in_channel = if let Some(guild) = ctx.cache.guild(channel.guild_id) {
-                                guild.voice_states.values()
-                                    .filter(|state| state.channel_id == Some(channel.voice_channel_id.into()))
-                                    .count()
-                            } else {
-                                0
-                            };
-                            
-                            if users_in_channel == 0 && channel.is_recording {
-                                let guild_id = SongbirdGuildId(NonZero::new(channel.guild_id).unwrap());
-                                if let Some(handler_lock) = manager.get(guild_id) {
-                                    // Handle recording stop and upload
-                                    if let Err(e) = self.handle_recording_stop(ctx, &channel, handler_lock).await {
-                                        error!("Failed to handle recording stop: {}", e);
-                                    }
-                                    
-                                    manager.remove(guild_id).await?;
-                                    
-                                    channel.is_recording = false;
-                                    channel.last_activity = Some(Utc::now());
-                                    
-                                    // Update database
-                                    self.db.transaction(|data| {
-                                        data.channels.insert(channel.guild_id, channel.clone());
-                                        Ok(())
-                                    }).await?;
-                                    
-                                    self.notify_channel(ctx, &channel, "⏹️ Recording stopped").await;
-                                }
-                            }
-                        },
-                        _ => {}
+        let manager = songbird::get(ctx).await.expect("Songbird not initialized");
+        let guild_id = SongbirdGuildId(NonZero::new(channel.guild_id).unwrap());
+
+        let started_at = channel.last_activity;
+        if let Some(handler_lock) = manager.get(guild_id) {
+            handler_lock.lock().await.remove_all_global_events();
+            webhook::emit(&self.http, &channel, RecordingEvent::RecordingStopped, started_at);
+            manager.remove(guild_id).await?;
+        }
+
+        channel.is_recording = false;
+        channel.last_activity = Some(Utc::now());
+
+        // Update database
+        self.db.transaction(|data| {
+            data.channels.insert(channel.guild_id, channel.clone());
+            Ok(())
+        }).await?;
+
+        let receiver = self
+            .receivers
+            .remove(&channel.guild_id)
+            .map(|(_, receiver)| receiver);
+        let diagnostics = receiver.as_ref().map(|r| r.inner.diagnostics.clone());
+        let participants = diagnostics
+            .as_ref()
+            .map(|diagnostics| diagnostics.user_ids())
+            .unwrap_or_default();
+        let mut text = match diagnostics.map(|d| d.summary()) {
+            Some(summary) => {
+                info!("Recording in guild {} ended: {}", channel.guild_id, summary.to_string().trim_end());
+                format!("⏹️ Recording stopped\n{}", summary)
+            }
+            None => "⏹️ Recording stopped".to_string(),
+        };
+
+        let file = match &receiver {
+            Some(receiver) => receiver.finish().await,
+            None => None,
+        };
+        let saved = match &file {
+            Some(Ok((path, _))) => Some(path.clone()),
+            _ => None,
+        };
+        let attachment = match file {
+            Some(Ok((path, size))) if size <= upload_limit(ctx, channel.guild_id) => {
+                match CreateAttachment::path(&path).await {
+                    Ok(attachment) => Some(attachment),
+                    Err(e) => {
+                        error!("Failed to read recording {}: {}", path.display(), e);
+                        None
                     }
                 }
-            },
-            _ => {}
+            }
+            Some(Ok((path, size))) => {
+                text.push_str(&format!(
+                    "\n💾 Saved as `{}` ({:.1} MB), too large to upload here.",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    size as f64 / 1_000_000.0
+                ));
+                None
+            }
+            Some(Err(e)) => {
+                error!("Failed to save recording in guild {}: {}", channel.guild_id, e);
+                text.push_str("\n⚠️ The audio couldn't be saved.");
+                None
+            }
+            None => None,
+        };
+
+        // Only a posted file counts as uploaded; fall back to the text if Discord refuses it
+        let uploaded = attachment.is_some();
+        let message = CreateMessage::new().content(&text).add_files(attachment);
+        let announcement = match self.post(ctx, &channel, message).await {
+            Some(announcement) => {
+                if uploaded {
+                    webhook::emit(&self.http, &channel, RecordingEvent::UploadComplete, started_at);
+                }
+                Some(announcement)
+            }
+            None if uploaded => self.notify_channel(ctx, &channel, &text).await,
+            None => None,
+        };
+        let session = self
+            .record_session(ctx, &channel, started_at, participants, announcement.as_ref())
+            .await;
+
+        if let (true, Some(path), Some(session), Some(announcement)) =
+            (channel.summaries, saved, session, announcement)
+        {
+            self.post_summary(ctx, path, session.title, announcement);
         }
-        
         Ok(())
     }
 }
 
+/// Largest file the guild accepts, which grows with its boost level.
+fn upload_limit(ctx: &Context, guild_id: u64) -> u64 {
+    const MB: u64 = 1024 * 1024;
+    let tier = ctx.cache.guild(guild_id).map(|guild| guild.premium_tier);
+    match tier {
+        Some(PremiumTier::Tier2) => 50 * MB,
+        Some(PremiumTier::Tier3) => 100 * MB,
+        _ => 10 * MB,
+    }
+}
+
+/// What a voice state update does to a guild's recording.
+#[derive(Debug)]
+enum Transition {
+    Start(RecordingChannel),
+    Stop(RecordingChannel),
+}
+
+/// Decides what a member moving from `old` to `new` (voice channel ids, `None` when not
+/// in voice) does to the recording in `guild_id`. `members_in` counts the members other
+/// than the bot still in a voice channel.
+fn transition(
+    db: &RecordingDatabase,
+    guild_id: u64,
+    old: Option<u64>,
+    new: Option<u64>,
+    members_in: impl FnOnce(u64) -> usize,
+) -> Option<Transition> {
+    let channel = db.channels.get(&guild_id)?;
+    let voice = Some(channel.voice_channel_id);
+    if old == new {
+        // Mute, deafen and stream toggles
+        return None;
+    }
+
+    if new == voice && !channel.is_recording {
+        Some(Transition::Start(channel.clone()))
+    } else if old == voice && channel.is_recording && members_in(channel.voice_channel_id) == 0 {
+        // Leaving or moving to another channel both count as leaving
+        Some(Transition::Stop(channel.clone()))
+    } else {
+        None
+    }
+}
+
 #[async_trait]
 impl events::EventHandler for RecordingHandler {
     fn name(&self) -> &str {
         "Recording"
     }
-    
+
+    fn module(&self) -> Option<Module> {
+        Some(Module::Recording)
+    }
+
     async fn handle(
         &self,
-        ctx: &Context, 
+        ctx: &Context,
         event: &FullEvent,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        match event {
-            FullEvent::VoiceStateUpdate { old, new } => {
-                // Check if this is for a recording channel
-                let channel = self.db.read(|data| {
-                    data.channels.values()
-                        .find(|c| c.voice_channel_id == new.channel_id.map(|c| c.get()).unwrap_or(0))
-                        .cloned()
-                }).await;
-
-                if let Some(mut channel) = channel {
-                    let manager = songbird::get(ctx).await.expect("Songbird not initialized");
-                    
-                    match (old, new) {
-                        // User joined - when going from no channel to a channel
-                        (vs_old, vs_new) if vs_new.channel_id.is_some() && vs_old.as_ref().and_then(|s| s.channel_id).is_none() => {
-                            if !channel.is_recording {
-                                let guild_id = SongbirdGuildId(NonZero::new(channel.guild_id).unwrap());
-                                let channel_id = SongbirdChannelId(NonZero::new(channel.voice_channel_id).unwrap());
-
-                                if let Some(handler_lock) = manager.join(guild_id, channel_id).await.ok() {
-                                    channel.is_recording = true;
-                                    channel.last_activity = Some(Utc::now());
-                                    
-                                    // Update database
-                                    self.db.transaction(|data| {
-                                        data.channels.insert(channel.guild_id, channel.clone());
-                                        Ok(())
-                                    }).await?;
-                                    
-                                    self.play_intro_sounds(ctx, &channel).await;
-                                    
-                                    // Start recording
-                                    self.notify_channel(ctx, &channel, "🎙️ Recording started").await;
-                                }
-                            }
-                        },
-                        // User left - when going from a channel to no channel
-                        (vs_old, vs_new) if vs_old.as_ref().and_then(|s| s.channel_id).is_some() && vs_new.channel_id.is_none() => {
-                            // Extract users count before await
-                            let users_in_channel = if let Some(guild) = ctx.cache.guild(channel.guild_id) {
-                                guild.voice_states.values()
-                                    .filter(|state| state.channel_id == Some(channel.voice_channel_id.into()))
-                                    .count()
-                            } else {
-                                0
-                            };
-                            
-                            if users_in_channel == 0 && channel.is_recording {
-                                let guild_id = SongbirdGuildId(NonZero::new(channel.guild_id).unwrap());
-                                if let Some(handler_lock) = manager.get(guild_id) {
-                                    // Handle recording stop and upload
-                                    if let Err(e) = self.handle_recording_stop(ctx, &channel, handler_lock).await {
-                                        error!("Failed to handle recording stop: {}", e);
-                                    }
-                                    
-                                    manager.remove(guild_id).await?;
-                                    
-                                    channel.is_recording = false;
-                                    channel.last_activity = Some(Utc::now());
-                                    
-                                    // Update database
-                                    self.db.transaction(|data| {
-                                        data.channels.insert(channel.guild_id, channel.clone());
-                                        Ok(())
-                                    }).await?;
-                                    
-                                    self.notify_channel(ctx, &channel, "⏹️ Recording stopped").await;
-                                }
-                            }
-                        },
-                        _ => {}
-                    }
-                }
-            },
-            _ => {}
+        let FullEvent::VoiceStateUpdate { old, new } = event else {
+            return Ok(());
+        };
+        // The bot's own joins and leaves follow from the recording, not the other way round
+        let bot_id = ctx.cache.current_user().id;
+        let Some(guild_id) = new.guild_id.filter(|_| new.user_id != bot_id) else {
+            return Ok(());
+        };
+
+        // Voice states in the cache already reflect this update
+        let members_in = |voice_channel_id: u64| {
+            ctx.cache.guild(guild_id).map_or(0, |guild| {
+                guild
+                    .voice_states
+                    .values()
+                    .filter(|state| state.channel_id == Some(voice_channel_id.into()))
+                    .filter(|state| state.user_id != bot_id)
+                    .count()
+            })
+        };
+        let old_channel = old.as_ref().and_then(|state| state.channel_id).map(|id| id.get());
+        let new_channel = new.channel_id.map(|id| id.get());
+        let transition = self
+            .db
+            .read(|db| transition(db, guild_id.get(), old_channel, new_channel, members_in))
+            .await;
+
+        match transition {
+            Some(Transition::Start(channel)) => self.start_recording(ctx, channel).await,
+            Some(Transition::Stop(channel)) => self.stop_recording(ctx, channel).await,
+            None => Ok(()),
         }
-        
-        Ok(())
     }
 
     fn box_clone(&self) -> Box<dyn EventHandler> {
         Box::new(Self {
-            db: self.db.clone()
+            db: self.db.clone(),
+            http: self.http.clone(),
+            config: self.config.clone(),
+            receivers: self.receivers.clone(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn recording_db(is_recording: bool) -> RecordingDatabase {
+        let channel = RecordingChannel {
+            guild_id: 1,
+            voice_channel_id: 10,
+            is_recording,
+            last_activity: None,
+            webhook: None,
+            summaries: false,
+        };
+        RecordingDatabase {
+            channels: HashMap::from([(1, channel)]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn last_member_leaving_stops_the_recording() {
+        let db = recording_db(true);
+
+        let left = transition(&db, 1, Some(10), None, |_| 0);
+        assert!(matches!(left, Some(Transition::Stop(channel)) if channel.voice_channel_id == 10));
+
+        let moved = transition(&db, 1, Some(10), Some(11), |_| 0);
+        assert!(matches!(moved, Some(Transition::Stop(_))));
+
+        let others_stay = transition(&db, 1, Some(10), None, |_| 2);
+        assert!(others_stay.is_none());
+    }
+
+    #[test]
+    fn joining_starts_the_recording() {
+        let db = recording_db(false);
+
+        assert!(matches!(transition(&db, 1, None, Some(10), |_| 1), Some(Transition::Start(_))));
+        assert!(matches!(transition(&db, 1, Some(11), Some(10), |_| 1), Some(Transition::Start(_))));
+        assert!(transition(&db, 1, None, Some(11), |_| 1).is_none());
+        assert!(transition(&db, 2, None, Some(10), |_| 1).is_none());
+    }
+
+    #[test]
+    fn voice_toggles_change_nothing() {
+        let db = recording_db(true);
+
+        assert!(transition(&db, 1, Some(10), Some(10), |_| 0).is_none());
+    }
+}
@@ -0,0 +1,23 @@
+use crate::metrics::MetricsRegistry;
+
+use super::storage;
+
+pub fn register(registry: &MetricsRegistry) {
+    registry.register_global_gauge(
+        "recording_storage_bytes",
+        "Disk space used by saved recordings (use the Bytes type)",
+        |_| async { storage::usage().await.map(|usage| usage.recordings_bytes as f64) },
+    );
+
+    registry.register_global_gauge(
+        "recording_disk_free_bytes",
+        "Free space left on the recordings disk (use the Bytes type)",
+        |_| async { storage::usage().await.map(|usage| usage.disk_free_bytes as f64) },
+    );
+
+    registry.register_global_gauge(
+        "recording_disk_used_percent",
+        "How full the recordings disk is, in percent",
+        |_| async { storage::usage().await.map(|usage| usage.disk_used_percent()) },
+    );
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::database::{RecordingChannel, RecordingDatabase};
+use crate::database::{decode, encode, DbError, Migration};
+
+/// The recording schema as first released, before rows were versioned. Frozen: never
+/// change these structs.
+mod v1 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct RecordingChannel {
+        pub guild_id: u64,
+        pub voice_channel_id: u64,
+        pub is_recording: bool,
+        pub last_activity: Option<chrono::DateTime<chrono::Utc>>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct RecordingDatabase {
+        pub channels: HashMap<u64, RecordingChannel>,
+    }
+}
+
+/// v1 → v2: channels gain an optional webhook, off for existing ones, and finished
+/// recordings are kept.
+pub struct V1ToV2;
+
+impl Migration for V1ToV2 {
+    fn from_version(&self) -> u32 {
+        1
+    }
+
+    fn migrate(&self, _key: &str, bytes: Vec<u8>) -> Result<Vec<u8>, DbError> {
+        let old: v1::RecordingDatabase = decode(&bytes)?;
+        let channels = old
+            .channels
+            .into_iter()
+            .map(|(guild_id, channel)| {
+                let channel = RecordingChannel {
+                    guild_id: channel.guild_id,
+                    voice_channel_id: channel.voice_channel_id,
+                    is_recording: channel.is_recording,
+                    last_activity: channel.last_activity,
+                    webhook: None,
+                    summaries: false,
+                };
+                (guild_id, channel)
+            })
+            .collect();
+        encode(&RecordingDatabase {
+            channels,
+            sessions: HashMap::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Rows;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn loads_baseline_at_current_version() {
+        let channel = v1::RecordingChannel {
+            guild_id: 1,
+            voice_channel_id: 2,
+            is_recording: true,
+            last_activity: None,
+        };
+        let baseline = v1::RecordingDatabase {
+            channels: HashMap::from([(1, channel)]),
+        };
+        let rows = BTreeMap::from([(String::new(), encode(&baseline).unwrap())]);
+
+        let rows = RecordingDatabase::upgrade(rows).unwrap();
+        let db: RecordingDatabase = decode(&rows[""]).unwrap();
+
+        let channel = &db.channels[&1];
+        assert_eq!(channel.voice_channel_id, 2);
+        assert!(channel.is_recording);
+        assert!(channel.webhook.is_none());
+        assert!(db.sessions.is_empty());
+    }
+}
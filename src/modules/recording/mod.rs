@@ -1,6 +1,13 @@
+pub mod api;
 pub mod commands;
 pub mod database;
+pub mod diagnostics;
 pub mod handler;
+pub mod metrics;
+pub mod migrations;
+pub mod storage;
+pub mod summary;
+pub mod webhook;
 
 use commands::*;
 use poise::command;
@@ -8,7 +15,7 @@ use poise::command;
 /// 🎙️ Voice channel recording
 #[command(
     slash_command,
-    subcommands("enable", "disable", "list", "toggle"),
+    subcommands("enable", "disable", "list", "toggle", "webhook", "summaries", "recent"),
     guild_only,
     required_permissions = "MANAGE_GUILD"
 )]
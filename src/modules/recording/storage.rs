@@ -0,0 +1,182 @@
+use crate::config::data_path;
+use chrono::{DateTime, Utc};
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Where finished recordings are written.
+pub fn recordings_dir() -> PathBuf {
+    data_path("recordings")
+}
+
+/// Where a guild's recording started at `started_at` is written.
+pub fn recording_path(guild_id: u64, started_at: DateTime<Utc>) -> PathBuf {
+    recordings_dir()
+        .join(guild_id.to_string())
+        .join(format!("{}.wav", started_at.format("%Y%m%d-%H%M%S")))
+}
+
+/// Rate of the audio songbird decodes, in samples per second.
+pub const SAMPLE_RATE: u32 = 48_000;
+
+/// Size of the RIFF header written before the samples.
+const WAV_HEADER_BYTES: u32 = 44;
+
+/// A mono 16-bit WAV file written as audio arrives. The sizes in its header are filled in
+/// by [`WavWriter::finish`].
+#[derive(Debug)]
+pub struct WavWriter {
+    file: BufWriter<File>,
+    path: PathBuf,
+    data_bytes: u32,
+}
+
+impl WavWriter {
+    pub fn create(path: PathBuf) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = BufWriter::new(File::create(&path)?);
+        file.write_all(&wav_header(0))?;
+        Ok(Self {
+            file,
+            path,
+            data_bytes: 0,
+        })
+    }
+
+    pub fn write(&mut self, samples: &[i16]) -> io::Result<()> {
+        let bytes = (samples.len() * 2) as u32;
+        if self.data_bytes.checked_add(bytes + WAV_HEADER_BYTES).is_none() {
+            return Err(io::Error::other("recording exceeds 4 GiB"));
+        }
+        for sample in samples {
+            self.file.write_all(&sample.to_le_bytes())?;
+        }
+        self.data_bytes += bytes;
+        Ok(())
+    }
+
+    /// Completes the header, returning the file's path and size in bytes.
+    pub fn finish(mut self) -> io::Result<(PathBuf, u64)> {
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&wav_header(self.data_bytes))?;
+        self.file.flush()?;
+        self.file.get_ref().sync_all()?;
+        Ok((self.path, u64::from(WAV_HEADER_BYTES + self.data_bytes)))
+    }
+}
+
+fn wav_header(data_bytes: u32) -> Vec<u8> {
+    let mut header = Vec::with_capacity(WAV_HEADER_BYTES as usize);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&(WAV_HEADER_BYTES - 8 + data_bytes).to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, one channel
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    // Byte rate and block alignment of 16-bit mono
+    header.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    header.extend_from_slice(&2u16.to_le_bytes());
+    header.extend_from_slice(&16u16.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_bytes.to_le_bytes());
+    header
+}
+
+/// Warn once free space on the recordings disk drops below this fraction.
+pub const LOW_DISK_THRESHOLD: f64 = 0.10;
+
+#[derive(Debug, Clone, Copy)]
+pub struct StorageUsage {
+    pub recordings_bytes: u64,
+    pub disk_free_bytes: u64,
+    pub disk_total_bytes: u64,
+}
+
+impl StorageUsage {
+    pub fn disk_used_percent(&self) -> f64 {
+        if self.disk_total_bytes == 0 {
+            return 0.0;
+        }
+        (1.0 - self.disk_free_bytes as f64 / self.disk_total_bytes as f64) * 100.0
+    }
+
+    pub fn is_low_on_space(&self) -> bool {
+        self.disk_total_bytes > 0
+            && (self.disk_free_bytes as f64 / self.disk_total_bytes as f64) < LOW_DISK_THRESHOLD
+    }
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Measures recordings size and disk space without blocking the runtime.
+pub async fn usage() -> Result<StorageUsage, String> {
+    tokio::task::spawn_blocking(|| -> Result<StorageUsage, String> {
+        let dir = recordings_dir();
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+        Ok(StorageUsage {
+            recordings_bytes: dir_size(&dir),
+            disk_free_bytes: fs2::available_space(&dir).map_err(|e| e.to_string())?,
+            disk_total_bytes: fs2::total_space(&dir).map_err(|e| e.to_string())?,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Deletes recordings last modified more than `max_age` ago, returning how many files and
+/// bytes were removed. Directories left empty are removed too.
+pub async fn prune_older_than(max_age: Duration) -> Result<(usize, u64), String> {
+    tokio::task::spawn_blocking(move || {
+        let cutoff = SystemTime::now()
+            .checked_sub(max_age)
+            .ok_or("retention period too long")?;
+        Ok(prune_dir(&recordings_dir(), cutoff))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn prune_dir(path: &Path, cutoff: SystemTime) -> (usize, u64) {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return (0, 0);
+    };
+
+    let (mut files, mut bytes) = (0, 0);
+    for entry in entries.filter_map(Result::ok) {
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            let (dir_files, dir_bytes) = prune_dir(&entry.path(), cutoff);
+            files += dir_files;
+            bytes += dir_bytes;
+            // Only succeeds once the directory is empty
+            let _ = std::fs::remove_dir(entry.path());
+        } else if meta.modified().is_ok_and(|modified| modified < cutoff)
+            && std::fs::remove_file(entry.path()).is_ok()
+        {
+            files += 1;
+            bytes += meta.len();
+        }
+    }
+    (files, bytes)
+}
@@ -0,0 +1,135 @@
+//! Summaries of finished recordings, posted next to the recording when a guild turns them
+//! on with `/recording summaries` and both `[recording] transcription_endpoint` and
+//! `summary_endpoint` are configured.
+//!
+//! The transcription endpoint receives the recording's WAV file as the request body and
+//! answers `{"text"}`. The summary endpoint receives `{"title", "transcript"}` as JSON and
+//! answers `{"summary", "action_items"}`. Both get their API key as a bearer token.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use tokio_util::io::ReaderStream;
+
+use crate::config::RecordingConfig;
+
+/// Longest summary posted, leaving room in the message for the action items.
+const MAX_SUMMARY_CHARS: usize = 1200;
+
+/// Discord's message length limit.
+const MAX_MESSAGE_CHARS: usize = 2000;
+
+/// Most action items listed.
+const MAX_ACTION_ITEMS: usize = 10;
+
+#[derive(Serialize)]
+struct SummaryRequest<'a> {
+    title: &'a str,
+    transcript: &'a str,
+}
+
+#[derive(Deserialize)]
+struct Transcript {
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Summary {
+    pub summary: String,
+    #[serde(default)]
+    pub action_items: Vec<String>,
+}
+
+impl Summary {
+    pub fn render(&self) -> String {
+        let summary = truncate(self.summary.trim(), MAX_SUMMARY_CHARS);
+        let mut text = format!("📝 **Summary**\n{}", summary);
+        let items: Vec<&str> = self
+            .action_items
+            .iter()
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .take(MAX_ACTION_ITEMS)
+            .collect();
+        if !items.is_empty() {
+            text.push_str("\n\n✅ **Action items**");
+            for item in items {
+                text.push_str(&format!("\n- {}", item));
+            }
+        }
+        truncate(&text, MAX_MESSAGE_CHARS - 1)
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Whether finished recordings can be summarized with this config.
+pub fn is_configured(config: &RecordingConfig) -> bool {
+    config.transcription_endpoint.is_some() && config.summary_endpoint.is_some()
+}
+
+/// Sends the recording at `path` to the configured transcription endpoint. `None` when no
+/// endpoint is configured.
+pub async fn transcribe(
+    client: &reqwest::Client,
+    config: &RecordingConfig,
+    path: &Path,
+) -> Option<Result<String, String>> {
+    let endpoint = config.transcription_endpoint.as_deref()?;
+
+    let result = async {
+        // Streamed from disk: an hour of audio is hundreds of megabytes
+        let file = tokio::fs::File::open(path).await.map_err(|e| e.to_string())?;
+        let length = file.metadata().await.map_err(|e| e.to_string())?.len();
+        let mut request = client
+            .post(endpoint)
+            .timeout(Duration::from_secs(config.transcription_timeout_secs))
+            .header(reqwest::header::CONTENT_TYPE, "audio/wav")
+            .header(reqwest::header::CONTENT_LENGTH, length)
+            .body(reqwest::Body::wrap_stream(ReaderStream::new(file)));
+        if let Some(key) = &config.transcription_api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+        let transcript = response.json::<Transcript>().await.map_err(|e| e.to_string())?;
+        Ok(transcript.text)
+    };
+    Some(result.await)
+}
+
+/// Asks the configured endpoint to summarize `transcript`. `None` when no endpoint is
+/// configured.
+pub async fn summarize(
+    client: &reqwest::Client,
+    config: &RecordingConfig,
+    title: &str,
+    transcript: &str,
+) -> Option<Result<Summary, String>> {
+    let endpoint = config.summary_endpoint.as_deref()?;
+
+    let mut request = client
+        .post(endpoint)
+        .timeout(Duration::from_secs(config.summary_timeout_secs))
+        .json(&SummaryRequest { title, transcript });
+    if let Some(key) = &config.summary_api_key {
+        request = request.bearer_auth(key);
+    }
+
+    let result = async {
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+        response.json::<Summary>().await.map_err(|e| e.to_string())
+    };
+    Some(result.await)
+}
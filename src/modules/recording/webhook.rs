@@ -0,0 +1,129 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use super::database::RecordingChannel;
+
+/// Header carrying `sha256=<hex hmac>` of `"{timestamp}.{body}"`.
+pub const SIGNATURE_HEADER: &str = "X-Prometheus-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Prometheus-Timestamp";
+
+const MAX_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingWebhook {
+    pub url: String,
+    pub secret: String,
+}
+
+impl RecordingWebhook {
+    pub fn new(url: String) -> Self {
+        let secret = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect();
+
+        Self { url, secret }
+    }
+
+    fn sign(&self, timestamp: i64, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(b".");
+        mac.update(body);
+
+        let digest = mac.finalize().into_bytes();
+        format!(
+            "sha256={}",
+            digest.iter().map(|b| format!("{:02x}", b)).collect::<String>()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingEvent {
+    RecordingStarted,
+    RecordingStopped,
+    UploadComplete,
+}
+
+#[derive(Debug, Serialize)]
+struct Payload {
+    event: RecordingEvent,
+    guild_id: String,
+    voice_channel_id: String,
+    timestamp: DateTime<Utc>,
+    started_at: Option<DateTime<Utc>>,
+}
+
+/// Sends `event` to the channel's webhook in the background, if one is configured.
+pub fn emit(
+    client: &reqwest::Client,
+    channel: &RecordingChannel,
+    event: RecordingEvent,
+    started_at: Option<DateTime<Utc>>,
+) {
+    let Some(hook) = channel.webhook.clone() else {
+        return;
+    };
+
+    let payload = Payload {
+        event,
+        guild_id: channel.guild_id.to_string(),
+        voice_channel_id: channel.voice_channel_id.to_string(),
+        timestamp: Utc::now(),
+        started_at,
+    };
+    let client = client.clone();
+
+    tokio::spawn(async move {
+        if let Err(e) = deliver(&client, &hook, &payload).await {
+            warn!("Recording webhook {:?} to {} failed: {}", payload.event, hook.url, e);
+        }
+    });
+}
+
+async fn deliver(
+    client: &reqwest::Client,
+    hook: &RecordingWebhook,
+    payload: &Payload,
+) -> Result<(), String> {
+    let body = serde_json::to_vec(payload).map_err(|e| e.to_string())?;
+    let timestamp = payload.timestamp.timestamp();
+    let signature = hook.sign(timestamp, &body);
+
+    let mut last_error = String::new();
+    for attempt in 1..=MAX_ATTEMPTS {
+        let result = client
+            .post(&hook.url)
+            .header("Content-Type", "application/json")
+            .header(TIMESTAMP_HEADER, timestamp)
+            .header(SIGNATURE_HEADER, &signature)
+            .timeout(Duration::from_secs(10))
+            .body(body.clone())
+            .send()
+            .await;
+
+        match result {
+            Ok(response) if response.status().is_success() => {
+                debug!("Delivered recording webhook {:?} to {}", payload.event, hook.url);
+                return Ok(());
+            }
+            Ok(response) => last_error = format!("HTTP {}", response.status()),
+            Err(e) => last_error = e.to_string(),
+        }
+
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+        }
+    }
+
+    Err(last_error)
+}
@@ -0,0 +1,169 @@
+use crate::database::{Database, Rows};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Roles to add to and remove from one member.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoleChange {
+    pub user_id: u64,
+    pub add: Vec<u64>,
+    pub remove: Vec<u64>,
+}
+
+/// A batch of role changes in one guild, applied a few at a time by the role worker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleJob {
+    pub id: u64,
+    pub guild_id: u64,
+    /// What the changes are for, shown in progress updates.
+    pub reason: String,
+    /// Changes not applied yet, in order.
+    pub pending: VecDeque<RoleChange>,
+    pub total: usize,
+    pub failed: usize,
+    /// Channel progress is posted in, if any.
+    pub progress_channel: Option<u64>,
+    /// The progress message, once posted, so later updates edit it.
+    pub progress_message: Option<u64>,
+}
+
+impl RoleJob {
+    pub fn done(&self) -> usize {
+        self.total - self.pending.len()
+    }
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct RoleJobsDatabase {
+    /// Oldest first. Jobs in the same guild run one after another.
+    pub jobs: Vec<RoleJob>,
+    pub next_id: u64,
+}
+
+impl Rows for RoleJobsDatabase {}
+
+impl Database<RoleJobsDatabase> {
+    /// Queues `changes`, returning the job's id.
+    pub async fn enqueue(
+        &self,
+        guild_id: u64,
+        reason: impl Into<String>,
+        changes: Vec<RoleChange>,
+        progress_channel: Option<u64>,
+    ) -> Result<u64, String> {
+        let reason = reason.into();
+        self.transaction(|db| {
+            db.next_id += 1;
+            db.jobs.push(RoleJob {
+                id: db.next_id,
+                guild_id,
+                reason,
+                total: changes.len(),
+                pending: changes.into(),
+                failed: 0,
+                progress_channel,
+                progress_message: None,
+            });
+            Ok(db.next_id)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// The job each guild is working through.
+    pub async fn current_jobs(&self) -> Vec<RoleJob> {
+        self.read(|db| {
+            let mut seen = std::collections::HashSet::new();
+            db.jobs
+                .iter()
+                .filter(|job| seen.insert(job.guild_id))
+                .cloned()
+                .collect()
+        })
+        .await
+    }
+
+    /// Drops the first `applied + failed` pending changes of a job, removing the job once
+    /// nothing is left. Returns the job as it is now.
+    pub async fn record_progress(
+        &self,
+        job_id: u64,
+        applied: usize,
+        failed: usize,
+        progress_message: Option<u64>,
+    ) -> Result<Option<RoleJob>, String> {
+        self.transaction(|db| {
+            let Some(index) = db.jobs.iter().position(|job| job.id == job_id) else {
+                return Ok(None);
+            };
+            let job = &mut db.jobs[index];
+            let handled = (applied + failed).min(job.pending.len());
+            job.pending.drain(..handled);
+            job.failed += failed;
+            job.progress_message = progress_message.or(job.progress_message);
+
+            let job = job.clone();
+            if job.pending.is_empty() {
+                db.jobs.remove(index);
+            }
+            Ok(Some(job))
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Drops the user's pending role changes, describing what was removed.
+    pub async fn forget_user(&self, user_id: u64) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            let mut removed = 0;
+            for job in &mut db.jobs {
+                let before = job.pending.len();
+                job.pending.retain(|change| change.user_id != user_id);
+                removed += before - job.pending.len();
+                job.total -= before - job.pending.len();
+            }
+            db.jobs.retain(|job| !job.pending.is_empty());
+            Ok(if removed > 0 {
+                vec![format!("{} pending role change(s)", removed)]
+            } else {
+                Vec::new()
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn forget_channel(
+        &self,
+        guild_id: u64,
+        channel_id: u64,
+    ) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            for job in db.jobs.iter_mut().filter(|job| job.guild_id == guild_id) {
+                if job.progress_channel == Some(channel_id) {
+                    job.progress_channel = None;
+                    job.progress_message = None;
+                }
+            }
+            // Progress messages aren't configuration, so there's nothing to report
+            Ok(Vec::new())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Stops pending changes from adding or removing a deleted role.
+    pub async fn forget_role(&self, guild_id: u64, role_id: u64) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            for job in db.jobs.iter_mut().filter(|job| job.guild_id == guild_id) {
+                for change in &mut job.pending {
+                    change.add.retain(|role| *role != role_id);
+                    change.remove.retain(|role| *role != role_id);
+                }
+            }
+            Ok(Vec::new())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,6 @@
+//! Role changes touching many members, e.g. rotating Lorax winners to alumni, are queued as
+//! jobs and applied a few at a time by [`worker::RoleJobTask`], which reports progress and
+//! picks up where it left off after a restart.
+
+pub mod database;
+pub mod worker;
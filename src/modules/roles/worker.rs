@@ -0,0 +1,148 @@
+use super::database::{RoleJob, RoleJobsDatabase};
+use crate::{database::Database, tasks::Task};
+use async_trait::async_trait;
+use poise::serenity_prelude::{
+    ChannelId, Context, CreateMessage, EditMessage, GuildId, MessageId, RoleId, UserId,
+};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Members updated per job per run.
+const CHANGES_PER_RUN: usize = 10;
+
+/// Pause between members, keeping well under Discord's member edit limits.
+const CHANGE_GAP: Duration = Duration::from_millis(500);
+
+/// Jobs smaller than this finish quickly enough that progress isn't posted.
+const PROGRESS_MIN: usize = 25;
+
+/// Applies queued role jobs, one job per guild at a time.
+#[derive(Debug, Clone)]
+pub struct RoleJobTask {
+    db: Database<RoleJobsDatabase>,
+}
+
+impl RoleJobTask {
+    pub fn new(db: Database<RoleJobsDatabase>) -> Self {
+        Self { db }
+    }
+
+    /// Applies the next few changes of a job, returning how many succeeded and failed.
+    async fn run_batch(ctx: &Context, job: &RoleJob) -> (usize, usize) {
+        let guild_id = GuildId::new(job.guild_id);
+        let reason = Some(job.reason.as_str());
+        let (mut applied, mut failed) = (0, 0);
+
+        for (i, change) in job.pending.iter().take(CHANGES_PER_RUN).enumerate() {
+            if i > 0 {
+                tokio::time::sleep(CHANGE_GAP).await;
+            }
+            let user_id = UserId::new(change.user_id);
+            let mut ok = true;
+            for role in &change.remove {
+                let result = ctx
+                    .http
+                    .remove_member_role(guild_id, user_id, RoleId::new(*role), reason)
+                    .await;
+                if let Err(e) = result {
+                    warn!("Failed to remove role {} from {}: {}", role, user_id, e);
+                    ok = false;
+                }
+            }
+            for role in &change.add {
+                let result = ctx
+                    .http
+                    .add_member_role(guild_id, user_id, RoleId::new(*role), reason)
+                    .await;
+                if let Err(e) = result {
+                    warn!("Failed to add role {} to {}: {}", role, user_id, e);
+                    ok = false;
+                }
+            }
+            if ok {
+                applied += 1;
+            } else {
+                failed += 1;
+            }
+        }
+        (applied, failed)
+    }
+
+    /// Posts or edits the job's progress message, returning its id.
+    async fn report(ctx: &Context, job: &RoleJob) -> Option<u64> {
+        let channel_id = ChannelId::new(job.progress_channel?);
+        if job.total < PROGRESS_MIN {
+            return None;
+        }
+
+        let mut content = if job.pending.is_empty() {
+            format!("✅ {}: updated roles for {} members", job.reason, job.total)
+        } else {
+            format!("🔁 {}: {}/{} members updated", job.reason, job.done(), job.total)
+        };
+        if job.failed > 0 {
+            content.push_str(&format!(" ({} failed)", job.failed));
+        }
+
+        if let Some(message_id) = job.progress_message {
+            let edit = EditMessage::new().content(&content);
+            match channel_id.edit_message(ctx, MessageId::new(message_id), edit).await {
+                Ok(_) => return Some(message_id),
+                // Deleted; post a new one
+                Err(e) => warn!("Failed to edit role job progress {}: {}", message_id, e),
+            }
+        }
+        match channel_id.send_message(ctx, CreateMessage::new().content(content)).await {
+            Ok(message) => Some(message.id.get()),
+            Err(e) => {
+                warn!("Failed to post role job progress in {}: {}", channel_id, e);
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Task for RoleJobTask {
+    fn name(&self) -> &str {
+        "RoleJobs"
+    }
+
+    fn schedule(&self) -> Option<Duration> {
+        Some(Duration::from_secs(15))
+    }
+
+    async fn execute(
+        &mut self,
+        ctx: &Context,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        for job in self.db.current_jobs().await {
+            let (applied, failed) = Self::run_batch(ctx, &job).await;
+
+            // Record before reporting so a restart never repeats finished changes
+            let Some(mut job) = self.db.record_progress(job.id, applied, failed, None).await?
+            else {
+                continue;
+            };
+            let message = Self::report(ctx, &job).await;
+            if message.is_some() && message != job.progress_message && !job.pending.is_empty() {
+                job = self
+                    .db
+                    .record_progress(job.id, 0, 0, message)
+                    .await?
+                    .unwrap_or(job);
+            }
+            if job.pending.is_empty() {
+                info!(
+                    "Role job {} in guild {} finished: {} members, {} failed",
+                    job.id, job.guild_id, job.total, job.failed
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn box_clone(&self) -> Box<dyn Task> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,279 @@
+//! Threshold alerts on stat bars, posted to the guild's admin channel. Alerts that keep
+//! firing can escalate by pinging a role and paging through a PagerDuty-compatible webhook.
+
+use super::database::{GuildSettings, StatBar};
+use crate::utils::duration::format_duration;
+use crate::utils::http;
+use poise::serenity_prelude::{ChannelId, Context, CreateAllowedMentions, CreateMessage, RoleId};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+
+/// How far back the explore link looks when an alert fires.
+const EXPLORE_RANGE_SECS: u64 = 3600;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StatAlert {
+    pub above: Option<f64>,
+    pub below: Option<f64>,
+    pub firing: bool,
+    /// Defaulted for `/settings import` files exported before alerts could escalate.
+    #[serde(default)]
+    pub firing_since: Option<SystemTime>,
+    #[serde(default)]
+    pub escalation: Option<Escalation>,
+    /// Whether the current firing has already escalated.
+    #[serde(default)]
+    pub escalated: bool,
+}
+
+/// Who else to tell when an alert keeps firing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Escalation {
+    /// How long the alert fires before escalating.
+    pub after_secs: u64,
+    /// Role pinged in the admin channel.
+    pub role_id: Option<u64>,
+    /// Events API v2 endpoint, e.g. `https://events.pagerduty.com/v2/enqueue`.
+    pub webhook_url: Option<String>,
+    /// Routing (integration) key sent with webhook events.
+    pub routing_key: Option<String>,
+}
+
+impl Escalation {
+    pub fn describe(&self) -> String {
+        let mut targets = Vec::new();
+        if let Some(role_id) = self.role_id {
+            targets.push(format!("ping <@&{}>", role_id));
+        }
+        if self.webhook_url.is_some() {
+            targets.push("page the webhook".to_string());
+        }
+        format!(
+            "after {}: {}",
+            format_duration(self.after_secs),
+            targets.join(" and ")
+        )
+    }
+}
+
+impl StatAlert {
+    pub fn is_breached(&self, value: f64) -> bool {
+        self.above.is_some_and(|limit| value > limit) || self.below.is_some_and(|limit| value < limit)
+    }
+
+    /// Copies the firing state from `other`, unless its thresholds have since changed.
+    pub fn take_state(&mut self, other: &StatAlert) {
+        if self.above == other.above && self.below == other.below {
+            self.firing = other.firing;
+            self.firing_since = other.firing_since;
+            self.escalated = other.escalated;
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match (self.above, self.below) {
+            (Some(above), Some(below)) => format!("above {} or below {}", above, below),
+            (Some(above), None) => format!("above {}", above),
+            (None, Some(below)) => format!("below {}", below),
+            (None, None) => "never".to_string(),
+        }
+    }
+}
+
+pub enum AlertChange {
+    Fired,
+    /// Still firing once the escalation delay passed.
+    Escalated,
+    Resolved { escalated: bool },
+}
+
+impl AlertChange {
+    /// Name used for the change in live updates.
+    pub fn state(&self) -> &'static str {
+        match self {
+            Self::Fired => "fired",
+            Self::Escalated => "escalated",
+            Self::Resolved { .. } => "resolved",
+        }
+    }
+}
+
+/// Updates the bar's alert state for a new value, returning a change worth announcing.
+pub fn evaluate(stat_bar: &mut StatBar, value: f64, now: SystemTime) -> Option<AlertChange> {
+    let alert = stat_bar.alert.as_mut()?;
+    let breached = alert.is_breached(value);
+
+    match (alert.firing, breached) {
+        (false, true) => {
+            alert.firing = true;
+            alert.firing_since = Some(now);
+            alert.escalated = false;
+            Some(AlertChange::Fired)
+        }
+        (true, false) => {
+            alert.firing = false;
+            alert.firing_since = None;
+            Some(AlertChange::Resolved {
+                escalated: std::mem::take(&mut alert.escalated),
+            })
+        }
+        (true, true) if !alert.escalated => {
+            let since = *alert.firing_since.get_or_insert(now);
+            let after = Duration::from_secs(alert.escalation.as_ref()?.after_secs);
+            if now.duration_since(since).unwrap_or_default() < after {
+                return None;
+            }
+            alert.escalated = true;
+            Some(AlertChange::Escalated)
+        }
+        _ => None,
+    }
+}
+
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Fills an explore URL template. `{query}` is URL-encoded, `{from}` and `{to}` are
+/// Unix milliseconds covering the last hour up to `now`.
+pub fn explore_link(template: &str, query: &str, now: u64) -> String {
+    let from = now.saturating_sub(EXPLORE_RANGE_SECS) * 1000;
+    template
+        .replace("{query}", &encode(query))
+        .replace("{from}", &from.to_string())
+        .replace("{to}", &(now * 1000).to_string())
+}
+
+/// Sends a PagerDuty Events API v2 event. Incidents are keyed by the stat bar channel, so
+/// resolving closes the incident its escalation opened.
+async fn page(escalation: &Escalation, stat_bar: &StatBar, summary: &str, action: &str) {
+    let Some(url) = &escalation.webhook_url else {
+        return;
+    };
+    let mut event = serde_json::json!({
+        "event_action": action,
+        "dedup_key": format!("pyrobot-stat-alert-{}", stat_bar.channel_id),
+        "payload": {
+            "summary": summary,
+            "source": "pyrobot",
+            "severity": "critical",
+            "custom_details": { "query": stat_bar.query },
+        },
+    });
+    if let Some(key) = &escalation.routing_key {
+        event["routing_key"] = key.as_str().into();
+    }
+
+    let client = http::client();
+    match client.send(client.post(url).json(&event)).await {
+        Ok(response) if !response.status().is_success() => {
+            warn!("Escalation webhook {} answered {}", url, response.status());
+        }
+        Ok(_) => {}
+        Err(e) => warn!("Failed to send escalation to {}: {}", url, e),
+    }
+}
+
+pub async fn notify(
+    ctx: &Context,
+    admin_channel: Option<u64>,
+    settings: &GuildSettings,
+    stat_bar: &StatBar,
+    value: f64,
+    change: AlertChange,
+) {
+    let Some(alert) = &stat_bar.alert else {
+        return;
+    };
+
+    let formatted = stat_bar.data_type.format_value(value);
+    // Paged even without an admin channel; it's the point of a webhook
+    if let Some(escalation) = &alert.escalation {
+        match change {
+            AlertChange::Escalated => {
+                let summary = format!(
+                    "Stat alert for {} is {} (alert when {})",
+                    stat_bar.format,
+                    formatted,
+                    alert.describe()
+                );
+                page(escalation, stat_bar, &summary, "trigger").await;
+            }
+            AlertChange::Resolved { escalated: true } => {
+                let summary = format!("Stat alert for {} resolved", stat_bar.format);
+                page(escalation, stat_bar, &summary, "resolve").await;
+            }
+            _ => {}
+        }
+    }
+
+    let Some(channel_id) = admin_channel else {
+        return;
+    };
+    let mut mentions = CreateAllowedMentions::new().empty_roles().empty_users();
+    let mut content = match change {
+        AlertChange::Fired => format!(
+            "🚨 **Stat alert** for <#{}>: value is **{}** (alert when {}).\nQuery: `{}`",
+            stat_bar.channel_id,
+            formatted,
+            alert.describe(),
+            stat_bar.query
+        ),
+        AlertChange::Escalated => {
+            let firing_for = alert
+                .firing_since
+                .and_then(|since| since.elapsed().ok())
+                .unwrap_or_default();
+            let mut content = format!(
+                "📟 **Stat alert escalated** for <#{}>: still **{}** after {}.",
+                stat_bar.channel_id,
+                formatted,
+                format_duration(firing_for.as_secs())
+            );
+            if let Some(role_id) = alert.escalation.as_ref().and_then(|e| e.role_id) {
+                content.push_str(&format!(" <@&{}>", role_id));
+                mentions = mentions.roles(vec![RoleId::new(role_id)]);
+            }
+            content
+        }
+        AlertChange::Resolved { .. } => format!(
+            "✅ **Stat alert resolved** for <#{}>: value is back to **{}**.",
+            stat_bar.channel_id, formatted
+        ),
+    };
+
+    // Internal metrics never reach Prometheus, so there's nothing to explore
+    if let (Some(template), AlertChange::Fired, false) = (
+        &settings.explore_url_template,
+        &change,
+        super::internal::is_internal(&stat_bar.query),
+    ) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        content.push_str(&format!(
+            "\n🔎 [Explore]({})",
+            explore_link(template, &stat_bar.query, now)
+        ));
+    }
+
+    if let Err(e) = ChannelId::new(channel_id)
+        .send_message(
+            ctx,
+            CreateMessage::new().content(content).allowed_mentions(mentions),
+        )
+        .await
+    {
+        warn!("Failed to send stat alert to channel {}: {}", channel_id, e);
+    }
+}
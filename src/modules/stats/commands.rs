@@ -1,75 +1,121 @@
-use super::database::{DataType, GuildSettings, StatBar};
+use super::alerts::{Escalation, StatAlert};
+use super::database::{DataType, StatBar, StatTarget, MIN_UPDATE_DELAY};
+use super::computed;
+use super::internal;
 use super::task::StatsTask;
-use crate::{Context, Error};
-use poise::command;
-use poise::serenity_prelude::{builder::CreateChannel, ChannelId, ChannelType};
+use crate::utils::{
+    embed,
+    reply::{defer, say, send},
+    validate,
+};
+use crate::{metrics::MetricKind, Context, Error};
+use poise::{command, CreateReply};
+use crate::utils::duration::DurationUnit;
+use poise::serenity_prelude::{builder::CreateChannel, ChannelId, ChannelType, RoleId};
+use std::time::Duration;
+
+/// Why stat bars need a given channel type, for channel validation errors.
+fn stat_bar_purpose(target: StatTarget) -> String {
+    format!("stat bars with this target show their value as the {}", target)
+}
+
+/// Internal metric names are checked when queried and computed expressions must parse;
+/// everything else must look like PromQL.
+fn validate_query(query: &str) -> Result<String, validate::Invalid> {
+    if internal::is_internal(query) {
+        Ok(query.trim().to_string())
+    } else if computed::is_computed(query) {
+        computed::parse(query).map_err(validate::Invalid)?;
+        Ok(query.trim().to_string())
+    } else {
+        validate::promql(query)
+    }
+}
+
+/// Whether the query can run without a Prometheus server. Computed expressions are let
+/// through, as their named queries may all be internal.
+fn is_bot_side(query: &str) -> bool {
+    internal::is_internal(query) || computed::is_computed(query)
+}
+
+/// The guild's Prometheus URL, falling back to the configured default.
+async fn prometheus_url(ctx: Context<'_>, guild_id: u64) -> Result<String, Error> {
+    let url = ctx.data().dbs.stats.get_settings(guild_id).await?.prometheus_url;
+    Ok(match url.as_str() {
+        "" => ctx.data().config.get().prometheus.default_url.clone(),
+        _ => url,
+    })
+}
 
 #[command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
 pub async fn set_prometheus(
     ctx: Context<'_>,
     #[description = "Prometheus server URL"] url: String,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
-
-    StatsTask::query_prometheus(&url, "up").await?;
+    let guild_id = validate::guild(ctx)?.get();
+    let url = validate::url(&url)?;
+
+    if let Err(e) = StatsTask::query_prometheus(&url, "up").await {
+        say(ctx, format!(
+            "❌ Couldn't query `{}/api/v1/query`: {}\nCheck the URL points at the Prometheus server itself (not Grafana) and that it's reachable from the bot.",
+            url, e
+        ))
+        .await?;
+        return Ok(());
+    }
 
     ctx.data()
         .dbs
         .stats
-        .transaction(|db| {
-            let mut settings = GuildSettings::default();
-            settings.prometheus_url = url;
-            db.guild_settings.insert(guild_id, settings);
-            Ok(())
-        })
+        .update_settings(guild_id, |settings| settings.prometheus_url = url)
         .await?;
 
-    ctx.say("✅ Prometheus server URL set!").await?;
+    say(ctx, "✅ Prometheus server URL set!").await?;
     Ok(())
 }
 
-/// Set a stat bar for a voice channel
+/// Set a stat bar for a voice channel name or text channel topic
 #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
 pub async fn set(
     ctx: Context<'_>,
-    #[description = "Voice channel to use"] channel: ChannelId,
-    #[description = "Prometheus query"] query: String,
+    #[description = "Channel to use"] channel: ChannelId,
+    #[description = "Prometheus query, internal:<metric> or computed:<expression>"] query: String,
     #[description = "Display format (use {value} for the value)"] format: String,
     #[description = "Value type"] data_type: DataType,
+    #[description = "Update the channel's name (default) or topic"] target: Option<StatTarget>,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
+    let target = target.unwrap_or_default();
 
-    let channel_info = channel.to_channel(&ctx.serenity_context()).await?;
-    if !matches!(channel_info.guild(), Some(c) if c.kind == ChannelType::Voice) {
-        ctx.say("❌ Please select a voice channel!").await?;
-        return Ok(());
-    }
+    validate::channel(ctx, channel, target.channel_kinds(), &stat_bar_purpose(target)).await?;
+    let query = validate_query(&query)?;
 
-    let prometheus_url = ctx
-        .data()
-        .dbs
-        .stats
-        .get_settings(guild_id)
-        .await?
-        .prometheus_url;
-    if prometheus_url.is_empty() {
-        ctx.say("❌ Please set a Prometheus server URL first using `/stats set_prometheus`!")
+    let prometheus_url = prometheus_url(ctx, guild_id).await?;
+    if prometheus_url.is_empty() && !is_bot_side(&query) {
+        say(ctx, "❌ Please set a Prometheus server URL first using `/stats set_prometheus`!")
             .await?;
         return Ok(());
     }
 
-    let _test_value = StatsTask::query_prometheus(&prometheus_url, &query).await?;
+    let data = ctx.data();
+    let _test_value =
+        StatsTask::run_query(&data.metrics, &data.dbs.stats, guild_id, &prometheus_url, &query)
+            .await?;
 
     let stat_bar = StatBar {
         channel_id: channel.get(),
         query,
         format,
         data_type,
+        target,
         last_value: None,
         last_update: None,
         error_count: 0,
         last_error: None,
         last_success: None,
+        alert: None,
+        pending: None,
+        recent_edits: Vec::new(),
     };
 
     ctx.data()
@@ -77,39 +123,54 @@ pub async fn set(
         .stats
         .update_stat_bar(guild_id, stat_bar)
         .await?;
-    ctx.say("✅ Stat bar set! The channel name will update shortly.")
+    say(ctx, format!("✅ Stat bar set! The {} will update shortly.", target))
         .await?;
     Ok(())
 }
 
-/// Create a new voice channel with a stat bar
+/// Create a new channel with a stat bar
 #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
 pub async fn create_channel(
     ctx: Context<'_>,
     #[description = "Name for the new channel"] name: String,
-    #[description = "Prometheus query"] query: String,
+    #[description = "Prometheus query, internal:<metric> or computed:<expression>"] query: String,
     #[description = "Display format (use {value} for the value)"] format: String,
     #[description = "Value type"] data_type: DataType,
     #[description = "Optional category to create the channel in"] category: Option<ChannelId>,
+    #[description = "Voice channel name (default) or text channel topic"]
+    target: Option<StatTarget>,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap();
+    let guild_id = validate::guild(ctx)?;
+    let target = target.unwrap_or_default();
 
-    let prometheus_url = ctx
-        .data()
-        .dbs
-        .stats
-        .get_settings(guild_id.get())
-        .await?
-        .prometheus_url;
-    if prometheus_url.is_empty() {
-        ctx.say("❌ Please set a Prometheus server URL first using `/stats set_prometheus`!")
+    let query = validate_query(&query)?;
+    if let Some(category) = category {
+        validate::channel(ctx, category, &[ChannelType::Category], "the stat bar is created inside it")
+            .await?;
+    }
+
+    let prometheus_url = prometheus_url(ctx, guild_id.get()).await?;
+    if prometheus_url.is_empty() && !is_bot_side(&query) {
+        say(ctx, "❌ Please set a Prometheus server URL first using `/stats set_prometheus`!")
             .await?;
         return Ok(());
     }
 
-    let test_value = StatsTask::query_prometheus(&prometheus_url, &query).await?;
+    let data = ctx.data();
+    let test_value = StatsTask::run_query(
+        &data.metrics,
+        &data.dbs.stats,
+        guild_id.get(),
+        &prometheus_url,
+        &query,
+    )
+    .await?;
 
-    let mut channel_builder = CreateChannel::new(name).kind(ChannelType::Voice);
+    let kind = match target {
+        StatTarget::ChannelName => ChannelType::Voice,
+        StatTarget::Topic => ChannelType::Text,
+    };
+    let mut channel_builder = CreateChannel::new(name).kind(kind);
 
     if let Some(cat_id) = category {
         channel_builder = channel_builder.category(cat_id);
@@ -124,11 +185,15 @@ pub async fn create_channel(
         query,
         format,
         data_type,
+        target,
         last_value: Some(test_value),
         last_update: Some(std::time::SystemTime::now()),
         error_count: 0,
         last_error: None,
         last_success: Some(std::time::SystemTime::now()),
+        alert: None,
+        pending: None,
+        recent_edits: Vec::new(),
     };
 
     ctx.data()
@@ -136,21 +201,22 @@ pub async fn create_channel(
         .stats
         .update_stat_bar(guild_id.get(), stat_bar)
         .await?;
-    ctx.say(format!(
-        "✅ Created voice channel with stat bar! <#{}>",
+    say(ctx, format!(
+        "✅ Created {} channel with stat bar! <#{}>",
+        if target == StatTarget::Topic { "text" } else { "voice" },
         channel.id
     ))
     .await?;
     Ok(())
 }
 
-/// Remove a stat bar from a voice channel
+/// Remove a stat bar from a channel
 #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
 pub async fn remove(
     ctx: Context<'_>,
-    #[description = "Voice channel to remove stats from"] channel: ChannelId,
+    #[description = "Channel to remove stats from"] channel: ChannelId,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
 
     let removed = ctx
         .data()
@@ -166,9 +232,9 @@ pub async fn remove(
         .await?;
 
     if removed {
-        ctx.say("✅ Stat bar removed!").await?;
+        say(ctx, "✅ Stat bar removed!").await?;
     } else {
-        ctx.say("❌ No stat bar found for this channel.").await?;
+        say(ctx, "❌ No stat bar found for this channel.").await?;
     }
 
     Ok(())
@@ -177,7 +243,7 @@ pub async fn remove(
 /// List all stat bars in the server
 #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
 pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
 
     let stat_bars = ctx
         .data()
@@ -192,44 +258,46 @@ pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
         .await;
 
     if stat_bars.is_empty() {
-        ctx.say("No stat bars configured.").await?;
+        say(ctx, "No stat bars configured.").await?;
         return Ok(());
     }
 
-    let mut response = String::from("📊 **Stat Bars**\n");
-    for bar in &stat_bars {
-        response.push_str(&format!(
-            "• <#{}>\n  Query: `{}`\n  Format: `{}`\n  Type: `{:?}`\n",
-            bar.channel_id, bar.query, bar.format, bar.data_type
-        ));
+    let theme = ctx.data().dbs.system.get_theme(guild_id).await;
+    let mut dashboard = embed::titled(&theme, "Stat Bars");
+    for bar in stat_bars.iter().take(25) {
+        dashboard = dashboard.field(
+            bar.format.clone(),
+            format!(
+                "<#{}> ({})\nQuery: `{}`\nType: `{:?}`",
+                bar.channel_id, bar.target, bar.query, bar.data_type
+            ),
+            false,
+        );
     }
 
-    ctx.say(response).await?;
+    send(ctx, CreateReply::default().embed(dashboard)).await?;
     Ok(())
 }
 
 /// Show the current Prometheus server URL
 #[poise::command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
 pub async fn show_prometheus(ctx: Context<'_>) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
 
-    let url = ctx
-        .data()
-        .dbs
-        .stats
-        .read(|db| {
-            db.guild_settings
-                .get(&guild_id)
-                .map(|s| s.prometheus_url.clone())
-        })
-        .await;
+    let configured = ctx.data().dbs.stats.get_settings(guild_id).await?.prometheus_url;
+    let config = ctx.data().config.get();
+    let default = &config.prometheus.default_url;
 
-    match url {
-        Some(url) => {
-            ctx.say(format!("🔗 Current Prometheus URL: `{}`", url))
+    match (configured.as_str(), default.as_str()) {
+        ("", "") => say(ctx, "❌ No Prometheus URL configured!").await?,
+        ("", default) => {
+            say(ctx, format!("🔗 Using the bot's default Prometheus URL: `{}`", default))
+                .await?
+        }
+        (url, _) => {
+            say(ctx, format!("🔗 Current Prometheus URL: `{}`", url))
                 .await?
         }
-        None => ctx.say("❌ No Prometheus URL configured!").await?,
     };
 
     Ok(())
@@ -241,24 +309,20 @@ pub async fn set_delay(
     ctx: Context<'_>,
     #[description = "Update delay in seconds (minimum 30)"] delay: u64,
 ) -> Result<(), Error> {
-    if delay < 30 {
-        ctx.say("❌ Minimum delay is 30 seconds!").await?;
+    if delay < MIN_UPDATE_DELAY {
+        say(ctx, format!("❌ Minimum delay is {} seconds!", MIN_UPDATE_DELAY)).await?;
         return Ok(());
     }
 
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
 
     ctx.data()
         .dbs
         .stats
-        .transaction(|db| {
-            let settings = db.guild_settings.entry(guild_id).or_default();
-            settings.update_delay = delay;
-            Ok(())
-        })
+        .update_settings(guild_id, |settings| settings.update_delay = delay)
         .await?;
 
-    ctx.say(format!(
+    say(ctx, format!(
         "✅ Stat bars will now update every {} seconds!",
         delay
     ))
@@ -273,38 +337,333 @@ pub async fn test_query(
     #[description = "Prometheus query to test"] query: String,
     #[description = "Value type"] data_type: DataType,
 ) -> Result<(), Error> {
-    let guild_id = ctx.guild_id().unwrap().get();
+    let guild_id = validate::guild(ctx)?.get();
+    let query = validate_query(&query)?;
 
-    let prometheus_url = ctx
-        .data()
-        .dbs
-        .stats
-        .get_settings(guild_id)
-        .await?
-        .prometheus_url;
+    let prometheus_url = prometheus_url(ctx, guild_id).await?;
 
-    if prometheus_url.is_empty() {
-        ctx.say("❌ Please set a Prometheus server URL first!")
+    if prometheus_url.is_empty() && !is_bot_side(&query) {
+        say(ctx, "❌ Please set a Prometheus server URL first!")
             .await?;
         return Ok(());
     }
 
-    ctx.defer().await?;
+    defer(ctx).await?;
 
-    match StatsTask::query_prometheus(&prometheus_url, &query).await {
+    let data = ctx.data();
+    match StatsTask::run_query(&data.metrics, &data.dbs.stats, guild_id, &prometheus_url, &query)
+        .await
+    {
         Ok(value) => {
             let formatted = data_type.format_value(value);
-            ctx.say(format!(
+            say(ctx, format!(
                 "✅ Query successful!\nRaw value: `{}`\nFormatted value: `{}`",
                 value, formatted
             ))
             .await?;
         }
         Err(e) => {
-            ctx.say(format!("❌ Query failed: {}", e)).await?;
+            say(ctx, format!("❌ Query failed: {}", e)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Alert the admin channel when a stat bar crosses a threshold
+#[command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
+pub async fn alert(
+    ctx: Context<'_>,
+    #[description = "Stat bar channel"] channel: ChannelId,
+    #[description = "Alert when the value goes above this"] above: Option<f64>,
+    #[description = "Alert when the value goes below this"] below: Option<f64>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    let alert = (above.is_some() || below.is_some()).then(|| StatAlert {
+        above,
+        below,
+        ..Default::default()
+    });
+    let description = alert.as_ref().map(|a| a.describe());
+
+    if !ctx
+        .data()
+        .dbs
+        .stats
+        .set_alert(guild_id, channel.get(), alert)
+        .await?
+    {
+        say(ctx, "❌ That channel doesn't have a stat bar!").await?;
+        return Ok(());
+    }
+
+    match description {
+        Some(description) => {
+            let admin_channel = ctx.data().dbs.guild_config().admin_channel(guild_id).await;
+            say(ctx, format!(
+                "🚨 <#{}> will alert when its value is {}.{}",
+                channel,
+                description,
+                if admin_channel.is_none() {
+                    "\n⚠️ No admin channel is set, so alerts won't be posted until you run `/settings admin_channel`."
+                } else {
+                    ""
+                }
+            ))
+            .await?
+        }
+        None => say(ctx, format!("🔕 Removed the alert from <#{}>.", channel)).await?,
+    };
+    Ok(())
+}
+
+/// Escalate a stat bar's alert when it keeps firing
+#[command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
+pub async fn escalate(
+    ctx: Context<'_>,
+    #[description = "Stat bar channel"] channel: ChannelId,
+    #[description = "How long the alert fires before escalating, e.g. 15m (empty stops escalating)"]
+    after: Option<String>,
+    #[description = "Role to ping in the admin channel"] role: Option<RoleId>,
+    #[description = "PagerDuty-compatible Events API v2 URL to page"] webhook_url: Option<String>,
+    #[description = "Routing key sent with webhook events"] routing_key: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    let escalation = match after {
+        Some(after) => {
+            let after = validate::duration(
+                &after,
+                DurationUnit::Minutes,
+                Duration::from_secs(60),
+                None,
+            )?;
+            if role.is_none() && webhook_url.is_none() {
+                say(ctx, "❌ Pick a role to ping, a webhook to page, or both.").await?;
+                return Ok(());
+            }
+            Some(Escalation {
+                after_secs: after.as_secs(),
+                role_id: role.map(|role| role.get()),
+                webhook_url: webhook_url.as_deref().map(validate::url).transpose()?,
+                routing_key: routing_key.map(|key| key.trim().to_string()),
+            })
+        }
+        None => None,
+    };
+    let description = escalation.as_ref().map(Escalation::describe);
+
+    if !ctx
+        .data()
+        .dbs
+        .stats
+        .set_escalation(guild_id, channel.get(), escalation)
+        .await?
+    {
+        say(
+            ctx,
+            "❌ That channel doesn't have a stat bar with an alert. Add one with `/stats alert` first.",
+        )
+        .await?;
+        return Ok(());
+    }
+
+    match description {
+        Some(description) => {
+            say(ctx, format!("📟 <#{}> will escalate {}.", channel, description)).await?
+        }
+        None => say(ctx, format!("🔕 <#{}> will no longer escalate.", channel)).await?,
+    };
+    Ok(())
+}
+
+/// Set the Grafana/Prometheus link template used in alerts
+#[command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
+pub async fn explore_url(
+    ctx: Context<'_>,
+    #[description = "URL with {query}, {from} and {to} placeholders (leave empty to remove)"]
+    template: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    if let Some(template) = &template {
+        validate::url(template)?;
+        if !template.contains("{query}") {
+            say(ctx, "❌ The template needs a `{query}` placeholder.").await?;
+            return Ok(());
+        }
+    }
+
+    let cleared = template.is_none();
+    ctx.data()
+        .dbs
+        .stats
+        .update_settings(guild_id, |settings| settings.explore_url_template = template)
+        .await?;
+
+    if cleared {
+        say(ctx, "✅ Alerts will no longer include an explore link.").await?;
+    } else {
+        say(ctx, "✅ Alerts will now link to your explore URL.").await?;
+    }
+    Ok(())
+}
+
+/// List the bot-side metrics usable as `internal:<name>` queries
+#[command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS")]
+pub async fn internal_metrics(ctx: Context<'_>) -> Result<(), Error> {
+    let mut response = String::from("🧮 **Internal Metrics**\nUse these as the query of a stat bar, e.g. `internal:lorax_submissions`.\n");
+    for (name, description, kind) in ctx.data().metrics.list() {
+        let kind = match kind {
+            MetricKind::Gauge => "gauge",
+            MetricKind::Counter => "counter",
+        };
+        response.push_str(&format!(
+            "\n• `{}{}` ({}) - {}",
+            internal::PREFIX,
+            name,
+            kind,
+            if description.is_empty() { "No description" } else { description.as_str() }
+        ));
+    }
+
+    say(ctx, response).await?;
+    Ok(())
+}
+
+/// Name queries for use in `computed:` stat bars
+#[command(
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS",
+    subcommands("query_add", "query_remove", "query_list")
+)]
+pub async fn queries(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Name a query, e.g. ram_used, to use it in computed stat bars
+#[command(
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS",
+    rename = "add"
+)]
+pub async fn query_add(
+    ctx: Context<'_>,
+    #[description = "Name to use in expressions, e.g. ram_used"] name: String,
+    #[description = "Prometheus query, or internal:<metric>"] query: String,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let name = name.trim().to_string();
+    if !computed::is_valid_name(&name) {
+        say(ctx, "❌ Names must start with a letter and use only letters, digits and `_`.")
+            .await?;
+        return Ok(());
+    }
+    if computed::is_computed(&query) {
+        say(ctx, "❌ Named queries can't be computed themselves.").await?;
+        return Ok(());
+    }
+    let query = validate_query(&query)?;
+
+    let prometheus_url = prometheus_url(ctx, guild_id).await?;
+    if prometheus_url.is_empty() && !is_bot_side(&query) {
+        say(ctx, "❌ Please set a Prometheus server URL first using `/stats set_prometheus`!")
+            .await?;
+        return Ok(());
+    }
+    defer(ctx).await?;
+    let data = ctx.data();
+    let value =
+        StatsTask::run_query(&data.metrics, &data.dbs.stats, guild_id, &prometheus_url, &query)
+            .await?;
+
+    match data.dbs.stats.set_named_query(guild_id, name.clone(), query).await {
+        Ok(()) => {
+            say(ctx, format!(
+                "✅ `{}` is now `{}`. Use it in a stat bar query like `{} {} * 100`.",
+                name, value, computed::PREFIX, name
+            ))
+            .await?;
+        }
+        Err(e) => {
+            say(ctx, format!("❌ {}", e)).await?;
         }
     }
+    Ok(())
+}
 
+/// Remove a named query
+#[command(
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS",
+    rename = "remove"
+)]
+pub async fn query_remove(
+    ctx: Context<'_>,
+    #[description = "Name of the query"] name: String,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let name = name.trim();
+    if !ctx.data().dbs.stats.remove_named_query(guild_id, name).await? {
+        say(ctx, format!("❌ No named query `{}`.", name)).await?;
+        return Ok(());
+    }
+
+    // Bars using it start failing, so point them out
+    let users: Vec<String> = ctx
+        .data()
+        .dbs
+        .stats
+        .get_stat_bars(guild_id)
+        .await?
+        .into_iter()
+        .filter(|bar| {
+            computed::is_computed(&bar.query)
+                && computed::parse(&bar.query).is_ok_and(|expr| expr.names().contains(&name))
+        })
+        .map(|bar| format!("<#{}>", bar.channel_id))
+        .collect();
+
+    let mut response = format!("🗑️ Removed `{}`.", name);
+    if !users.is_empty() {
+        response.push_str(&format!(
+            "\n⚠️ These stat bars still use it and will fail until it's added back: {}",
+            users.join(", ")
+        ));
+    }
+    say(ctx, response).await?;
+    Ok(())
+}
+
+/// List this server's named queries
+#[command(
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS",
+    rename = "list"
+)]
+pub async fn query_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let named = ctx.data().dbs.stats.named_queries(guild_id).await;
+    if named.is_empty() {
+        say(ctx, "⚪ No named queries. Add one with `/stats queries add`.").await?;
+        return Ok(());
+    }
+
+    let lines: Vec<String> = named
+        .iter()
+        .map(|(name, query)| format!("• `{}` = `{}`", name, query))
+        .collect();
+    say(ctx, format!(
+        "🧮 **Named Queries**\nUse them in a stat bar query like `{} a / b * 100`.\n{}",
+        computed::PREFIX,
+        lines.join("\n")
+    ))
+    .await?;
     Ok(())
 }
 
@@ -318,7 +677,8 @@ pub async fn test_query(
         "create_channel",
         "remove",
         "list",
-        "test_query"
+        "test_query",
+        "internal_metrics"
     )
 )]
 pub async fn stats(_ctx: crate::Context<'_>) -> Result<(), crate::Error> {
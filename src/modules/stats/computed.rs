@@ -0,0 +1,216 @@
+//! Stat bars can show arithmetic over the guild's named queries with a `computed:` query,
+//! e.g. `computed: ram_used / ram_total * 100`, evaluated bot-side for values Prometheus
+//! recording rules don't provide.
+
+use std::collections::HashMap;
+
+pub const PREFIX: &str = "computed:";
+
+/// Named queries a guild can define.
+pub const MAX_NAMED_QUERIES: usize = 25;
+
+pub fn is_computed(query: &str) -> bool {
+    query.trim().starts_with(PREFIX)
+}
+
+/// Whether `name` can be used as a named query: a letter or `_`, then letters, digits or `_`.
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && name.len() <= 32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Name(String),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, Op, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Op(Op),
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            ' ' | '\t' => {
+                chars.next();
+            }
+            '+' | '-' | '*' | '/' => {
+                chars.next();
+                tokens.push(Token::Op(match c {
+                    '+' => Op::Add,
+                    '-' => Op::Sub,
+                    '*' => Op::Mul,
+                    _ => Op::Div,
+                }));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                    number.push(c);
+                    chars.next();
+                }
+                let value = number
+                    .parse()
+                    .map_err(|_| format!("`{}` isn't a number", number))?;
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                let is_name = |c: &&char| c.is_ascii_alphanumeric() || **c == '_';
+                while let Some(&c) = chars.peek().filter(is_name) {
+                    name.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Name(name));
+            }
+            other => return Err(format!("Unexpected `{}`", other)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over `+ -` then `* /` then unary minus, numbers, names and brackets.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut left = self.product()?;
+        while let Some(Token::Op(op @ (Op::Add | Op::Sub))) = self.peek().cloned() {
+            self.pos += 1;
+            left = Expr::Binary(Box::new(left), op, Box::new(self.product()?));
+        }
+        Ok(left)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some(Token::Op(op @ (Op::Mul | Op::Div))) = self.peek().cloned() {
+            self.pos += 1;
+            left = Expr::Binary(Box::new(left), op, Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Op(Op::Sub)) => Ok(Expr::Neg(Box::new(self.unary()?))),
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Name(name)) => Ok(Expr::Name(name)),
+            Some(Token::Open) => {
+                let inner = self.sum()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err("Missing `)`".to_string()),
+                }
+            }
+            Some(_) => Err("Expected a number, name or `(`".to_string()),
+            None => Err("The expression ends too early".to_string()),
+        }
+    }
+}
+
+/// Parses the expression of a `computed:` query (with or without the prefix).
+pub fn parse(query: &str) -> Result<Expr, String> {
+    let input = query.trim().trim_start_matches(PREFIX).trim();
+    if input.is_empty() {
+        return Err("The expression is empty".to_string());
+    }
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+    };
+    let expr = parser.sum()?;
+    if parser.pos < parser.tokens.len() {
+        return Err("Unexpected text after the expression".to_string());
+    }
+    Ok(expr)
+}
+
+impl Expr {
+    /// Named queries the expression uses, each once.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_names(&mut names);
+        names
+    }
+
+    fn collect_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Self::Number(_) => {}
+            Self::Name(name) => {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+            Self::Neg(inner) => inner.collect_names(names),
+            Self::Binary(left, _, right) => {
+                left.collect_names(names);
+                right.collect_names(names);
+            }
+        }
+    }
+
+    pub fn eval(&self, values: &HashMap<String, f64>) -> Result<f64, String> {
+        match self {
+            Self::Number(value) => Ok(*value),
+            Self::Name(name) => values
+                .get(name)
+                .copied()
+                .ok_or_else(|| format!("No value for `{}`", name)),
+            Self::Neg(inner) => Ok(-inner.eval(values)?),
+            Self::Binary(left, op, right) => {
+                let (left, right) = (left.eval(values)?, right.eval(values)?);
+                match op {
+                    Op::Add => Ok(left + right),
+                    Op::Sub => Ok(left - right),
+                    Op::Mul => Ok(left * right),
+                    Op::Div if right == 0.0 => Err("Division by zero".to_string()),
+                    Op::Div => Ok(left / right),
+                }
+            }
+        }
+    }
+}
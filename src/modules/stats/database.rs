@@ -1,6 +1,20 @@
-use crate::{database::Database, default_struct};
+use crate::{
+    database::{
+        decode, encode, put_guild_rows, take_guild_rows, Database, DbError, Migration, Rows,
+    },
+    default_struct,
+    utils::history::SettingsHistory,
+};
+use super::alerts::{Escalation, StatAlert};
+use super::computed::MAX_NAMED_QUERIES;
+use super::migrations;
+use poise::serenity_prelude::ChannelType;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fmt};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    time::SystemTime,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, poise::ChoiceParameter)]
 pub enum DataType {
@@ -91,31 +105,163 @@ impl fmt::Display for DataType {
     }
 }
 
+/// Where a stat bar shows its value.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, poise::ChoiceParameter,
+)]
+pub enum StatTarget {
+    #[default]
+    #[name = "Voice channel name"]
+    ChannelName,
+    /// Topics allow longer text and aren't as tightly rate limited as names.
+    #[name = "Text channel topic"]
+    Topic,
+}
+
+impl StatTarget {
+    /// Channel types the value can be shown in.
+    pub fn channel_kinds(self) -> &'static [ChannelType] {
+        match self {
+            Self::ChannelName => &[ChannelType::Voice],
+            Self::Topic => &[ChannelType::Text, ChannelType::News],
+        }
+    }
+
+    /// Longest text Discord accepts for the target.
+    pub fn max_len(self) -> usize {
+        match self {
+            Self::ChannelName => 100,
+            Self::Topic => 1024,
+        }
+    }
+}
+
+impl fmt::Display for StatTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ChannelName => write!(f, "channel name"),
+            Self::Topic => write!(f, "channel topic"),
+        }
+    }
+}
+
 default_struct! {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuildSettings {
     pub prometheus_url: String = String::new(),
     pub update_delay: u64 = 60,
+    /// Link opened from alerts, with `{query}`, `{from}` and `{to}` placeholders.
+    pub explore_url_template: Option<String>,
 }
 }
 
+/// Shortest `update_delay` `/stats set_delay` accepts, in seconds.
+pub const MIN_UPDATE_DELAY: u64 = 30;
+
+impl GuildSettings {
+    /// Checks limits the stats commands enforce, for settings that came from elsewhere,
+    /// such as an import. Errors name the offending field.
+    pub fn check(&self) -> Result<(), String> {
+        if self.update_delay < MIN_UPDATE_DELAY {
+            return Err(format!("update_delay: must be at least {} seconds", MIN_UPDATE_DELAY));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StatBar {
     pub channel_id: u64,
     pub query: String,
     pub format: String,
     pub data_type: DataType,
+    /// Defaulted for `/settings import` files exported before topics were supported.
+    #[serde(default)]
+    pub target: StatTarget,
     pub last_value: Option<f64>,
     pub last_update: Option<std::time::SystemTime>,
     pub error_count: u32,
     pub last_error: Option<String>,
     pub last_success: Option<std::time::SystemTime>,
+    pub alert: Option<StatAlert>,
+    /// Text waiting for Discord's rename limit, applied by the rename queue.
+    #[serde(default)]
+    pub pending: Option<String>,
+    /// Renames within the current rate limit window.
+    #[serde(default)]
+    pub recent_edits: Vec<std::time::SystemTime>,
+}
+
+impl StatBar {
+    /// Takes what the stats task changed during a run from `run`, a copy taken when the run
+    /// started, leaving configuration edited meanwhile alone.
+    pub fn take_run(&mut self, run: StatBar) {
+        self.last_value = run.last_value;
+        self.last_update = run.last_update;
+        self.error_count = run.error_count;
+        self.last_error = run.last_error;
+        self.last_success = run.last_success;
+        self.pending = run.pending;
+        self.recent_edits = run.recent_edits;
+        if let (Some(alert), Some(run_alert)) = (&mut self.alert, &run.alert) {
+            alert.take_state(run_alert);
+        }
+    }
+}
+
+/// A Prometheus query result kept across restarts.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CachedQuery {
+    pub value: f64,
+    pub fetched_at: SystemTime,
+}
+
+impl CachedQuery {
+    pub fn age(&self) -> std::time::Duration {
+        self.fetched_at.elapsed().unwrap_or_default()
+    }
 }
 
 #[derive(Default, Serialize, Deserialize, Clone, Debug)]
 pub struct StatsDatabase {
     pub stat_bars: HashMap<u64, HashMap<u64, StatBar>>,
     pub guild_settings: HashMap<u64, GuildSettings>,
+    pub settings_history: HashMap<u64, SettingsHistory<GuildSettings>>,
+    /// Recent results by `{prometheus_url}:{query}`, saved by the stats task.
+    pub query_cache: HashMap<String, CachedQuery>,
+    /// Queries `computed:` stat bars refer to by name, per guild.
+    pub named_queries: HashMap<u64, BTreeMap<String, String>>,
+}
+
+impl Rows for StatsDatabase {
+    const VERSION: u32 = 2;
+
+    fn migrations() -> Vec<Box<dyn Migration>> {
+        vec![Box::new(migrations::V1ToV2)]
+    }
+
+    fn to_rows(&self) -> Result<BTreeMap<String, Vec<u8>>, DbError> {
+        let mut rows = BTreeMap::new();
+        put_guild_rows(&mut rows, "stat_bars", &self.stat_bars)?;
+        put_guild_rows(&mut rows, "guild_settings", &self.guild_settings)?;
+        put_guild_rows(&mut rows, "settings_history", &self.settings_history)?;
+        put_guild_rows(&mut rows, "named_queries", &self.named_queries)?;
+        rows.insert("query_cache".to_string(), encode(&self.query_cache)?);
+        Ok(rows)
+    }
+
+    fn from_rows(rows: BTreeMap<String, Vec<u8>>) -> Result<Self, DbError> {
+        Ok(Self {
+            stat_bars: take_guild_rows(&rows, "stat_bars")?,
+            guild_settings: take_guild_rows(&rows, "guild_settings")?,
+            settings_history: take_guild_rows(&rows, "settings_history")?,
+            named_queries: take_guild_rows(&rows, "named_queries")?,
+            query_cache: match rows.get("query_cache") {
+                Some(bytes) => decode(bytes)?,
+                None => HashMap::new(),
+            },
+        })
+    }
 }
 
 impl Database<StatsDatabase> {
@@ -136,6 +282,41 @@ impl Database<StatsDatabase> {
             .map_err(|e| e.to_string())
     }
 
+    /// Applies a settings change, recording the previous value for `/settings undo`.
+    pub async fn update_settings<F>(&self, guild_id: u64, f: F) -> Result<GuildSettings, String>
+    where
+        F: FnOnce(&mut GuildSettings),
+    {
+        self.transaction(|db| {
+            let settings = db.guild_settings.entry(guild_id).or_default();
+            let before = settings.clone();
+            f(settings);
+            let after = settings.clone();
+
+            db.settings_history.entry(guild_id).or_default().record(before);
+            Ok(after)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Reverts the most recent settings change, returning the restored settings.
+    pub async fn undo_settings(&self, guild_id: u64) -> Result<Option<GuildSettings>, String> {
+        self.transaction(|db| {
+            let previous = db
+                .settings_history
+                .get_mut(&guild_id)
+                .and_then(|history| history.pop());
+
+            if let Some(previous) = &previous {
+                db.guild_settings.insert(guild_id, previous.clone());
+            }
+            Ok(previous)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
     pub async fn get_stat_bars(&self, guild_id: u64) -> Result<Vec<StatBar>, String> {
         Ok(self
             .read(|db| {
@@ -147,6 +328,143 @@ impl Database<StatsDatabase> {
             .await)
     }
 
+    /// Sets or clears the alert on an existing stat bar, returning false if there is no such bar.
+    /// New thresholds keep the bar's escalation policy.
+    pub async fn set_alert(
+        &self,
+        guild_id: u64,
+        channel_id: u64,
+        mut alert: Option<StatAlert>,
+    ) -> Result<bool, String> {
+        self.transaction(|db| {
+            match db
+                .stat_bars
+                .get_mut(&guild_id)
+                .and_then(|bars| bars.get_mut(&channel_id))
+            {
+                Some(bar) => {
+                    if let (Some(alert), Some(old)) = (&mut alert, bar.alert.take()) {
+                        alert.escalation = old.escalation;
+                    }
+                    bar.alert = alert;
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Saves an alert's firing state as soon as it's announced, so a failing update of the
+    /// bar doesn't announce it again next run.
+    pub async fn save_alert_state(
+        &self,
+        guild_id: u64,
+        channel_id: u64,
+        alert: &StatAlert,
+    ) -> Result<(), String> {
+        self.transaction(|db| {
+            if let Some(stored) = db
+                .stat_bars
+                .get_mut(&guild_id)
+                .and_then(|bars| bars.get_mut(&channel_id))
+                .and_then(|bar| bar.alert.as_mut())
+            {
+                stored.take_state(alert);
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Sets or clears the escalation policy of a stat bar's alert, returning false if the
+    /// bar has no alert.
+    pub async fn set_escalation(
+        &self,
+        guild_id: u64,
+        channel_id: u64,
+        escalation: Option<Escalation>,
+    ) -> Result<bool, String> {
+        self.transaction(|db| {
+            match db
+                .stat_bars
+                .get_mut(&guild_id)
+                .and_then(|bars| bars.get_mut(&channel_id))
+                .and_then(|bar| bar.alert.as_mut())
+            {
+                Some(alert) => {
+                    alert.escalation = escalation;
+                    alert.escalated = false;
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Removes the stat bar on a deleted channel, returning a description of the change.
+    /// The guild's named queries by name.
+    pub async fn named_queries(&self, guild_id: u64) -> BTreeMap<String, String> {
+        self.read(|db| db.named_queries.get(&guild_id).cloned().unwrap_or_default())
+            .await
+    }
+
+    /// Adds or replaces a named query. Fails once the guild has the most it may define.
+    pub async fn set_named_query(
+        &self,
+        guild_id: u64,
+        name: String,
+        query: String,
+    ) -> Result<(), String> {
+        self.transaction(|db| {
+            let named = db.named_queries.entry(guild_id).or_default();
+            if !named.contains_key(&name) && named.len() >= MAX_NAMED_QUERIES {
+                return Err(format!(
+                    "This server already has {} named queries; remove one first",
+                    MAX_NAMED_QUERIES
+                ));
+            }
+            named.insert(name, query);
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Removes a named query, returning whether there was one.
+    pub async fn remove_named_query(&self, guild_id: u64, name: &str) -> Result<bool, String> {
+        self.transaction(|db| {
+            let Some(named) = db.named_queries.get_mut(&guild_id) else {
+                return Ok(false);
+            };
+            let removed = named.remove(name).is_some();
+            if named.is_empty() {
+                db.named_queries.remove(&guild_id);
+            }
+            Ok(removed)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn forget_channel(&self, guild_id: u64, channel_id: u64) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            Ok(db
+                .stat_bars
+                .get_mut(&guild_id)
+                .and_then(|bars| bars.remove(&channel_id))
+                .map(|bar| format!("Stat bar \"{}\" (`{}`) removed", bar.format, bar.query))
+                .into_iter()
+                .collect())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
     pub async fn update_stat_bar(&self, guild_id: u64, bar: StatBar) -> Result<(), String> {
         self.transaction(|db| {
             db.stat_bars
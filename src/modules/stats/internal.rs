@@ -0,0 +1,25 @@
+//! Stat bars bind to bot-side metrics from the [`MetricsRegistry`] with an
+//! `internal:` query instead of asking Prometheus.
+
+use crate::metrics::MetricsRegistry;
+
+pub const PREFIX: &str = "internal:";
+
+pub fn is_internal(query: &str) -> bool {
+    query.trim().starts_with(PREFIX)
+}
+
+pub async fn query(metrics: &MetricsRegistry, guild_id: u64, query: &str) -> Result<f64, String> {
+    let name = query.trim().trim_start_matches(PREFIX).trim();
+
+    if !metrics.contains(name) {
+        return Err(format!(
+            "Unknown internal metric `{}`. Use `/stats internal_metrics` to see what's available.",
+            name
+        ));
+    }
+    metrics
+        .read(name, guild_id)
+        .await
+        .map_err(|e| format!("Failed to read internal metric `{}`: {}", name, e))
+}
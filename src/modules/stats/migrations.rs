@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::database as live;
+use crate::database::{decode, encode, DbError, Migration};
+
+/// The stats schema as first released, before rows were versioned. Frozen: never change
+/// these types.
+mod v1 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    pub enum DataType {
+        Integer,
+        Float,
+        Percentage,
+        Bytes,
+        Duration,
+        Temperature,
+        Speed,
+        Currency,
+        Scientific,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct GuildSettings {
+        pub prometheus_url: String,
+        pub update_delay: u64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct StatBar {
+        pub channel_id: u64,
+        pub query: String,
+        pub format: String,
+        pub data_type: DataType,
+        pub last_value: Option<f64>,
+        pub last_update: Option<std::time::SystemTime>,
+        pub error_count: u32,
+        pub last_error: Option<String>,
+        pub last_success: Option<std::time::SystemTime>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct StatsDatabase {
+        pub stat_bars: HashMap<u64, HashMap<u64, StatBar>>,
+        pub guild_settings: HashMap<u64, GuildSettings>,
+    }
+}
+
+impl From<v1::DataType> for live::DataType {
+    fn from(old: v1::DataType) -> Self {
+        match old {
+            v1::DataType::Integer => Self::Integer,
+            v1::DataType::Float => Self::Float,
+            v1::DataType::Percentage => Self::Percentage,
+            v1::DataType::Bytes => Self::Bytes,
+            v1::DataType::Duration => Self::Duration,
+            v1::DataType::Temperature => Self::Temperature,
+            v1::DataType::Speed => Self::Speed,
+            v1::DataType::Currency => Self::Currency,
+            v1::DataType::Scientific => Self::Scientific,
+        }
+    }
+}
+
+impl From<v1::GuildSettings> for live::GuildSettings {
+    fn from(old: v1::GuildSettings) -> Self {
+        Self {
+            prometheus_url: old.prometheus_url,
+            update_delay: old.update_delay,
+            ..Self::default()
+        }
+    }
+}
+
+impl From<v1::StatBar> for live::StatBar {
+    fn from(old: v1::StatBar) -> Self {
+        Self {
+            channel_id: old.channel_id,
+            query: old.query,
+            format: old.format,
+            data_type: old.data_type.into(),
+            target: live::StatTarget::ChannelName,
+            last_value: old.last_value,
+            last_update: old.last_update,
+            error_count: old.error_count,
+            last_error: old.last_error,
+            last_success: old.last_success,
+            alert: None,
+            pending: None,
+            recent_edits: Vec::new(),
+        }
+    }
+}
+
+/// v1 → v2: stat bars keep updating their channel name and gain no alert; settings history,
+/// the query cache and named queries start out empty.
+pub struct V1ToV2;
+
+impl Migration for V1ToV2 {
+    fn from_version(&self) -> u32 {
+        1
+    }
+
+    fn migrate(&self, key: &str, bytes: Vec<u8>) -> Result<Vec<u8>, DbError> {
+        // v1 predates partitioned storage, so it only ever exists as the single row
+        if !key.is_empty() {
+            return Err(DbError::Codec(format!("unexpected v1 row {:?}", key)));
+        }
+        let old: v1::StatsDatabase = decode(&bytes)?;
+        encode(&live::StatsDatabase {
+            stat_bars: old
+                .stat_bars
+                .into_iter()
+                .map(|(guild_id, bars)| {
+                    (guild_id, bars.into_iter().map(|(id, bar)| (id, bar.into())).collect())
+                })
+                .collect(),
+            guild_settings: old
+                .guild_settings
+                .into_iter()
+                .map(|(guild_id, settings)| (guild_id, settings.into()))
+                .collect(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Rows;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn loads_baseline_at_current_version() {
+        let bar = v1::StatBar {
+            channel_id: 2,
+            query: "up".to_string(),
+            format: "Up: {}".to_string(),
+            data_type: v1::DataType::Bytes,
+            last_value: Some(1.0),
+            last_update: None,
+            error_count: 0,
+            last_error: None,
+            last_success: None,
+        };
+        let settings = v1::GuildSettings {
+            prometheus_url: "http://prometheus:9090".to_string(),
+            update_delay: 120,
+        };
+        let baseline = v1::StatsDatabase {
+            stat_bars: HashMap::from([(1, HashMap::from([(2, bar)]))]),
+            guild_settings: HashMap::from([(1, settings)]),
+        };
+        let rows = BTreeMap::from([(String::new(), encode(&baseline).unwrap())]);
+
+        let rows = live::StatsDatabase::upgrade(rows).unwrap();
+        let db: live::StatsDatabase = decode(&rows[""]).unwrap();
+
+        let bar = &db.stat_bars[&1][&2];
+        assert_eq!(bar.query, "up");
+        assert!(matches!(bar.data_type, live::DataType::Bytes));
+        assert_eq!(bar.target, live::StatTarget::ChannelName);
+        assert!(bar.alert.is_none() && bar.pending.is_none());
+        let settings = &db.guild_settings[&1];
+        assert_eq!(settings.prometheus_url, "http://prometheus:9090");
+        assert_eq!(settings.update_delay, 120);
+        assert!(settings.explore_url_template.is_none());
+        assert!(db.query_cache.is_empty() && db.named_queries.is_empty());
+    }
+}
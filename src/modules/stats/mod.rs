@@ -1,11 +1,16 @@
+pub mod alerts;
 pub mod commands;
+pub mod computed;
 pub mod database;
+pub mod internal;
+pub mod migrations;
+pub mod rename;
 pub mod task;
 
 use commands::*;
 use poise::command;
 
-/// 📊 Prometheus stat bars in voice channels
+/// 📊 Prometheus stat bars in channel names and topics
 #[command(
     slash_command,
     subcommands(
@@ -14,7 +19,12 @@ use poise::command;
         "set",
         "create_channel",
         "remove",
-        "list"
+        "list",
+        "alert",
+        "escalate",
+        "explore_url",
+        "internal_metrics",
+        "queries"
     )
 )]
 pub async fn stats(_ctx: crate::Context<'_>) -> Result<(), crate::Error> {
@@ -0,0 +1,145 @@
+//! Discord allows two renames of a channel per ten minutes. Stat bar names that would go
+//! over that are parked on the bar as `pending` and applied by [`RenameQueueTask`] as soon
+//! as the window allows, instead of waiting for the next stats run.
+
+use super::database::{StatBar, StatTarget, StatsDatabase};
+use crate::{database::Database, tasks::Task};
+use async_trait::async_trait;
+use poise::serenity_prelude::{ChannelId, Context, EditChannel};
+use std::time::{Duration, SystemTime};
+use tokio::time::timeout;
+use tracing::{debug, warn};
+
+const WINDOW: Duration = Duration::from_secs(10 * 60);
+const RENAMES_PER_WINDOW: usize = 2;
+
+pub enum Outcome {
+    Applied,
+    Queued,
+}
+
+/// Forgets edits outside the window. Returns whether the bar may edit its channel now.
+fn has_quota(bar: &mut StatBar, now: SystemTime) -> bool {
+    if bar.target != StatTarget::ChannelName {
+        return true;
+    }
+    bar.recent_edits
+        .retain(|time| now.duration_since(*time).map_or(true, |age| age < WINDOW));
+    bar.recent_edits.len() < RENAMES_PER_WINDOW
+}
+
+/// Shows `text` on the bar's channel now, or queues it if the rename limit is used up.
+pub async fn apply(
+    ctx: &Context,
+    bar: &mut StatBar,
+    text: String,
+) -> Result<Outcome, Box<dyn std::error::Error + Send + Sync>> {
+    let now = SystemTime::now();
+    if !has_quota(bar, now) {
+        debug!("Rename limit reached for {}, queueing \"{}\"", bar.channel_id, text);
+        bar.pending = Some(text);
+        return Ok(Outcome::Queued);
+    }
+
+    let edit = match bar.target {
+        StatTarget::ChannelName => EditChannel::default().name(&text),
+        StatTarget::Topic => EditChannel::default().topic(&text),
+    };
+    match timeout(
+        Duration::from_secs(5),
+        ChannelId::new(bar.channel_id).edit(&ctx.http, edit),
+    )
+    .await
+    {
+        Ok(Ok(_)) => {
+            bar.pending = None;
+            if bar.target == StatTarget::ChannelName {
+                bar.recent_edits.push(now);
+            }
+            Ok(Outcome::Applied)
+        }
+        Ok(Err(e)) => Err(e.into()),
+        Err(_) => {
+            // Serenity sits out 429s, so a stalled edit means the limit was hit elsewhere
+            warn!("Timeout updating channel {}, queueing the update", bar.channel_id);
+            bar.recent_edits = vec![now; RENAMES_PER_WINDOW];
+            bar.pending = Some(text);
+            Ok(Outcome::Queued)
+        }
+    }
+}
+
+/// Applies queued stat bar renames once their channel's window allows.
+#[derive(Debug, Clone)]
+pub struct RenameQueueTask {
+    db: Database<StatsDatabase>,
+}
+
+impl RenameQueueTask {
+    pub fn new(db: Database<StatsDatabase>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Task for RenameQueueTask {
+    fn name(&self) -> &str {
+        "StatsRenameQueue"
+    }
+
+    fn schedule(&self) -> Option<Duration> {
+        Some(Duration::from_secs(30))
+    }
+
+    async fn execute(
+        &mut self,
+        ctx: &Context,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let now = SystemTime::now();
+        let due = self
+            .db
+            .read(|db| {
+                db.stat_bars
+                    .iter()
+                    .flat_map(|(guild_id, bars)| bars.values().map(move |bar| (*guild_id, bar)))
+                    .filter(|(_, bar)| bar.pending.is_some())
+                    .map(|(guild_id, bar)| (guild_id, bar.clone()))
+                    .filter_map(|(guild_id, mut bar)| has_quota(&mut bar, now).then_some((guild_id, bar)))
+                    .collect::<Vec<_>>()
+            })
+            .await;
+
+        for (guild_id, mut bar) in due {
+            let Some(text) = bar.pending.clone() else {
+                continue;
+            };
+            let queued = text.clone();
+            if let Err(e) = apply(ctx, &mut bar, text).await {
+                warn!("Failed to apply queued update to {}: {}", bar.channel_id, e);
+                // Don't retry a rename Discord refused, e.g. for a deleted channel
+                bar.pending = None;
+            }
+
+            // Only touch the queue fields, and only if no newer value was queued meanwhile
+            self.db
+                .transaction(|db| {
+                    if let Some(stored) = db
+                        .stat_bars
+                        .get_mut(&guild_id)
+                        .and_then(|bars| bars.get_mut(&bar.channel_id))
+                        .filter(|stored| stored.pending.as_deref() == Some(queued.as_str()))
+                    {
+                        stored.pending = bar.pending;
+                        stored.recent_edits = bar.recent_edits;
+                    }
+                    Ok(())
+                })
+                .await?;
+        }
+        Ok(())
+    }
+
+    fn box_clone(&self) -> Box<dyn Task> {
+        Box::new(self.clone())
+    }
+}
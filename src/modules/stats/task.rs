@@ -1,56 +1,160 @@
-use crate::tasks::Task;
-use crate::{database::Database, modules::stats::database::StatsDatabase};
+use crate::health::Health;
+use crate::tasks::{Task, TaskGroup};
+use crate::{
+    config::LiveConfig,
+    database::Database,
+    metrics::MetricsRegistry,
+    modules::{
+        stats::database::StatsDatabase,
+        system::database::SystemDatabase,
+        toggles::database::{Module, ModulesDatabase},
+    },
+    utils::{
+        http,
+        live::{self, LiveUpdate},
+    },
+};
 use async_trait::async_trait;
-use poise::serenity_prelude::{ChannelId, Context, EditChannel};
-use std::collections::HashMap;
+use poise::serenity_prelude::{ChannelId, Context};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 use tokio::sync::RwLock;
 use tokio::time::{sleep, timeout};
 use tracing::{debug, error, info, warn};
 
-use super::database::StatBar;
+use super::alerts;
+use super::database::{CachedQuery, GuildSettings, StatBar, StatTarget};
+use super::computed;
+use super::internal;
+use super::rename::{self, Outcome};
+
+/// How long a query result is reused before querying Prometheus again.
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Oldest saved result still shown after a restart while its query waits its turn.
+const RESTORED_MAX_AGE: Duration = Duration::from_secs(15 * 60);
+
+/// Most saved results re-queried per run after a restart; the rest keep their saved value.
+const RESTORED_REFRESHES_PER_RUN: usize = 20;
+
+/// Query results, loaded from the database on the first run.
+#[derive(Debug, Default)]
+struct QueryCache {
+    loaded: bool,
+    entries: HashMap<String, CachedQuery>,
+    /// Keys loaded from the database that haven't been re-queried since.
+    restored: HashSet<String>,
+}
 
 #[derive(Debug)]
 pub struct StatsTask {
     db: Database<StatsDatabase>,
-    query_cache: Arc<RwLock<HashMap<String, (f64, std::time::Instant)>>>,
+    system: Database<SystemDatabase>,
+    modules: Database<ModulesDatabase>,
+    metrics: Arc<MetricsRegistry>,
+    query_cache: Arc<RwLock<QueryCache>>,
     channel_updates: Arc<RwLock<HashMap<u64, std::time::Instant>>>,
+    /// Stat bars that failed and were attempted in the latest run.
+    last_run: (usize, usize),
+    interval: Duration,
+    /// Read on each run for the Prometheus URL of guilds that haven't set their own.
+    config: Arc<LiveConfig>,
 }
 
 impl StatsTask {
-    pub fn new(db: Database<StatsDatabase>) -> Self {
+    pub fn new(
+        db: Database<StatsDatabase>,
+        system: Database<SystemDatabase>,
+        modules: Database<ModulesDatabase>,
+        metrics: Arc<MetricsRegistry>,
+        interval: Duration,
+        config: Arc<LiveConfig>,
+    ) -> Self {
         Self {
             db,
-            query_cache: Arc::new(RwLock::new(HashMap::new())),
+            system,
+            modules,
+            metrics,
+            query_cache: Arc::new(RwLock::new(QueryCache::default())),
             channel_updates: Arc::new(RwLock::new(HashMap::new())),
+            last_run: (0, 0),
+            interval,
+            config,
         }
     }
 
+    /// A cached result that's still fresh. Results saved before a restart are also reused
+    /// once `refreshes` runs out, so the bars re-query gradually over the next runs.
     async fn get_cached_query(
-        cache: &Arc<RwLock<HashMap<String, (f64, std::time::Instant)>>>,
+        cache: &Arc<RwLock<QueryCache>>,
         prometheus_url: &str,
         query: &str,
+        refreshes: &mut usize,
     ) -> Option<f64> {
         let cache_key = format!("{}:{}", prometheus_url, query);
         let cache = cache.read().await;
-        if let Some((value, timestamp)) = cache.get(&cache_key) {
-            if timestamp.elapsed() < Duration::from_secs(60) {
-                return Some(*value);
+        let cached = cache.entries.get(&cache_key)?;
+        if cached.age() < CACHE_TTL {
+            return Some(cached.value);
+        }
+        if cache.restored.contains(&cache_key) && cached.age() < RESTORED_MAX_AGE {
+            if *refreshes == 0 {
+                return Some(cached.value);
             }
+            *refreshes -= 1;
         }
         None
     }
 
     async fn cache_query(
-        cache: &Arc<RwLock<HashMap<String, (f64, std::time::Instant)>>>,
+        cache: &Arc<RwLock<QueryCache>>,
         prometheus_url: &str,
         query: &str,
         value: f64,
     ) {
         let cache_key = format!("{}:{}", prometheus_url, query);
         let mut cache = cache.write().await;
-        cache.insert(cache_key, (value, std::time::Instant::now()));
+        cache.restored.remove(&cache_key);
+        cache.entries.insert(
+            cache_key,
+            CachedQuery {
+                value,
+                fetched_at: SystemTime::now(),
+            },
+        );
+    }
+
+    /// Loads the results saved by the previous process, once.
+    async fn restore_cache(&self) {
+        let mut cache = self.query_cache.write().await;
+        if cache.loaded {
+            return;
+        }
+        cache.loaded = true;
+
+        let saved = self.db.read(|db| db.query_cache.clone()).await;
+        if !saved.is_empty() {
+            info!("Restored {} cached stat queries", saved.len());
+        }
+        cache.restored = saved.keys().cloned().collect();
+        cache.entries = saved;
+    }
+
+    /// Saves results recent enough to be reused after a restart.
+    async fn save_cache(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let saved: HashMap<_, _> = {
+            let mut cache = self.query_cache.write().await;
+            cache.entries.retain(|_, cached| cached.age() < RESTORED_MAX_AGE);
+            cache.entries.clone()
+        };
+        self.db
+            .transaction(|db| {
+                db.query_cache = saved;
+                Ok(())
+            })
+            .await?;
+        Ok(())
     }
 
     async fn can_update_channel(
@@ -96,11 +200,9 @@ impl StatsTask {
             value: (i64, String),
         }
 
-        let client = reqwest::Client::new();
+        let client = http::client();
         let response = client
-            .get(format!("{}/api/v1/query", url))
-            .query(&[("query", query)])
-            .send()
+            .send(client.get(format!("{}/api/v1/query", url)).query(&[("query", query)]))
             .await?;
 
         debug!("Query time: {:?}", start.elapsed());
@@ -117,18 +219,72 @@ impl StatsTask {
         }
     }
 
+    /// Resolves a stat bar query: bot-side for `internal:` metrics, from the guild's named
+    /// queries for `computed:` expressions, otherwise via Prometheus.
+    pub async fn run_query(
+        metrics: &MetricsRegistry,
+        stats: &Database<StatsDatabase>,
+        guild_id: u64,
+        prometheus_url: &str,
+        query: &str,
+    ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+        if !computed::is_computed(query) {
+            return Self::run_simple_query(metrics, guild_id, prometheus_url, query).await;
+        }
+
+        let expr = computed::parse(query)?;
+        let named = stats.named_queries(guild_id).await;
+        let mut values = HashMap::new();
+        for name in expr.names() {
+            let Some(named_query) = named.get(name) else {
+                return Err(format!(
+                    "`{}` isn't a named query. Add it with `/stats queries add`.",
+                    name
+                )
+                .into());
+            };
+            let value =
+                Self::run_simple_query(metrics, guild_id, prometheus_url, named_query).await?;
+            values.insert(name.to_string(), value);
+        }
+        Ok(expr.eval(&values)?)
+    }
+
+    async fn run_simple_query(
+        metrics: &MetricsRegistry,
+        guild_id: u64,
+        prometheus_url: &str,
+        query: &str,
+    ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+        if internal::is_internal(query) {
+            Ok(internal::query(metrics, guild_id, query).await?)
+        } else {
+            Self::query_prometheus(prometheus_url, query).await
+        }
+    }
+
     async fn update_stat_bar(
         &self,
         ctx: &Context,
-        prometheus_url: &str,
+        guild_id: u64,
+        settings: &GuildSettings,
         stat_bar: &mut StatBar,
+        refreshes: &mut usize,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        if !Self::can_update_channel(&self.channel_updates, stat_bar.channel_id).await {
-            return Ok(());
-        }
+        let config = self.config.get();
+        let prometheus_url = match settings.prometheus_url.as_str() {
+            "" => config.prometheus.default_url.as_str(),
+            url => url,
+        };
 
-        let value = if let Some(cached) =
-            Self::get_cached_query(&self.query_cache, prometheus_url, &stat_bar.query).await
+        let bot_side =
+            internal::is_internal(&stat_bar.query) || computed::is_computed(&stat_bar.query);
+        let value = if bot_side {
+            Self::run_query(&self.metrics, &self.db, guild_id, prometheus_url, &stat_bar.query)
+                .await?
+        } else if let Some(cached) =
+            Self::get_cached_query(&self.query_cache, prometheus_url, &stat_bar.query, refreshes)
+                .await
         {
             cached
         } else {
@@ -137,9 +293,40 @@ impl StatsTask {
             value
         };
 
+        if let Some(change) = alerts::evaluate(stat_bar, value, std::time::SystemTime::now()) {
+            live::publish(LiveUpdate::StatAlert {
+                guild_id,
+                channel_id: stat_bar.channel_id,
+                query: stat_bar.query.clone(),
+                value,
+                state: change.state(),
+            });
+            let admin_channel = self.system.get_guild_config(guild_id).await.admin_channel;
+            alerts::notify(ctx, admin_channel, settings, stat_bar, value, change).await;
+            if let Some(alert) = &stat_bar.alert {
+                if let Err(e) = self.db.save_alert_state(guild_id, stat_bar.channel_id, alert).await
+                {
+                    error!("Failed to save alert state of {}: {}", stat_bar.channel_id, e);
+                }
+            }
+        }
+
+        // Alerts are checked above even for channels renamed moments ago
+        if !Self::can_update_channel(&self.channel_updates, stat_bar.channel_id).await {
+            return Ok(());
+        }
+
         let channel = ChannelId::new(stat_bar.channel_id);
-        let formatted_value = stat_bar.data_type.format_value(value);
-        let new_name = stat_bar.format.replace("{value}", &formatted_value);
+        let target = stat_bar.target;
+        let render = |value: f64| -> String {
+            stat_bar
+                .format
+                .replace("{value}", &stat_bar.data_type.format_value(value))
+                .chars()
+                .take(target.max_len())
+                .collect()
+        };
+        let new_text = render(value);
 
         let channel_info =
             match timeout(Duration::from_secs(5), channel.to_channel(&ctx.http)).await {
@@ -154,9 +341,15 @@ impl StatsTask {
                 }
             };
 
-        if let Some(current_name) = channel_info.guild().map(|c| c.name().to_string()) {
-            if current_name == new_name {
+        let current = channel_info.guild().map(|c| match target {
+            StatTarget::ChannelName => c.name().to_string(),
+            StatTarget::Topic => c.topic.clone().unwrap_or_default(),
+        });
+        if let Some(current) = current {
+            if current == new_text {
                 stat_bar.last_value = Some(value);
+                // Whatever was queued is out of date now
+                stat_bar.pending = None;
                 debug!(
                     "Skipping update for {} - value unchanged",
                     stat_bar.channel_id
@@ -165,9 +358,7 @@ impl StatsTask {
             }
 
             if let Some(prev_value) = stat_bar.last_value {
-                let prev_formatted = stat_bar.data_type.format_value(prev_value);
-                let prev_name = stat_bar.format.replace("{value}", &prev_formatted);
-                if new_name == prev_name {
+                if new_text == render(prev_value) {
                     debug!(
                         "Skipping update for {} - formatted value unchanged",
                         stat_bar.channel_id
@@ -178,31 +369,22 @@ impl StatsTask {
         }
 
         debug!(
-            "Updating channel {} to \"{}\"",
-            stat_bar.channel_id, new_name
+            "Updating {} of {} to \"{}\"",
+            target, stat_bar.channel_id, new_text
         );
 
-        match timeout(
-            Duration::from_secs(5),
-            channel.edit(&ctx.http, EditChannel::default().name(&new_name)),
-        )
-        .await
-        {
-            Ok(Ok(_)) => {
+        match rename::apply(ctx, stat_bar, new_text).await {
+            Ok(outcome) => {
                 stat_bar.last_value = Some(value);
                 stat_bar.last_update = Some(std::time::SystemTime::now());
-                debug!(
-                    "Updated stat bar {} to \"{}\"",
-                    stat_bar.channel_id, new_name
-                );
+                match outcome {
+                    Outcome::Applied => debug!("Updated stat bar {}", stat_bar.channel_id),
+                    Outcome::Queued => debug!("Queued update for stat bar {}", stat_bar.channel_id),
+                }
             }
-            Ok(Err(e)) => {
+            Err(e) => {
                 error!("Failed to update channel {}: {}", stat_bar.channel_id, e);
-                return Err(e.into());
-            }
-            Err(_) => {
-                error!("Timeout updating channel {}", stat_bar.channel_id);
-                return Err("Channel update timeout".into());
+                return Err(e);
             }
         }
 
@@ -221,7 +403,28 @@ impl Task for StatsTask {
     }
 
     fn schedule(&self) -> Option<Duration> {
-        Some(Duration::from_secs(300))
+        Some(self.interval)
+    }
+
+    fn group(&self) -> TaskGroup {
+        TaskGroup::Network
+    }
+
+    fn max_runtime(&self) -> Option<Duration> {
+        // Bars are updated one at a time with a pause in between, so large setups take a while
+        Some(self.interval.max(Duration::from_secs(10 * 60)))
+    }
+
+    fn health(&self) -> Health {
+        match self.last_run {
+            (0, _) => Health::ok(),
+            (failed, attempted) if failed == attempted => {
+                Health::failed(format!("All {} stat bar updates failed", attempted))
+            }
+            (failed, attempted) => {
+                Health::degraded(format!("{} of {} stat bar updates failed", failed, attempted))
+            }
+        }
     }
 
     async fn execute(
@@ -230,32 +433,29 @@ impl Task for StatsTask {
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let start = std::time::Instant::now();
         info!("Starting stats update");
+        self.restore_cache().await;
 
-        let updates = self
+        let mut updates = self
             .db
             .read(|db| {
                 let mut updates = Vec::new();
                 for (guild_id, bars) in &db.stat_bars {
-                    if let Some(settings) = db.guild_settings.get(guild_id) {
-                        for stat_bar in bars.values() {
-                            let should_update = if let Some(_last_value) = stat_bar.last_value {
-                                let elapsed = stat_bar
-                                    .last_update
-                                    .and_then(|t| t.elapsed().ok())
-                                    .map(|d| d.as_secs())
-                                    .unwrap_or(u64::MAX);
-                                elapsed >= settings.update_delay
-                            } else {
-                                true
-                            };
-
-                            if should_update {
-                                updates.push((
-                                    *guild_id,
-                                    settings.prometheus_url.clone(),
-                                    stat_bar.clone(),
-                                ));
-                            }
+                    // Guilds using only internal metrics may never have set a Prometheus URL
+                    let settings = db.guild_settings.get(guild_id).cloned().unwrap_or_default();
+                    for stat_bar in bars.values() {
+                        let should_update = if let Some(_last_value) = stat_bar.last_value {
+                            let elapsed = stat_bar
+                                .last_update
+                                .and_then(|t| t.elapsed().ok())
+                                .map(|d| d.as_secs())
+                                .unwrap_or(u64::MAX);
+                            elapsed >= settings.update_delay
+                        } else {
+                            true
+                        };
+
+                        if should_update {
+                            updates.push((*guild_id, settings.clone(), stat_bar.clone()));
                         }
                     }
                 }
@@ -263,24 +463,39 @@ impl Task for StatsTask {
             })
             .await;
 
+        // Bars in guilds that turned stats off keep their last value
+        let disabled = self.modules.disabled_guilds(Module::Stats).await;
+        updates.retain(|(guild_id, _, _)| !disabled.contains(guild_id));
+
         debug!("Processing {} stat bars", updates.len());
 
         let mut all_updates = Vec::new();
+        let attempted = updates.len();
+        let mut failed = 0;
+        let mut refreshes = RESTORED_REFRESHES_PER_RUN;
 
-        for (guild_id, prometheus_url, mut stat_bar) in updates {
+        for (guild_id, settings, mut stat_bar) in updates {
             sleep(Duration::from_millis(250)).await;
 
             match timeout(
                 Duration::from_secs(10),
-                self.update_stat_bar(ctx, &prometheus_url, &mut stat_bar),
+                self.update_stat_bar(ctx, guild_id, &settings, &mut stat_bar, &mut refreshes),
             )
             .await
             {
                 Ok(Ok(_)) => all_updates.push((guild_id, stat_bar)),
-                Ok(Err(e)) => error!("Failed to update stat bar {}: {}", stat_bar.channel_id, e),
-                Err(_) => error!("Timeout updating stat bar {}", stat_bar.channel_id),
+                Ok(Err(e)) => {
+                    failed += 1;
+                    error!("Failed to update stat bar {}: {}", stat_bar.channel_id, e)
+                }
+                Err(_) => {
+                    failed += 1;
+                    error!("Timeout updating stat bar {}", stat_bar.channel_id)
+                }
             }
         }
+        self.last_run = (failed, attempted);
+        self.save_cache().await?;
 
         if !all_updates.is_empty() {
             debug!("Writing updates for {} stat bars", all_updates.len());
@@ -288,9 +503,14 @@ impl Task for StatsTask {
 
             self.db
                 .transaction(|db| {
+                    // Bars may have been edited or removed while the run was going
                     for (guild_id, stat_bar) in all_updates {
-                        if let Some(bars) = db.stat_bars.get_mut(&guild_id) {
-                            bars.insert(stat_bar.channel_id, stat_bar);
+                        if let Some(stored) = db
+                            .stat_bars
+                            .get_mut(&guild_id)
+                            .and_then(|bars| bars.get_mut(&stat_bar.channel_id))
+                        {
+                            stored.take_run(stat_bar);
                         }
                     }
                     Ok(())
@@ -313,8 +533,14 @@ impl Clone for StatsTask {
     fn clone(&self) -> Self {
         Self {
             db: self.db.clone(),
+            system: self.system.clone(),
+            modules: self.modules.clone(),
+            metrics: Arc::clone(&self.metrics),
             query_cache: Arc::clone(&self.query_cache),
             channel_updates: Arc::clone(&self.channel_updates),
+            last_run: self.last_run,
+            interval: self.interval,
+            config: Arc::clone(&self.config),
         }
     }
 }
@@ -0,0 +1,144 @@
+//! Per-guild tokens for the HTTP API. Only a SHA-256 hash of each token is stored; the token
+//! itself is shown once when it's created.
+
+use super::database::SystemDatabase;
+use crate::database::Database;
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Prefix of every token, so leaked ones are easy to recognise.
+const TOKEN_PREFIX: &str = "pyro";
+
+/// Tokens a guild can have at once.
+pub const MAX_TOKENS_PER_GUILD: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, poise::ChoiceParameter)]
+pub enum ApiScope {
+    /// Read the guild's data.
+    #[name = "Read only"]
+    Read,
+    /// Read and change the guild's data.
+    Manage,
+}
+
+impl ApiScope {
+    /// Whether a token with this scope may be used for something needing `needed`.
+    pub fn allows(&self, needed: ApiScope) -> bool {
+        *self == ApiScope::Manage || needed == ApiScope::Read
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    /// Public part of the token, shown in listings and used to revoke it.
+    pub id: String,
+    /// Hex SHA-256 of the whole token.
+    pub hash: String,
+    pub scope: ApiScope,
+    pub label: String,
+    pub created_by: u64,
+    /// Unix time.
+    pub created_at: u64,
+}
+
+fn hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn random(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+impl Database<SystemDatabase> {
+    /// Issues a token for the guild, returning it in full. Only its hash is kept.
+    pub async fn create_api_token(
+        &self,
+        guild_id: u64,
+        scope: ApiScope,
+        label: String,
+        created_by: u64,
+        now: u64,
+    ) -> Result<(ApiToken, String), String> {
+        let id = random(8).to_lowercase();
+        let token = format!("{}_{}_{}", TOKEN_PREFIX, id, random(40));
+        let issued = ApiToken {
+            id,
+            hash: hash(&token),
+            scope,
+            label,
+            created_by,
+            created_at: now,
+        };
+
+        self.transaction(|db| {
+            let tokens = db.api_tokens.entry(guild_id).or_default();
+            if tokens.len() >= MAX_TOKENS_PER_GUILD {
+                return Err(format!(
+                    "This server already has {} API tokens; revoke one first",
+                    MAX_TOKENS_PER_GUILD
+                ));
+            }
+            tokens.push(issued.clone());
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok((issued, token))
+    }
+
+    /// Removes the guild's token with this id, returning it if there was one.
+    pub async fn revoke_api_token(
+        &self,
+        guild_id: u64,
+        id: &str,
+    ) -> Result<Option<ApiToken>, String> {
+        self.transaction(|db| {
+            let Some(tokens) = db.api_tokens.get_mut(&guild_id) else {
+                return Ok(None);
+            };
+            let revoked = tokens
+                .iter()
+                .position(|token| token.id.eq_ignore_ascii_case(id.trim()))
+                .map(|index| tokens.remove(index));
+            if tokens.is_empty() {
+                db.api_tokens.remove(&guild_id);
+            }
+            Ok(revoked)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn api_tokens(&self, guild_id: u64) -> Vec<ApiToken> {
+        self.read(|db| db.api_tokens.get(&guild_id).cloned().unwrap_or_default())
+            .await
+    }
+
+    /// The guild and scope `token` grants, if it's a live token.
+    pub async fn verify_api_token(&self, token: &str) -> Option<(u64, ApiScope)> {
+        let mut parts = token.trim().splitn(3, '_');
+        let (Some(TOKEN_PREFIX), Some(id), Some(_)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return None;
+        };
+        let hashed = hash(token.trim());
+
+        self.read(|db| {
+            db.api_tokens.iter().find_map(|(guild_id, tokens)| {
+                tokens
+                    .iter()
+                    .find(|issued| issued.id == id && issued.hash == hashed)
+                    .map(|issued| (*guild_id, issued.scope))
+            })
+        })
+        .await
+    }
+}
@@ -0,0 +1,74 @@
+use super::notify::notify_admins;
+use crate::{databases::Databases, events::EventHandler};
+use async_trait::async_trait;
+use poise::serenity_prelude::{Context, FullEvent};
+use std::sync::Arc;
+use tracing::info;
+
+/// Removes or unsets configuration pointing at channels and roles that were deleted, so
+/// background tasks don't keep failing on them, and tells the guild's admins what changed.
+#[derive(Debug, Clone)]
+pub struct ConfigCleanupHandler {
+    pub dbs: Arc<Databases>,
+}
+
+impl ConfigCleanupHandler {
+    async fn report(&self, ctx: &Context, guild_id: u64, deleted: String, changed: Vec<String>) {
+        if changed.is_empty() {
+            return;
+        }
+        info!("Cleaned up {} after {} was deleted in guild {}", changed.len(), deleted, guild_id);
+
+        let mut report = format!(
+            "🧹 **{} was deleted**, so I updated the settings that used it:",
+            deleted
+        );
+        for change in &changed {
+            report.push_str(&format!("\n- {}", change));
+        }
+        notify_admins(ctx, &self.dbs, guild_id, report).await;
+    }
+}
+
+#[async_trait]
+impl EventHandler for ConfigCleanupHandler {
+    fn name(&self) -> &str {
+        "ConfigCleanup"
+    }
+
+    async fn handle(
+        &self,
+        ctx: &Context,
+        event: &FullEvent,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match event {
+            FullEvent::ChannelDelete { channel, .. } => {
+                let guild_id = channel.guild_id.get();
+                let changed = self.dbs.forget_channel(guild_id, channel.id.get()).await?;
+                self.report(ctx, guild_id, format!("#{}", channel.name), changed)
+                    .await;
+            }
+            FullEvent::GuildRoleDelete {
+                guild_id,
+                removed_role_id,
+                removed_role_data_if_available,
+            } => {
+                let changed = self
+                    .dbs
+                    .forget_role(guild_id.get(), removed_role_id.get())
+                    .await?;
+                let name = match removed_role_data_if_available {
+                    Some(role) => format!("@{}", role.name),
+                    None => format!("Role `{}`", removed_role_id),
+                };
+                self.report(ctx, guild_id.get(), name, changed).await;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn box_clone(&self) -> Box<dyn EventHandler> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,492 @@
+use super::database::Theme;
+use super::guild_config::{self, Setting, SettingKind, SettingValue, SETTINGS};
+use crate::utils::{
+    duration::DurationUnit,
+    embed,
+    reply::{command_paths, say, send},
+    validate::{self, Invalid},
+};
+use crate::{Context, Error};
+use chrono_tz::{Tz, TZ_VARIANTS};
+use poise::serenity_prelude::{ChannelId, ChannelType};
+use poise::{command, serenity_prelude as serenity, ChoiceParameter, CreateReply};
+use std::time::Duration;
+
+pub async fn autocomplete_timezone<'a>(
+    _ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Iterator<Item = serenity::AutocompleteChoice> {
+    let partial = partial.to_lowercase();
+
+    TZ_VARIANTS
+        .iter()
+        .map(|tz| tz.name())
+        .filter(|name| name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|name| serenity::AutocompleteChoice::new(name, name))
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// Set the timezone used when showing times for this server
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn timezone(
+    ctx: Context<'_>,
+    #[description = "Timezone name, e.g. Europe/Berlin"]
+    #[autocomplete = "autocomplete_timezone"]
+    timezone: String,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    let tz: Tz = match timezone.trim().parse() {
+        Ok(tz) => tz,
+        Err(_) => {
+            say(ctx, format!(
+                "❌ Unknown timezone `{}`. Try something like `Europe/Berlin` or `America/New_York`.",
+                timezone
+            ))
+            .await?;
+            return Ok(());
+        }
+    };
+
+    ctx.data().dbs.system.set_timezone(guild_id, tz).await?;
+    say(ctx, format!("🕒 Server timezone set to **{}**.", tz.name()))
+        .await?;
+    Ok(())
+}
+
+/// Set the channel where admin reports (like the weekly health report) are posted
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn admin_channel(
+    ctx: Context<'_>,
+    #[description = "Channel for admin reports (leave empty to DM the owner instead)"]
+    channel: Option<serenity::GuildChannel>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    ctx.data()
+        .dbs
+        .system
+        .set_admin_channel(guild_id, channel.as_ref().map(|c| c.id.get()))
+        .await?;
+
+    match channel {
+        Some(channel) => {
+            say(ctx, format!("✅ Admin reports will be posted in <#{}>.", channel.id))
+                .await?
+        }
+        None => {
+            say(ctx, "✅ Admin reports will be sent to the server owner by DM.")
+                .await?
+        }
+    };
+    Ok(())
+}
+
+/// Parses `#5865F2`, `5865F2` or `0x5865F2`.
+fn parse_color(input: &str) -> Option<u32> {
+    let hex = input.trim().trim_start_matches('#').trim_start_matches("0x");
+    if hex.len() != 6 {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok()
+}
+
+/// Set the accent color, footer and emoji used in the bot's embeds
+///
+/// Leave every option empty to preview the current theme.
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+#[allow(clippy::too_many_arguments)]
+pub async fn theme(
+    ctx: Context<'_>,
+    #[description = "Accent color as hex, e.g. #5865F2"] color: Option<String>,
+    #[description = "Footer text (use \"none\" to remove it)"]
+    #[max_length = 2048]
+    footer: Option<String>,
+    #[description = "Emoji shown before embed titles"] title_emoji: Option<String>,
+    #[description = "Emoji for successes"] success_emoji: Option<String>,
+    #[description = "Emoji for warnings"] warning_emoji: Option<String>,
+    #[description = "Emoji for errors"] error_emoji: Option<String>,
+    #[description = "Go back to the default theme"] reset: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let system = &ctx.data().dbs.system;
+
+    let mut theme = if reset.unwrap_or(false) {
+        Theme::default()
+    } else {
+        system.get_theme(guild_id).await
+    };
+
+    if let Some(color) = color {
+        match parse_color(&color) {
+            Some(color) => theme.accent_color = color,
+            None => {
+                say(ctx, format!("❌ `{}` isn't a hex color like `#5865F2`.", color)).await?;
+                return Ok(());
+            }
+        }
+    }
+    if let Some(footer) = footer {
+        let footer = footer.trim();
+        theme.footer = (!footer.is_empty() && !footer.eq_ignore_ascii_case("none"))
+            .then(|| footer.to_string());
+    }
+    for (emoji, slot) in [
+        (title_emoji, &mut theme.emoji.title),
+        (success_emoji, &mut theme.emoji.success),
+        (warning_emoji, &mut theme.emoji.warning),
+        (error_emoji, &mut theme.emoji.error),
+    ] {
+        if let Some(emoji) = emoji.map(|e| e.trim().to_string()).filter(|e| !e.is_empty()) {
+            *slot = emoji;
+        }
+    }
+
+    let current = system.get_theme(guild_id).await;
+    let changed = theme != current;
+    if changed {
+        system.set_theme(guild_id, theme.clone()).await?;
+    }
+
+    let preview = embed::titled(&theme, "Theme preview").description(format!(
+        "Accent color: `#{:06X}`\n{} Success  {} Warning  {} Error",
+        theme.accent_color, theme.emoji.success, theme.emoji.warning, theme.emoji.error
+    ));
+    let content = if changed {
+        "🎨 Theme updated."
+    } else {
+        "🎨 Current theme:"
+    };
+    send(ctx, CreateReply::default().content(content).embed(preview)).await?;
+    Ok(())
+}
+
+/// Show every module's settings for this server
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn view(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let settings = ctx.data().dbs.guild_config().get(guild_id).await?;
+
+    let mut panel = embed::titled(&settings.server.theme, "Server Settings")
+        .footer(serenity::CreateEmbedFooter::new("Change one with /settings set"));
+    let mut sections: Vec<(&str, Vec<String>)> = Vec::new();
+    for setting in SETTINGS {
+        let (module, name) = setting.key.split_once('.').unwrap_or(("server", setting.key));
+        let line = format!("`{}` {}", name, settings.display(setting));
+        match sections.last_mut() {
+            Some((last, lines)) if *last == module => lines.push(line),
+            _ => sections.push((module, vec![line])),
+        }
+    }
+    for (module, lines) in sections {
+        panel = panel.field(module, lines.join("\n"), false);
+    }
+
+    send(ctx, CreateReply::default().embed(panel)).await?;
+    Ok(())
+}
+
+async fn autocomplete_setting<'a>(
+    _ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Iterator<Item = serenity::AutocompleteChoice> {
+    let partial = partial.to_lowercase();
+
+    SETTINGS
+        .iter()
+        .filter(|setting| setting.key.contains(&partial))
+        .map(|setting| {
+            serenity::AutocompleteChoice::new(
+                format!("{} — {}", setting.key, setting.description),
+                setting.key,
+            )
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// Reads a channel or role ID from a mention like `<#123>` or a bare ID.
+fn parse_id(input: &str) -> Option<u64> {
+    input
+        .trim()
+        .trim_start_matches('<')
+        .trim_start_matches(['#', '@', '&'])
+        .trim_end_matches('>')
+        .parse()
+        .ok()
+        .filter(|id| *id != 0)
+}
+
+/// Checks `input` against the kind of value `setting` holds.
+async fn parse_setting(
+    ctx: Context<'_>,
+    setting: &Setting,
+    input: Option<&str>,
+) -> Result<Option<SettingValue>, Invalid> {
+    let Some(input) = input.map(str::trim).filter(|input| !input.is_empty()) else {
+        if setting.optional {
+            return Ok(None);
+        }
+        return Err(Invalid(format!("`{}` needs a value.", setting.key)));
+    };
+
+    let value = match setting.kind {
+        SettingKind::Timezone => {
+            let tz: Tz = input.parse().map_err(|_| {
+                Invalid(format!(
+                    "Unknown timezone `{}`. Try something like `Europe/Berlin`.",
+                    input
+                ))
+            })?;
+            SettingValue::Text(tz.name().to_string())
+        }
+        SettingKind::Channel | SettingKind::AnnouncementChannel => {
+            let id = parse_id(input).ok_or_else(|| {
+                Invalid(format!("`{}` isn't a channel. Mention one like #general.", input))
+            })?;
+            let (kinds, purpose): (&[ChannelType], _) = match setting.kind {
+                SettingKind::Channel => (
+                    &[ChannelType::Text, ChannelType::News],
+                    "admin reports are posted there",
+                ),
+                _ => (
+                    &[ChannelType::Text, ChannelType::News, ChannelType::Forum],
+                    "Lorax announcements are posted there",
+                ),
+            };
+            validate::channel(ctx, ChannelId::new(id), kinds, purpose).await?;
+            SettingValue::Id(id)
+        }
+        SettingKind::Role => {
+            let exists = |id: &u64| {
+                ctx.guild()
+                    .is_some_and(|guild| guild.roles.contains_key(&serenity::RoleId::new(*id)))
+            };
+            let id = parse_id(input)
+                .filter(exists)
+                .ok_or_else(|| Invalid(format!("`{}` isn't a role in this server.", input)))?;
+            SettingValue::Id(id)
+        }
+        SettingKind::Minutes => {
+            let duration =
+                validate::duration(input, DurationUnit::Minutes, Duration::from_secs(60), None)?;
+            SettingValue::Number(duration.as_secs() / 60)
+        }
+        SettingKind::Seconds => {
+            let duration =
+                validate::duration(input, DurationUnit::Seconds, Duration::from_secs(30), None)?;
+            SettingValue::Number(duration.as_secs())
+        }
+        SettingKind::Count => match input.parse() {
+            Ok(count @ 1..=10) => SettingValue::Number(count),
+            _ => return Err(Invalid(format!("`{}` needs a number from 1 to 10.", setting.key))),
+        },
+        SettingKind::Url => SettingValue::Text(validate::url(input)?),
+        SettingKind::Template => {
+            let template = validate::url(input)?;
+            if !template.contains("{query}") {
+                return Err(Invalid("The template needs a `{query}` placeholder.".to_string()));
+            }
+            SettingValue::Text(template)
+        }
+    };
+    Ok(Some(value))
+}
+
+/// Change any module's setting for this server
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "Setting to change"]
+    #[autocomplete = "autocomplete_setting"]
+    setting: String,
+    #[description = "New value (leave empty to unset it)"] value: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let Some(setting) = guild_config::find(&setting) else {
+        say(ctx, format!(
+            "❌ There's no setting called `{}`. See them all with `/settings view`.",
+            setting
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    let value = parse_setting(ctx, setting, value.as_deref()).await?;
+    let config = ctx.data().dbs.guild_config();
+    config.set(guild_id, setting, value).await?;
+
+    let settings = config.get(guild_id).await?;
+    say(ctx, format!("✅ `{}` is now {}.", setting.key, settings.display(setting))).await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum SettingsModule {
+    Lorax,
+    Stats,
+    Testing,
+}
+
+async fn autocomplete_command<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Iterator<Item = serenity::AutocompleteChoice> {
+    let partial = partial.to_lowercase();
+
+    command_paths(&ctx.framework().options().commands)
+        .into_iter()
+        .filter(|path| path.contains(&partial))
+        .take(25)
+        .map(|path| serenity::AutocompleteChoice::new(path.clone(), path))
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum ReplyVisibility {
+    #[name = "Public"]
+    Public,
+    #[name = "Only visible to the user"]
+    Ephemeral,
+    #[name = "Default"]
+    Default,
+}
+
+/// Choose whether a command's replies are public or only visible to whoever used it
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn replies(
+    ctx: Context<'_>,
+    #[description = "Command or subcommand, e.g. lorax or lorax status"]
+    #[autocomplete = "autocomplete_command"]
+    command: String,
+    #[description = "How replies should be shown"] visibility: ReplyVisibility,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let command = command.trim().trim_start_matches('/').to_lowercase();
+
+    if !command_paths(&ctx.framework().options().commands).contains(&command) {
+        say(ctx, format!("❌ Unknown command `/{}`.", command)).await?;
+        return Ok(());
+    }
+
+    let ephemeral = match visibility {
+        ReplyVisibility::Public => Some(false),
+        ReplyVisibility::Ephemeral => Some(true),
+        ReplyVisibility::Default => None,
+    };
+
+    ctx.data()
+        .dbs
+        .system
+        .set_reply_policy(guild_id, &command, ephemeral)
+        .await?;
+
+    let description = match ephemeral {
+        Some(false) => "will be public",
+        Some(true) => "will only be visible to whoever used it",
+        None => "are back to the default",
+    };
+    say(ctx, format!("✅ Replies to `/{}` {}.", command, description)).await?;
+    Ok(())
+}
+
+/// Limit how often a command can be used in this server
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn cooldown(
+    ctx: Context<'_>,
+    #[description = "Command or subcommand, e.g. lorax submit"]
+    #[autocomplete = "autocomplete_command"]
+    command: String,
+    #[description = "Seconds between uses by the same member (0 for none)"] per_user: Option<u64>,
+    #[description = "Seconds between uses by anyone in the server (0 for none)"]
+    per_server: Option<u64>,
+    #[description = "Go back to the default cooldown"] reset: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let command = command.trim().trim_start_matches('/').to_lowercase();
+
+    if !command_paths(&ctx.framework().options().commands).contains(&command) {
+        say(ctx, format!("❌ Unknown command `/{}`.", command)).await?;
+        return Ok(());
+    }
+
+    let cooldowns = &ctx.data().dbs.cooldowns;
+    if reset.unwrap_or(false) {
+        cooldowns.set_cooldown(guild_id, &command, None).await?;
+    } else if per_user.is_some() || per_server.is_some() {
+        let mut cooldown = cooldowns.cooldown(guild_id, &command).await;
+        cooldown.user_secs = per_user.unwrap_or(cooldown.user_secs);
+        cooldown.guild_secs = per_server.unwrap_or(cooldown.guild_secs);
+        cooldowns.set_cooldown(guild_id, &command, Some(cooldown)).await?;
+    }
+
+    let cooldown = cooldowns.cooldown(guild_id, &command).await;
+    let content = if cooldown.is_off() {
+        format!("⏱️ `/{}` has no cooldown.", command)
+    } else {
+        format!(
+            "⏱️ `/{}` cooldown: **{}s** per member, **{}s** per server.",
+            command, cooldown.user_secs, cooldown.guild_secs
+        )
+    };
+    say(ctx, content).await?;
+    Ok(())
+}
+
+/// Revert the most recent settings change for a module
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn undo(
+    ctx: Context<'_>,
+    #[description = "Module whose last change should be reverted"] module: SettingsModule,
+) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    let reverted = match module {
+        SettingsModule::Lorax => ctx
+            .data()
+            .dbs
+            .lorax
+            .undo_settings(guild_id)
+            .await?
+            .is_some(),
+        SettingsModule::Stats => ctx
+            .data()
+            .dbs
+            .stats
+            .undo_settings(guild_id)
+            .await?
+            .is_some(),
+        SettingsModule::Testing => {
+            // Server limits are shared across guilds, so keep them admin-only
+            let is_admin = ctx
+                .author_member()
+                .await
+                .and_then(|m| m.permissions)
+                .map_or(false, |p| p.administrator());
+            if !is_admin {
+                say(ctx, "❌ Administrator permission required to revert testing limits!")
+                    .await?;
+                return Ok(());
+            }
+            ctx.data().dbs.testing.undo_user_limits().await?
+        }
+    };
+
+    if reverted {
+        say(ctx, format!(
+            "↩️ Reverted the most recent {} settings change.",
+            module.name()
+        ))
+        .await?;
+    } else {
+        say(ctx, format!(
+            "❌ There are no recorded {} settings changes to revert.",
+            module.name()
+        ))
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use crate::{
+    database::{Database, Rows},
+    default_struct,
+    utils::time::parse_timezone,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+use super::api_tokens::ApiToken;
+use super::task_runs::TaskRun;
+
+/// Accent color used when a guild hasn't picked one (Discord blurple).
+pub const DEFAULT_ACCENT_COLOR: u32 = 0x5865F2;
+
+default_struct! {
+/// Emoji used in themed embeds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmojiSet {
+    /// Prefixed to embed titles.
+    pub title: String = "🔹".to_string(),
+    pub success: String = "✅".to_string(),
+    pub warning: String = "⚠️".to_string(),
+    pub error: String = "❌".to_string(),
+}
+}
+
+default_struct! {
+/// Per-guild branding applied by [`crate::utils::embed`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    /// Embed accent color as `0xRRGGBB`.
+    pub accent_color: u32 = DEFAULT_ACCENT_COLOR,
+    pub footer: Option<String>,
+    pub emoji: EmojiSet,
+}
+}
+
+default_struct! {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildConfig {
+    pub timezone: String = "UTC".to_string(),
+    pub admin_channel: Option<u64>,
+    pub last_health_report: Option<u64>,
+    /// Command path (`lorax` or `lorax status`) to whether its replies are ephemeral.
+    pub reply_policy: HashMap<String, bool>,
+    pub theme: Theme,
+}
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct SystemDatabase {
+    pub guilds: HashMap<u64, GuildConfig>,
+    /// HTTP API tokens issued for each guild.
+    pub api_tokens: HashMap<u64, Vec<ApiToken>>,
+    /// Latest runs of each background task, oldest first.
+    pub task_runs: HashMap<String, VecDeque<TaskRun>>,
+}
+
+impl Rows for SystemDatabase {}
+
+impl Database<SystemDatabase> {
+    pub async fn get_guild_config(&self, guild_id: u64) -> GuildConfig {
+        self.read(|db| db.guilds.get(&guild_id).cloned().unwrap_or_default())
+            .await
+    }
+
+    pub async fn get_timezone(&self, guild_id: u64) -> Tz {
+        parse_timezone(&self.get_guild_config(guild_id).await.timezone)
+    }
+
+    pub async fn set_timezone(&self, guild_id: u64, timezone: Tz) -> Result<(), String> {
+        self.transaction(|db| {
+            db.guilds.entry(guild_id).or_default().timezone = timezone.name().to_string();
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn set_admin_channel(&self, guild_id: u64, channel_id: Option<u64>) -> Result<(), String> {
+        self.transaction(|db| {
+            db.guilds.entry(guild_id).or_default().admin_channel = channel_id;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn set_reply_policy(
+        &self,
+        guild_id: u64,
+        command: &str,
+        ephemeral: Option<bool>,
+    ) -> Result<(), String> {
+        self.transaction(|db| {
+            let policy = &mut db.guilds.entry(guild_id).or_default().reply_policy;
+            match ephemeral {
+                Some(ephemeral) => policy.insert(command.to_string(), ephemeral),
+                None => policy.remove(command),
+            };
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn get_theme(&self, guild_id: u64) -> Theme {
+        self.read(|db| db.guilds.get(&guild_id).map(|c| c.theme.clone()).unwrap_or_default())
+            .await
+    }
+
+    pub async fn set_theme(&self, guild_id: u64, theme: Theme) -> Result<(), String> {
+        self.transaction(|db| {
+            db.guilds.entry(guild_id).or_default().theme = theme;
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Unsets a deleted admin channel, returning a description of the change.
+    pub async fn forget_channel(&self, guild_id: u64, channel_id: u64) -> Result<Vec<String>, String> {
+        self.transaction(|db| {
+            match db.guilds.get_mut(&guild_id) {
+                Some(config) if config.admin_channel == Some(channel_id) => {
+                    config.admin_channel = None;
+                    Ok(vec!["Admin channel unset".to_string()])
+                }
+                _ => Ok(Vec::new()),
+            }
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn mark_health_report(&self, guild_id: u64, timestamp: u64) -> Result<(), String> {
+        self.transaction(|db| {
+            db.guilds.entry(guild_id).or_default().last_health_report = Some(timestamp);
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,230 @@
+//! Every module's per-guild settings behind one surface. Values stay in their module's
+//! database, so settings history and `/settings undo` keep working; this maps keys such as
+//! `lorax.voting_duration` onto them for `/settings view` and `/settings set`, and gives
+//! modules one accessor for settings they share.
+
+use super::database::GuildConfig;
+use crate::databases::Databases;
+use crate::modules::lorax::database::LoraxSettings;
+use crate::modules::stats::database::GuildSettings as StatsSettings;
+use crate::utils::{duration::format_duration, time::parse_timezone};
+use chrono_tz::Tz;
+
+/// How a setting's value is entered and shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingKind {
+    Timezone,
+    /// A text channel for bot posts.
+    Channel,
+    /// A channel Lorax announcements can go in: text, news or forum.
+    AnnouncementChannel,
+    Role,
+    /// A duration stored in whole minutes.
+    Minutes,
+    /// A duration stored in seconds.
+    Seconds,
+    Count,
+    Url,
+    /// A URL template with a `{query}` placeholder.
+    Template,
+}
+
+/// One entry of [`SETTINGS`].
+#[derive(Debug)]
+pub struct Setting {
+    pub key: &'static str,
+    pub description: &'static str,
+    pub kind: SettingKind,
+    /// Whether leaving the value empty unsets it.
+    pub optional: bool,
+}
+
+const fn setting(
+    key: &'static str,
+    description: &'static str,
+    kind: SettingKind,
+    optional: bool,
+) -> Setting {
+    Setting {
+        key,
+        description,
+        kind,
+        optional,
+    }
+}
+
+/// Every setting `/settings set` can change, grouped by module.
+pub const SETTINGS: &[Setting] = &[
+    setting("server.timezone", "Timezone times are shown in", SettingKind::Timezone, false),
+    setting("server.admin_channel", "Where admin reports are posted", SettingKind::Channel, true),
+    setting(
+        "lorax.channel",
+        "Where Lorax events are announced",
+        SettingKind::AnnouncementChannel,
+        true,
+    ),
+    setting("lorax.event_role", "Role mentioned for events", SettingKind::Role, true),
+    setting("lorax.winner_role", "Role awarded to winners", SettingKind::Role, true),
+    setting("lorax.alumni_role", "Role for previous winners", SettingKind::Role, true),
+    setting("lorax.submission_duration", "Submission phase", SettingKind::Minutes, false),
+    setting("lorax.voting_duration", "Voting phase", SettingKind::Minutes, false),
+    setting("lorax.tiebreaker_duration", "Tiebreaker rounds", SettingKind::Minutes, false),
+    setting("lorax.max_submissions", "Tree names per user", SettingKind::Count, false),
+    setting("stats.prometheus_url", "Prometheus queried by stat bars", SettingKind::Url, true),
+    setting("stats.update_delay", "Time between stat bar updates", SettingKind::Seconds, false),
+    setting("stats.explore_url", "Link opened from alerts", SettingKind::Template, true),
+];
+
+/// Looks up a setting by key, ignoring case.
+pub fn find(key: &str) -> Option<&'static Setting> {
+    SETTINGS
+        .iter()
+        .find(|setting| setting.key.eq_ignore_ascii_case(key.trim()))
+}
+
+/// A parsed value for a setting; `None` in [`GuildConfigDatabase::set`] unsets it.
+#[derive(Debug, Clone)]
+pub enum SettingValue {
+    Text(String),
+    Id(u64),
+    Number(u64),
+}
+
+/// Every module's settings for one guild.
+#[derive(Debug, Clone)]
+pub struct GuildSettingsSnapshot {
+    pub server: GuildConfig,
+    pub lorax: LoraxSettings,
+    pub stats: StatsSettings,
+}
+
+impl GuildSettingsSnapshot {
+    /// The current value of `setting`, formatted for Discord.
+    pub fn display(&self, setting: &Setting) -> String {
+        let id = |id: Option<u64>, mention: fn(u64) -> String| id.map(mention);
+        let channel = |id: u64| format!("<#{}>", id);
+        let role = |id: u64| format!("<@&{}>", id);
+        let minutes = |mins: u64| Some(format_duration(mins * 60));
+        let text = |text: &str| (!text.is_empty()).then(|| format!("`{}`", text));
+
+        let value = match setting.key {
+            "server.timezone" => Some(self.server.timezone.clone()),
+            "server.admin_channel" => id(self.server.admin_channel, channel),
+            "lorax.channel" => id(self.lorax.lorax_channel, channel),
+            "lorax.event_role" => id(self.lorax.lorax_role, role),
+            "lorax.winner_role" => id(self.lorax.winner_role, role),
+            "lorax.alumni_role" => id(self.lorax.alumni_role, role),
+            "lorax.submission_duration" => minutes(self.lorax.submission_duration),
+            "lorax.voting_duration" => minutes(self.lorax.voting_duration),
+            "lorax.tiebreaker_duration" => minutes(self.lorax.tiebreaker_duration),
+            "lorax.max_submissions" => Some(self.lorax.max_submissions.to_string()),
+            "stats.prometheus_url" => text(&self.stats.prometheus_url),
+            "stats.update_delay" => Some(format_duration(self.stats.update_delay)),
+            "stats.explore_url" => self.stats.explore_url_template.as_deref().and_then(text),
+            _ => None,
+        };
+        value.unwrap_or_else(|| "*not set*".to_string())
+    }
+}
+
+/// Reads and writes settings across the module databases. Get one from
+/// [`Databases::guild_config`].
+pub struct GuildConfigDatabase<'a> {
+    dbs: &'a Databases,
+}
+
+impl Databases {
+    pub fn guild_config(&self) -> GuildConfigDatabase<'_> {
+        GuildConfigDatabase { dbs: self }
+    }
+}
+
+impl GuildConfigDatabase<'_> {
+    pub async fn get(&self, guild_id: u64) -> Result<GuildSettingsSnapshot, String> {
+        Ok(GuildSettingsSnapshot {
+            server: self.dbs.system.get_guild_config(guild_id).await,
+            lorax: self.dbs.lorax.get_settings(guild_id).await?,
+            stats: self.dbs.stats.get_settings(guild_id).await?,
+        })
+    }
+
+    /// Where admin reports and alerts for this guild go, if anywhere.
+    pub async fn admin_channel(&self, guild_id: u64) -> Option<u64> {
+        self.dbs.system.get_guild_config(guild_id).await.admin_channel
+    }
+
+    pub async fn timezone(&self, guild_id: u64) -> Tz {
+        parse_timezone(&self.dbs.system.get_guild_config(guild_id).await.timezone)
+    }
+
+    /// Writes a value already checked against `setting.kind`. Module settings go through
+    /// their module so the change is recorded for `/settings undo`.
+    pub async fn set(
+        &self,
+        guild_id: u64,
+        setting: &Setting,
+        value: Option<SettingValue>,
+    ) -> Result<(), String> {
+        let id = || match &value {
+            Some(SettingValue::Id(id)) => Some(*id),
+            _ => None,
+        };
+        let number = || match &value {
+            Some(SettingValue::Number(number)) => Ok(*number),
+            _ => Err(format!("{} needs a value", setting.key)),
+        };
+        let text = || match &value {
+            Some(SettingValue::Text(text)) => Some(text.clone()),
+            _ => None,
+        };
+
+        match setting.key {
+            "server.timezone" => {
+                let timezone = text().ok_or("server.timezone needs a value")?;
+                self.dbs.system.set_timezone(guild_id, parse_timezone(&timezone)).await
+            }
+            "server.admin_channel" => self.dbs.system.set_admin_channel(guild_id, id()).await,
+            "lorax.channel" => self.lorax(guild_id, |s| s.lorax_channel = id()).await,
+            "lorax.event_role" => self.lorax(guild_id, |s| s.lorax_role = id()).await,
+            "lorax.winner_role" => self.lorax(guild_id, |s| s.winner_role = id()).await,
+            "lorax.alumni_role" => self.lorax(guild_id, |s| s.alumni_role = id()).await,
+            "lorax.submission_duration" => {
+                let mins = number()?;
+                self.lorax(guild_id, |s| s.submission_duration = mins).await
+            }
+            "lorax.voting_duration" => {
+                let mins = number()?;
+                self.lorax(guild_id, |s| s.voting_duration = mins).await
+            }
+            "lorax.tiebreaker_duration" => {
+                let mins = number()?;
+                self.lorax(guild_id, |s| s.tiebreaker_duration = mins).await
+            }
+            "lorax.max_submissions" => {
+                let count = number()? as usize;
+                self.lorax(guild_id, |s| s.max_submissions = count).await
+            }
+            "stats.prometheus_url" => {
+                let url = text().unwrap_or_default();
+                self.stats(guild_id, |s| s.prometheus_url = url).await
+            }
+            "stats.update_delay" => {
+                let secs = number()?;
+                self.stats(guild_id, |s| s.update_delay = secs).await
+            }
+            "stats.explore_url" => {
+                let template = text();
+                self.stats(guild_id, |s| s.explore_url_template = template).await
+            }
+            key => Err(format!("unknown setting {}", key)),
+        }
+    }
+
+    async fn lorax(&self, guild_id: u64, f: impl FnOnce(&mut LoraxSettings)) -> Result<(), String> {
+        self.dbs.lorax.update_settings(guild_id, f).await.map(|_| ())
+    }
+
+    async fn stats(&self, guild_id: u64, f: impl FnOnce(&mut StatsSettings)) -> Result<(), String> {
+        self.dbs.stats.update_settings(guild_id, f).await.map(|_| ())
+    }
+}
@@ -1 +1,39 @@
-pub mod events;
+pub mod api_tokens;
+pub mod cleanup;
+pub mod commands;
+pub mod database;
+pub mod guild_config;
+pub mod notify;
+pub mod owner;
+pub mod presence;
+pub mod retention;
+pub mod task;
+pub mod task_runs;
+
+use crate::modules::admin::api_tokens::api_token;
+use crate::modules::admin::commands::{export, import};
+use commands::*;
+use poise::command;
+
+/// ⚙️ Server-wide bot settings
+#[command(
+    slash_command,
+    subcommands(
+        "view",
+        "set",
+        "timezone",
+        "admin_channel",
+        "replies",
+        "cooldown",
+        "theme",
+        "undo",
+        "export",
+        "import",
+        "api_token"
+    ),
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn settings(_ctx: crate::Context<'_>) -> Result<(), crate::Error> {
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use crate::{databases::Databases, modules::preferences::notify::send_dm};
+use poise::serenity_prelude::{ChannelId, Context, CreateMessage, GuildId};
+use tracing::{error, warn};
+
+/// Posts `content` in the guild's admin channel, or DMs the owner when there is none or it
+/// can't be posted in.
+pub async fn notify_admins(ctx: &Context, dbs: &Databases, guild_id: u64, content: String) {
+    if let Some(channel_id) = dbs.guild_config().admin_channel(guild_id).await {
+        match ChannelId::new(channel_id).say(&ctx.http, &content).await {
+            Ok(_) => return,
+            Err(e) => warn!(
+                "Failed to post to admin channel {} of guild {}: {}",
+                channel_id, guild_id, e
+            ),
+        }
+    }
+
+    match GuildId::new(guild_id).to_partial_guild(ctx).await {
+        Ok(partial) => {
+            send_dm(
+                ctx,
+                &dbs.preferences,
+                partial.owner_id.get(),
+                CreateMessage::default().content(content),
+            )
+            .await;
+        }
+        Err(e) => error!("Failed to look up owner of guild {}: {}", guild_id, e),
+    }
+}
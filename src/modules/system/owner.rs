@@ -0,0 +1,139 @@
+//! Maintenance commands for the bot's owners (`discord.owners` plus the application's
+//! owners), which act on the whole process rather than one guild.
+
+use super::presence::{activity, pin, status};
+use crate::config::PresenceActivity;
+use crate::utils::reply::truncate_message;
+use crate::{Context, Error};
+use poise::command;
+use poise::serenity_prelude::GuildId;
+use tracing::info;
+
+/// 👑 Bot owner maintenance
+#[command(
+    slash_command,
+    subcommands("guilds", "leave", "reload", "flush", "presence"),
+    owners_only
+)]
+pub async fn owner(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// List the guilds this process is in, largest first
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn guilds(ctx: Context<'_>) -> Result<(), Error> {
+    let mut guilds: Vec<_> = ctx
+        .cache()
+        .guilds()
+        .into_iter()
+        .filter_map(|guild_id| {
+            let guild = ctx.cache().guild(guild_id)?;
+            Some((guild.member_count, guild.name.clone(), guild_id))
+        })
+        .collect();
+    guilds.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut lines = vec![format!("🏠 **{} guilds**", guilds.len())];
+    for (members, name, guild_id) in &guilds {
+        lines.push(format!("**{}** `{}` — {} members", name, guild_id, members));
+    }
+
+    let content = truncate_message(lines.join("\n"));
+
+    ctx.say(content).await?;
+    Ok(())
+}
+
+/// Make the bot leave a guild
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn leave(
+    ctx: Context<'_>,
+    #[description = "ID of the guild to leave"] guild: String,
+) -> Result<(), Error> {
+    let Some(guild_id) = guild.trim().parse::<u64>().ok().filter(|id| *id != 0) else {
+        ctx.say("❌ That isn't a guild ID.").await?;
+        return Ok(());
+    };
+    let guild_id = GuildId::new(guild_id);
+    let name = ctx
+        .cache()
+        .guild(guild_id)
+        .map_or_else(|| guild_id.to_string(), |guild| guild.name.clone());
+
+    match guild_id.leave(ctx.http()).await {
+        Ok(()) => {
+            info!("Left guild {} ({}) at the request of {}", name, guild_id, ctx.author().tag());
+            ctx.say(format!("👋 Left **{}**.", name)).await?;
+        }
+        Err(e) => {
+            ctx.say(format!("❌ Couldn't leave **{}**: {}", name, e)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Load the config file and environment again
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn reload(ctx: Context<'_>) -> Result<(), Error> {
+    match ctx.data().config.reload() {
+        Ok(restart_needed) if restart_needed.is_empty() => {
+            ctx.say("🔄 Reloaded the config.").await?;
+        }
+        Ok(restart_needed) => {
+            ctx.say(format!(
+                "🔄 Reloaded the config. Changes to `[{}]` take effect after a restart.",
+                restart_needed.join("]`, `[")
+            ))
+            .await?;
+        }
+        Err(e) => {
+            ctx.say(format!("❌ The config wasn't reloaded: {}", e)).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Write every database to disk now
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn flush(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+    ctx.data().dbs.flush_all().await;
+    ctx.say("💾 Flushed every database to disk.").await?;
+    Ok(())
+}
+
+/// Pin the bot's presence, or resume the configured rotation
+#[command(slash_command, owners_only, ephemeral)]
+pub async fn presence(
+    ctx: Context<'_>,
+    #[description = "Activity text; leave empty to resume the rotation"] text: Option<String>,
+    #[description = "Activity kind"]
+    #[choices("playing", "listening", "watching", "competing")]
+    kind: Option<&'static str>,
+    #[description = "Online status"]
+    #[choices("online", "idle", "dnd", "invisible")]
+    online_status: Option<&'static str>,
+) -> Result<(), Error> {
+    let shards = &ctx.data().shards;
+    let Some(text) = text else {
+        pin(shards, None).await;
+        ctx.say("🔁 Resuming the configured presence rotation.").await?;
+        return Ok(());
+    };
+
+    let kind = kind.unwrap_or("watching");
+    let pinned = activity(
+        &PresenceActivity {
+            kind: kind.to_string(),
+            text: text.clone(),
+        },
+        text.clone(),
+    );
+    pin(shards, Some((Some(pinned), status(online_status.unwrap_or("online"))))).await;
+    ctx.say(format!(
+        "📌 Presence pinned to *{} {}* until it's cleared with `/owner presence`.",
+        kind, text
+    ))
+    .await?;
+    Ok(())
+}
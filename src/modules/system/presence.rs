@@ -0,0 +1,137 @@
+//! Cycles the bot's presence through the activities in `[presence]`, filling in live counts.
+//! Every process sets the presence of its own shards. `/owner presence` can pin one
+//! presence instead, pausing the rotation until it's cleared.
+
+use crate::config::{PresenceActivity, PresenceConfig};
+use crate::modules::lorax::database::LoraxStage;
+use crate::{databases::Databases, tasks::Task};
+use async_trait::async_trait;
+use poise::serenity_prelude::{ActivityData, Context, OnlineStatus, ShardManager};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The presence pinned by `/owner presence`, if any.
+static PINNED: Mutex<Option<(Option<ActivityData>, OnlineStatus)>> = Mutex::new(None);
+
+/// Pins `presence` on every shard this process runs, or resumes the rotation with `None`
+/// from its next run.
+pub async fn pin(shards: &ShardManager, presence: Option<(Option<ActivityData>, OnlineStatus)>) {
+    if let Some((activity, status)) = &presence {
+        for runner in shards.runners.lock().await.values() {
+            runner.runner_tx.set_presence(activity.clone(), *status);
+        }
+    }
+    *PINNED.lock().unwrap() = presence;
+}
+
+#[derive(Clone, Debug)]
+pub struct PresenceTask {
+    dbs: Arc<Databases>,
+    config: PresenceConfig,
+    shards: Arc<ShardManager>,
+    /// Index of the next activity to show.
+    next: usize,
+}
+
+impl PresenceTask {
+    pub fn new(dbs: Arc<Databases>, config: PresenceConfig, shards: Arc<ShardManager>) -> Self {
+        Self {
+            dbs,
+            config,
+            shards,
+            next: 0,
+        }
+    }
+
+    async fn render(&self, ctx: &Context, text: &str) -> String {
+        let mut text = text.to_string();
+        if text.contains("{guilds}") {
+            text = text.replace("{guilds}", &ctx.cache.guild_count().to_string());
+        }
+        if text.contains("{test_servers}") {
+            let servers = self.dbs.testing.read(|db| db.servers.len()).await;
+            text = text.replace("{test_servers}", &servers.to_string());
+        }
+        if text.contains("{lorax_events}") {
+            let events = self
+                .dbs
+                .lorax
+                .read(|db| {
+                    db.events
+                        .values()
+                        .filter(|event| {
+                            !matches!(event.stage, LoraxStage::Inactive | LoraxStage::Completed)
+                        })
+                        .count()
+                })
+                .await;
+            text = text.replace("{lorax_events}", &events.to_string());
+        }
+        text
+    }
+
+    /// Sets the presence on every shard this process runs.
+    async fn set_presence(&self, activity: Option<ActivityData>, status: OnlineStatus) {
+        for runner in self.shards.runners.lock().await.values() {
+            runner.runner_tx.set_presence(activity.clone(), status);
+        }
+    }
+}
+
+pub fn activity(activity: &PresenceActivity, text: String) -> ActivityData {
+    // Kinds are checked when the config is loaded
+    match activity.kind.as_str() {
+        "playing" => ActivityData::playing(text),
+        "listening" => ActivityData::listening(text),
+        "competing" => ActivityData::competing(text),
+        _ => ActivityData::watching(text),
+    }
+}
+
+pub fn status(status: &str) -> OnlineStatus {
+    match status {
+        "online" => OnlineStatus::Online,
+        "idle" => OnlineStatus::Idle,
+        "invisible" => OnlineStatus::Invisible,
+        _ => OnlineStatus::DoNotDisturb,
+    }
+}
+
+#[async_trait]
+impl Task for PresenceTask {
+    fn name(&self) -> &str {
+        "PresenceRotation"
+    }
+
+    fn schedule(&self) -> Option<Duration> {
+        Some(self.config.rotate_interval())
+    }
+
+    async fn execute(
+        &mut self,
+        ctx: &Context,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let pinned = PINNED.lock().unwrap().clone();
+        if let Some((activity, status)) = pinned {
+            self.set_presence(activity, status).await;
+            return Ok(());
+        }
+
+        let status = status(&self.config.status);
+        if self.config.activities.is_empty() {
+            self.set_presence(None, status).await;
+            return Ok(());
+        }
+
+        let current = &self.config.activities[self.next % self.config.activities.len()];
+        let text = self.render(ctx, &current.text).await;
+        self.set_presence(Some(activity(current, text)), status).await;
+
+        self.next = (self.next + 1) % self.config.activities.len();
+        Ok(())
+    }
+
+    fn box_clone(&self) -> Box<dyn Task> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,87 @@
+use crate::config::RetentionConfig;
+use crate::modules::recording::storage;
+use crate::{databases::Databases, tasks::Task};
+use async_trait::async_trait;
+use poise::serenity_prelude::Context;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// Drops module data older than the `[retention]` limits so databases and the recordings
+/// directory stop growing once a guild has been around for a while.
+#[derive(Clone, Debug)]
+pub struct RetentionTask {
+    dbs: Arc<Databases>,
+    config: RetentionConfig,
+}
+
+impl RetentionTask {
+    pub fn new(dbs: Arc<Databases>, config: RetentionConfig) -> Self {
+        Self { dbs, config }
+    }
+}
+
+#[async_trait]
+impl Task for RetentionTask {
+    fn name(&self) -> &str {
+        "Retention"
+    }
+
+    fn schedule(&self) -> Option<Duration> {
+        Some(self.config.interval())
+    }
+
+    async fn execute(
+        &mut self,
+        _ctx: &Context,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut pruned = Vec::new();
+        let mut failures = Vec::new();
+
+        if self.config.lorax_resets > 0 {
+            match self.dbs.lorax.trim_resets(self.config.lorax_resets).await {
+                Ok(count) => pruned.push(format!("{} Lorax reset(s)", count)),
+                Err(e) => failures.push(format!("Lorax resets: {}", e)),
+            }
+        }
+
+        if self.config.audit_days > 0 {
+            let cutoff = now.saturating_sub(self.config.audit_days * DAY_SECS);
+            match self.dbs.audit.prune_before(cutoff).await {
+                Ok(count) => pruned.push(format!("{} audit entries", count)),
+                Err(e) => failures.push(format!("audit entries: {}", e)),
+            }
+        }
+
+        if self.config.recording_days > 0 {
+            let max_age = Duration::from_secs(self.config.recording_days * DAY_SECS);
+            match storage::prune_older_than(max_age).await {
+                Ok((files, bytes)) => {
+                    pruned.push(format!("{} recording file(s), {} bytes", files, bytes))
+                }
+                Err(e) => failures.push(format!("recordings: {}", e)),
+            }
+        }
+
+        if !pruned.is_empty() {
+            info!("Retention pruned {}", pruned.join(", "));
+        }
+        if failures.is_empty() {
+            return Ok(());
+        }
+        for failure in &failures {
+            warn!("Retention failed for {}", failure);
+        }
+        Err(failures.join("; ").into())
+    }
+
+    fn box_clone(&self) -> Box<dyn Task> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,243 @@
+use super::notify::notify_admins;
+use crate::{config::LiveConfig, databases::Databases, tasks::Task};
+use async_trait::async_trait;
+use poise::serenity_prelude::{ChannelId, Context, GuildId, RoleId};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Minimum time between two reports for the same guild.
+const REPORT_INTERVAL_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug)]
+struct HealthIssue {
+    problem: String,
+    fix: String,
+}
+
+impl HealthIssue {
+    fn new(problem: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            problem: problem.into(),
+            fix: fix.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HealthReportTask {
+    dbs: Arc<Databases>,
+    /// Read on each run for the Prometheus URL of guilds without their own.
+    config: Arc<LiveConfig>,
+}
+
+impl HealthReportTask {
+    pub fn new(dbs: Arc<Databases>, config: Arc<LiveConfig>) -> Self {
+        Self { dbs, config }
+    }
+
+    /// Every guild that has configured at least one module.
+    async fn configured_guilds(&self) -> HashSet<u64> {
+        let mut guilds = HashSet::new();
+        guilds.extend(
+            self.dbs
+                .lorax
+                .read(|db| db.settings.keys().chain(db.events.keys()).copied().collect::<Vec<_>>())
+                .await,
+        );
+        guilds.extend(
+            self.dbs
+                .stats
+                .read(|db| {
+                    db.stat_bars
+                        .keys()
+                        .chain(db.guild_settings.keys())
+                        .copied()
+                        .collect::<Vec<_>>()
+                })
+                .await,
+        );
+        guilds.extend(
+            self.dbs
+                .recording
+                .read(|db| db.channels.keys().copied().collect::<Vec<_>>())
+                .await,
+        );
+        guilds
+    }
+
+    async fn audit_guild(
+        &self,
+        ctx: &Context,
+        guild_id: u64,
+    ) -> Result<Vec<HealthIssue>, Box<dyn std::error::Error + Send + Sync>> {
+        let guild = GuildId::new(guild_id);
+        let channels = guild.channels(ctx).await?;
+        let roles = guild.roles(ctx).await?;
+        let channel_exists = |id: u64| channels.contains_key(&ChannelId::new(id));
+        let role_exists = |id: u64| roles.contains_key(&RoleId::new(id));
+
+        let mut issues = Vec::new();
+
+        let (lorax_settings, has_lorax_events) = self
+            .dbs
+            .lorax
+            .read(|db| {
+                (
+                    db.settings.get(&guild_id).cloned(),
+                    db.events.contains_key(&guild_id),
+                )
+            })
+            .await;
+
+        if let Some(settings) = lorax_settings.as_ref() {
+            match settings.lorax_channel {
+                Some(id) if !channel_exists(id) => issues.push(HealthIssue::new(
+                    format!("Lorax announcement channel `{}` no longer exists", id),
+                    "Pick a new one with `/lorax channel`",
+                )),
+                None if has_lorax_events => issues.push(HealthIssue::new(
+                    "Lorax has events but no announcement channel",
+                    "Set one with `/lorax channel`",
+                )),
+                _ => {}
+            }
+
+            for (label, role) in [
+                ("event", settings.lorax_role),
+                ("winner", settings.winner_role),
+                ("alumni", settings.alumni_role),
+            ] {
+                if let Some(id) = role.filter(|id| !role_exists(*id)) {
+                    issues.push(HealthIssue::new(
+                        format!("Lorax {} role `{}` no longer exists", label, id),
+                        "Update it with `/lorax roles`",
+                    ));
+                }
+            }
+        } else if has_lorax_events {
+            issues.push(HealthIssue::new(
+                "Lorax has events but no announcement channel",
+                "Set one with `/lorax channel`",
+            ));
+        }
+
+        let (stat_bars, prometheus_url) = self
+            .dbs
+            .stats
+            .read(|db| {
+                (
+                    db.stat_bars
+                        .get(&guild_id)
+                        .map(|bars| bars.keys().copied().collect::<Vec<_>>())
+                        .unwrap_or_default(),
+                    db.guild_settings
+                        .get(&guild_id)
+                        .map(|s| s.prometheus_url.clone())
+                        .unwrap_or_default(),
+                )
+            })
+            .await;
+
+        for channel_id in stat_bars.iter().filter(|id| !channel_exists(**id)) {
+            issues.push(HealthIssue::new(
+                format!("Stat bar points at deleted channel `{}`", channel_id),
+                format!("Remove it with `/stats remove channel:{}`", channel_id),
+            ));
+        }
+        let default_url_missing = self.config.get().prometheus.default_url.is_empty();
+        if !stat_bars.is_empty() && prometheus_url.is_empty() && default_url_missing {
+            issues.push(HealthIssue::new(
+                "Stat bars are configured but no Prometheus URL is set",
+                "Set one with `/stats set_prometheus`",
+            ));
+        }
+
+        let recording = self
+            .dbs
+            .recording
+            .read(|db| db.channels.get(&guild_id).cloned())
+            .await;
+        if let Some(recording) = recording {
+            if !channel_exists(recording.voice_channel_id) {
+                issues.push(HealthIssue::new(
+                    format!(
+                        "Recording channel `{}` no longer exists, so recordings have nowhere to report",
+                        recording.voice_channel_id
+                    ),
+                    "Pick a new channel with `/recording toggle` or turn it off with `/recording disable`",
+                ));
+            }
+        }
+
+        Ok(issues)
+    }
+
+    async fn deliver(&self, ctx: &Context, guild_id: u64, issues: &[HealthIssue]) {
+        let guild = GuildId::new(guild_id);
+        let guild_name = guild
+            .name(ctx)
+            .unwrap_or_else(|| format!("server {}", guild_id));
+
+        let mut report = format!("🩺 **Weekly health report for {}**\n", guild_name);
+        for issue in issues {
+            report.push_str(&format!("\n⚠️ {}\n> 💡 {}", issue.problem, issue.fix));
+        }
+
+        notify_admins(ctx, &self.dbs, guild_id, report).await;
+    }
+}
+
+#[async_trait]
+impl Task for HealthReportTask {
+    fn name(&self) -> &str {
+        "HealthReport"
+    }
+
+    fn schedule(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60 * 60))
+    }
+
+    async fn execute(
+        &mut self,
+        ctx: &Context,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let now = chrono::Utc::now().timestamp() as u64;
+
+        for guild_id in self.configured_guilds().await {
+            let last_report = self
+                .dbs
+                .system
+                .get_guild_config(guild_id)
+                .await
+                .last_health_report
+                .unwrap_or(0);
+            if now.saturating_sub(last_report) < REPORT_INTERVAL_SECS {
+                continue;
+            }
+            // Left, or not available yet; retried on the next run
+            if ctx.cache.guild(guild_id).is_none() {
+                continue;
+            }
+
+            match self.audit_guild(ctx, guild_id).await {
+                Ok(issues) if issues.is_empty() => {}
+                Ok(issues) => {
+                    info!("Guild {} has {} health issues", guild_id, issues.len());
+                    self.deliver(ctx, guild_id, &issues).await;
+                }
+                // Still counts as this week's report, so a failing guild isn't retried hourly
+                Err(e) => warn!("Failed to audit guild {}: {}", guild_id, e),
+            }
+
+            self.dbs.system.mark_health_report(guild_id, now).await?;
+        }
+
+        Ok(())
+    }
+
+    fn box_clone(&self) -> Box<dyn Task> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,77 @@
+//! Recent runs of each background task, kept across restarts so slow drifts show up.
+
+use super::database::SystemDatabase;
+use crate::database::Database;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RunOutcome {
+    Succeeded,
+    Failed,
+    Panicked,
+    TimedOut,
+}
+
+impl RunOutcome {
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Self::Succeeded => "✅",
+            Self::Failed => "❌",
+            Self::Panicked => "💥",
+            Self::TimedOut => "⏱️",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRun {
+    /// Unix time.
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub outcome: RunOutcome,
+    pub error: Option<String>,
+}
+
+impl Database<SystemDatabase> {
+    /// Records a finished run of `task`, keeping only its latest `keep` runs.
+    pub async fn record_task_run(&self, task: &str, run: TaskRun, keep: usize) -> Result<(), String> {
+        self.transaction(|db| {
+            let runs = db.task_runs.entry(task.to_string()).or_default();
+            runs.push_back(run);
+            while runs.len() > keep {
+                runs.pop_front();
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Recorded runs of `task`, newest first. A guild task's name matches all its instances.
+    pub async fn task_runs(&self, task: &str) -> Vec<(String, TaskRun)> {
+        let instance_prefix = format!("{} (", task);
+        let mut runs: Vec<(String, TaskRun)> = self
+            .read(|db| {
+                db.task_runs
+                    .iter()
+                    .filter(|(name, _)| *name == task || name.starts_with(&instance_prefix))
+                    .flat_map(|(name, runs)| runs.iter().map(|run| (name.clone(), run.clone())))
+                    .collect()
+            })
+            .await;
+        runs.sort_by(|a, b| b.1.started_at.cmp(&a.1.started_at));
+        runs
+    }
+
+    /// Drops the recorded runs of a guild's task instances, returning how many were removed.
+    pub async fn forget_task_runs(&self, guild_id: u64) -> Result<usize, String> {
+        let suffix = format!(" ({})", guild_id);
+        self.transaction(|db| {
+            let before = db.task_runs.len();
+            db.task_runs.retain(|name, _| !name.ends_with(&suffix));
+            Ok(before - db.task_runs.len())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+}
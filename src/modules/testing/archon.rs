@@ -0,0 +1,262 @@
+//! Client for the Archon server API, with an in-process fake for local development.
+
+use crate::utils::http::{self, RetryPolicy};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::{info, warn};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// How long the fake takes to "provision" or delete a server.
+const MOCK_DELAY: Duration = Duration::from_secs(2);
+
+/// Fake UUIDs are this followed by the counter as 12 hex digits.
+const MOCK_UUID_PREFIX: &str = "00000000-0000-4000-8000-";
+
+/// Where a server can be reached and how far Archon has got setting it up.
+#[derive(Debug, Clone)]
+pub struct ServerInfo {
+    /// Host name or IP; missing until the server has been assigned a node.
+    pub address: Option<String>,
+    pub port: u16,
+    /// Archon's status, e.g. `installing` or `running`.
+    pub status: String,
+}
+
+impl ServerInfo {
+    pub fn is_ready(&self) -> bool {
+        self.address.is_some() && !matches!(self.status.as_str(), "installing" | "provisioning")
+    }
+}
+
+#[derive(Debug)]
+pub struct ArchonClient {
+    backend: Backend,
+}
+
+#[derive(Debug)]
+enum Backend {
+    Live { master_key: String, base_url: String },
+    Mock(MockArchon),
+}
+
+/// Fake Archon used with `--mock-archon`: UUIDs come from a counter so runs are
+/// reproducible, and every `fail_every`th request fails to exercise error paths. The counter
+/// continues after the servers already stored, so a restart doesn't hand out their UUIDs.
+#[derive(Debug)]
+struct MockArchon {
+    next_id: AtomicU64,
+    requests: AtomicU64,
+    fail_every: Option<u64>,
+}
+
+/// The counter behind a fake UUID; `None` for anything else.
+fn mock_id(uuid: &str) -> Option<u64> {
+    u64::from_str_radix(uuid.strip_prefix(MOCK_UUID_PREFIX)?, 16).ok()
+}
+
+impl MockArchon {
+    async fn request(&self, action: &str) -> Result<(), Error> {
+        tokio::time::sleep(MOCK_DELAY).await;
+
+        let count = self.requests.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.fail_every.is_some_and(|n| n > 0 && count % n == 0) {
+            warn!("Mock Archon: inducing failure for {} (request #{})", action, count);
+            return Err(format!("Mock Archon induced failure on request #{}", count).into());
+        }
+        Ok(())
+    }
+}
+
+impl ArchonClient {
+    pub fn live(master_key: String, base_url: String) -> Self {
+        Self {
+            backend: Backend::Live {
+                master_key,
+                base_url: base_url.trim_end_matches('/').to_string(),
+            },
+        }
+    }
+
+    /// A fake Archon, given the IDs of servers already stored.
+    pub fn mock(fail_every: Option<u64>, existing: &[String]) -> Self {
+        info!("Using mock Archon API");
+        let last_id = existing.iter().filter_map(|uuid| mock_id(uuid)).max().unwrap_or(0);
+        Self {
+            backend: Backend::Mock(MockArchon {
+                next_id: AtomicU64::new(last_id + 1),
+                requests: AtomicU64::new(0),
+                fail_every,
+            }),
+        }
+    }
+
+    /// Creates a server from an Archon create payload, returning its UUID.
+    pub async fn create_server(&self, payload: &Value) -> Result<String, Error> {
+        match &self.backend {
+            Backend::Live {
+                master_key,
+                base_url,
+            } => {
+                let client = http::client();
+                // Not retried: a timed-out create may still have provisioned a server
+                let response: Value = client
+                    .send_with(
+                        client
+                            .post(format!("{}/servers/create", base_url))
+                            .header("X-MASTER-KEY", master_key)
+                            .json(payload),
+                        RetryPolicy::none(),
+                    )
+                    .await?
+                    .json()
+                    .await?;
+
+                Ok(response["uuid"]
+                    .as_str()
+                    .ok_or("Invalid server ID in response")?
+                    .to_string())
+            }
+            Backend::Mock(mock) => {
+                mock.request("create").await?;
+                let id = mock.next_id.fetch_add(1, Ordering::Relaxed);
+                let uuid = format!("{}{:012x}", MOCK_UUID_PREFIX, id);
+                info!("Mock Archon: created server {} ({})", uuid, payload["name"]);
+                Ok(uuid)
+            }
+        }
+    }
+
+    /// Looks up a server's address and status.
+    pub async fn server_info(&self, server_id: &str) -> Result<ServerInfo, Error> {
+        match &self.backend {
+            Backend::Live {
+                master_key,
+                base_url,
+            } => {
+                let client = http::client();
+                let response: Value = client
+                    .send(
+                        client
+                            .get(format!("{}/servers/{}", base_url, server_id))
+                            .header("X-MASTER-KEY", master_key),
+                    )
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+
+                let net = &response["net"];
+                Ok(ServerInfo {
+                    address: net["domain"]
+                        .as_str()
+                        .or(net["ip"].as_str())
+                        .filter(|address| !address.is_empty())
+                        .map(str::to_string),
+                    port: net["port"].as_u64().unwrap_or(25565) as u16,
+                    status: response["status"].as_str().unwrap_or("unknown").to_string(),
+                })
+            }
+            Backend::Mock(mock) => {
+                mock.request("info").await?;
+                Ok(ServerInfo {
+                    address: Some(format!(
+                        "{}.mock.local",
+                        server_id.rsplit('-').next().unwrap_or(server_id)
+                    )),
+                    port: 25565,
+                    status: "running".to_string(),
+                })
+            }
+        }
+    }
+
+    /// Checks that the API answers at all; any HTTP response counts, even an error status.
+    pub async fn ping(&self) -> Result<(), Error> {
+        match &self.backend {
+            Backend::Live { base_url, .. } => {
+                let client = http::client();
+                client
+                    .send_with(client.get(base_url), RetryPolicy::none())
+                    .await?;
+                Ok(())
+            }
+            Backend::Mock(_) => Ok(()),
+        }
+    }
+
+    /// Installs a build artifact on a server; Archon downloads it from the URL itself.
+    pub async fn install_artifact(&self, server_id: &str, payload: &Value) -> Result<(), Error> {
+        match &self.backend {
+            Backend::Live {
+                master_key,
+                base_url,
+            } => {
+                let client = http::client();
+                client
+                    .send(
+                        client
+                            .post(format!("{}/servers/{}/install", base_url, server_id))
+                            .header("X-MASTER-KEY", master_key)
+                            .json(payload),
+                    )
+                    .await?
+                    .error_for_status()?;
+                Ok(())
+            }
+            Backend::Mock(mock) => {
+                mock.request("install").await?;
+                info!("Mock Archon: installed {} on {}", payload["url"], server_id);
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn delete_server(&self, server_id: &str) -> Result<(), Error> {
+        match &self.backend {
+            Backend::Live {
+                master_key,
+                base_url,
+            } => {
+                let client = http::client();
+                client
+                    .send(
+                        client
+                            .post(format!("{}/servers/{}/delete", base_url, server_id))
+                            .header("X-MASTER-KEY", master_key),
+                    )
+                    .await?;
+                Ok(())
+            }
+            Backend::Mock(mock) => {
+                mock.request("delete").await?;
+                info!("Mock Archon: deleted server {}", server_id);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_ids_continue_after_stored_servers() {
+        let stored = vec![
+            format!("{}{:012x}", MOCK_UUID_PREFIX, 3),
+            format!("{}{:012x}", MOCK_UUID_PREFIX, 0x1a),
+            "9b2c4e1a-5f6d-4c3b-8a7e-2d1f0e9c8b7a".to_string(),
+        ];
+        let Backend::Mock(mock) = ArchonClient::mock(None, &stored).backend else {
+            panic!("expected the mock backend");
+        };
+        assert_eq!(mock.next_id.load(Ordering::Relaxed), 0x1b);
+
+        let Backend::Mock(mock) = ArchonClient::mock(None, &[]).backend else {
+            panic!("expected the mock backend");
+        };
+        assert_eq!(mock.next_id.load(Ordering::Relaxed), 1);
+    }
+}
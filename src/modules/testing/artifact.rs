@@ -0,0 +1,79 @@
+//! Build artifacts `/testing create` installs on a new server: a plugin/mod JAR or a modpack,
+//! fetched by Archon from an allowlisted host such as a GitHub release.
+
+use crate::utils::validate::{self, Invalid};
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    Jar,
+    Modpack,
+}
+
+impl ArtifactKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Jar => "jar",
+            Self::Modpack => "modpack",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub url: String,
+    pub kind: ArtifactKind,
+}
+
+impl Artifact {
+    /// The file name at the end of the URL, for replies.
+    pub fn file_name(&self) -> &str {
+        self.url.rsplit('/').next().unwrap_or(&self.url)
+    }
+
+    pub fn install_payload(&self) -> Value {
+        json!({ "url": self.url, "kind": self.kind.as_str() })
+    }
+}
+
+fn host_allowed(host: &str, allowed: &[String]) -> bool {
+    allowed.iter().any(|domain| {
+        let domain = domain.trim().trim_start_matches('.').to_lowercase();
+        host == domain || host.ends_with(&format!(".{}", domain))
+    })
+}
+
+/// An https URL on an allowlisted host ending in `.jar`, `.mrpack` or `.zip`.
+pub fn parse(input: &str, allowed: &[String]) -> Result<Artifact, Invalid> {
+    let url = validate::url(input)?;
+    let parsed = reqwest::Url::parse(&url).map_err(|e| Invalid(e.to_string()))?;
+
+    if parsed.scheme() != "https" {
+        return Err(Invalid("Artifact URLs must use `https://`.".to_string()));
+    }
+    let host = parsed.host_str().unwrap_or_default().to_lowercase();
+    if !host_allowed(&host, allowed) {
+        return Err(Invalid(format!(
+            "`{}` isn't an allowed artifact host. Allowed: {}.",
+            host,
+            allowed
+                .iter()
+                .map(|domain| format!("`{}`", domain))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+
+    let path = parsed.path().to_lowercase();
+    let kind = if path.ends_with(".jar") {
+        ArtifactKind::Jar
+    } else if path.ends_with(".mrpack") || path.ends_with(".zip") {
+        ArtifactKind::Modpack
+    } else {
+        return Err(Invalid(
+            "The artifact must be a `.jar` file or a `.mrpack`/`.zip` modpack.".to_string(),
+        ));
+    };
+
+    Ok(Artifact { url, kind })
+}
@@ -1,10 +1,20 @@
+use super::artifact::{self, ArtifactKind};
 use super::database::TestServer;
-use crate::{Context, Error};
+use super::slp;
+use super::templates::{self, autocomplete_template};
+use crate::utils::plan::Plan;
+use crate::utils::reply::{defer, say, send};
+use crate::{
+    utils::{
+        duration::{format_duration, DurationUnit},
+        embed, validate,
+    },
+    Context, Error,
+};
 use poise::serenity_prelude::{self as serenity, ButtonStyle, CreateActionRow, CreateButton};
 use poise::{command, CreateReply};
-use serde_json::{json, Value};
+use serde_json::json;
 use std::time::{Duration, SystemTime};
-use reqwest::Client;
 use tracing::error;
 
 const MAX_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
@@ -17,26 +27,6 @@ async fn format_expiry(time: SystemTime) -> String {
     format!("<t:{}:R>", expires)
 }
 
-async fn send_api_request(
-    ctx: Context<'_>,
-    url: &str,
-    method: reqwest::Method,
-    payload: Option<Value>,
-) -> Result<Value, Error> {
-    let client = Client::new();
-    let mut request = client
-        .request(method, url)
-        .header("X-MASTER-KEY", &ctx.data().config.master_key);
-
-    if let Some(payload) = payload {
-        request = request.json(&payload);
-    }
-
-    let response = request.send().await?;
-    let response: Value = response.json().await?;
-    Ok(response)
-}
-
 async fn check_administrator(ctx: &Context<'_>) -> bool {
     let Some(member) = ctx.author_member().await else { return false };
     let Some(_guild) = ctx.guild() else { return false };
@@ -52,35 +42,67 @@ async fn check_administrator(ctx: &Context<'_>) -> bool {
 pub async fn create(
     ctx: Context<'_>,
     #[description = "Server name (defaults to your username)"] name: Option<String>,
-    #[description = "Lifetime in hours (admins: unlimited, others: max 24)"] hours: Option<u64>,
+    #[description = "Preset to set the server up from"]
+    #[autocomplete = "autocomplete_template"]
+    template: Option<String>,
+    #[description = "Lifetime, e.g. 8h or 1d12h (admins: unlimited, others: max 24h)"]
+    lifetime: Option<String>,
     #[description = "Create for another user (admin only)"] user: Option<serenity::User>,
     #[description = "Create for specific Modrinth ID (admin only)"] modrinth_id: Option<String>,
     #[description = "RAM in GB (admin only)"] ram_gb: Option<f32>,
+    #[description = "JAR or modpack URL to install, e.g. a GitHub release asset"]
+    artifact: Option<String>,
 ) -> Result<(), Error> {
-    ctx.defer_ephemeral().await?;
+    defer(ctx).await?;
+
+    let artifact = match artifact {
+        Some(url) => Some(artifact::parse(&url, &ctx.data().config.get().archon.artifact_domains)?),
+        None => None,
+    };
 
     let is_admin = check_administrator(&ctx).await;
 
     // Ensure only admins can use user/modrinth_id parameters
     if (user.is_some() || modrinth_id.is_some()) && !is_admin {
-        ctx.say("❌ Administrator permission required to create servers for others!").await?;
+        say(ctx, "❌ Administrator permission required to create servers for others!").await?;
         return Ok(());
     }
 
     // Ensure only one of user or modrinth_id is specified
     if user.is_some() && modrinth_id.is_some() {
-        ctx.say("❌ Cannot specify both user and Modrinth ID!").await?;
+        say(ctx, "❌ Cannot specify both user and Modrinth ID!").await?;
         return Ok(());
     }
 
+    let template = match template {
+        Some(name) => match ctx.data().dbs.testing.get_template(&name).await {
+            Some(template) => Some(template),
+            None => {
+                say(ctx, format!(
+                    "❌ There is no template called **{}**. See `/servers template list`.",
+                    name
+                ))
+                .await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    if template.is_some() && artifact.as_ref().is_some_and(|a| a.kind == ArtifactKind::Modpack) {
+        say(ctx, "❌ A modpack artifact sets the whole server up, so it can't be combined with a template!").await?;
+        return Ok(());
+    }
+
+    // Templates are set up by admins, so their specs apply to everyone
     let ram_gb = if is_admin {
-        ram_gb.unwrap_or(2.0)
+        ram_gb.or(template.as_ref().map(|t| t.ram_gb)).unwrap_or(2.0)
     } else {
         if ram_gb.is_some() {
-            ctx.say("❌ Only administrators can configure server RAM!").await?;
+            say(ctx, "❌ Only administrators can configure server RAM!").await?;
             return Ok(());
         }
-        1.0
+        template.as_ref().map_or(1.0, |t| t.ram_gb)
     };
 
     // Resolve user ID and Modrinth ID
@@ -89,7 +111,7 @@ pub async fn create(
         match ctx.data().dbs.modrinth.get_modrinth_id(user_id).await {
             Some(id) => (user_id, id),
             None => {
-                ctx.say("❌ Target user has not linked their Modrinth account!").await?;
+                say(ctx, "❌ Target user has not linked their Modrinth account!").await?;
                 return Ok(());
             }
         }
@@ -102,7 +124,7 @@ pub async fn create(
         match ctx.data().dbs.modrinth.get_modrinth_id(user_id).await {
             Some(id) => (user_id, id),
             None => {
-                ctx.say("❌ Please link your Modrinth account first:\n> Use `/modrinth link` to get started").await?;
+                say(ctx, "❌ Please link your Modrinth account first:\n> Use `/modrinth link` to get started").await?;
                 return Ok(());
             }
         }
@@ -112,7 +134,7 @@ pub async fn create(
     let user_limit = ctx.data().dbs.testing.get_user_limit(user_id).await;
 
     if current_servers.len() >= user_limit {
-        ctx.say(format!(
+        say(ctx, format!(
             "❌ User has reached their server limit ({}/{})",
             current_servers.len(), user_limit
         )).await?;
@@ -130,15 +152,30 @@ pub async fn create(
         .filter(|n| !n.is_empty())
         .unwrap_or_else(|| format!("{}'s Test Server", username));
 
-    let duration = Duration::from_secs(hours.unwrap_or(8) * 3600);
-    if !is_admin && duration > MAX_DURATION {
-        ctx.say("❌ Maximum server duration is 24 hours for non-administrator users!").await?;
-        return Ok(());
-    }
+    let max = (!is_admin).then_some(MAX_DURATION);
+    let duration = match lifetime {
+        Some(lifetime) => {
+            validate::duration(&lifetime, DurationUnit::Hours, Duration::from_secs(1), max)?
+        }
+        None => Duration::from_secs(8 * 3600),
+    };
 
-    ctx.defer().await?;
+    defer(ctx).await?;
 
     let base_ram = (ram_gb * 1024.0) as u32;
+    let source = match &template {
+        Some(template) => json!({
+            "loader": template.loader,
+            "game_version": template.game_version,
+            "loader_version": template.loader_version,
+            "projects": template.projects,
+        }),
+        None => json!({
+            "loader": "Vanilla",
+            "game_version": "latest",
+            "loader_version": "latest"
+        }),
+    };
     let payload = json!({
         "user_id": modrinth_id,
         "name": server_name,
@@ -149,40 +186,56 @@ pub async fn create(
             "swap_mb": base_ram / 4,
             "storage_mb": base_ram * 8,
         },
-        "source": {
-            "loader": "Vanilla",
-            "game_version": "latest",
-            "loader_version": "latest"
-        }
+        "source": source
     });
 
-    let response = send_api_request(
-        ctx.clone(),
-        "https://archon.pyro.host/modrinth/v0/servers/create",
-        reqwest::Method::POST,
-        Some(payload),
-    ).await?;
+    let server_id = ctx.data().archon.create_server(&payload).await?;
 
-    let server_id = response["uuid"]
-        .as_str()
-        .ok_or("Invalid server ID in response")?;
+    // The server exists either way, so a failed install is reported rather than undone
+    let installed = match &artifact {
+        Some(artifact) => match ctx
+            .data()
+            .archon
+            .install_artifact(&server_id, &artifact.install_payload())
+            .await
+        {
+            Ok(()) => format!("\n> Installed: `{}`", artifact.file_name()),
+            Err(e) => {
+                error!("Failed to install {} on {}: {}", artifact.url, server_id, e);
+                format!(
+                    "\n> ⚠️ Couldn't install `{}`; upload it from the panel instead.",
+                    artifact.file_name()
+                )
+            }
+        },
+        None => String::new(),
+    };
 
+    let guild_id = validate::guild(ctx)?.get();
     let server = TestServer {
-        server_id: server_id.to_string(),
+        server_id: server_id.clone(),
         user_id,
         name: server_name.clone(),
         created_at: SystemTime::now(),
         expires_at: SystemTime::now() + duration,
+        reminder_sent: false,
+        memory_mb: base_ram,
+        guild_id: Some(guild_id),
     };
 
     let expires_at = server.expires_at;
-    ctx.data().dbs.testing.add_server(server).await?;
+    ctx.data().dbs.testing.add_server(server, guild_id).await?;
 
     let expiry_str = format_expiry(expires_at).await;
 
-    ctx.say(format!(
-        "✅ Created test server successfully!\n> **{}**\n> Expires {}\n> Manage at: https://modrinth.com/servers/manage/{}",
+    let setup = template
+        .map(|t| format!("\n> Template: {} ({})", t.name, templates::describe(&t)))
+        .unwrap_or_default();
+    say(ctx, format!(
+        "✅ Created test server successfully!\n> **{}**{}{}\n> Expires {}\n> Manage at: https://modrinth.com/servers/manage/{}",
         server_name,
+        setup,
+        installed,
         expiry_str,
         server_id
     )).await?;
@@ -190,6 +243,68 @@ pub async fn create(
     Ok(())
 }
 
+/// How long `/servers connect` waits for a new server to finish provisioning.
+const READY_TIMEOUT: Duration = Duration::from_secs(120);
+const READY_POLL: Duration = Duration::from_secs(10);
+
+/// Show how to join a test server
+///
+/// Waits for the server to finish provisioning, then shows its address and whether it answers
+/// players yet.
+#[command(slash_command, guild_only, ephemeral)]
+pub async fn connect(
+    ctx: Context<'_>,
+    #[description = "Server to connect to (defaults to your only one)"]
+    #[autocomplete = "autocomplete_server_id"]
+    server: Option<String>,
+) -> Result<(), Error> {
+    defer(ctx).await?;
+
+    let is_admin = check_administrator(&ctx).await;
+    let Some(server) = pick_server(ctx, server, is_admin).await? else {
+        return Ok(());
+    };
+
+    let archon = &ctx.data().archon;
+    let deadline = tokio::time::Instant::now() + READY_TIMEOUT;
+    let info = loop {
+        let info = archon.server_info(&server.server_id).await?;
+        if info.is_ready() || tokio::time::Instant::now() >= deadline {
+            break info;
+        }
+        tokio::time::sleep(READY_POLL).await;
+    };
+
+    let Some(address) = info.address.as_deref().filter(|_| info.is_ready()) else {
+        say(ctx, format!(
+            "⏳ **{}** is still being set up (`{}`). Try again in a minute!",
+            server.name, info.status
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    let target = if info.port == 25565 {
+        address.to_string()
+    } else {
+        format!("{}:{}", address, info.port)
+    };
+    let state = match slp::ping(address, info.port, Duration::from_secs(5)).await {
+        Ok(status) => format!(
+            "🟢 Online • {} • {}/{} players",
+            status.version, status.online, status.max
+        ),
+        Err(_) => format!("🔴 Not answering yet (Archon: `{}`)", info.status),
+    };
+
+    say(ctx, format!(
+        "🔌 **{}**\n> Address: `{}`\n> {}\n```\n/server connect {}\n```",
+        server.name, target, state, target
+    ))
+    .await?;
+    Ok(())
+}
+
 /// Set the maximum number of test servers a user can create
 /// 
 /// Administrators can grant users the ability to create multiple test servers simultaneously.
@@ -211,7 +326,7 @@ pub async fn setlimit(
     let limit = limit.unwrap_or(1);
     ctx.data().dbs.testing.set_user_limit(user.id.get(), limit).await?;
 
-    ctx.say(format!(
+    say(ctx, format!(
         "✅ Set {}'s server limit to {}",
         user.name, limit
     )).await?;
@@ -234,29 +349,87 @@ pub async fn limits(ctx: Context<'_>) -> Result<(), Error> {
         .await;
 
     if limits.is_empty() {
-        ctx.say("📊 No custom server limits set.").await?;
+        say(ctx, "📊 No custom server limits set.").await?;
         return Ok(());
     }
 
-    let mut response = String::from("📊 **Custom Server Limits**\n");
+    let mut response = String::new();
     for (user_id, limit) in limits {
         response.push_str(&format!("• <@{}> - {} servers\n", user_id, limit));
     }
 
-    ctx.say(response).await?;
+    let guild_id = validate::guild(ctx)?.get();
+    let theme = ctx.data().dbs.system.get_theme(guild_id).await;
+    let panel = embed::titled(&theme, "Custom Server Limits").description(response);
+    send(ctx, CreateReply::default().embed(panel)).await?;
     Ok(())
 }
 
 /// Helper function for server ID autocomplete
+/// The server a command acts on: the one picked, which must be the author's unless they're an
+/// administrator, or else the author's only server. Replies and returns `None` otherwise.
+async fn pick_server(
+    ctx: Context<'_>,
+    server_id: Option<String>,
+    is_admin: bool,
+) -> Result<Option<TestServer>, Error> {
+    let user_id = ctx.author().id.get();
+    let Some(server_id) = server_id else {
+        let mut servers = ctx.data().dbs.testing.get_user_servers(user_id).await;
+        return Ok(match servers.len() {
+            0 => {
+                say(ctx, "❌ You don't have an active server!").await?;
+                None
+            }
+            1 => servers.pop(),
+            count => {
+                say(ctx, format!(
+                    "❌ You have {} servers; pick one with the `server` option.",
+                    count
+                ))
+                .await?;
+                None
+            }
+        });
+    };
+
+    let server = ctx
+        .data()
+        .dbs
+        .testing
+        .read(|db| db.servers.get(&server_id).cloned())
+        .await;
+    match server {
+        Some(server) if server.user_id == user_id || is_admin => Ok(Some(server)),
+        Some(_) => {
+            say(ctx, "❌ You can only manage your own servers!").await?;
+            Ok(None)
+        }
+        None => {
+            say(ctx, "❌ Server not found!").await?;
+            Ok(None)
+        }
+    }
+}
+
+/// Administrators can pick any server; everyone else sees their own.
 async fn autocomplete_server_id<'a>(
     ctx: Context<'_>,
     partial: &'a str,
 ) -> impl Iterator<Item = serenity::AutocompleteChoice> {
+    let is_admin = check_administrator(&ctx).await;
+    let user_id = ctx.author().id.get();
     let servers = ctx
         .data()
         .dbs
         .testing
-        .read(|db| db.servers.values().cloned().collect::<Vec<_>>())
+        .read(|db| {
+            db.servers
+                .values()
+                .filter(|server| is_admin || server.user_id == user_id)
+                .cloned()
+                .collect::<Vec<_>>()
+        })
         .await;
 
     let usernames: Vec<String> = servers
@@ -298,54 +471,44 @@ async fn autocomplete_server_id<'a>(
 )]
 pub async fn delete(
     ctx: Context<'_>,
-    #[description = "Specific server to delete (admins only)"]
+    #[description = "Server to delete (defaults to your only one)"]
     #[autocomplete = "autocomplete_server_id"]
-    server_id: Option<String>,
+    server: Option<String>,
     #[description = "Delete all of your servers"] 
     all: Option<bool>,
+    #[description = "Only show what would be deleted"] dry_run: Option<bool>,
 ) -> Result<(), Error> {
-    ctx.defer_ephemeral().await?;
+    defer(ctx).await?;
 
     let is_admin = check_administrator(&ctx).await;
     let user_id = ctx.author().id.get();
 
-    let servers = if let Some(server_id) = server_id {
-        // Admin deleting specific server
-        if !is_admin {
-            ctx.say("❌ Administrator permission required to delete specific servers!")
-                .await?;
-            return Ok(());
-        }
-
-        if let Some(server) = ctx.data()
-            .dbs
-            .testing
-            .read(|db| db.servers.get(&server_id).cloned())
-            .await
-        {
-            vec![server]
-        } else {
-            ctx.say("❌ Server not found!").await?;
-            return Ok(());
-        }
-    } else if all.unwrap_or(false) {
+    let servers = if all.unwrap_or(false) {
         // Deleting all user's servers
         let servers = ctx.data().dbs.testing.get_user_servers(user_id).await;
         if servers.is_empty() {
-            ctx.say("❌ You don't have any active servers!").await?;
+            say(ctx, "❌ You don't have any active servers!").await?;
             return Ok(());
         }
         servers
     } else {
-        // Deleting single user server
-        if let Some(server) = ctx.data().dbs.testing.get_user_server(user_id).await {
-            vec![server]
-        } else {
-            ctx.say("❌ You don't have an active server!").await?;
-            return Ok(());
+        match pick_server(ctx, server, is_admin).await? {
+            Some(server) => vec![server],
+            None => return Ok(()),
         }
     };
 
+    let mut plan = Plan::new();
+    for server in &servers {
+        plan.add(format!(
+            "Delete test server **{}** (`{}`) owned by <@{}>",
+            server.name, server.server_id, server.user_id
+        ));
+    }
+    if plan.preview(ctx, dry_run).await? {
+        return Ok(());
+    }
+
     let count = servers.len();
     let multiple = count > 1;
 
@@ -374,7 +537,7 @@ pub async fn delete(
         .content(confirmation)
         .components(vec![action_row]);
 
-    let confirm = ctx.send(reply).await?;
+    let confirm = send(ctx, reply).await?;
     let interaction = confirm
         .message()
         .await?
@@ -399,19 +562,10 @@ pub async fn delete(
         .components(vec![]))
         .await?;
 
-    let client = reqwest::Client::new();
     let mut deleted = 0;
 
     for server in &servers {
-        match client
-            .post(format!(
-                "https://archon.pyro.host/modrinth/v0/servers/{}/delete",
-                server.server_id
-            ))
-            .header("X-MASTER-KEY", &ctx.data().config.master_key)
-            .send()
-            .await
-        {
+        match ctx.data().archon.delete_server(&server.server_id).await {
             Ok(_) => {
                 if let Err(e) = ctx.data()
                     .dbs
@@ -465,11 +619,11 @@ pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
         .await;
 
     if servers.is_empty() {
-        ctx.say("📭 No active test servers.").await?;
+        say(ctx, "📭 No active test servers.").await?;
         return Ok(());
     }
 
-    let mut response = String::from("📊 **Active Test Servers**\n");
+    let mut response = String::new();
     for (i, server) in servers.iter().enumerate() {
         let expires = server
             .expires_at
@@ -488,7 +642,10 @@ pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
         ));
     }
 
-    ctx.say(response).await?;
+    let guild_id = validate::guild(ctx)?.get();
+    let theme = ctx.data().dbs.system.get_theme(guild_id).await;
+    let panel = embed::titled(&theme, "Active Test Servers").description(response.trim_start());
+    send(ctx, CreateReply::default().embed(panel)).await?;
     Ok(())
 }
 
@@ -504,27 +661,21 @@ pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
 )]
 pub async fn extend(
     ctx: Context<'_>,
-    #[description = "Additional hours (admins: unlimited, others: max 24)"]
-    hours: u64,
+    #[description = "New lifetime from now, e.g. 12h or 1d (admins: unlimited, others: max 24h)"]
+    duration: String,
+    #[description = "Server to extend (defaults to your only one)"]
+    #[autocomplete = "autocomplete_server_id"]
+    server: Option<String>,
 ) -> Result<(), Error> {
-    ctx.defer_ephemeral().await?;
+    defer(ctx).await?;
 
     let is_admin = check_administrator(&ctx).await;
-    let duration = Duration::from_secs(hours * 3600);
-    
-    if !is_admin && duration > MAX_DURATION {
-        ctx.say("❌ Maximum extension is 24 hours for non-administrator users!").await?;
-        return Ok(());
-    }
+    let max = (!is_admin).then_some(MAX_DURATION);
+    let duration =
+        validate::duration(&duration, DurationUnit::Hours, Duration::from_secs(1), max)?;
 
-    let user_id = ctx.author().id.get();
-
-    let server = match ctx.data().dbs.testing.get_user_server(user_id).await {
-        Some(s) => s,
-        None => {
-            ctx.say("❌ You don't have a test server!").await?;
-            return Ok(());
-        }
+    let Some(server) = pick_server(ctx, server, is_admin).await? else {
+        return Ok(());
     };
 
     ctx.data()
@@ -538,8 +689,10 @@ pub async fn extend(
         .unwrap()
         .as_secs();
 
-    ctx.say(format!(
-        "✅ Extended server lifetime! New expiry: <t:{}:R>",
+    say(ctx, format!(
+        "✅ Extended **{}**! It now expires in {} (<t:{}:R>)",
+        server.name,
+        format_duration(duration.as_secs()),
         new_expiry
     ))
     .await?;
@@ -1,6 +1,12 @@
-use crate::database::Database;
+use crate::{
+    database::{Database, Migration, Rows},
+    utils::{
+        history::SettingsHistory,
+        live::{self, LiveUpdate},
+    },
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::time::{Duration, SystemTime};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,49 +16,125 @@ pub struct TestServer {
     pub name: String,
     pub created_at: SystemTime,
     pub expires_at: SystemTime,
+    pub reminder_sent: bool,
+    pub memory_mb: u32,
+    /// Guild the server was created from; unknown for servers created before it was kept.
+    pub guild_id: Option<u64>,
+}
+
+/// A named server setup QA can create with one option.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestTemplate {
+    pub name: String,
+    /// Archon loader name, e.g. `Fabric` or `Paper`.
+    pub loader: String,
+    pub game_version: String,
+    pub loader_version: String,
+    /// Modrinth projects (mods or plugins) installed on creation, by ID or slug.
+    pub projects: Vec<String>,
+    pub ram_gb: f32,
+    /// Admin who last saved it.
+    pub updated_by: u64,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct TestingDatabase {
     pub servers: HashMap<String, TestServer>,
     pub user_limits: HashMap<u64, usize>,
+    pub limits_history: SettingsHistory<HashMap<u64, usize>>,
+    /// Templates by lowercased name.
+    pub templates: BTreeMap<String, TestTemplate>,
+    /// Servers created from each guild, by month (`YYYY-MM`).
+    pub creations: HashMap<u64, BTreeMap<String, u32>>,
 }
 
-impl Database<TestingDatabase> {
-    pub async fn get_user_server(&self, user_id: u64) -> Option<TestServer> {
-        self.read(|db| db.servers.values().find(|s| s.user_id == user_id).cloned())
-            .await
+impl Rows for TestingDatabase {
+    const VERSION: u32 = 2;
+
+    fn migrations() -> Vec<Box<dyn Migration>> {
+        vec![Box::new(super::migrations::V1ToV2)]
     }
+}
 
-    pub async fn add_server(&self, server: TestServer) -> Result<(), String> {
+impl Database<TestingDatabase> {
+    /// Removes the user's test server records and limit, describing what was removed.
+    /// Deleting the servers themselves from Archon is up to the caller.
+    pub async fn forget_user(&self, user_id: u64) -> Result<Vec<String>, String> {
         self.transaction(|db| {
-            db.servers.insert(server.server_id.clone(), server);
-            Ok(())
+            let mut removed = Vec::new();
+            db.servers.retain(|_, server| {
+                let owned = server.user_id == user_id;
+                if owned {
+                    removed.push(format!("Test server record \"{}\"", server.name));
+                }
+                !owned
+            });
+            if let Some(limit) = db.user_limits.remove(&user_id) {
+                removed.push(format!("Test server limit ({})", limit));
+            }
+            Ok(removed)
         })
         .await
         .map_err(|e| e.to_string())
     }
 
-    pub async fn remove_server(&self, server_id: &str) -> Result<(), String> {
+    /// Adds a new server, counting it towards the guild it was created from.
+    pub async fn add_server(&self, server: TestServer, guild_id: u64) -> Result<(), String> {
+        let month = chrono::Utc::now().format("%Y-%m").to_string();
         self.transaction(|db| {
-            db.servers.remove(server_id);
+            db.servers.insert(server.server_id.clone(), server.clone());
+            *db.creations.entry(guild_id).or_default().entry(month).or_default() += 1;
             Ok(())
         })
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+        publish(&server, "created");
+        Ok(())
     }
 
-    pub async fn extend_server(&self, server_id: &str, duration: Duration) -> Result<(), String> {
-        self.transaction(|db| {
-            if let Some(server) = db.servers.get_mut(server_id) {
-                server.expires_at = SystemTime::now() + duration;
-                Ok(())
-            } else {
-                Err("Server not found".to_string())
-            }
+    /// Servers created from the guild in `month` (`YYYY-MM`).
+    pub async fn creations_in(&self, guild_id: u64, month: &str) -> u32 {
+        self.read(|db| {
+            db.creations
+                .get(&guild_id)
+                .and_then(|months| months.get(month))
+                .copied()
+                .unwrap_or(0)
         })
         .await
-        .map_err(|e| e.to_string())
+    }
+
+    pub async fn remove_server(&self, server_id: &str) -> Result<(), String> {
+        let removed = self
+            .transaction(|db| Ok(db.servers.remove(server_id)))
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Some(server) = removed {
+            publish(&server, "deleted");
+        }
+        Ok(())
+    }
+
+    pub async fn extend_server(&self, server_id: &str, duration: Duration) -> Result<(), String> {
+        let extended = self
+            .transaction(|db| {
+                if let Some(server) = db.servers.get_mut(server_id) {
+                    server.expires_at = SystemTime::now() + duration;
+                    server.reminder_sent = false;
+                    Ok(server.clone())
+                } else {
+                    Err("Server not found".to_string())
+                }
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+        publish(&extended, "extended");
+        Ok(())
+    }
+
+    /// Archon IDs of every stored server.
+    pub async fn server_ids(&self) -> Vec<String> {
+        self.read(|db| db.servers.keys().cloned().collect()).await
     }
 
     pub async fn get_user_servers(&self, user_id: u64) -> Vec<TestServer> {
@@ -73,6 +155,7 @@ impl Database<TestingDatabase> {
 
     pub async fn set_user_limit(&self, user_id: u64, limit: usize) -> Result<(), String> {
         self.transaction(|db| {
+            db.limits_history.record(db.user_limits.clone());
             if limit == 1 {
                 db.user_limits.remove(&user_id);
             } else {
@@ -83,4 +166,47 @@ impl Database<TestingDatabase> {
         .await
         .map_err(|e| e.to_string())
     }
+
+    pub async fn get_template(&self, name: &str) -> Option<TestTemplate> {
+        self.read(|db| db.templates.get(&name.trim().to_lowercase()).cloned())
+            .await
+    }
+
+    /// Adds or replaces a template, returning whether one was replaced.
+    pub async fn save_template(&self, template: TestTemplate) -> Result<bool, String> {
+        self.transaction(|db| {
+            let key = template.name.to_lowercase();
+            Ok(db.templates.insert(key, template).is_some())
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    pub async fn remove_template(&self, name: &str) -> Result<Option<TestTemplate>, String> {
+        self.transaction(|db| Ok(db.templates.remove(&name.trim().to_lowercase())))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Reverts the most recent limit change, returning whether anything was undone.
+    pub async fn undo_user_limits(&self) -> Result<bool, String> {
+        self.transaction(|db| match db.limits_history.pop() {
+            Some(previous) => {
+                db.user_limits = previous;
+                Ok(true)
+            }
+            None => Ok(false),
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+}
+
+fn publish(server: &TestServer, state: &'static str) {
+    live::publish(LiveUpdate::TestServer {
+        server_id: server.server_id.clone(),
+        name: server.name.clone(),
+        user_id: server.user_id,
+        state,
+    });
 }
@@ -0,0 +1,29 @@
+use crate::{database::Database, metrics::MetricsRegistry};
+
+use super::database::TestingDatabase;
+
+pub fn register(registry: &MetricsRegistry, db: Database<TestingDatabase>) {
+    let servers_db = db.clone();
+    registry.register_global_gauge(
+        "testing_servers",
+        "Active test servers across all users",
+        move |_| {
+            let db = servers_db.clone();
+            async move { Ok::<_, String>(db.read(|db| db.servers.len()).await as f64) }
+        },
+    );
+
+    registry.register_global_gauge(
+        "testing_memory_gb",
+        "Memory allocated to active test servers, in GB",
+        move |_| {
+            let db = db.clone();
+            async move {
+                let memory_mb: u64 = db
+                    .read(|db| db.servers.values().map(|s| s.memory_mb as u64).sum())
+                    .await;
+                Ok::<_, String>(memory_mb as f64 / 1024.0)
+            }
+        },
+    );
+}
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use super::database::{TestServer, TestingDatabase};
+use crate::database::{decode, encode, DbError, Migration};
+
+/// RAM of servers created before it was recorded: the `/testing create` default.
+const UNRECORDED_MEMORY_MB: u32 = 2048;
+
+/// The testing schema as first released, before rows were versioned. Frozen: never change
+/// these structs.
+mod v1 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct TestServer {
+        pub server_id: String,
+        pub user_id: u64,
+        pub name: String,
+        pub created_at: SystemTime,
+        pub expires_at: SystemTime,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct TestingDatabase {
+        pub servers: HashMap<String, TestServer>,
+        pub user_limits: HashMap<u64, usize>,
+    }
+}
+
+/// v1 → v2: servers remember whether their expiry reminder went out and their RAM; limits
+/// history, templates and creation counts start out empty.
+pub struct V1ToV2;
+
+impl Migration for V1ToV2 {
+    fn from_version(&self) -> u32 {
+        1
+    }
+
+    fn migrate(&self, _key: &str, bytes: Vec<u8>) -> Result<Vec<u8>, DbError> {
+        let old: v1::TestingDatabase = decode(&bytes)?;
+        let servers = old
+            .servers
+            .into_iter()
+            .map(|(id, server)| {
+                let server = TestServer {
+                    server_id: server.server_id,
+                    user_id: server.user_id,
+                    name: server.name,
+                    created_at: server.created_at,
+                    expires_at: server.expires_at,
+                    // Servers close to expiry still get their reminder
+                    reminder_sent: false,
+                    memory_mb: UNRECORDED_MEMORY_MB,
+                    guild_id: None,
+                };
+                (id, server)
+            })
+            .collect();
+        encode(&TestingDatabase {
+            servers,
+            user_limits: old.user_limits,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Rows;
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    #[test]
+    fn loads_baseline_at_current_version() {
+        let created_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let server = v1::TestServer {
+            server_id: "abc".to_string(),
+            user_id: 7,
+            name: "qa".to_string(),
+            created_at,
+            expires_at: created_at + Duration::from_secs(3600),
+        };
+        let baseline = v1::TestingDatabase {
+            servers: HashMap::from([("abc".to_string(), server)]),
+            user_limits: HashMap::from([(7, 3)]),
+        };
+        let rows = BTreeMap::from([(String::new(), encode(&baseline).unwrap())]);
+
+        let rows = TestingDatabase::upgrade(rows).unwrap();
+        let db: TestingDatabase = decode(&rows[""]).unwrap();
+
+        let server = &db.servers["abc"];
+        assert_eq!(server.user_id, 7);
+        assert_eq!(server.expires_at, created_at + Duration::from_secs(3600));
+        assert!(!server.reminder_sent);
+        assert_eq!(server.memory_mb, UNRECORDED_MEMORY_MB);
+        assert!(server.guild_id.is_none());
+        assert_eq!(db.user_limits[&7], 3);
+        assert!(db.templates.is_empty() && db.creations.is_empty());
+    }
+}
@@ -1,6 +1,12 @@
+pub mod archon;
+pub mod artifact;
 pub mod commands;
 pub mod database;
+pub mod metrics;
+pub mod migrations;
+pub mod slp;
 pub mod task;
+pub mod templates;
 
 use commands::*;
 use poise::command;
@@ -8,7 +14,16 @@ use poise::command;
 /// 🧪 Create and manage temporary Minecraft test servers
 #[command(
     slash_command,
-    subcommands("create", "delete", "list", "extend", "setlimit", "limits"),
+    subcommands(
+        "create",
+        "connect",
+        "delete",
+        "list",
+        "extend",
+        "setlimit",
+        "limits",
+        "templates::template"
+    ),
     guild_only
 )]
 pub async fn servers(_ctx: crate::Context<'_>) -> Result<(), crate::Error> {
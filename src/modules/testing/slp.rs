@@ -0,0 +1,101 @@
+//! Minecraft Server List Ping, to check a test server actually answers players and not just
+//! that Archon thinks it's running.
+
+use serde_json::Value;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// The status packet is a JSON string; anything past this is not a real server.
+const MAX_PACKET: usize = 64 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct ServerStatus {
+    pub version: String,
+    pub online: u64,
+    pub max: u64,
+}
+
+fn write_varint(buf: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7F == 0 {
+            buf.push(value as u8);
+            return;
+        }
+        buf.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+async fn read_varint(stream: &mut TcpStream) -> Result<i32, String> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let byte = stream.read_u8().await.map_err(|e| e.to_string())?;
+        value |= ((byte & 0x7F) as u32) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    Err("VarInt is too long".to_string())
+}
+
+fn packet(id: i32, body: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    write_varint(&mut data, id);
+    data.extend_from_slice(body);
+    let mut packet = Vec::new();
+    write_varint(&mut packet, data.len() as i32);
+    packet.extend(data);
+    packet
+}
+
+async fn exchange(host: &str, port: u16) -> Result<ServerStatus, String> {
+    let mut stream = TcpStream::connect((host, port))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Handshake: any protocol version, then switch to the status state
+    let mut handshake = Vec::new();
+    write_varint(&mut handshake, -1);
+    write_varint(&mut handshake, host.len() as i32);
+    handshake.extend_from_slice(host.as_bytes());
+    handshake.extend_from_slice(&port.to_be_bytes());
+    write_varint(&mut handshake, 1);
+    stream
+        .write_all(&packet(0x00, &handshake))
+        .await
+        .map_err(|e| e.to_string())?;
+    stream
+        .write_all(&packet(0x00, &[]))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let _length = read_varint(&mut stream).await?;
+    if read_varint(&mut stream).await? != 0x00 {
+        return Err("Unexpected status response".to_string());
+    }
+    let json_length = read_varint(&mut stream).await? as usize;
+    if json_length > MAX_PACKET {
+        return Err("Status response is too large".to_string());
+    }
+    let mut json = vec![0; json_length];
+    stream
+        .read_exact(&mut json)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let status: Value = serde_json::from_slice(&json).map_err(|e| e.to_string())?;
+    Ok(ServerStatus {
+        version: status["version"]["name"].as_str().unwrap_or("unknown").to_string(),
+        online: status["players"]["online"].as_u64().unwrap_or(0),
+        max: status["players"]["max"].as_u64().unwrap_or(0),
+    })
+}
+
+/// Asks the server at `host:port` for its status, giving up after `timeout`.
+pub async fn ping(host: &str, port: u16, timeout: Duration) -> Result<ServerStatus, String> {
+    tokio::time::timeout(timeout, exchange(host, port))
+        .await
+        .map_err(|_| "Timed out".to_string())?
+}
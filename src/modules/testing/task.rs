@@ -1,37 +1,95 @@
 use crate::database::Database;
-use crate::tasks::Task;
+use crate::modules::preferences::{database::PreferencesDatabase, notify::send_dm};
+use crate::modules::system::database::SystemDatabase;
+use crate::tasks::{Task, TaskGroup};
 use async_trait::async_trait;
-use poise::serenity_prelude::Context;
+use chrono_tz::Tz;
+use poise::serenity_prelude::{Context, CreateMessage};
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use tracing::{error, info};
 
+use super::archon::ArchonClient;
 use super::database::TestingDatabase;
 
-#[derive(Debug)]
+/// How long before expiry owners get a heads-up DM.
+const REMINDER_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone)]
 pub struct TestingTask {
     db: Database<TestingDatabase>,
-    master_key: String,
+    preferences: Database<PreferencesDatabase>,
+    system: Database<SystemDatabase>,
+    archon: Arc<ArchonClient>,
+    interval: Duration,
 }
 
 impl TestingTask {
-    pub fn new(db: Database<TestingDatabase>, master_key: String) -> Self {
-        Self { db, master_key }
+    pub fn new(
+        db: Database<TestingDatabase>,
+        preferences: Database<PreferencesDatabase>,
+        system: Database<SystemDatabase>,
+        archon: Arc<ArchonClient>,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            db,
+            preferences,
+            system,
+            archon,
+            interval,
+        }
     }
 
-    async fn delete_server(
-        &self,
-        server_id: &str,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let client = reqwest::Client::new();
-        client
-            .post(format!(
-                "https://archon.pyro.host/modrinth/v0/servers/{}/delete",
-                server_id
-            ))
-            .header("X-MASTER-KEY", &self.master_key)
-            .send()
-            .await?;
-        Ok(())
+    async fn send_expiry_reminders(&self, ctx: &Context, now: SystemTime) {
+        let expiring = self
+            .db
+            .read(|db| {
+                db.servers
+                    .values()
+                    .filter(|s| !s.reminder_sent && s.expires_at > now)
+                    .filter(|s| s.expires_at <= now + REMINDER_WINDOW)
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .await;
+
+        for server in expiring {
+            let expires = server
+                .expires_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            // Owners without a timezone of their own see the server's
+            let fallback_tz = match server.guild_id {
+                Some(guild_id) => self.system.get_timezone(guild_id).await,
+                None => Tz::UTC,
+            };
+            let expires_at = self
+                .preferences
+                .format_time(server.user_id, expires, fallback_tz)
+                .await;
+
+            let message = CreateMessage::default().content(format!(
+                "⏰ Your test server **{}** expires <t:{}:R> ({}).\n> Use `/servers extend` if you still need it.",
+                server.name, expires, expires_at
+            ));
+            send_dm(ctx, &self.preferences, server.user_id, message).await;
+
+            // Mark as reminded even if DMs are off so we don't retry every cycle
+            if let Err(e) = self
+                .db
+                .transaction(|db| {
+                    if let Some(server) = db.servers.get_mut(&server.server_id) {
+                        server.reminder_sent = true;
+                    }
+                    Ok(())
+                })
+                .await
+            {
+                error!("Failed to mark reminder for {}: {}", server.server_id, e);
+            }
+        }
     }
 }
 
@@ -42,16 +100,27 @@ impl Task for TestingTask {
     }
 
     fn schedule(&self) -> Option<Duration> {
-        Some(Duration::from_secs(300))
+        Some(self.interval)
+    }
+
+    fn group(&self) -> TaskGroup {
+        TaskGroup::Network
+    }
+
+    fn max_runtime(&self) -> Option<Duration> {
+        // Each Archon call is bounded by the HTTP client, so this only catches a stuck run
+        Some(self.interval.max(Duration::from_secs(5 * 60)))
     }
 
     async fn execute(
         &mut self,
-        _ctx: &Context,
+        ctx: &Context,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         info!("Starting testing servers cleanup");
         let now = SystemTime::now();
 
+        self.send_expiry_reminders(ctx, now).await;
+
         let expired = self
             .db
             .read(|db| {
@@ -64,7 +133,7 @@ impl Task for TestingTask {
             .await;
 
         for server_id in expired {
-            match self.delete_server(&server_id).await {
+            match self.archon.delete_server(&server_id).await {
                 Ok(_) => {
                     if let Err(e) = self.db.remove_server(&server_id).await {
                         error!("Failed to remove server from database: {}", e);
@@ -78,9 +147,6 @@ impl Task for TestingTask {
     }
 
     fn box_clone(&self) -> Box<dyn Task> {
-        Box::new(Self {
-            db: self.db.clone(),
-            master_key: self.master_key.clone(),
-        })
+        Box::new(self.clone())
     }
 }
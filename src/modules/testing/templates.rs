@@ -0,0 +1,192 @@
+//! Named server presets for `/servers create template:`, maintained by admins.
+
+use super::database::TestTemplate;
+use crate::utils::{embed, reply::{say, send}, validate};
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use poise::{command, ChoiceParameter, CreateReply};
+
+/// Most projects a template may install.
+const MAX_PROJECTS: usize = 50;
+
+#[derive(Debug, Clone, Copy, ChoiceParameter)]
+pub enum Loader {
+    Vanilla,
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+    Paper,
+    Purpur,
+}
+
+pub async fn autocomplete_template<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Iterator<Item = serenity::AutocompleteChoice> {
+    let partial = partial.to_lowercase();
+    ctx.data()
+        .dbs
+        .testing
+        .read(|db| {
+            db.templates
+                .iter()
+                .filter(|(key, _)| key.contains(&partial))
+                .take(25)
+                .map(|(_, template)| {
+                    serenity::AutocompleteChoice::new(
+                        template.name.clone(),
+                        template.name.clone(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .into_iter()
+}
+
+/// One line describing what a template sets up.
+pub fn describe(template: &TestTemplate) -> String {
+    let mut line = format!(
+        "{} {} (loader {}) • {} GB",
+        template.loader, template.game_version, template.loader_version, template.ram_gb
+    );
+    if !template.projects.is_empty() {
+        line.push_str(&format!(" • {}", template.projects.join(", ")));
+    }
+    line
+}
+
+/// Parses a comma- or space-separated list of Modrinth project IDs or slugs.
+fn parse_projects(projects: &str) -> Result<Vec<String>, String> {
+    let mut parsed: Vec<String> = Vec::new();
+    for project in projects.split([',', ' ']).map(str::trim).filter(|p| !p.is_empty()) {
+        let valid = project
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid {
+            return Err(format!("`{}` isn't a Modrinth project ID or slug", project));
+        }
+        if !parsed.iter().any(|p| p.eq_ignore_ascii_case(project)) {
+            parsed.push(project.to_string());
+        }
+    }
+    if parsed.len() > MAX_PROJECTS {
+        return Err(format!("A template can install at most {} projects", MAX_PROJECTS));
+    }
+    Ok(parsed)
+}
+
+/// 📋 Server presets for common QA setups
+#[command(
+    slash_command,
+    subcommands("save", "remove", "list"),
+    guild_only,
+    required_permissions = "MANAGE_CHANNELS"
+)]
+pub async fn template(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Create or update a server template
+#[command(slash_command, guild_only, required_permissions = "ADMINISTRATOR", ephemeral)]
+pub async fn save(
+    ctx: Context<'_>,
+    #[description = "Template name, e.g. Fabric 1.21 + Lithium"]
+    #[max_length = 50]
+    name: String,
+    #[description = "Mod or plugin loader"] loader: Loader,
+    #[description = "Minecraft version (default: latest)"] game_version: Option<String>,
+    #[description = "Loader version (default: latest)"] loader_version: Option<String>,
+    #[description = "Modrinth projects to install, by ID or slug, separated by commas"]
+    projects: Option<String>,
+    #[description = "RAM in GB (default: 2)"]
+    #[min = 0.5]
+    #[max = 32]
+    ram_gb: Option<f32>,
+) -> Result<(), Error> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        say(ctx, "❌ Template names can't be empty.").await?;
+        return Ok(());
+    }
+    let projects = match parse_projects(projects.as_deref().unwrap_or_default()) {
+        Ok(projects) => projects,
+        Err(e) => {
+            say(ctx, format!("❌ {}.", e)).await?;
+            return Ok(());
+        }
+    };
+    let version = |version: Option<String>| {
+        version
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "latest".to_string())
+    };
+
+    let template = TestTemplate {
+        name: name.clone(),
+        loader: loader.name().to_string(),
+        game_version: version(game_version),
+        loader_version: version(loader_version),
+        projects,
+        ram_gb: ram_gb.unwrap_or(2.0),
+        updated_by: ctx.author().id.get(),
+    };
+    let description = describe(&template);
+    let replaced = ctx.data().dbs.testing.save_template(template).await?;
+
+    say(ctx, format!(
+        "✅ {} template **{}**\n> {}",
+        if replaced { "Updated" } else { "Saved" },
+        name,
+        description
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Delete a server template
+#[command(slash_command, guild_only, required_permissions = "ADMINISTRATOR", ephemeral)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "Template to delete"]
+    #[autocomplete = "autocomplete_template"]
+    name: String,
+) -> Result<(), Error> {
+    let content = match ctx.data().dbs.testing.remove_template(&name).await? {
+        Some(template) => format!("🗑️ Deleted template **{}**.", template.name),
+        None => format!("❌ There is no template called **{}**.", name),
+    };
+    say(ctx, content).await?;
+    Ok(())
+}
+
+/// List the server templates
+#[command(slash_command, guild_only, required_permissions = "MANAGE_CHANNELS", ephemeral)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let templates = ctx
+        .data()
+        .dbs
+        .testing
+        .read(|db| db.templates.values().cloned().collect::<Vec<_>>())
+        .await;
+
+    if templates.is_empty() {
+        say(ctx, "📭 No templates yet. Admins can add one with `/servers template save`.")
+            .await?;
+        return Ok(());
+    }
+
+    let description = templates
+        .iter()
+        .map(|template| format!("**{}**\n> {}", template.name, describe(template)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let guild_id = validate::guild(ctx)?.get();
+    let theme = ctx.data().dbs.system.get_theme(guild_id).await;
+    let panel = embed::titled(&theme, "Server Templates").description(description);
+    send(ctx, CreateReply::default().embed(panel)).await?;
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use super::database::Module;
+use crate::utils::reply::say;
+use crate::utils::validate;
+use crate::{Context, Error};
+use poise::serenity_prelude::GuildId;
+use poise::{command, ChoiceParameter};
+use tracing::error;
+
+/// Show which modules are on in this server
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD", ephemeral)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+
+    let mut lines = Vec::new();
+    for module in Module::ALL {
+        let enabled = ctx.data().dbs.modules.is_enabled(guild_id, module).await;
+        lines.push(format!(
+            "{} **{}** (`/{}`)",
+            if enabled { "🟢" } else { "🔴" },
+            module.name(),
+            module.command()
+        ));
+    }
+
+    say(ctx, format!("🧩 **Modules**\n{}", lines.join("\n"))).await?;
+    Ok(())
+}
+
+/// Turn a module back on
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn enable(
+    ctx: Context<'_>,
+    #[description = "Module to turn on"] module: Module,
+) -> Result<(), Error> {
+    set(ctx, module, true).await
+}
+
+/// Turn a module off: its commands stop working and it stops reacting to events here
+#[command(slash_command, guild_only, required_permissions = "MANAGE_GUILD")]
+pub async fn disable(
+    ctx: Context<'_>,
+    #[description = "Module to turn off"] module: Module,
+) -> Result<(), Error> {
+    set(ctx, module, false).await
+}
+
+async fn set(ctx: Context<'_>, module: Module, enabled: bool) -> Result<(), Error> {
+    let guild_id = validate::guild(ctx)?.get();
+    let changed = ctx
+        .data()
+        .dbs
+        .modules
+        .set_enabled(guild_id, module, enabled)
+        .await?;
+
+    // Guild tasks such as a running Lorax event follow the module
+    ctx.data().task_manager.sync_guild(guild_id).await;
+
+    // The recording handler won't see the channel empty out any more, so leave now
+    if changed && module == Module::Recording && !enabled {
+        stop_recording(ctx, guild_id).await;
+    }
+
+    let content = match (changed, enabled) {
+        (false, true) => format!("⚪ **{}** is already on.", module.name()),
+        (false, false) => format!("⚪ **{}** is already off.", module.name()),
+        (true, true) => format!("🟢 **{}** is on again.", module.name()),
+        (true, false) => format!(
+            "🔴 **{}** is off. Its settings are kept; turn it back on with `/modules enable`.",
+            module.name()
+        ),
+    };
+    say(ctx, content).await?;
+    Ok(())
+}
+
+async fn stop_recording(ctx: Context<'_>, guild_id: u64) {
+    if let Some(manager) = songbird::get(ctx.serenity_context()).await {
+        if manager.get(GuildId::new(guild_id)).is_some() {
+            if let Err(e) = manager.remove(GuildId::new(guild_id)).await {
+                error!("Failed to leave voice call in {}: {:?}", guild_id, e);
+            }
+        }
+    }
+    if let Err(e) = ctx
+        .data()
+        .dbs
+        .recording
+        .transaction(|db| {
+            if let Some(channel) = db.channels.get_mut(&guild_id) {
+                channel.is_recording = false;
+            }
+            Ok(())
+        })
+        .await
+    {
+        error!("Failed to mark recording in {} as stopped: {}", guild_id, e);
+    }
+}
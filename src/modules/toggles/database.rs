@@ -0,0 +1,89 @@
+use crate::database::{Database, Rows};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// A feature guild admins can turn off with `/modules disable`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, poise::ChoiceParameter,
+)]
+pub enum Module {
+    Lorax,
+    Stats,
+    Recording,
+    Testing,
+}
+
+impl Module {
+    pub const ALL: [Module; 4] = [Self::Lorax, Self::Stats, Self::Recording, Self::Testing];
+
+    /// The top-level command belonging to this module.
+    pub fn command(&self) -> &'static str {
+        match self {
+            Self::Lorax => "lorax",
+            Self::Stats => "stats",
+            Self::Recording => "recording",
+            Self::Testing => "servers",
+        }
+    }
+
+    /// The module a top-level command belongs to, if it can be turned off.
+    pub fn of_command(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|module| module.command() == name)
+    }
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct ModulesDatabase {
+    /// Modules turned off in each guild; everything else is on.
+    pub disabled: HashMap<u64, HashSet<Module>>,
+}
+
+impl Rows for ModulesDatabase {}
+
+impl Database<ModulesDatabase> {
+    pub async fn is_enabled(&self, guild_id: u64, module: Module) -> bool {
+        self.read(|db| {
+            !db.disabled
+                .get(&guild_id)
+                .is_some_and(|disabled| disabled.contains(&module))
+        })
+        .await
+    }
+
+    /// Guilds that turned `module` off.
+    pub async fn disabled_guilds(&self, module: Module) -> HashSet<u64> {
+        self.read(|db| {
+            db.disabled
+                .iter()
+                .filter(|(_, disabled)| disabled.contains(&module))
+                .map(|(guild_id, _)| *guild_id)
+                .collect()
+        })
+        .await
+    }
+
+    /// Turns `module` on or off in a guild, returning whether that changed anything.
+    pub async fn set_enabled(
+        &self,
+        guild_id: u64,
+        module: Module,
+        enabled: bool,
+    ) -> Result<bool, String> {
+        self.transaction(|db| {
+            let disabled = db.disabled.entry(guild_id).or_default();
+            let changed = if enabled {
+                disabled.remove(&module)
+            } else {
+                disabled.insert(module)
+            };
+            if disabled.is_empty() {
+                db.disabled.remove(&guild_id);
+            }
+            Ok(changed)
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,17 @@
+pub mod commands;
+pub mod database;
+
+use commands::*;
+use poise::command;
+
+/// 🧩 Turn the bot's features on or off for this server
+#[command(
+    slash_command,
+    rename = "modules",
+    subcommands("list", "enable", "disable"),
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn toggles(_ctx: crate::Context<'_>) -> Result<(), crate::Error> {
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use crate::utils::reply::say;
+use crate::utils::validate;
 use crate::{Context, Error};
 use chrono::{Datelike, NaiveDate, Utc};
 use std::collections::HashMap;
@@ -14,7 +16,7 @@ struct ServerEntry {
 }
 
 impl ServerEntry {
-    fn parse(block: &str) -> Option<Self> {
+    fn parse(block: &str, today: NaiveDate) -> Option<Self> {
         let lines: Vec<&str> = block
             .lines()
             .filter(|line| !line.trim().is_empty())
@@ -55,10 +57,9 @@ impl ServerEntry {
         let date_str = lines[2].split_whitespace().nth(1)?.trim();
         let date = NaiveDate::parse_from_str(date_str, "%m/%d/%Y").ok()?;
 
-        let now = Utc::now().naive_utc().date();
-        let payment_period = if date.month() == now.month() && date.year() == now.year() {
+        let payment_period = if date.month() == today.month() && date.year() == today.year() {
             "Current Month"
-        } else if date > now {
+        } else if date > today {
             "Future"
         } else {
             "Past"
@@ -130,7 +131,16 @@ pub async fn server_costs<'a>(
         .collect::<Vec<_>>()
         .join("\n");
 
-    // 3) Parse server blocks
+    // 3) Work out "today" in the guild's timezone so month boundaries match local billing
+    let tz = ctx
+        .data()
+        .dbs
+        .system
+        .get_timezone(validate::guild(ctx)?.get())
+        .await;
+    let today = Utc::now().with_timezone(&tz).date_naive();
+
+    // 4) Parse server blocks
     let servers: Vec<ServerEntry> = cleaned_input
         .split("Rapid Deploy Server")
         .filter(|block| !block.trim().is_empty())
@@ -140,7 +150,7 @@ pub async fn server_costs<'a>(
             } else {
                 format!("Rapid Deploy Server - {}", block)
             };
-            match ServerEntry::parse(&full_block) {
+            match ServerEntry::parse(&full_block, today) {
                 Some(server) if server.price > 0.0
                     && server.date > chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap() =>
                 {
@@ -151,13 +161,13 @@ pub async fn server_costs<'a>(
         })
         .collect();
 
-    println!("Parsed {} server entries", servers.len());
+    tracing::debug!("Parsed {} server entries", servers.len());
 
-    let current_month = Utc::now().month();
-    let current_year = Utc::now().year();
+    let current_month = today.month();
+    let current_year = today.year();
 
     if servers.is_empty() {
-        ctx.say("❌ No valid server entries found in input.")
+        say(ctx, "❌ No valid server entries found in input.")
             .await?;
         return Ok(());
     }
@@ -168,7 +178,7 @@ pub async fn server_costs<'a>(
         .collect();
 
     if current_servers.is_empty() {
-        ctx.say("❌ No servers due for payment in the current period.")
+        say(ctx, "❌ No servers due for payment in the current period.")
             .await?;
         return Ok(());
     }
@@ -188,8 +198,10 @@ pub async fn server_costs<'a>(
     }
 
     let mut response = format!(
-        "🔒 **Server Cost Analysis for {}/{}**\n\n",
-        current_month, current_year
+        "🔒 **Server Cost Analysis for {}/{}** ({})\n\n",
+        current_month,
+        current_year,
+        tz.name()
     );
 
     response.push_str("**Payment Period Breakdown:**\n");
@@ -223,6 +235,6 @@ pub async fn server_costs<'a>(
         total_cost / current_servers.len() as f64
     ));
 
-    ctx.say(response).await?;
+    say(ctx, response).await?;
     Ok(())
 }
\ No newline at end of file
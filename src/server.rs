@@ -0,0 +1,311 @@
+//! A small HTTP server for operators, enabled with `[server] listen`. It serves
+//! `/metrics` in the Prometheus text format so the bot can be scraped like any node, and
+//! `/healthz` and `/readyz` for liveness and readiness probes.
+//!
+//! `/api/...` routes answer with JSON for the guild of the API token sent as
+//! `Authorization: Bearer <token>`.
+//!
+//! With `[server] live` on, `/live` upgrades to a WebSocket streaming
+//! [`LiveUpdate`](crate::utils::live::LiveUpdate)s as JSON.
+
+use crate::config::data_path;
+use crate::metrics::{escape_label, write_header};
+use crate::modules::recording::{self, database::MAX_SESSIONS};
+use crate::modules::system::api_tokens::ApiScope;
+use crate::tasks::{TaskStats, DURATION_BUCKETS_MS};
+use crate::Data;
+use axum::{
+    extract::{self, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use poise::serenity_prelude::{ConnectionStage, ShardManager};
+use songbird::Songbird;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::{error, info};
+
+mod websocket;
+
+/// Prefix of every exported metric name.
+const PREFIX: &str = "pyrobot_";
+
+/// How long a readiness check may take before it counts as failed.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the handlers need from the running bot.
+#[derive(Clone)]
+pub struct State {
+    pub data: Arc<Data>,
+    pub songbird: Arc<Songbird>,
+}
+
+/// Starts listening in the background. Failing to bind is logged and otherwise ignored, so
+/// a taken port doesn't keep the bot offline.
+pub fn start(listen: SocketAddr, state: State) {
+    tokio::spawn(async move {
+        let listener = match TcpListener::bind(listen).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Failed to start the HTTP server on {}: {}", listen, e);
+                return;
+            }
+        };
+        info!("HTTP server listening on {}", listen);
+
+        if let Err(e) = axum::serve(listener, router(state)).await {
+            error!("HTTP server on {} stopped: {}", listen, e);
+        }
+    });
+}
+
+fn router(state: State) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics))
+        // Answering at all means the process is alive
+        .route("/healthz", get(|| async { "ok\n" }))
+        .route("/readyz", get(readyz))
+        .route("/live", get(websocket::live))
+        .route("/api/recordings", get(recordings))
+        .with_state(state)
+}
+
+/// An API failure, answered as `{"error": "..."}`.
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self(status, message.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+/// The token of an `Authorization: Bearer <token>` header.
+fn bearer(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+/// The guild a request's API token grants, or why it was refused.
+async fn authorize(headers: &HeaderMap, data: &Data, needed: ApiScope) -> Result<u64, ApiError> {
+    let Some(token) = bearer(headers) else {
+        return Err(ApiError::new(StatusCode::UNAUTHORIZED, "missing bearer token"));
+    };
+    match data.dbs.system.verify_api_token(token).await {
+        Some((guild_id, scope)) if scope.allows(needed) => Ok(guild_id),
+        Some(_) => Err(ApiError::new(StatusCode::FORBIDDEN, "token doesn't allow this")),
+        None => Err(ApiError::new(StatusCode::UNAUTHORIZED, "invalid token")),
+    }
+}
+
+async fn metrics(extract::State(state): extract::State<State>) -> impl IntoResponse {
+    let body = render_metrics(&state.data, &state.songbird).await;
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+async fn readyz(extract::State(state): extract::State<State>) -> (StatusCode, String) {
+    let checks = readiness(&state).await;
+    let ready = checks.iter().all(|(_, result)| result.is_ok());
+    let body: String = checks
+        .iter()
+        .map(|(name, result)| match result {
+            Ok(()) => format!("ok {}\n", name),
+            Err(e) => format!("failed {}: {}\n", name, e),
+        })
+        .collect();
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, body)
+}
+
+/// `GET /api/recordings?limit=N`: the token's guild's latest finished recordings.
+async fn recordings(
+    extract::State(state): extract::State<State>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let data = &state.data;
+    let guild_id = authorize(&headers, data, ApiScope::Read).await?;
+    let limit = match params.get("limit") {
+        Some(limit) => match limit.parse::<usize>() {
+            Ok(limit) if (1..=MAX_SESSIONS).contains(&limit) => limit,
+            _ => {
+                let error = format!("limit must be between 1 and {}", MAX_SESSIONS);
+                return Err(ApiError::new(StatusCode::BAD_REQUEST, error));
+            }
+        },
+        None => recording::api::DEFAULT_LIMIT,
+    };
+
+    let sessions = data.dbs.recording.recent_sessions(guild_id, limit).await;
+    let body = recording::api::render(&sessions)
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(([(header::CONTENT_TYPE, "application/json")], body))
+}
+
+/// Runs every readiness check at once: the gateway is connected, the data directory is
+/// writable and Archon answers.
+async fn readiness(state: &State) -> Vec<(&'static str, Result<(), String>)> {
+    let (gateway, storage, archon) = tokio::join!(
+        check(gateway(&state.data.shards)),
+        check(storage()),
+        check(async { state.data.archon.ping().await.map_err(|e| e.to_string()) }),
+    );
+    vec![("gateway", gateway), ("storage", storage), ("archon", archon)]
+}
+
+async fn check(check: impl std::future::Future<Output = Result<(), String>>) -> Result<(), String> {
+    tokio::time::timeout(CHECK_TIMEOUT, check)
+        .await
+        .unwrap_or_else(|_| Err("timed out".to_string()))
+}
+
+/// Every shard has an open gateway connection.
+async fn gateway(shards: &ShardManager) -> Result<(), String> {
+    let runners = shards.runners.lock().await;
+    if runners.is_empty() {
+        return Err("no shards running".to_string());
+    }
+    let down: Vec<_> = runners
+        .iter()
+        .filter(|(_, runner)| runner.stage != ConnectionStage::Connected)
+        .map(|(id, runner)| format!("shard {} {}", id, runner.stage))
+        .collect();
+    if down.is_empty() {
+        Ok(())
+    } else {
+        Err(down.join(", "))
+    }
+}
+
+/// The data directory, which holds every database, accepts writes.
+async fn storage() -> Result<(), String> {
+    let probe = data_path(".readyz");
+    tokio::fs::write(&probe, b"ok")
+        .await
+        .map_err(|e| format!("can't write {}: {}", probe.display(), e))?;
+    tokio::fs::remove_file(&probe)
+        .await
+        .map_err(|e| format!("can't remove {}: {}", probe.display(), e))
+}
+
+/// Everything `/metrics` exports: the registry's global metrics, then task and event
+/// handler timings, database sizes and voice calls.
+async fn render_metrics(data: &Data, songbird: &Songbird) -> String {
+    let mut out = data.metrics.render(PREFIX).await;
+
+    let tasks: Vec<_> = data
+        .task_manager
+        .list_tasks()
+        .into_iter()
+        .map(|status| (status.name, status.stats))
+        .collect();
+    write_histogram(
+        &mut out,
+        "task_duration_seconds",
+        "How long task runs took",
+        "task",
+        &tasks,
+    );
+    write_failures(&mut out, "task_run_failures", "Failed or panicked task runs", "task", &tasks);
+
+    let handlers = data.event_manager.handler_stats();
+    write_histogram(
+        &mut out,
+        "event_handler_duration_seconds",
+        "How long event handlers took to handle an event",
+        "handler",
+        &handlers,
+    );
+    write_failures(
+        &mut out,
+        "event_handler_errors",
+        "Events a handler failed to handle",
+        "handler",
+        &handlers,
+    );
+
+    let name = format!("{PREFIX}database_size_bytes");
+    write_header(&mut out, &name, "Stored size of each database, before encryption", "gauge");
+    for (database, size) in data.dbs.sizes().await {
+        out.push_str(&format!("{}{{database=\"{}\"}} {}\n", name, database, size));
+    }
+
+    let name = format!("{PREFIX}voice_calls");
+    write_header(&mut out, &name, "Voice calls the bot is in", "gauge");
+    out.push_str(&format!("{} {}\n", name, songbird.iter().count()));
+
+    out
+}
+
+/// Writes run timings as a Prometheus histogram with one series per `label` value.
+fn write_histogram(
+    out: &mut String,
+    name: &str,
+    description: &str,
+    label: &str,
+    stats: &[(String, TaskStats)],
+) {
+    let name = format!("{PREFIX}{name}");
+    write_header(out, &name, description, "histogram");
+    for (value, stats) in stats {
+        let value = escape_label(value);
+        let mut cumulative = 0;
+        for (bound, count) in DURATION_BUCKETS_MS.iter().zip(&stats.buckets) {
+            cumulative += count;
+            out.push_str(&format!(
+                "{}_bucket{{{}=\"{}\",le=\"{}\"}} {}\n",
+                name,
+                label,
+                value,
+                *bound as f64 / 1000.0,
+                cumulative
+            ));
+        }
+        out.push_str(&format!(
+            "{}_bucket{{{}=\"{}\",le=\"+Inf\"}} {}\n",
+            name, label, value, stats.runs
+        ));
+        out.push_str(&format!(
+            "{}_sum{{{}=\"{}\"}} {}\n",
+            name,
+            label,
+            value,
+            stats.total.as_secs_f64()
+        ));
+        out.push_str(&format!("{}_count{{{}=\"{}\"}} {}\n", name, label, value, stats.runs));
+    }
+}
+
+fn write_failures(
+    out: &mut String,
+    name: &str,
+    description: &str,
+    label: &str,
+    stats: &[(String, TaskStats)],
+) {
+    let name = format!("{PREFIX}{name}_total");
+    write_header(out, &name, description, "counter");
+    for (value, stats) in stats {
+        out.push_str(&format!(
+            "{}{{{}=\"{}\"}} {}\n",
+            name,
+            label,
+            escape_label(value),
+            stats.failures
+        ));
+    }
+}
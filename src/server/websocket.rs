@@ -0,0 +1,110 @@
+//! `GET /live`: a WebSocket streaming live updates as JSON text messages. The
+//! `[server] live_token` sees every update; a guild API token sees its guild's. Browsers
+//! can't set headers on WebSockets, so the token may also be sent as `?token=`.
+
+use super::{bearer, ApiError, State};
+use crate::utils::live::{self, LiveUpdate};
+use crate::Data;
+use axum::{
+    extract::{
+        self,
+        ws::{rejection::WebSocketUpgradeRejection, Message, WebSocket, WebSocketUpgrade},
+        Query,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Subscribers allowed at once; dashboards need few.
+const MAX_SUBSCRIBERS: usize = 32;
+
+/// How often an idle connection is pinged, so dead ones get noticed.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Largest message accepted from a client. Clients only send control frames.
+const MAX_CLIENT_MESSAGE: usize = 1024;
+
+/// Upgrades the request and streams updates until the client leaves or the bot stops.
+pub async fn live(
+    extract::State(state): extract::State<State>,
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+    ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Response {
+    if !state.data.config.get().server.live {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let ws = match ws {
+        Ok(ws) => ws,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let token = bearer(&headers).or(params.get("token").map(String::as_str));
+    let scope = match authorize(token, &state.data).await {
+        Ok(scope) => scope,
+        Err(e) => return e.into_response(),
+    };
+    if live::subscribers() >= MAX_SUBSCRIBERS {
+        let error = ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "too many live subscribers");
+        return error.into_response();
+    }
+
+    ws.max_message_size(MAX_CLIENT_MESSAGE)
+        .on_upgrade(move |socket| stream(socket, scope))
+}
+
+async fn stream(mut socket: WebSocket, scope: Option<u64>) {
+    let mut updates = live::subscribe();
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    loop {
+        let sent = tokio::select! {
+            update = updates.recv() => {
+                let message = match update {
+                    Ok(update) if visible(&update, scope) => {
+                        serde_json::to_string(&update).unwrap_or_default()
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(missed)) => {
+                        serde_json::json!({ "type": "lagged", "missed": missed }).to_string()
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                socket.send(Message::Text(message)).await
+            }
+            // Pings from the client are answered by the socket itself
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => continue,
+            },
+            _ = ping.tick() => socket.send(Message::Ping(Vec::new())).await,
+        };
+        if sent.is_err() {
+            return;
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+/// The guild whose updates the token may see, or `None` for every guild.
+async fn authorize(token: Option<&str>, data: &Data) -> Result<Option<u64>, ApiError> {
+    let Some(token) = token else {
+        return Err(ApiError::new(StatusCode::UNAUTHORIZED, "missing token"));
+    };
+
+    // Compared as hashes so the comparison's timing doesn't leak the token
+    let live_token = data.config.get().server.live_token.clone();
+    if live_token.is_some_and(|live| Sha256::digest(live) == Sha256::digest(token)) {
+        return Ok(None);
+    }
+    match data.dbs.system.verify_api_token(token).await {
+        Some((guild_id, _)) => Ok(Some(guild_id)),
+        None => Err(ApiError::new(StatusCode::UNAUTHORIZED, "invalid token")),
+    }
+}
+
+fn visible(update: &LiveUpdate, scope: Option<u64>) -> bool {
+    scope.is_none() || update.guild_id() == scope
+}
@@ -1,19 +1,121 @@
+use crate::database::Database;
+use crate::health::{ComponentHealth, Health};
+use crate::metrics::MetricsRegistry;
+use crate::modules::system::database::SystemDatabase;
+use crate::modules::system::task_runs::{RunOutcome, TaskRun};
+use crate::utils::errors::{self, ErrorReport};
+use crate::utils::operators::{self, Severity};
+use dashmap::{DashMap, DashSet};
 use futures::future::join_all;
-use futures::StreamExt;
 use poise::serenity_prelude::Context;
-use std::time::Duration;
-use tokio::sync::{broadcast, Mutex};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{watch, Mutex, Notify, Semaphore};
 use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+/// Most task runs allowed at once across all groups.
+const MAX_CONCURRENT_RUNS: usize = 8;
+
+/// Groups tasks by the kind of work they do so heavy tasks can't starve light ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaskGroup {
+    /// Tasks that mostly call external HTTP APIs.
+    Network,
+    /// Quick tasks that mostly touch the databases or Discord.
+    Light,
+}
+
+impl TaskGroup {
+    /// How many tasks of this group may run at once.
+    fn concurrency(self) -> usize {
+        match self {
+            Self::Network => 2,
+            Self::Light => 6,
+        }
+    }
+}
+
+/// How soon a failed run is retried before the task falls back to its normal schedule.
+#[derive(Debug, Clone, Copy)]
+pub struct TaskRetry {
+    /// Extra attempts after a failed scheduled run.
+    pub retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for TaskRetry {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            base_delay: Duration::from_secs(5),
+            max_delay: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+impl TaskRetry {
+    /// Failed runs wait for the next scheduled run.
+    pub fn none() -> Self {
+        Self {
+            retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Exponential backoff before retry number `attempt` (from 1), or `None` once the
+    /// retries are used up.
+    fn delay(&self, attempt: u32) -> Option<Duration> {
+        (attempt <= self.retries).then(|| {
+            self.base_delay
+                .saturating_mul(2u32.saturating_pow(attempt - 1))
+                .min(self.max_delay)
+        })
+    }
+}
+
+/// When to tell operators about tasks that keep failing.
+#[derive(Debug, Clone, Default)]
+pub struct FailureAlerts {
+    /// Consecutive failed runs, retries included, before alerting. 0 disables alerts.
+    pub threshold: u32,
+}
+
+/// Where finished runs are recorded for `/admin tasks history`.
+#[derive(Debug, Clone)]
+pub struct RunHistory {
+    pub db: Database<SystemDatabase>,
+    /// Runs kept per task; 0 records nothing.
+    pub keep: usize,
+}
 
 #[async_trait::async_trait]
 pub trait Task: Send + Sync + std::fmt::Debug {
     fn name(&self) -> &str;
     fn schedule(&self) -> Option<Duration>;
+    fn group(&self) -> TaskGroup {
+        TaskGroup::Light
+    }
     async fn execute(
         &mut self,
         ctx: &Context,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
     fn box_clone(&self) -> Box<dyn Task>;
+    /// Task-specific health, checked after every run. Failed or panicking runs are
+    /// reported by the task manager on top of this.
+    fn health(&self) -> Health {
+        Health::ok()
+    }
+    fn retry(&self) -> TaskRetry {
+        TaskRetry::default()
+    }
+    /// How long a single run may take before the task manager aborts it and counts it
+    /// as failed. `None` lets runs take as long as they need.
+    fn max_runtime(&self) -> Option<Duration> {
+        None
+    }
 }
 
 impl Clone for Box<dyn Task> {
@@ -22,11 +124,142 @@ impl Clone for Box<dyn Task> {
     }
 }
 
+/// A task that runs one instance per guild. The task manager starts an instance for every
+/// guild in [`GuildTask::guilds`] and, as guilds join/leave or modules are toggled,
+/// starts or stops instances according to [`GuildTask::wanted`].
+#[async_trait::async_trait]
+pub trait GuildTask: Send + Sync + std::fmt::Debug {
+    fn name(&self) -> &str;
+    /// Guilds that should have an instance at startup.
+    async fn guilds(&self) -> Vec<u64>;
+    /// Whether `guild_id` should currently have an instance running.
+    async fn wanted(&self, guild_id: u64) -> bool;
+    fn create(&self, guild_id: u64) -> Box<dyn Task>;
+}
+
+/// The shard Discord delivers `guild_id`'s events on when the bot runs `total` shards.
+pub fn shard_of(guild_id: u64, total: u32) -> u32 {
+    ((guild_id >> 22) % total.max(1) as u64) as u32
+}
+
+/// Upper bounds of the run duration histogram buckets, in milliseconds.
+pub const DURATION_BUCKETS_MS: [u64; 11] = [
+    10, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000,
+];
+
+/// Run counts and timings of a task since it started.
+#[derive(Debug, Clone, Default)]
+pub struct TaskStats {
+    pub runs: u64,
+    /// Runs that returned an error or panicked.
+    pub failures: u64,
+    pub total: Duration,
+    pub max: Duration,
+    pub last: Option<Duration>,
+    /// Runs per [`DURATION_BUCKETS_MS`] bucket, plus a final bucket for longer runs.
+    pub buckets: [u64; DURATION_BUCKETS_MS.len() + 1],
+}
+
+impl TaskStats {
+    pub(crate) fn record(&mut self, duration: Duration, failed: bool) {
+        self.runs += 1;
+        if failed {
+            self.failures += 1;
+        }
+        self.total += duration;
+        self.max = self.max.max(duration);
+        self.last = Some(duration);
+
+        let ms = duration.as_millis() as u64;
+        let bucket = DURATION_BUCKETS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(DURATION_BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (self.runs > 0).then(|| self.total / self.runs as u32)
+    }
+
+    /// Upper bound of the bucket holding quantile `q` (0–1), or `None` if there are no
+    /// runs or it falls past the last bucket, in which case [`TaskStats::max`] is the bound.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let target = (self.runs as f64 * q).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return DURATION_BUCKETS_MS
+                    .get(bucket)
+                    .map(|ms| Duration::from_millis(*ms));
+            }
+        }
+        None
+    }
+}
+
+/// What the task manager knows about a running task, for `/admin tasks`.
+#[derive(Debug, Clone)]
+pub struct TaskStatus {
+    /// Display name: the task name, plus the guild id for guild task instances.
+    pub name: String,
+    pub health: Health,
+    pub last_run: Option<SystemTime>,
+    pub last_error: Option<(SystemTime, String)>,
+    /// `None` while the task is running or paused.
+    pub next_run: Option<SystemTime>,
+    pub paused: bool,
+    pub stats: TaskStats,
+}
+
+impl TaskStatus {
+    fn new(name: String, paused: bool) -> Self {
+        Self {
+            name,
+            health: Health::ok(),
+            last_run: None,
+            last_error: None,
+            next_run: None,
+            paused,
+            stats: TaskStats::default(),
+        }
+    }
+}
+
+/// Operator controls for one running task.
+#[derive(Debug)]
+struct Control {
+    paused: watch::Sender<bool>,
+    trigger: Notify,
+}
+
+#[derive(Debug)]
+struct GuildInstance {
+    stop_tx: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
 #[derive(Debug)]
 pub struct TaskManager {
     tasks: Mutex<Vec<Box<dyn Task>>>,
     handles: Mutex<Vec<JoinHandle<()>>>,
-    shutdown_tx: broadcast::Sender<()>,
+    guild_tasks: Mutex<Vec<Arc<dyn GuildTask>>>,
+    /// Running per-guild instances by (guild task name, guild id).
+    guild_instances: Mutex<HashMap<(String, u64), GuildInstance>>,
+    ctx: OnceLock<Context>,
+    /// Context of each connected shard, so guild task instances run on their guild's shard.
+    shard_contexts: DashMap<u32, Context>,
+    /// State of each running task, by display name.
+    status: Arc<DashMap<String, TaskStatus>>,
+    controls: DashMap<String, Arc<Control>>,
+    /// Names passed to [`TaskManager::pause`], so instances started later begin paused.
+    paused: DashSet<String>,
+    shutdown_tx: watch::Sender<bool>,
+    global_limit: Arc<Semaphore>,
+    group_limits: HashMap<TaskGroup, Arc<Semaphore>>,
+    alerts: Arc<FailureAlerts>,
+    history: Option<RunHistory>,
 }
 
 impl Default for TaskManager {
@@ -37,60 +270,526 @@ impl Default for TaskManager {
 
 impl TaskManager {
     pub fn new() -> Self {
-        let (shutdown_tx, _) = broadcast::channel(1);
+        let (shutdown_tx, _) = watch::channel(false);
+        let group_limits = [TaskGroup::Network, TaskGroup::Light]
+            .into_iter()
+            .map(|group| (group, Arc::new(Semaphore::new(group.concurrency()))))
+            .collect();
+
         Self {
             tasks: Mutex::new(Vec::new()),
             handles: Mutex::new(Vec::new()),
+            guild_tasks: Mutex::new(Vec::new()),
+            guild_instances: Mutex::new(HashMap::new()),
+            ctx: OnceLock::new(),
+            shard_contexts: DashMap::new(),
+            status: Arc::new(DashMap::new()),
+            controls: DashMap::new(),
+            paused: DashSet::new(),
             shutdown_tx,
+            global_limit: Arc::new(Semaphore::new(MAX_CONCURRENT_RUNS)),
+            group_limits,
+            alerts: Arc::new(FailureAlerts::default()),
+            history: None,
         }
     }
 
+    /// Reports tasks that fail `alerts.threshold` times in a row, and their recovery.
+    pub fn with_alerts(mut self, alerts: FailureAlerts) -> Self {
+        self.alerts = Arc::new(alerts);
+        self
+    }
+
+    /// Records every run's start, duration and outcome in the system database.
+    pub fn with_history(mut self, history: RunHistory) -> Self {
+        self.history = Some(history).filter(|history| history.keep > 0);
+        self
+    }
+
+    /// Remembers a shard's context once it connects, so instances for its guilds run on it.
+    pub fn register_shard(&self, ctx: &Context) {
+        self.shard_contexts.insert(ctx.shard_id.0, ctx.clone());
+    }
+
     pub async fn add_task(&self, task: impl Task + 'static) {
         self.tasks.lock().await.push(Box::new(task));
     }
 
+    pub async fn add_guild_task(&self, task: impl GuildTask + 'static) {
+        self.guild_tasks.lock().await.push(Arc::new(task));
+    }
+
+    fn spawn(
+        &self,
+        key: String,
+        task: Box<dyn Task>,
+        ctx: Context,
+        stop: watch::Receiver<bool>,
+    ) -> Option<JoinHandle<()>> {
+        let interval = task.schedule()?;
+        let group_limit = self.group_limits[&task.group()].clone();
+        let paused = self.paused.contains(task.name()) || self.paused.contains(&key);
+        self.status
+            .insert(key.clone(), TaskStatus::new(key.clone(), paused));
+        let control = Arc::new(Control {
+            paused: watch::Sender::new(paused),
+            trigger: Notify::new(),
+        });
+        self.controls.insert(key.clone(), control.clone());
+
+        Some(tokio::spawn(supervise(
+            key,
+            task,
+            interval,
+            ctx,
+            self.global_limit.clone(),
+            group_limit,
+            self.status.clone(),
+            self.alerts.clone(),
+            self.history.clone(),
+            control,
+            stop,
+        )))
+    }
+
+    /// Registers totals across all tasks as internal metrics.
+    pub fn register_metrics(&self, registry: &MetricsRegistry) {
+        let status = self.status.clone();
+        registry.register_global_gauge(
+            "task_runs_total",
+            "Task runs since startup, across all tasks",
+            move |_| {
+                let total = status.iter().map(|entry| entry.stats.runs).sum::<u64>();
+                async move { Ok(total as f64) }
+            },
+        );
+
+        let status = self.status.clone();
+        registry.register_global_gauge(
+            "task_failures_total",
+            "Failed or panicked task runs since startup, across all tasks",
+            move |_| {
+                let total = status.iter().map(|entry| entry.stats.failures).sum::<u64>();
+                async move { Ok(total as f64) }
+            },
+        );
+
+        let status = self.status.clone();
+        registry.register_global_gauge(
+            "task_slowest_mean_ms",
+            "Mean run time of the slowest task, in milliseconds",
+            move |_| {
+                let slowest = status
+                    .iter()
+                    .filter_map(|entry| entry.stats.mean())
+                    .max()
+                    .unwrap_or_default();
+                async move { Ok(slowest.as_millis() as f64) }
+            },
+        );
+    }
+
+    /// Every running task, by name.
+    pub fn list_tasks(&self) -> Vec<TaskStatus> {
+        let mut tasks: Vec<TaskStatus> = self.status.iter().map(|entry| entry.value().clone()).collect();
+        tasks.sort_by(|a, b| a.name.cmp(&b.name));
+        tasks
+    }
+
+    /// Controls of the tasks called `name`. A guild task's name matches all its instances.
+    fn matching(&self, name: &str) -> Result<Vec<Arc<Control>>, String> {
+        let instance_prefix = format!("{} (", name);
+        let controls: Vec<_> = self
+            .controls
+            .iter()
+            .filter(|entry| entry.key() == name || entry.key().starts_with(&instance_prefix))
+            .map(|entry| entry.value().clone())
+            .collect();
+
+        if controls.is_empty() {
+            Err(format!("No running task is called `{}`", name))
+        } else {
+            Ok(controls)
+        }
+    }
+
+    /// Stops scheduled runs of `name` until [`TaskManager::resume`]. A run in progress
+    /// finishes normally. Returns how many tasks were paused.
+    pub fn pause(&self, name: &str) -> Result<usize, String> {
+        let controls = self.matching(name)?;
+        for control in &controls {
+            control.paused.send_replace(true);
+        }
+        self.paused.insert(name.to_string());
+        Ok(controls.len())
+    }
+
+    /// Resumes `name`'s schedule. Returns how many tasks were resumed.
+    pub fn resume(&self, name: &str) -> Result<usize, String> {
+        let controls = self.matching(name)?;
+        for control in &controls {
+            control.paused.send_replace(false);
+        }
+        self.paused.remove(name);
+        Ok(controls.len())
+    }
+
+    /// Runs `name` as soon as it's idle, even if paused. Its schedule restarts from that run.
+    pub fn trigger_now(&self, name: &str) -> Result<usize, String> {
+        let controls = self.matching(name)?;
+        for control in &controls {
+            control.trigger.notify_one();
+        }
+        Ok(controls.len())
+    }
+
+    /// Health of every running task, worst first.
+    pub fn health(&self) -> Vec<ComponentHealth> {
+        let mut components: Vec<ComponentHealth> = self
+            .status
+            .iter()
+            .map(|entry| ComponentHealth {
+                kind: "task",
+                name: entry.key().clone(),
+                health: entry.value().health.clone(),
+            })
+            .collect();
+        components.sort_by(|a, b| b.health.state.cmp(&a.health.state).then(a.name.cmp(&b.name)));
+        components
+    }
+
+    /// Starts every added task in its own supervised loop, plus guild task instances for
+    /// the guilds that want them.
     pub async fn start_tasks(&self, ctx: Context) {
-        let mut tasks = self.tasks.lock().await;
+        let _ = self.ctx.set(ctx.clone());
+        self.register_shard(&ctx);
+
+        {
+            let mut tasks = self.tasks.lock().await;
+            let mut handles = self.handles.lock().await;
+            for task in tasks.drain(..) {
+                let key = task.name().to_string();
+                if let Some(handle) = self.spawn(key, task, ctx.clone(), self.shutdown_tx.subscribe()) {
+                    handles.push(handle);
+                }
+            }
+        }
+
+        let guild_tasks = self.guild_tasks.lock().await.clone();
+        for guild_task in guild_tasks {
+            for guild_id in guild_task.guilds().await {
+                self.start_instance(&guild_task, guild_id).await;
+            }
+        }
+    }
+
+    async fn start_instance(&self, guild_task: &Arc<dyn GuildTask>, guild_id: u64) {
+        let Some(total) = self.ctx.get().map(|ctx| ctx.cache.shard_count()) else {
+            return;
+        };
+        let shard = shard_of(guild_id, total);
+        // Guilds on shards that haven't connected yet start with that shard's GuildCreate
+        let Some(ctx) = self.shard_contexts.get(&shard).map(|ctx| ctx.clone()) else {
+            return;
+        };
+
+        let mut instances = self.guild_instances.lock().await;
+        let key = (guild_task.name().to_string(), guild_id);
+        if instances.get(&key).is_some_and(|i| !i.handle.is_finished()) {
+            return;
+        }
+
+        let (stop_tx, stop_rx) = watch::channel(false);
+        let display = format!("{} ({})", key.0, guild_id);
+        if let Some(handle) = self.spawn(display, guild_task.create(guild_id), ctx, stop_rx) {
+            info!("Started {} for guild {}", key.0, guild_id);
+            instances.insert(key, GuildInstance { stop_tx, handle });
+        }
+    }
+
+    async fn stop_instance(&self, name: &str, guild_id: u64) {
+        let instance = self
+            .guild_instances
+            .lock()
+            .await
+            .remove(&(name.to_string(), guild_id));
+
+        if let Some(instance) = instance {
+            let _ = instance.stop_tx.send(true);
+            let _ = instance.handle.await;
+            let key = format!("{} ({})", name, guild_id);
+            self.status.remove(&key);
+            self.controls.remove(&key);
+            info!("Stopped {} for guild {}", name, guild_id);
+        }
+    }
+
+    /// Starts or stops this guild's instances to match what each guild task wants. Call
+    /// after joining a guild or enabling/disabling something a guild task depends on.
+    pub async fn sync_guild(&self, guild_id: u64) {
+        let guild_tasks = self.guild_tasks.lock().await.clone();
+        for guild_task in guild_tasks {
+            if guild_task.wanted(guild_id).await {
+                self.start_instance(&guild_task, guild_id).await;
+            } else {
+                self.stop_instance(guild_task.name(), guild_id).await;
+            }
+        }
+    }
+
+    /// Stops every instance for a guild the bot has left.
+    pub async fn remove_guild(&self, guild_id: u64) {
+        let names = self
+            .guild_tasks
+            .lock()
+            .await
+            .iter()
+            .map(|t| t.name().to_string())
+            .collect::<Vec<_>>();
+        for name in names {
+            self.stop_instance(&name, guild_id).await;
+        }
+        if let Some(history) = &self.history {
+            if let Err(e) = history.db.forget_task_runs(guild_id).await {
+                warn!("Couldn't forget task runs of guild {}: {}", guild_id, e);
+            }
+        }
+    }
+
+    pub async fn shutdown(&self) {
+        let _ = self.shutdown_tx.send(true);
+
+        let instances = std::mem::take(&mut *self.guild_instances.lock().await);
         let mut handles = self.handles.lock().await;
+        for instance in instances.into_values() {
+            let _ = instance.stop_tx.send(true);
+            handles.push(instance.handle);
+        }
 
-        for chunk in tasks.drain(..).collect::<Vec<_>>().chunks(10) {
-            let tasks_chunk = chunk.iter().map(|t| t.box_clone()).collect::<Vec<_>>();
-            let ctx = ctx.clone();
-            let mut shutdown_rx = self.shutdown_tx.subscribe();
-
-            let handle = tokio::spawn(async move {
-                let mut intervals = futures::stream::FuturesUnordered::new();
-
-                for mut task in tasks_chunk {
-                    if let Some(interval) = task.schedule() {
-                        let ctx = ctx.clone();
-                        intervals.push(tokio::spawn(async move {
-                            loop {
-                                task.execute(&ctx).await.ok();
-                                tokio::time::sleep(interval).await;
-                            }
-                        }));
+        join_all(handles.iter_mut()).await;
+    }
+}
+
+/// Runs `task` every `interval` until `stop` is set. Each run waits for a global and a group
+/// permit, and runs in its own tokio task so a panic only costs that run: the task is
+/// restored from its initial state and keeps its schedule. Failed or panicking runs are
+/// retried with backoff per [`Task::retry`], and reported once they fail
+/// [`FailureAlerts::threshold`] times in a row. Every run is recorded in `history`.
+/// Operators can pause the schedule or trigger a run through `control`. Setting `stop`
+/// lets a run that has already started finish, or hit `max_runtime`, before returning.
+#[allow(clippy::too_many_arguments)]
+async fn supervise(
+    name: String,
+    task: Box<dyn Task>,
+    interval: Duration,
+    ctx: Context,
+    global_limit: Arc<Semaphore>,
+    group_limit: Arc<Semaphore>,
+    status: Arc<DashMap<String, TaskStatus>>,
+    alerts: Arc<FailureAlerts>,
+    history: Option<RunHistory>,
+    control: Arc<Control>,
+    mut stop: watch::Receiver<bool>,
+) {
+    let template = task.box_clone();
+    let retry = task.retry();
+    let max_runtime = task.max_runtime();
+    let mut task = Some(task);
+    let mut failures = 0u32;
+    let mut attempt = 0u32;
+
+    // Instances of a paused guild task start out waiting too
+    if *control.paused.borrow()
+        && !wait_for_next_run(&name, Duration::ZERO, &control, &status, &mut stop).await
+    {
+        return;
+    }
+
+    loop {
+        // Waiting for a permit ends with `stop`; a run that has started is seen through
+        let permits = tokio::select! {
+            _ = stop.wait_for(|stopped| *stopped) => return,
+            permits = async {
+                let global = global_limit.acquire().await.expect("task semaphore closed");
+                let group = group_limit.acquire().await.expect("task semaphore closed");
+                (global, group)
+            } => permits,
+        };
+
+        let mut current = task.take().unwrap_or_else(|| template.box_clone());
+        let run_ctx = ctx.clone();
+        let started_at = SystemTime::now();
+        let started = Instant::now();
+        let mut handle = tokio::spawn(async move {
+            let result = current.execute(&run_ctx).await;
+            (current, result)
+        });
+        // `None` when the run was aborted for going over `max_runtime`
+        let run = async move {
+            match max_runtime {
+                Some(limit) => match tokio::time::timeout(limit, &mut handle).await {
+                    Ok(outcome) => Some(outcome),
+                    Err(_) => {
+                        handle.abort();
+                        None
                     }
+                },
+                None => Some(handle.await),
+            }
+        };
+        tokio::pin!(run);
+
+        // Stopping waits for the run, so nothing it writes lands after its instance is
+        // reported stopped or the databases get their final save
+        let (outcome, stopping) = tokio::select! {
+            outcome = &mut run => (outcome, false),
+            // The watch guard is dropped inside so it isn't held across the await below
+            _ = async { let _ = stop.wait_for(|stopped| *stopped).await; } => (run.await, true),
+        };
+        drop(permits);
+        if stopping {
+            return;
+        }
+        let duration = started.elapsed();
+
+        let (mut health, run_outcome, error) = match outcome {
+            Some(Ok((current, result))) => {
+                let health = current.health();
+                task = Some(current);
+                match result {
+                    Ok(()) => (health, RunOutcome::Succeeded, None),
+                    Err(e) => {
+                        warn!("Task {} failed: {}", name, e);
+                        errors::report(
+                            ErrorReport::new(format!("task {}", name), &e)
+                                .tag("task", &name)
+                                .tag("failures_in_a_row", failures + 1),
+                        );
+                        (health, RunOutcome::Failed, Some(format!("failed: {}", e)))
+                    }
+                }
+            }
+            // Reported with its backtrace by the panic hook
+            Some(Err(e)) => {
+                error!("Task {} panicked, restarting from its initial state: {}", name, e);
+                (
+                    Health::failed(format!("Last run panicked: {}", e)),
+                    RunOutcome::Panicked,
+                    Some(format!("panicked: {}", e)),
+                )
+            }
+            None => {
+                let limit = max_runtime.unwrap_or_default();
+                error!(
+                    "Task {} ran longer than {:?} and was aborted, restarting from its initial state",
+                    name, limit
+                );
+                let message = format!("timed out after {:?}", limit);
+                errors::report(
+                    ErrorReport::new(format!("task {}", name), message).tag("task", &name),
+                );
+                (
+                    Health::failed(format!("Last run timed out after {:?}", limit)),
+                    RunOutcome::TimedOut,
+                    Some(format!("timed out after {:?}", limit)),
+                )
+            }
+        };
+
+        let wait = match &error {
+            Some(error) => {
+                failures += 1;
+                health = health.worst(Health::degraded(format!(
+                    "Last run {} ({} in a row)",
+                    error, failures
+                )));
+                if failures == alerts.threshold {
+                    operators::notify(
+                        Severity::Critical,
+                        format!(
+                            "Task **{}** has failed {} times in a row. Last run {}",
+                            name, failures, error
+                        ),
+                    );
                 }
 
-                tokio::select! {
-                    _ = shutdown_rx.recv() => {
-                        for interval in intervals {
-                            interval.abort();
-                        }
+                attempt += 1;
+                match retry.delay(attempt) {
+                    Some(delay) => delay.min(interval),
+                    None => {
+                        attempt = 0;
+                        interval
                     }
-                    _ = async {
-                        while intervals.next().await.is_some() {}
-                    } => {}
                 }
-            });
-            handles.push(handle);
+            }
+            None => {
+                if alerts.threshold > 0 && failures >= alerts.threshold {
+                    operators::notify(
+                        Severity::Info,
+                        format!("Task **{}** recovered after {} failed runs.", name, failures),
+                    );
+                }
+                failures = 0;
+                attempt = 0;
+                interval
+            }
+        };
+        if let Some(mut entry) = status.get_mut(&name) {
+            let now = SystemTime::now();
+            entry.health = health;
+            entry.last_run = Some(now);
+            entry.stats.record(duration, error.is_some());
+            if let Some(error) = &error {
+                entry.last_error = Some((now, error.clone()));
+            }
+        }
+        if let Some(history) = &history {
+            let run = TaskRun {
+                started_at: started_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+                duration_ms: duration.as_millis() as u64,
+                outcome: run_outcome,
+                error,
+            };
+            if let Err(e) = history.db.record_task_run(&name, run, history.keep).await {
+                warn!("Couldn't record a run of task {}: {}", name, e);
+            }
+        }
+
+        if !wait_for_next_run(&name, wait, &control, &status, &mut stop).await {
+            return;
+        }
+        if let Some(mut entry) = status.get_mut(&name) {
+            entry.next_run = None;
         }
     }
+}
 
-    pub async fn shutdown(&self) {
-        let _ = self.shutdown_tx.send(());
-        let mut handles = self.handles.lock().await;
-        join_all(handles.iter_mut()).await;
+/// Waits `wait` for the next run, plus however long the task stays paused, unless a run
+/// is triggered first. Returns false once the task is stopped.
+async fn wait_for_next_run(
+    name: &str,
+    wait: Duration,
+    control: &Control,
+    status: &DashMap<String, TaskStatus>,
+    stop: &mut watch::Receiver<bool>,
+) -> bool {
+    let due = tokio::time::Instant::now() + wait;
+    let mut paused = control.paused.subscribe();
+
+    loop {
+        let is_paused = *paused.borrow_and_update();
+        if let Some(mut entry) = status.get_mut(name) {
+            entry.paused = is_paused;
+            entry.next_run = (!is_paused).then(|| {
+                SystemTime::now() + due.saturating_duration_since(tokio::time::Instant::now())
+            });
+        }
+
+        tokio::select! {
+            _ = stop.wait_for(|stopped| *stopped) => return false,
+            _ = control.trigger.notified() => return true,
+            _ = tokio::time::sleep_until(due), if !is_paused => return true,
+            _ = paused.changed() => {}
+        }
     }
 }
@@ -1,3 +1,17 @@
+pub mod aliases;
+pub mod duration;
+pub mod embed;
+pub mod errors;
+pub mod history;
+pub mod http;
+pub mod json;
+pub mod live;
+pub mod operators;
+pub mod plan;
+pub mod reply;
+pub mod time;
+pub mod validate;
+
 #[macro_export]
 macro_rules! default_struct {
     (
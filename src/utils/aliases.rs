@@ -0,0 +1,44 @@
+//! Old names of renamed commands. The old command stays registered and runs the same code,
+//! but each use gets a note pointing at the new name, and `/help` only lists the new one.
+
+use crate::Context;
+use poise::CreateReply;
+use tracing::warn;
+
+/// A renamed command, by qualified name.
+#[derive(Debug)]
+pub struct Alias {
+    pub old: &'static str,
+    pub new: &'static str,
+}
+
+pub const ALIASES: &[Alias] = &[Alias {
+    old: "lorax duration",
+    new: "lorax extend",
+}];
+
+/// The current name of `qualified_name`, if it's an old name.
+pub fn renamed(qualified_name: &str) -> Option<&'static str> {
+    ALIASES
+        .iter()
+        .find(|alias| alias.old == qualified_name)
+        .map(|alias| alias.new)
+}
+
+/// Tells the user the command they ran has a new name. Called after the command ran.
+pub async fn notice(ctx: Context<'_>) {
+    let old = &ctx.command().qualified_name;
+    let Some(new) = renamed(old) else {
+        return;
+    };
+
+    let reply = CreateReply::default()
+        .content(format!(
+            "ℹ️ `/{}` is now `/{}`. The old name still works for now, but will be removed.",
+            old, new
+        ))
+        .ephemeral(true);
+    if let Err(e) = ctx.send(reply).await {
+        warn!("Failed to send rename notice for /{}: {}", old, e);
+    }
+}
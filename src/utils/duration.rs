@@ -0,0 +1,198 @@
+use std::time::Duration;
+
+const EXAMPLES: &str = "Try something like `90m`, `2h30m` or `1d`.";
+
+/// Unit applied to bare numbers, so plain integer input keeps working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationUnit {
+    Seconds,
+    Minutes,
+    Hours,
+    Days,
+}
+
+impl DurationUnit {
+    fn secs(self) -> i64 {
+        match self {
+            Self::Seconds => 1,
+            Self::Minutes => 60,
+            Self::Hours => 3600,
+            Self::Days => 86400,
+        }
+    }
+
+    fn parse(unit: &str) -> Option<i64> {
+        let secs = match unit {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+            "d" | "day" | "days" => 86400,
+            "w" | "wk" | "week" | "weeks" => 604800,
+            _ => return None,
+        };
+        Some(secs)
+    }
+}
+
+/// Parses a signed duration such as `-15m` or `+1h30m` into seconds.
+pub fn parse_duration_secs(input: &str, default_unit: DurationUnit) -> Result<i64, String> {
+    let input = input.trim().to_lowercase();
+    let (sign, body) = match input.strip_prefix('-') {
+        Some(rest) => (-1, rest.trim_start()),
+        None => (1, input.strip_prefix('+').unwrap_or(&input).trim_start()),
+    };
+
+    if body.is_empty() {
+        return Err(format!("Please provide a duration. {}", EXAMPLES));
+    }
+
+    if body.chars().all(|c| c.is_ascii_digit()) {
+        return body
+            .parse::<i64>()
+            .ok()
+            .and_then(|value| value.checked_mul(default_unit.secs()))
+            .map(|secs| sign * secs)
+            .ok_or_else(|| "That duration is too long.".to_string());
+    }
+
+    let mut total: i64 = 0;
+    let mut chars = body.chars().peekable();
+
+    while chars.peek().is_some() {
+        let mut number = String::new();
+        while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+            number.push(*c);
+            chars.next();
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut unit = String::new();
+        while let Some(c) = chars.peek().filter(|c| c.is_alphabetic()) {
+            unit.push(*c);
+            chars.next();
+        }
+        while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+
+        if number.is_empty() {
+            return Err(format!("Couldn't understand `{}` as a duration. {}", input, EXAMPLES));
+        }
+
+        // Only a lone number takes the default unit; `2h30` is more likely a typo than
+        // 2h 30 of whatever the command defaults to
+        if unit.is_empty() {
+            return Err(format!("`{}` needs a unit after {}. {}", input, number, EXAMPLES));
+        }
+        let unit_secs = DurationUnit::parse(&unit).ok_or_else(|| {
+            format!("Unknown time unit `{}`. Use s, m, h, d or w. {}", unit, EXAMPLES)
+        })?;
+
+        total = number
+            .parse::<i64>()
+            .ok()
+            .and_then(|n| n.checked_mul(unit_secs))
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(|| "That duration is too long.".to_string())?;
+    }
+
+    Ok(sign * total)
+}
+
+/// Parses a non-negative duration such as `90m`, `1d` or `2h30m`.
+pub fn parse_duration(input: &str, default_unit: DurationUnit) -> Result<Duration, String> {
+    let secs = parse_duration_secs(input, default_unit)?;
+    if secs < 0 {
+        return Err("Duration cannot be negative.".to_string());
+    }
+    Ok(Duration::from_secs(secs as u64))
+}
+
+/// Renders seconds as a compact human-readable duration, e.g. `1d 2h 5m`.
+pub fn format_duration(secs: u64) -> String {
+    let days = secs / 86400;
+    let hours = (secs % 86400) / 3600;
+    let mins = (secs % 3600) / 60;
+    let secs = secs % 60;
+
+    let parts: Vec<String> = [(days, "d"), (hours, "h"), (mins, "m"), (secs, "s")]
+        .iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect();
+
+    if parts.is_empty() {
+        "0m".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<i64, String> {
+        parse_duration_secs(input, DurationUnit::Minutes)
+    }
+
+    #[test]
+    fn parses_units() {
+        assert_eq!(parse("2h30m"), Ok(9000));
+        assert_eq!(parse("1d"), Ok(86400));
+        assert_eq!(parse("90m"), Ok(5400));
+        assert_eq!(parse("1w"), Ok(604800));
+        assert_eq!(parse("45s"), Ok(45));
+    }
+
+    #[test]
+    fn ignores_whitespace_and_case() {
+        assert_eq!(parse("  2H 30M  "), Ok(9000));
+        assert_eq!(parse("1 Day, 2 Hours"), Ok(93600));
+        assert_eq!(parse("+ 1h"), Ok(3600));
+    }
+
+    #[test]
+    fn bare_numbers_use_default_unit() {
+        assert_eq!(parse("15"), Ok(900));
+        assert_eq!(parse_duration_secs("15", DurationUnit::Seconds), Ok(15));
+        assert_eq!(parse_duration_secs("2", DurationUnit::Days), Ok(172800));
+    }
+
+    #[test]
+    fn negative_input() {
+        assert_eq!(parse("-15m"), Ok(-900));
+        assert_eq!(parse("- 1h30m"), Ok(-5400));
+        assert!(parse_duration("-15m", DurationUnit::Minutes).is_err());
+        assert_eq!(
+            parse_duration("15m", DurationUnit::Minutes),
+            Ok(Duration::from_secs(900))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(parse("").is_err());
+        assert!(parse("   ").is_err());
+        assert!(parse("-").is_err());
+        assert!(parse("5 fortnights").is_err());
+        assert!(parse("h").is_err());
+        assert!(parse("2h30").is_err());
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        assert!(parse("99999999999999999999").is_err());
+        assert!(parse("9223372036854775807").is_err());
+        assert!(parse("999999999999999w").is_err());
+        assert!(parse("9223372036854775807s 1s").is_err());
+    }
+
+    #[test]
+    fn format_round_trips() {
+        for secs in [0, 45, 60, 5400, 9000, 86400, 93784, 1_000_000] {
+            let formatted = format_duration(secs);
+            assert_eq!(parse(&formatted), Ok(secs as i64), "{}", formatted);
+        }
+        assert_eq!(format_duration(0), "0m");
+        assert_eq!(format_duration(93784), "1d 2h 3m 4s");
+    }
+}
@@ -0,0 +1,18 @@
+//! Embed builder that applies a guild's theme (`/settings theme`).
+
+use crate::modules::system::database::Theme;
+use poise::serenity_prelude::{CreateEmbed, CreateEmbedFooter};
+
+/// An embed with the theme's accent color and footer.
+pub fn themed(theme: &Theme) -> CreateEmbed {
+    let embed = CreateEmbed::new().color(theme.accent_color);
+    match &theme.footer {
+        Some(footer) => embed.footer(CreateEmbedFooter::new(footer)),
+        None => embed,
+    }
+}
+
+/// A themed embed titled with the theme's title emoji.
+pub fn titled(theme: &Theme, title: impl std::fmt::Display) -> CreateEmbed {
+    themed(theme).title(format!("{} {}", theme.emoji.title, title))
+}
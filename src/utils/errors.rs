@@ -0,0 +1,277 @@
+//! Error reports for failed commands, task runs and panics, sent to Sentry and/or a Discord
+//! channel (`[errors]`). Anything can call [`report`]; reports queue until [`start`] is
+//! called. An error that repeats within the dedup window is only counted, and the count is
+//! included with its next report, so a Prometheus server that stays down reports once.
+
+use crate::config::ErrorConfig;
+use crate::utils::http;
+use poise::serenity_prelude::{ChannelId, Http};
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Longest backtrace posted to Discord, leaving room for the rest of the message.
+const MAX_DISCORD_BACKTRACE: usize = 1200;
+
+/// One failure and what was happening when it occurred.
+#[derive(Debug, Clone)]
+pub struct ErrorReport {
+    /// What failed, e.g. `/lorax submit` or `task StatsUpdate`.
+    pub source: String,
+    pub message: String,
+    pub tags: Vec<(&'static str, String)>,
+    pub backtrace: Option<String>,
+}
+
+impl ErrorReport {
+    pub fn new(source: impl Into<String>, message: impl ToString) -> Self {
+        Self {
+            source: source.into(),
+            message: message.to_string(),
+            tags: Vec::new(),
+            backtrace: None,
+        }
+    }
+
+    pub fn tag(mut self, key: &'static str, value: impl ToString) -> Self {
+        self.tags.push((key, value.to_string()));
+        self
+    }
+
+    /// Attaches `backtrace` if one was captured (see `RUST_BACKTRACE`).
+    pub fn backtrace(mut self, backtrace: Backtrace) -> Self {
+        if backtrace.status() == BacktraceStatus::Captured {
+            self.backtrace = Some(backtrace.to_string());
+        }
+        self
+    }
+
+    /// Groups reports of the same error: the source plus the message with numbers masked,
+    /// so IDs, durations and status codes don't make every failure unique.
+    fn fingerprint(&self) -> String {
+        let mut masked = String::with_capacity(self.message.len());
+        let mut in_number = false;
+        for c in self.message.chars() {
+            if c.is_ascii_digit() {
+                if !in_number {
+                    masked.push('#');
+                }
+                in_number = true;
+            } else {
+                masked.push(c);
+                in_number = false;
+            }
+        }
+        format!("{}: {}", self.source, masked)
+    }
+}
+
+struct Queue {
+    sender: UnboundedSender<ErrorReport>,
+    /// Taken by [`start`]; reports sent before then wait in the channel.
+    receiver: Mutex<Option<UnboundedReceiver<ErrorReport>>>,
+}
+
+fn queue() -> &'static Queue {
+    static QUEUE: OnceLock<Queue> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Queue {
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    })
+}
+
+/// Queues `report` for the configured error sinks. Callers log the error themselves.
+pub fn report(report: ErrorReport) {
+    // Fails only once delivery is disabled, in which case there's nowhere to send it
+    let _ = queue().sender.send(report);
+}
+
+/// Reports panics, with the backtrace at the panic, on top of the default panic output.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "panic".to_string());
+        let mut panic = ErrorReport::new("panic", message).backtrace(Backtrace::force_capture());
+        if let Some(location) = info.location() {
+            panic = panic.tag("location", location);
+        }
+        if let Some(thread) = std::thread::current().name() {
+            panic = panic.tag("thread", thread);
+        }
+        report(panic);
+    }));
+}
+
+/// A parsed Sentry DSN: `https://<key>@<host>/<project id>`.
+#[derive(Debug, Clone)]
+pub struct SentryDsn {
+    key: String,
+    /// Where events are posted.
+    store_url: String,
+}
+
+impl SentryDsn {
+    pub fn parse(dsn: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid Sentry DSN `{}`", dsn);
+        let url = reqwest::Url::parse(dsn.trim()).map_err(|_| invalid())?;
+        let project = url.path().trim_matches('/');
+        if url.username().is_empty() || project.is_empty() {
+            return Err(invalid());
+        }
+        let host = url.host_str().ok_or_else(invalid)?;
+        let port = url.port().map(|port| format!(":{}", port)).unwrap_or_default();
+
+        Ok(Self {
+            key: url.username().to_string(),
+            store_url: format!("{}://{}{}/api/{}/store/", url.scheme(), host, port, project),
+        })
+    }
+}
+
+/// Starts delivering reports, including any queued during startup. Without a Sentry DSN
+/// or error channel reports are dropped; the errors are logged where they happen.
+pub fn start(config: ErrorConfig, discord: Arc<Http>) {
+    let Some(receiver) = queue().receiver.lock().unwrap().take() else {
+        return;
+    };
+    // Checked when the config is loaded
+    let sentry = config.sentry_dsn.as_deref().and_then(|dsn| SentryDsn::parse(dsn).ok());
+    if sentry.is_none() && config.channel.is_none() {
+        return;
+    }
+    tokio::spawn(deliver(config, sentry, discord, receiver));
+}
+
+async fn deliver(
+    config: ErrorConfig,
+    sentry: Option<SentryDsn>,
+    discord: Arc<Http>,
+    mut receiver: UnboundedReceiver<ErrorReport>,
+) {
+    // Fingerprint to when it was last reported and how many repeats were held back since
+    let mut seen: HashMap<String, (Instant, u32)> = HashMap::new();
+    let window = config.dedup_window();
+
+    while let Some(report) = receiver.recv().await {
+        let now = Instant::now();
+        let fingerprint = report.fingerprint();
+        let held_back = match seen.get_mut(&fingerprint) {
+            Some((last, repeats)) if now.duration_since(*last) < window => {
+                *repeats += 1;
+                continue;
+            }
+            Some((_, repeats)) => *repeats,
+            None => 0,
+        };
+        seen.insert(fingerprint.clone(), (now, 0));
+        // Held-back counts are kept until their error comes back
+        seen.retain(|_, (last, repeats)| *repeats > 0 || now.duration_since(*last) < window);
+
+        if let Some(sentry) = &sentry {
+            send_to_sentry(sentry, &config, &report, &fingerprint, held_back).await;
+        }
+        if let Some(channel) = config.channel {
+            let content = render(&report, held_back);
+            if let Err(e) = ChannelId::new(channel).say(&discord, content).await {
+                warn!("Failed to post error report in channel {}: {}", channel, e);
+            }
+        }
+    }
+}
+
+/// The Discord message for a report, within the message limit.
+fn render(report: &ErrorReport, held_back: u32) -> String {
+    let mut content = format!("🐞 **{}**: {}", report.source, report.message);
+    if held_back > 0 {
+        content.push_str(&format!("\n↳ {} more times since it was last reported", held_back));
+    }
+    if !report.tags.is_empty() {
+        let tags: Vec<_> = report
+            .tags
+            .iter()
+            .map(|(key, value)| format!("{}: `{}`", key, value))
+            .collect();
+        content.push_str(&format!("\n{}", tags.join(" · ")));
+    }
+    if content.chars().count() > 1800 {
+        content = content.chars().take(1790).collect::<String>() + "…";
+    }
+    if let Some(backtrace) = &report.backtrace {
+        let mut backtrace: String = backtrace.chars().take(MAX_DISCORD_BACKTRACE).collect();
+        if backtrace.len() < report.backtrace.as_ref().map_or(0, String::len) {
+            backtrace.push('…');
+        }
+        content.push_str(&format!("\n```\n{}\n```", backtrace.replace("```", "'''")));
+    }
+    content
+}
+
+async fn send_to_sentry(
+    sentry: &SentryDsn,
+    config: &ErrorConfig,
+    report: &ErrorReport,
+    fingerprint: &str,
+    held_back: u32,
+) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    let event_id: String = (0..32)
+        .map(|_| char::from_digit(fastrand::u32(0..16), 16).unwrap())
+        .collect();
+    let tags: serde_json::Map<_, _> = report
+        .tags
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.clone().into()))
+        .chain([("source".to_string(), report.source.clone().into())])
+        .collect();
+
+    let event = serde_json::json!({
+        "event_id": event_id,
+        "timestamp": timestamp,
+        "level": "error",
+        "platform": "other",
+        "logger": "pyrobot",
+        "environment": config.environment,
+        "message": { "formatted": format!("{}: {}", report.source, report.message) },
+        "fingerprint": [fingerprint],
+        "tags": tags,
+        "extra": {
+            "backtrace": report.backtrace,
+            "repeats_since_last_report": held_back,
+        },
+    });
+
+    let auth = format!(
+        "Sentry sentry_version=7, sentry_client=pyrobot/{}, sentry_key={}",
+        env!("CARGO_PKG_VERSION"),
+        sentry.key
+    );
+    let client = http::client();
+    let request = client
+        .post(&sentry.store_url)
+        .header("X-Sentry-Auth", auth)
+        .json(&event);
+    match client.send(request).await {
+        Ok(response) if !response.status().is_success() => {
+            warn!("Sentry rejected an error report: {}", response.status());
+        }
+        Ok(_) => {}
+        Err(e) => warn!("Failed to send an error report to Sentry: {}", e),
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// How many before-images are kept per history.
+pub const HISTORY_LIMIT: usize = 10;
+
+/// Bounded stack of previous configuration values, newest last.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsHistory<T> {
+    entries: VecDeque<T>,
+}
+
+impl<T> Default for SettingsHistory<T> {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::new(),
+        }
+    }
+}
+
+impl<T> SettingsHistory<T> {
+    /// Records the value as it was before a change, dropping the oldest entry if full.
+    pub fn record(&mut self, before: T) {
+        self.entries.push_back(before);
+        while self.entries.len() > HISTORY_LIMIT {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Drops all but the newest `keep` entries, returning how many were dropped.
+    pub fn keep_newest(&mut self, keep: usize) -> usize {
+        let excess = self.entries.len().saturating_sub(keep);
+        self.entries.drain(..excess);
+        excess
+    }
+
+    /// Takes the most recent before-image, if any.
+    pub fn pop(&mut self) -> Option<T> {
+        self.entries.pop_back()
+    }
+
+    /// Every entry, oldest first.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.entries.iter_mut()
+    }
+
+    /// Converts every entry, keeping their order.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> SettingsHistory<U> {
+        SettingsHistory {
+            entries: self.entries.into_iter().map(f).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
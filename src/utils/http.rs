@@ -0,0 +1,248 @@
+//! Shared HTTP client with retries and per-host circuit breakers.
+
+use crate::metrics::MetricsRegistry;
+use dashmap::DashMap;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    LazyLock,
+};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+/// Consecutive failures after which a host's circuit opens.
+const FAILURE_THRESHOLD: u32 = 5;
+
+/// How long an open circuit rejects requests before letting a trial request through.
+const OPEN_DURATION: Duration = Duration::from_secs(30);
+
+static CLIENT: LazyLock<HttpClient> = LazyLock::new(HttpClient::new);
+
+/// The process-wide client; reuse it so connections are pooled.
+pub fn client() -> &'static HttpClient {
+    &CLIENT
+}
+
+/// Exposes the shared client's counters as internal metrics.
+pub fn register_metrics(registry: &MetricsRegistry) {
+    registry.register_global_gauge(
+        "http_requests_total",
+        "Outbound HTTP requests sent, including retries",
+        |_| async { Ok(client().stats.requests.load(Ordering::Relaxed) as f64) },
+    );
+
+    registry.register_global_gauge(
+        "http_retries_total",
+        "Outbound HTTP requests that were retried",
+        |_| async { Ok(client().stats.retries.load(Ordering::Relaxed) as f64) },
+    );
+
+    registry.register_global_gauge(
+        "http_failures_total",
+        "Outbound HTTP requests that failed after all retries",
+        |_| async { Ok(client().stats.failures.load(Ordering::Relaxed) as f64) },
+    );
+
+    registry.register_global_gauge(
+        "http_open_circuits",
+        "Hosts currently rejected by their circuit breaker",
+        |_| async { Ok(client().open_circuits() as f64) },
+    );
+}
+
+#[derive(Error, Debug)]
+pub enum HttpError {
+    #[error("{0} is unavailable (circuit open after repeated failures)")]
+    CircuitOpen(String),
+    #[error("HTTP {0}")]
+    Status(StatusCode),
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total attempts including the first.
+    pub attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A single attempt, for requests that aren't safe to repeat.
+    pub fn none() -> Self {
+        Self {
+            attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Exponential backoff with full jitter.
+    fn delay(&self, attempt: u32) -> Duration {
+        let cap = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+        cap.mul_f64(fastrand::f64())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// Counters exposed as internal metrics.
+#[derive(Debug, Default)]
+pub struct HttpStats {
+    pub requests: AtomicU64,
+    pub retries: AtomicU64,
+    pub failures: AtomicU64,
+    pub rejected: AtomicU64,
+}
+
+#[derive(Debug)]
+pub struct HttpClient {
+    inner: reqwest::Client,
+    breakers: DashMap<String, Breaker>,
+    pub stats: HttpStats,
+}
+
+impl HttpClient {
+    fn new() -> Self {
+        Self {
+            inner: reqwest::Client::builder()
+                .timeout(Duration::from_secs(15))
+                .pool_idle_timeout(Duration::from_secs(90))
+                .build()
+                .expect("failed to build HTTP client"),
+            breakers: DashMap::new(),
+            stats: HttpStats::default(),
+        }
+    }
+
+    pub fn get(&self, url: impl reqwest::IntoUrl) -> RequestBuilder {
+        self.inner.get(url)
+    }
+
+    pub fn post(&self, url: impl reqwest::IntoUrl) -> RequestBuilder {
+        self.inner.post(url)
+    }
+
+    /// Hosts whose circuit is currently open.
+    pub fn open_circuits(&self) -> usize {
+        let now = Instant::now();
+        self.breakers
+            .iter()
+            .filter(|b| b.open_until.is_some_and(|until| until > now))
+            .count()
+    }
+
+    /// Sends with the default retry policy.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, HttpError> {
+        self.send_with(request, RetryPolicy::default()).await
+    }
+
+    /// Sends `request`, retrying connection errors, timeouts, 429s and 5xx responses.
+    /// Other responses, including 4xx, are returned for the caller to inspect.
+    pub async fn send_with(
+        &self,
+        request: RequestBuilder,
+        policy: RetryPolicy,
+    ) -> Result<Response, HttpError> {
+        let request = request.build()?;
+        let host = request.url().host_str().unwrap_or_default().to_string();
+
+        if self.is_open(&host) {
+            self.stats.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(HttpError::CircuitOpen(host));
+        }
+
+        let mut request = Some(request);
+        let mut last_error = None;
+
+        for attempt in 1..=policy.attempts.max(1) {
+            // Keep a copy for the next attempt; streaming bodies can't be cloned and get one try
+            let current = match request.as_ref().and_then(|r| r.try_clone()) {
+                Some(copy) if attempt < policy.attempts => copy,
+                _ => match request.take() {
+                    Some(request) => request,
+                    None => break,
+                },
+            };
+
+            self.stats.requests.fetch_add(1, Ordering::Relaxed);
+            let error = match self.inner.execute(current).await {
+                Ok(response)
+                    if response.status().is_server_error()
+                        || response.status() == StatusCode::TOO_MANY_REQUESTS =>
+                {
+                    HttpError::Status(response.status())
+                }
+                Ok(response) => {
+                    self.record_success(&host);
+                    return Ok(response);
+                }
+                Err(e) if e.is_timeout() || e.is_connect() || e.is_request() => e.into(),
+                Err(e) => {
+                    self.record_failure(&host);
+                    return Err(e.into());
+                }
+            };
+
+            debug!("Request to {} failed (attempt {}): {}", host, attempt, error);
+            last_error = Some(error);
+
+            if attempt < policy.attempts && request.is_some() {
+                self.stats.retries.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(policy.delay(attempt)).await;
+            }
+        }
+
+        self.record_failure(&host);
+        Err(last_error.unwrap_or(HttpError::CircuitOpen(host)))
+    }
+
+    fn is_open(&self, host: &str) -> bool {
+        self.breakers
+            .get(host)
+            .and_then(|b| b.open_until)
+            .is_some_and(|until| until > Instant::now())
+    }
+
+    fn record_success(&self, host: &str) {
+        if let Some(mut breaker) = self.breakers.get_mut(host) {
+            breaker.consecutive_failures = 0;
+            breaker.open_until = None;
+        }
+    }
+
+    fn record_failure(&self, host: &str) {
+        self.stats.failures.fetch_add(1, Ordering::Relaxed);
+
+        let mut breaker = self.breakers.entry(host.to_string()).or_default();
+        breaker.consecutive_failures += 1;
+        // Past the threshold every failure (including a failed trial request) re-opens it
+        if breaker.consecutive_failures >= FAILURE_THRESHOLD {
+            if breaker.open_until.is_none_or(|until| until <= Instant::now()) {
+                warn!(
+                    "Opening circuit for {} after {} consecutive failures",
+                    host, breaker.consecutive_failures
+                );
+            }
+            breaker.open_until = Some(Instant::now() + OPEN_DURATION);
+        }
+    }
+}
@@ -0,0 +1,17 @@
+//! Reading JSON that operators hand the bot, such as `/settings import` files.
+
+use serde::de::DeserializeOwned;
+
+/// Parses `bytes` as a `T`. Errors give the path to the offending value, e.g.
+/// `lorax_settings.name_rules.min_length: invalid type: string "3", expected usize`.
+pub fn parse<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(|e| {
+        match e.path().to_string().as_str() {
+            "." => e.into_inner().to_string(),
+            path => format!("{}: {}", path, e.into_inner()),
+        }
+    })?;
+    deserializer.end().map_err(|e| e.to_string())?;
+    Ok(value)
+}
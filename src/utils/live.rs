@@ -0,0 +1,70 @@
+//! Live updates for internal dashboards. Anything can [`publish`] an update as it happens;
+//! the HTTP server streams them to `/live` WebSocket subscribers. Updates published while
+//! nobody is subscribed are dropped.
+
+use crate::modules::lorax::database::LoraxStage;
+use serde::Serialize;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+/// Updates a slow subscriber can fall behind by before it starts missing some.
+const CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveUpdate {
+    /// Votes per tree in a guild's current voting or tiebreaker round, most first.
+    LoraxTally {
+        guild_id: u64,
+        stage: LoraxStage,
+        tally: Vec<(String, usize)>,
+    },
+    /// A stat bar's alert changed state.
+    StatAlert {
+        guild_id: u64,
+        channel_id: u64,
+        query: String,
+        value: f64,
+        /// `fired`, `escalated` or `resolved`.
+        state: &'static str,
+    },
+    /// A test server was created, extended or deleted.
+    TestServer {
+        server_id: String,
+        name: String,
+        user_id: u64,
+        /// `created`, `extended` or `deleted`.
+        state: &'static str,
+    },
+}
+
+impl LiveUpdate {
+    /// The guild the update is about; `None` for updates only operators see.
+    pub fn guild_id(&self) -> Option<u64> {
+        match self {
+            Self::LoraxTally { guild_id, .. } | Self::StatAlert { guild_id, .. } => Some(*guild_id),
+            Self::TestServer { .. } => None,
+        }
+    }
+}
+
+fn bus() -> &'static broadcast::Sender<LiveUpdate> {
+    static BUS: OnceLock<broadcast::Sender<LiveUpdate>> = OnceLock::new();
+    BUS.get_or_init(|| broadcast::channel(CAPACITY).0)
+}
+
+/// Sends `update` to every current subscriber.
+pub fn publish(update: LiveUpdate) {
+    // Fails only when nobody is subscribed, and then there's no one to tell
+    let _ = bus().send(update);
+}
+
+/// Receives every update published from now on.
+pub fn subscribe() -> broadcast::Receiver<LiveUpdate> {
+    bus().subscribe()
+}
+
+/// How many subscribers are connected.
+pub fn subscribers() -> usize {
+    bus().receiver_count()
+}
@@ -0,0 +1,141 @@
+//! Notifications for the people running the bot. Anything can call [`notify`]; messages are
+//! batched and posted to the `[operators]` channel and/or webhook once [`start`] is called,
+//! so a burst of failures turns into one message instead of a flood.
+
+use crate::config::OperatorConfig;
+use crate::utils::http;
+use poise::serenity_prelude::{ChannelId, Http};
+use serde::Deserialize;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::{sleep, timeout_at, Instant};
+use tracing::{error, info, warn};
+
+/// Minimum time between two posts, even for critical notifications.
+const MIN_GAP: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    /// Sent without waiting for the batch window.
+    Critical,
+}
+
+impl Severity {
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Self::Info => "ℹ️",
+            Self::Warning => "⚠️",
+            Self::Critical => "🚨",
+        }
+    }
+}
+
+type Notification = (Severity, String);
+
+struct Queue {
+    sender: UnboundedSender<Notification>,
+    /// Taken by [`start`]; notifications sent before then wait in the channel.
+    receiver: Mutex<Option<UnboundedReceiver<Notification>>>,
+}
+
+fn queue() -> &'static Queue {
+    static QUEUE: OnceLock<Queue> = OnceLock::new();
+    QUEUE.get_or_init(|| {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Queue {
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    })
+}
+
+/// Logs `message` and queues it for the operators.
+pub fn notify(severity: Severity, message: impl Into<String>) {
+    let message = message.into();
+    match severity {
+        Severity::Info => info!("Operator notification: {}", message),
+        Severity::Warning => warn!("Operator notification: {}", message),
+        Severity::Critical => error!("Operator notification: {}", message),
+    }
+    // Fails only once delivery is disabled, in which case logging is all that's wanted
+    let _ = queue().sender.send((severity, message));
+}
+
+/// Starts delivering notifications, including any queued during startup. Without a
+/// configured channel or webhook notifications are only logged.
+pub fn start(config: OperatorConfig, discord: Arc<Http>) {
+    let Some(receiver) = queue().receiver.lock().unwrap().take() else {
+        return;
+    };
+    if config.channel.is_none() && config.webhook_url.is_none() {
+        return;
+    }
+    tokio::spawn(deliver(config, discord, receiver));
+}
+
+async fn deliver(
+    config: OperatorConfig,
+    discord: Arc<Http>,
+    mut receiver: UnboundedReceiver<Notification>,
+) {
+    let wanted = |(severity, _): &Notification| *severity >= config.min_severity;
+    while let Some(first) = receiver.recv().await {
+        if !wanted(&first) {
+            continue;
+        }
+        let mut batch = vec![first];
+
+        // Wait for the rest of a burst unless something critical needs to go out now
+        let deadline = Instant::now() + config.batch_window();
+        while batch.iter().all(|(severity, _)| *severity < Severity::Critical) {
+            match timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(notification)) if wanted(&notification) => batch.push(notification),
+                Ok(Some(_)) => {}
+                _ => break,
+            }
+        }
+        while let Ok(notification) = receiver.try_recv() {
+            if wanted(&notification) {
+                batch.push(notification);
+            }
+        }
+
+        post(&config, &discord, &render(&batch)).await;
+        sleep(MIN_GAP).await;
+    }
+}
+
+/// One line per notification, within Discord's message limit.
+fn render(batch: &[Notification]) -> String {
+    let mut content = String::new();
+    for (shown, (severity, message)) in batch.iter().enumerate() {
+        let line = format!("{} {}\n", severity.emoji(), message);
+        if content.chars().count() + line.chars().count() > 1950 {
+            content.push_str(&format!("…and {} more", batch.len() - shown));
+            break;
+        }
+        content.push_str(&line);
+    }
+    content
+}
+
+async fn post(config: &OperatorConfig, discord: &Http, content: &str) {
+    if let Some(channel) = config.channel {
+        if let Err(e) = ChannelId::new(channel).say(discord, content).await {
+            warn!("Failed to notify operators in channel {}: {}", channel, e);
+        }
+    }
+    if let Some(url) = &config.webhook_url {
+        let client = http::client();
+        let request = client
+            .post(url)
+            .json(&serde_json::json!({ "content": content }));
+        if let Err(e) = client.send(request).await {
+            warn!("Failed to notify operators through the webhook: {}", e);
+        }
+    }
+}
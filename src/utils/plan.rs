@@ -0,0 +1,54 @@
+//! Plan-then-apply for destructive commands. A command first lists what it would remove.
+//! With `dry_run` set it only reports that list; otherwise it goes on to apply it.
+
+use crate::utils::reply::say;
+use crate::Context;
+use poise::serenity_prelude as serenity;
+
+/// What a destructive command is about to remove, one line per item.
+#[derive(Debug, Clone, Default)]
+pub struct Plan {
+    items: Vec<String>,
+}
+
+impl Plan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, item: impl Into<String>) {
+        self.items.push(item.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn describe(&self) -> String {
+        self.items
+            .iter()
+            .map(|item| format!("• {}", item))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Replies with the plan if `dry_run` is set. Returns whether it was a dry run, in which
+    /// case the caller must stop without applying anything.
+    pub async fn preview(
+        &self,
+        ctx: Context<'_>,
+        dry_run: Option<bool>,
+    ) -> Result<bool, serenity::Error> {
+        if !dry_run.unwrap_or(false) {
+            return Ok(false);
+        }
+
+        let content = if self.is_empty() {
+            "🔍 **Dry run:** nothing would be removed.".to_string()
+        } else {
+            format!("🔍 **Dry run:** nothing was changed. This would:\n{}", self.describe())
+        };
+        say(ctx, content).await?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,72 @@
+//! Reply helpers that honour a guild's ephemeral reply policy (`/settings replies`).
+
+use crate::{Context, Data, Error};
+use poise::{serenity_prelude as serenity, CreateReply, ReplyHandle};
+
+/// Whether replies to the running command should only be visible to its user.
+///
+/// A policy for the exact command (`lorax status`) wins over one for its top-level
+/// command (`lorax`); without either, the command's own `ephemeral` flag applies.
+pub async fn is_ephemeral(ctx: Context<'_>) -> bool {
+    let command = ctx.command();
+    let Some(guild_id) = ctx.guild_id() else {
+        return command.ephemeral;
+    };
+
+    let config = ctx.data().dbs.system.get_guild_config(guild_id.get()).await;
+    let qualified_name = &command.qualified_name;
+    let root = qualified_name.split(' ').next().unwrap_or(qualified_name);
+
+    config
+        .reply_policy
+        .get(qualified_name)
+        .or_else(|| config.reply_policy.get(root))
+        .copied()
+        .unwrap_or(command.ephemeral)
+}
+
+/// Cuts `content` down to fit in one Discord message, marking the cut with an ellipsis.
+pub fn truncate_message(content: String) -> String {
+    if content.chars().count() > 2000 {
+        content.chars().take(1990).collect::<String>() + "\n…"
+    } else {
+        content
+    }
+}
+
+pub async fn say<'a>(
+    ctx: Context<'a>,
+    content: impl Into<String>,
+) -> Result<ReplyHandle<'a>, serenity::Error> {
+    send(ctx, CreateReply::default().content(content)).await
+}
+
+/// Sends a reply, applying the guild policy unless the reply sets `ephemeral` itself.
+pub async fn send<'a>(ctx: Context<'a>, reply: CreateReply) -> Result<ReplyHandle<'a>, serenity::Error> {
+    let reply = match reply.ephemeral {
+        Some(_) => reply,
+        None => {
+            let ephemeral = is_ephemeral(ctx).await;
+            reply.ephemeral(ephemeral)
+        }
+    };
+    ctx.send(reply).await
+}
+
+pub async fn defer(ctx: Context<'_>) -> Result<(), serenity::Error> {
+    if is_ephemeral(ctx).await {
+        ctx.defer_ephemeral().await
+    } else {
+        ctx.defer().await
+    }
+}
+
+/// Every command path users can set a reply policy for, e.g. `lorax` and `lorax status`.
+pub fn command_paths(commands: &[poise::Command<Data, Error>]) -> Vec<String> {
+    let mut paths = Vec::new();
+    for command in commands {
+        paths.push(command.qualified_name.clone());
+        paths.extend(command_paths(&command.subcommands));
+    }
+    paths
+}
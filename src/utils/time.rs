@@ -0,0 +1,84 @@
+use chrono::{Locale, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Parses an IANA timezone name (e.g. `Europe/Berlin`), falling back to UTC.
+pub fn parse_timezone(name: &str) -> Tz {
+    name.trim().parse().unwrap_or(Tz::UTC)
+}
+
+/// Renders a unix timestamp as an absolute time in the given timezone.
+pub fn format_timestamp(timestamp: u64, tz: Tz) -> String {
+    match Utc.timestamp_opt(timestamp as i64, 0).single() {
+        Some(time) => time
+            .with_timezone(&tz)
+            .format("%b %-d, %H:%M %Z")
+            .to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+/// Resolves a locale such as `de`, `en-US` or `pt_BR` to one times can be formatted in.
+pub fn parse_locale(name: &str) -> Option<Locale> {
+    let name = name.trim().replace('-', "_");
+    let (language, region) = match name.split_once('_') {
+        Some((language, region)) => (language.to_lowercase(), Some(region.to_uppercase())),
+        None => (name.to_lowercase(), None),
+    };
+    let candidates = match region {
+        Some(region) => vec![format!("{}_{}", language, region)],
+        // A bare language uses the conventions of the country that shares its code
+        None if language == "en" => vec!["en_US".to_string()],
+        None => vec![language.clone(), format!("{}_{}", language, language.to_uppercase())],
+    };
+    candidates
+        .iter()
+        .find_map(|candidate| Locale::try_from(candidate.as_str()).ok())
+}
+
+/// Like [`format_timestamp`], with month names and the clock written the way `locale` does.
+pub fn format_timestamp_localized(timestamp: u64, tz: Tz, locale: Locale) -> String {
+    match Utc.timestamp_opt(timestamp as i64, 0).single() {
+        Some(time) => time
+            .with_timezone(&tz)
+            .format_localized("%-d %b, %X %Z", locale)
+            .to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+/// Parses `YYYY-MM-DD HH:MM` as a time in the given timezone, returning a unix timestamp.
+pub fn parse_datetime(input: &str, tz: Tz) -> Result<u64, String> {
+    let naive = chrono::NaiveDateTime::parse_from_str(input.trim(), "%Y-%m-%d %H:%M")
+        .map_err(|_| format!("`{}` isn't a time like `2025-06-01 18:00`.", input.trim()))?;
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|time| time.timestamp().max(0) as u64)
+        .ok_or_else(|| format!("`{}` doesn't exist in {}.", input.trim(), tz))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_locales_in_either_spelling() {
+        assert_eq!(parse_locale("de-DE"), Some(Locale::de_DE));
+        assert_eq!(parse_locale("pt_br"), Some(Locale::pt_BR));
+        assert_eq!(parse_locale("de"), Some(Locale::de_DE));
+        assert_eq!(parse_locale("en"), Some(Locale::en_US));
+        assert_eq!(parse_locale("xx"), None);
+    }
+
+    #[test]
+    fn formats_times_in_the_locale() {
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        assert_eq!(
+            format_timestamp_localized(1_760_000_000, berlin, Locale::de_DE),
+            "9 Okt, 10:53:20 CEST"
+        );
+        assert_eq!(
+            format_timestamp_localized(1_760_000_000, berlin, Locale::en_US),
+            "9 Oct, 10:53:20 AM CEST"
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! Argument validators for commands. Each returns [`Invalid`] with guidance for the user,
+//! which the framework error handler shows as an ephemeral reply, so commands can simply
+//! write `let url = validate::url(&url)?;`.
+
+use super::duration::{format_duration, parse_duration, DurationUnit};
+use crate::Context;
+use poise::serenity_prelude::{ChannelId, ChannelType, GuildChannel, GuildId};
+use std::{fmt, time::Duration};
+
+/// A command argument that failed validation. The message is shown to the user as is.
+#[derive(Debug, Clone)]
+pub struct Invalid(pub String);
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Invalid {}
+
+fn invalid<T>(message: impl Into<String>) -> Result<T, Invalid> {
+    Err(Invalid(message.into()))
+}
+
+/// The guild a command was used in, for commands that need one.
+pub fn guild(ctx: Context<'_>) -> Result<GuildId, Invalid> {
+    match ctx.guild_id() {
+        Some(guild_id) => Ok(guild_id),
+        None => invalid("This command only works in a server, not in DMs."),
+    }
+}
+
+/// The guild looked up with `ctx.guild()`, which is missing from the cache while its shard
+/// is still starting up.
+pub fn cached_guild<T>(guild: Option<T>) -> Result<T, Invalid> {
+    match guild {
+        Some(guild) => Ok(guild),
+        None => invalid("I'm still loading this server. Try again in a moment."),
+    }
+}
+
+/// An http(s) URL with a host, returned without a trailing slash.
+pub fn url(input: &str) -> Result<String, Invalid> {
+    let input = input.trim();
+    // Checked first: `localhost:9090` would otherwise parse with `localhost` as the scheme
+    if !input.contains("://") {
+        return invalid(format!(
+            "`{}` is missing a scheme. Try `https://{}`.",
+            input, input
+        ));
+    }
+    let parsed = match reqwest::Url::parse(input) {
+        Ok(parsed) => parsed,
+        Err(e) => return invalid(format!("`{}` isn't a valid URL ({}).", input, e)),
+    };
+
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return invalid(format!(
+            "`{}` uses `{}://`; only http and https URLs are supported.",
+            input,
+            parsed.scheme()
+        ));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return invalid(format!("`{}` has no host name.", input));
+    }
+
+    Ok(input.trim_end_matches('/').to_string())
+}
+
+/// A non-empty PromQL query with balanced brackets and quotes. Full syntax is left to
+/// Prometheus; this catches the common copy-paste mistakes early.
+pub fn promql(query: &str) -> Result<String, Invalid> {
+    let query = query.trim();
+    if query.is_empty() {
+        return invalid("The query is empty. Try something like `up` or `sum(rate(http_requests_total[5m]))`.");
+    }
+
+    let mut open = Vec::new();
+    let mut quote = None;
+    let mut escaped = false;
+    for c in query.chars() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '(' | '{' | '[') => open.push(c),
+            (None, ')' | '}' | ']') => {
+                let expected = match c {
+                    ')' => '(',
+                    '}' => '{',
+                    _ => '[',
+                };
+                if open.pop() != Some(expected) {
+                    return invalid(format!("The query has an unmatched `{}`.", c));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(q) = quote {
+        return invalid(format!("The query has an unclosed {} quote.", q));
+    }
+    if let Some(c) = open.pop() {
+        return invalid(format!("The query has an unclosed `{}`.", c));
+    }
+    Ok(query.to_string())
+}
+
+/// A duration such as `90m` or `1d`, between `min` and `max` inclusive.
+pub fn duration(
+    input: &str,
+    unit: DurationUnit,
+    min: Duration,
+    max: Option<Duration>,
+) -> Result<Duration, Invalid> {
+    let duration = parse_duration(input, unit).map_err(Invalid)?;
+
+    if duration < min {
+        return invalid(format!(
+            "`{}` is too short; the minimum is {}.",
+            input.trim(),
+            format_duration(min.as_secs())
+        ));
+    }
+    if let Some(max) = max.filter(|max| duration > *max) {
+        return invalid(format!(
+            "`{}` is too long; the maximum is {}.",
+            input.trim(),
+            format_duration(max.as_secs())
+        ));
+    }
+    Ok(duration)
+}
+
+fn kind_name(kind: ChannelType) -> &'static str {
+    match kind {
+        ChannelType::Text => "text channel",
+        ChannelType::Voice => "voice channel",
+        ChannelType::Category => "category",
+        ChannelType::News => "news channel",
+        ChannelType::Stage => "stage channel",
+        ChannelType::Forum => "forum channel",
+        ChannelType::PublicThread | ChannelType::PrivateThread | ChannelType::NewsThread => {
+            "thread"
+        }
+        _ => "channel",
+    }
+}
+
+/// A channel of this guild whose type is one of `kinds`. `purpose` completes the sentence
+/// "… needs a voice channel because …".
+pub async fn channel(
+    ctx: Context<'_>,
+    channel: ChannelId,
+    kinds: &[ChannelType],
+    purpose: &str,
+) -> Result<GuildChannel, Invalid> {
+    let found = channel
+        .to_channel(&ctx.serenity_context())
+        .await
+        .ok()
+        .and_then(|c| c.guild())
+        .filter(|c| Some(c.guild_id) == ctx.guild_id());
+
+    let Some(found) = found else {
+        return invalid(format!(
+            "I can't see <#{}>. Make sure it's in this server and I have access to it.",
+            channel
+        ));
+    };
+
+    if !kinds.contains(&found.kind) {
+        let wanted = kinds
+            .iter()
+            .map(|kind| kind_name(*kind))
+            .collect::<Vec<_>>()
+            .join(" or ");
+        return invalid(format!(
+            "<#{}> is a {}, but this needs a {} because {}.",
+            channel,
+            kind_name(found.kind),
+            wanted,
+            purpose
+        ));
+    }
+    Ok(found)
+}